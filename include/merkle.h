@@ -0,0 +1,38 @@
+/* C declarations of the functions in src/ffi.rs. Build the library with
+ *     cargo rustc --release --lib --features ffi --crate-type staticlib
+ * and link against target/release/libtree.a */
+
+#ifndef MERKLE_H
+#define MERKLE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define MERKLE_OK 0
+#define MERKLE_NULL_POINTER 1
+#define MERKLE_EMPTY_TREE 2
+#define MERKLE_INVALID_INDEX 3
+#define MERKLE_BUFFER_TOO_SMALL 4
+#define MERKLE_PANIC 5
+
+typedef struct MerkleTree MerkleTree;
+
+/* Creates a tree with a leaf for each buffer. Release it with merkle_free. */
+int32_t merkle_new(const uint8_t *const *buffers, const size_t *lens, size_t count, MerkleTree **out);
+
+/* Writes the root of the tree. */
+int32_t merkle_root(const MerkleTree *tree, uint64_t *out);
+
+/* Writes the proof of the element at index into hashes, and its len into len.
+ * If capacity is too small, only len is written. */
+int32_t merkle_proof(const MerkleTree *tree, size_t index, uint64_t *hashes, size_t capacity, size_t *len);
+
+/* Writes into valid whether the proof of the element leads to the root. */
+int32_t merkle_verify(uint64_t root, size_t index, const uint64_t *hashes, size_t len,
+                      const uint8_t *leaf, size_t leaf_len, bool *valid);
+
+/* Releases a tree. Null is ignored. */
+void merkle_free(MerkleTree *tree);
+
+#endif
@@ -0,0 +1,445 @@
+use std::collections::HashSet;
+use std::io::{self, Read};
+
+use crate::merkle::{create_remaining_levels, extend_elements, fold_proof, hash_element};
+use crate::{MerkleTree, RootHash};
+
+/// Size of the pieces the content of a member is split into before hashing.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Size of every header and of the padding unit in a tar archive.
+const BLOCK_SIZE: usize = 512;
+
+/// A member of the archive, as it is committed in a leaf of the tree.
+/// - `path`: The path of the member inside the archive.
+/// - `size`: The quantity of bytes of its content.
+/// - `content_root`: The root of a Merkle Tree built over the content split in `CHUNK_SIZE` pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub path: String,
+    pub size: u64,
+    pub content_root: u64,
+}
+
+/// The proof that a member is part of an archive.
+/// - `index`: The position of the member among the committed members.
+/// - `siblings`: The hashes that make up the proof to get to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberProof {
+    pub index: usize,
+    pub siblings: Vec<u64>,
+}
+
+/// A Merkle Tree over the members of a tar archive, in archive order.
+///
+/// Each leaf commits to the path, the size and the content root of a
+/// member, so a single member can be verified against the root of the
+/// archive without unpacking anything else.
+///
+/// Only regular files are committed. The entries are handled as follows:
+/// - Directories, symbolic links and hard links are skipped, since they
+///   have no content of their own. A hard link can be verified through
+///   the member it points to.
+/// - Pax extended headers (and GNU long names) are applied to the entry
+///   that follows them, so their `path` and `size` take precedence over
+///   the ustar header. Global pax headers are ignored.
+/// - Two members with the same path make the archive be rejected, since
+///   it would be ambiguous which one a proof refers to.
+pub struct TarTree {
+    members: Vec<Member>,
+    tree: MerkleTree,
+    root: RootHash,
+}
+
+impl TarTree {
+    /// Creates a TarTree by reading every entry of a tar archive.
+    /// The content of each member is hashed as it is read, so it is never
+    /// fully held in memory.
+    ///
+    /// ### Arguments
+    ///
+    /// - `reader`: A reader positioned at the start of the archive.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the TarTree, or an Error if the archive is malformed,
+    /// has no members or has duplicate paths
+    pub fn build<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut members = Vec::new();
+        let mut paths = HashSet::new();
+        let mut pending_path = None;
+        let mut pending_size = None;
+        let mut header = [0u8; BLOCK_SIZE];
+
+        while read_header(&mut reader, &mut header)? {
+            let size = match pending_size.take() {
+                Some(size) => size,
+                None => parse_number(&header[124..136])?,
+            };
+            let path = pending_path.take().unwrap_or_else(|| header_path(&header));
+
+            match header[156] {
+                // Regular files
+                b'0' | b'7' | 0 => {
+                    if !paths.insert(path.clone()) {
+                        return Err(invalid_data(format!("Duplicate path '{path}' in the archive")));
+                    }
+                    let content_root = hash_content(&mut reader, size)?;
+                    members.push(Member { path, size, content_root });
+                }
+                // Pax extended header for the next entry
+                b'x' => {
+                    let records = read_content(&mut reader, size)?;
+                    (pending_path, pending_size) = parse_pax(&records)?;
+                }
+                // GNU long name for the next entry
+                b'L' => {
+                    let name = read_content(&mut reader, size)?;
+                    pending_path = Some(until_nul(&name));
+                }
+                _ => skip_content(&mut reader, size)?,
+            }
+        }
+
+        if members.is_empty() {
+            return Err(invalid_data("The archive has no members".to_string()));
+        }
+        let leaves = members.iter().map(|member| (member.path.clone(), member.size, member.content_root)).collect();
+        let tree = MerkleTree::new(leaves);
+        let root = tree.root_hash().ok_or_else(|| invalid_data("The archive has no root".to_string()))?;
+        Ok(Self { members, tree, root })
+    }
+
+    /// Gets the root of the archive
+    pub fn root(&self) -> RootHash {
+        self.root
+    }
+
+    /// Gets the committed members, in archive order
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Generates the proof that the member with the given path is part of the archive
+    ///
+    /// ### Arguments
+    ///
+    /// - `path`: The path of the member inside the archive.
+    ///
+    /// ### Returns
+    ///
+    /// The MemberProof, or None if no member has that path
+    pub fn prove_member(&self, path: &str) -> Option<MemberProof> {
+        let index = self.members.iter().position(|member| member.path == path)?;
+        let siblings = self.tree.generate_proof(index).ok()?.hashes();
+        Some(MemberProof { index, siblings })
+    }
+}
+
+/// Checks if a member is part of the archive with the given root.
+///
+/// The member is split in chunks again to get its content root, so both
+/// its content and its path have to be the ones that were committed.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the archive.
+/// - `path`: The path of the member inside the archive.
+/// - `member_bytes`: The whole content of the member.
+/// - `proof`: The proof generated for that member.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_member(root: RootHash, path: &str, member_bytes: &[u8], proof: &MemberProof) -> bool {
+    let chunk_hashes = member_bytes.chunks(CHUNK_SIZE).map(hash_element).collect();
+    let content_root = content_root(chunk_hashes);
+    let leaf = hash_element((path, member_bytes.len() as u64, content_root));
+    fold_proof(&proof.siblings, proof.index, leaf) == root.into_raw()
+}
+
+/// Gets the root of the tree built over the hashes of the chunks of a member.
+/// A member without content is committed as a single empty chunk.
+fn content_root(mut chunk_hashes: Vec<u64>) -> u64 {
+    if chunk_hashes.is_empty() {
+        chunk_hashes.push(hash_element::<&[u8]>(&[]));
+    }
+    extend_elements(&mut chunk_hashes);
+    let levels = create_remaining_levels(chunk_hashes);
+    levels[levels.len() - 1][0]
+}
+
+/// Reads the content of a member in `CHUNK_SIZE` pieces, hashing each one,
+/// and skips the padding that follows it.
+fn hash_content<R: Read>(reader: &mut R, size: u64) -> io::Result<u64> {
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut chunk_hashes = Vec::new();
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = remaining.min(CHUNK_SIZE as u64) as usize;
+        reader.read_exact(&mut chunk[..len])?;
+        chunk_hashes.push(hash_element(&chunk[..len]));
+        remaining -= len as u64;
+    }
+    skip_exact(reader, padding(size))?;
+    Ok(content_root(chunk_hashes))
+}
+
+/// Reads the whole content of a small entry, like a pax header, and skips its padding
+fn read_content<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.by_ref().take(size).read_to_end(&mut content)?;
+    if content.len() as u64 != size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    skip_exact(reader, padding(size))?;
+    Ok(content)
+}
+
+/// Skips the content of an entry that is not committed, and its padding
+fn skip_content<R: Read>(reader: &mut R, size: u64) -> io::Result<()> {
+    skip_exact(reader, size + padding(size))
+}
+
+/// Discards exactly `len` bytes from the reader
+fn skip_exact<R: Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.by_ref().take(len), &mut io::sink())?;
+    if skipped != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Gets the quantity of bytes that complete the last block of an entry
+fn padding(size: u64) -> u64 {
+    (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64
+}
+
+/// Reads the next header of the archive.
+///
+/// ### Returns
+///
+/// A Result with false if the archive ended, either with the end-of-archive
+/// zero block or without more data, and true if a valid header was read
+fn read_header<R: Read>(reader: &mut R, header: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < BLOCK_SIZE {
+        match reader.read(&mut header[read..])? {
+            0 if read == 0 => return Ok(false),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => read += n,
+        }
+    }
+    if header.iter().all(|byte| *byte == 0) {
+        return Ok(false);
+    }
+
+    // The checksum is the sum of every byte of the header, taking the
+    // checksum field itself as if it were filled with spaces.
+    let stored = parse_number(&header[148..156])?;
+    let computed: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| if (148..156).contains(&i) { b' ' as u64 } else { *byte as u64 })
+        .sum();
+    if stored != computed {
+        return Err(invalid_data("Invalid header checksum".to_string()));
+    }
+    Ok(true)
+}
+
+/// Gets the path of an entry, joining the ustar prefix and the name
+fn header_path(header: &[u8; BLOCK_SIZE]) -> String {
+    let name = until_nul(&header[0..100]);
+    let prefix = until_nul(&header[345..500]);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{prefix}/{name}")
+    } else {
+        name
+    }
+}
+
+/// Parses a numeric field, which is either octal text or, for
+/// large values, big-endian base-256 marked by the highest bit.
+fn parse_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..].iter().fold(0, |acc, byte| (acc << 8) | *byte as u64));
+    }
+    let text = until_nul(field);
+    let text = text.trim_matches(' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid_data(format!("Invalid numeric field '{text}'")))
+}
+
+/// Parses the records of a pax extended header, which have the form
+/// `"<len> <key>=<value>\n"`, keeping only the path and the size.
+fn parse_pax(records: &[u8]) -> io::Result<(Option<String>, Option<u64>)> {
+    let mut path = None;
+    let mut size = None;
+    let mut rest = records;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|byte| *byte == b' ').ok_or_else(|| invalid_data("Invalid pax record".to_string()))?;
+        let len: usize = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .filter(|len| *len > space && *len <= rest.len())
+            .ok_or_else(|| invalid_data("Invalid pax record length".to_string()))?;
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        let record = record.strip_suffix('\n').unwrap_or(&record);
+        match record.split_once('=') {
+            Some(("path", value)) => path = Some(value.to_string()),
+            Some(("size", value)) => {
+                size = Some(value.parse().map_err(|_| invalid_data(format!("Invalid pax size '{value}'")))?)
+            }
+            _ => {}
+        }
+        rest = &rest[len..];
+    }
+    Ok((path, size))
+}
+
+/// Gets the text of a field up to its first NUL byte
+fn until_nul(field: &[u8]) -> String {
+    let end = field.iter().position(|byte| *byte == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a ustar header for an entry
+    fn header(path: &str, size: usize, typeflag: u8) -> [u8; BLOCK_SIZE] {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+        header[136..148].copy_from_slice(b"00000000000\0");
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+        header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+        header
+    }
+
+    /// Appends an entry with its content and padding to an archive
+    fn append(tar: &mut Vec<u8>, path: &str, content: &[u8], typeflag: u8) {
+        tar.extend_from_slice(&header(path, content.len(), typeflag));
+        tar.extend_from_slice(content);
+        tar.resize(tar.len() + padding(content.len() as u64) as usize, 0);
+    }
+
+    /// Creates an archive with a small file, a directory, a file larger
+    /// than a chunk and an empty file.
+    fn archive() -> Vec<u8> {
+        let mut tar = Vec::new();
+        append(&mut tar, "README.md", b"Merkle Tree in Rust", b'0');
+        append(&mut tar, "src/", b"", b'5');
+        append(&mut tar, "src/merkle.rs", &big_member(), b'0');
+        append(&mut tar, "empty.txt", b"", b'0');
+        tar.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+        tar
+    }
+
+    fn big_member() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    /// Test if only the regular files are committed, in archive order
+    fn commits_regular_files_in_order() {
+        let tar_tree = TarTree::build(archive().as_slice()).unwrap();
+        let paths: Vec<&str> = tar_tree.members().iter().map(|member| member.path.as_str()).collect();
+
+        assert_eq!(paths, vec!["README.md", "src/merkle.rs", "empty.txt"]);
+        assert_eq!(tar_tree.members()[1].size, 10_000);
+    }
+
+    #[test]
+    /// Test if every member verifies against the root with only its bytes
+    fn verifies_members() {
+        let tar_tree = TarTree::build(archive().as_slice()).unwrap();
+        let members: [(&str, Vec<u8>); 3] =
+            [("README.md", b"Merkle Tree in Rust".to_vec()), ("src/merkle.rs", big_member()), ("empty.txt", Vec::new())];
+
+        for (path, content) in members {
+            let proof = tar_tree.prove_member(path).unwrap();
+            assert!(verify_member(tar_tree.root(), path, &content, &proof));
+        }
+        assert_eq!(tar_tree.prove_member("src/"), None);
+    }
+
+    #[test]
+    /// Test if a member with a single modified byte does not verify
+    fn detects_modified_member() {
+        let tar_tree = TarTree::build(archive().as_slice()).unwrap();
+        let proof = tar_tree.prove_member("src/merkle.rs").unwrap();
+        let mut modified = big_member();
+        modified[9_000] ^= 1;
+
+        assert!(!verify_member(tar_tree.root(), "src/merkle.rs", &modified, &proof));
+    }
+
+    #[test]
+    /// Test if the content of a member can not be passed off under the path
+    /// of another member, with the proof of either of them.
+    fn detects_path_swap() {
+        let tar_tree = TarTree::build(archive().as_slice()).unwrap();
+        let readme_proof = tar_tree.prove_member("README.md").unwrap();
+        let empty_proof = tar_tree.prove_member("empty.txt").unwrap();
+
+        assert!(verify_member(tar_tree.root(), "empty.txt", b"", &empty_proof));
+        assert!(!verify_member(tar_tree.root(), "README.md", b"", &empty_proof));
+        assert!(!verify_member(tar_tree.root(), "README.md", b"", &readme_proof));
+    }
+
+    #[test]
+    /// Test if a pax header overrides the path of the entry that follows it
+    fn applies_pax_path() {
+        let long_path = format!("{}/file.txt", "nested".repeat(20));
+        let record = format!(" path={long_path}\n");
+        let record = format!("{}{record}", record.len() + 3);
+        let mut tar = Vec::new();
+        append(&mut tar, "PaxHeader", record.as_bytes(), b'x');
+        append(&mut tar, "short-name", b"content", b'0');
+
+        let tar_tree = TarTree::build(tar.as_slice()).unwrap();
+        let proof = tar_tree.prove_member(&long_path).unwrap();
+
+        assert!(verify_member(tar_tree.root(), &long_path, b"content", &proof));
+    }
+
+    #[test]
+    /// Test if hard links are skipped and duplicate paths are rejected
+    fn links_and_duplicates() {
+        let mut tar = Vec::new();
+        append(&mut tar, "a.txt", b"a", b'0');
+        append(&mut tar, "b.txt", b"", b'1');
+        let tar_tree = TarTree::build(tar.as_slice()).unwrap();
+        assert_eq!(tar_tree.members().len(), 1);
+
+        append(&mut tar, "a.txt", b"another a", b'0');
+        let error = TarTree::build(tar.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    /// Test if malformed archives are rejected instead of being committed
+    fn rejects_malformed_archives() {
+        let mut corrupted = archive();
+        corrupted[0] = b'X';
+        let truncated = &archive()[..BLOCK_SIZE + 4];
+
+        assert_eq!(TarTree::build(corrupted.as_slice()).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(TarTree::build(truncated).err().unwrap().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(TarTree::build(&[0u8; 1024][..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
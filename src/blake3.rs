@@ -0,0 +1,334 @@
+use crate::digest_tree::{Bytes32, Digest, DigestTree};
+
+/// Bytes compressed at once.
+const BLOCK_LEN: usize = 64;
+
+/// Bytes of a chunk, the leaves of the internal BLAKE3 tree.
+const CHUNK_LEN: usize = 1024;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+
+const IV: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
+const MESSAGE_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+/// Hashes some bytes with BLAKE3.
+///
+/// ### Arguments
+///
+/// - `data`: The bytes to hash.
+///
+/// ### Returns
+///
+/// The 32 bytes of the hash
+pub fn blake3(data: &[u8]) -> Bytes32 {
+    let mut hasher = Blake3Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// The quarter round of the compression function
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn round(state: &mut [u32; 16], m: &[u32; 16]) {
+    // Columns
+    g(state, 0, 4, 8, 12, m[0], m[1]);
+    g(state, 1, 5, 9, 13, m[2], m[3]);
+    g(state, 2, 6, 10, 14, m[4], m[5]);
+    g(state, 3, 7, 11, 15, m[6], m[7]);
+    // Diagonals
+    g(state, 0, 5, 10, 15, m[8], m[9]);
+    g(state, 1, 6, 11, 12, m[10], m[11]);
+    g(state, 2, 7, 8, 13, m[12], m[13]);
+    g(state, 3, 4, 9, 14, m[14], m[15]);
+}
+
+/// The BLAKE3 compression function.
+///
+/// ### Arguments
+///
+/// - `chaining_value`: The chaining value of the previous block, or the IV.
+/// - `block`: The 16 little endian words of the block.
+/// - `counter`: The index of the chunk, or 0 for parent nodes.
+/// - `block_len`: Quantity of meaningful bytes in the block.
+/// - `flags`: The domain flags of the block.
+fn compress(chaining_value: &[u32; 8], block: &[u32; 16], counter: u64, block_len: u32, flags: u32) -> [u32; 16] {
+    let mut state = [
+        chaining_value[0], chaining_value[1], chaining_value[2], chaining_value[3],
+        chaining_value[4], chaining_value[5], chaining_value[6], chaining_value[7],
+        IV[0], IV[1], IV[2], IV[3],
+        counter as u32, (counter >> 32) as u32, block_len, flags,
+    ];
+    let mut message = *block;
+    for i in 0..7 {
+        round(&mut state, &message);
+        if i < 6 {
+            message = MESSAGE_PERMUTATION.map(|j| message[j]);
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+fn first_8_words(words: [u32; 16]) -> [u32; 8] {
+    let mut first = [0u32; 8];
+    first.copy_from_slice(&words[..8]);
+    first
+}
+
+fn words_from_block(bytes: &[u8; BLOCK_LEN]) -> [u32; 16] {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().expect("chunks of 4 bytes"));
+    }
+    words
+}
+
+/// The inputs of a compression that was not done yet, since its flags
+/// depend on whether it is the root or not.
+struct Output {
+    chaining_value: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(compress(&self.chaining_value, &self.block, self.counter, self.block_len, self.flags))
+    }
+
+    fn root_hash(&self) -> Bytes32 {
+        let words = compress(&self.chaining_value, &self.block, 0, self.block_len, self.flags | ROOT);
+        let mut hash = [0u8; 32];
+        for (bytes, word) in hash.chunks_exact_mut(4).zip(words) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        hash
+    }
+}
+
+fn parent_output(left: [u32; 8], right: [u32; 8]) -> Output {
+    let mut block = [0u32; 16];
+    block[..8].copy_from_slice(&left);
+    block[8..].copy_from_slice(&right);
+    Output { chaining_value: IV, block, counter: 0, block_len: BLOCK_LEN as u32, flags: PARENT }
+}
+
+/// The state of the chunk that is currently being hashed.
+/// - `chaining_value`: The chaining value after the blocks compressed so far.
+/// - `counter`: The index of the chunk in the input.
+/// - `block`: The bytes of the block that is being filled.
+/// - `block_len`: Quantity of bytes in `block`.
+/// - `blocks_compressed`: Quantity of blocks of this chunk already compressed.
+struct ChunkState {
+    chaining_value: [u32; 8],
+    counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+}
+
+impl ChunkState {
+    fn new(counter: u64) -> Self {
+        Self { chaining_value: IV, counter, block: [0; BLOCK_LEN], block_len: 0, blocks_compressed: 0 }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 { CHUNK_START } else { 0 }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            // The last block of a chunk is only compressed in `output`,
+            // so a full block waits until more input arrives.
+            if self.block_len == BLOCK_LEN {
+                let words = words_from_block(&self.block);
+                let flags = self.start_flag();
+                self.chaining_value = first_8_words(compress(&self.chaining_value, &words, self.counter, BLOCK_LEN as u32, flags));
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+            let take = (BLOCK_LEN - self.block_len).min(input.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&input[..take]);
+            self.block_len += take;
+            input = &input[take..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            chaining_value: self.chaining_value,
+            block: words_from_block(&self.block),
+            counter: self.counter,
+            block_len: self.block_len as u32,
+            flags: self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+/// An incremental BLAKE3 hasher, so a leaf can be hashed as its bytes
+/// arrive instead of having it whole in memory.
+/// - `chunk`: The chunk that is currently being hashed.
+/// - `stack`: The chaining values of the complete subtrees on the left, largest first.
+pub struct Blake3Hasher {
+    chunk: ChunkState,
+    stack: Vec<[u32; 8]>,
+}
+
+impl Default for Blake3Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Blake3Hasher {
+    /// Creates a hasher that did not receive any byte yet
+    pub fn new() -> Self {
+        Self { chunk: ChunkState::new(0), stack: Vec::new() }
+    }
+
+    /// Hashes more bytes of the input
+    pub fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            // A full chunk is only closed once we know it is not the last one
+            if self.chunk.len() == CHUNK_LEN {
+                let chaining_value = self.chunk.output().chaining_value();
+                let total_chunks = self.chunk.counter + 1;
+                self.push_chunk(chaining_value, total_chunks);
+                self.chunk = ChunkState::new(total_chunks);
+            }
+            let take = (CHUNK_LEN - self.chunk.len()).min(input.len());
+            self.chunk.update(&input[..take]);
+            input = &input[take..];
+        }
+    }
+
+    /// Merges the subtrees that were completed by a new chunk. There is one
+    /// merge for each trailing 0 bit in the quantity of chunks.
+    fn push_chunk(&mut self, mut chaining_value: [u32; 8], mut total_chunks: u64) {
+        while total_chunks & 1 == 0 {
+            let left = self.stack.pop().expect("a complete subtree on the left");
+            chaining_value = parent_output(left, chaining_value).chaining_value();
+            total_chunks >>= 1;
+        }
+        self.stack.push(chaining_value);
+    }
+
+    /// Gets the hash of every byte received
+    pub fn finalize(&self) -> Bytes32 {
+        let mut output = self.chunk.output();
+        for left in self.stack.iter().rev() {
+            output = parent_output(*left, output.chaining_value());
+        }
+        output.root_hash()
+    }
+}
+
+/// The BLAKE3 backend of a DigestTree
+pub struct Blake3;
+
+impl Digest for Blake3 {
+    fn digest(data: &[u8]) -> Bytes32 {
+        blake3(data)
+    }
+}
+
+/// A Merkle Tree where each leaf is BLAKE3 of the raw bytes of an element
+/// and each parent is BLAKE3 of the 64 bytes of its children, left first.
+pub type Blake3Tree = DigestTree<Blake3>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest_tree::{to_hex as hex, verify_proof};
+
+    /// The input of the official test vectors: the bytes 0, 1, ..., 250 repeated
+    fn test_input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    /// Test BLAKE3 against the official test vectors, with inputs of one
+    /// block, of exactly one chunk and of several chunks.
+    fn blake3_matches_known_vectors() {
+        let vectors = [
+            (0, "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"),
+            (1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213"),
+            (1023, "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11"),
+            (1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7"),
+            (1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+            (2048, "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a"),
+            (3073, "7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd3"),
+            (8193, "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b"),
+        ];
+        for (len, expected) in vectors {
+            assert_eq!(hex(&blake3(&test_input(len))), expected, "input of {len} bytes");
+        }
+        assert_eq!(hex(&blake3(b"abc")), "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+    }
+
+    #[test]
+    /// Test if feeding the input in pieces of any size gives the same hash
+    fn incremental_hashing_matches() {
+        let input = test_input(5000);
+        for piece in [1, 63, 64, 65, 1024, 1500] {
+            let mut hasher = Blake3Hasher::new();
+            for bytes in input.chunks(piece) {
+                hasher.update(bytes);
+            }
+            assert_eq!(hasher.finalize(), blake3(&input), "pieces of {piece} bytes");
+        }
+    }
+
+    #[test]
+    /// Test if proofs of a tree with multi-kilobyte leaves verify
+    fn proofs_verify() {
+        let leaves: Vec<Vec<u8>> = (0..5).map(|i| test_input(3000 + i)).collect();
+        let merkle = Blake3Tree::new(&leaves).unwrap();
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify(&proof, index, blake3(leaf)));
+            assert!(verify_proof::<Blake3>(merkle.root(), &proof, index, blake3(leaf)));
+        }
+    }
+
+    #[test]
+    #[ignore]
+    /// Test if a tree of 4096 leaves of 4 KiB each is built and proves
+    /// every leaf. Run it with
+    /// `cargo test --release --features blake3 -- --ignored blake3_tree_at_scale`.
+    fn blake3_tree_at_scale() {
+        let leaves: Vec<Vec<u8>> = (0..4096).map(|i| test_input(4096 + i % 7)).collect();
+        let merkle = Blake3Tree::new(&leaves).unwrap();
+
+        assert_eq!(merkle.len(), 4096);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert_eq!(proof.len(), 12);
+            assert!(verify_proof::<Blake3>(merkle.root(), &proof, index, blake3(leaf)));
+        }
+    }
+}
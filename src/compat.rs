@@ -0,0 +1,269 @@
+use crate::MerkleTree;
+use crate::digest_tree::{Bytes32, Digest, Double, OddNode, Sorted, root_of};
+use crate::keccak::{Keccak256, keccak256};
+use crate::sha256::{Rfc6962, Sha256};
+
+/// A change to the input that makes a profile reproduce the expected root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// The root matches as it was given.
+    None,
+    /// The root matches with its bytes reversed, like the roots Bitcoin shows.
+    ReversedRoot,
+    /// The root matches when the bytes of every leaf are reversed.
+    ReversedLeaves,
+    /// The root matches when the bytes of every leaf and of the root are reversed.
+    ReversedLeavesAndRoot,
+}
+
+/// A profile that reproduces the expected root.
+/// - `profile`: How the profile hashes leaves, combines pairs and completes
+///   the odd levels.
+/// - `libraries`: The libraries and options known to build trees this way.
+/// - `transform`: The change that had to be made for the roots to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatMatch {
+    pub profile: String,
+    pub libraries: Vec<&'static str>,
+    pub transform: Transform,
+}
+
+/// A way of calculating a root, that gives None if it can not be used for the leaves
+type RootFn = Box<dyn Fn(&[Vec<u8>]) -> Option<Vec<u8>>>;
+
+/// A known way of building a tree.
+/// - `name`: Describes the hashes and construction of the profile.
+/// - `libraries`: The libraries and options known to build trees this way.
+/// - `root`: Calculates the root of some leaves with the profile.
+struct Profile {
+    name: String,
+    libraries: Vec<&'static str>,
+    root: RootFn,
+}
+
+/// Finds why a root given by another library does not match, by
+/// calculating the root of the leaves with every known profile: the backends
+/// of this crate, with the leaves hashed or taken as they are, with
+/// positional or sorted pairs, and with each way of completing odd levels.
+/// It also tries Bitcoin, RFC 6962, OpenZeppelin's StandardMerkleTree and
+/// the u64 trees of this crate, whose roots are the 8 big endian bytes.
+///
+/// ### Arguments
+///
+/// - `leaves`: The raw bytes of every element, in order.
+/// - `expected_root`: The root the other library gives.
+///
+/// ### Returns
+///
+/// A vector with the profiles that reproduce the root as it was given. If
+/// there are none, it has the near-misses, the profiles that reproduce it
+/// after reversing the bytes of the root or of the leaves. It is empty if no
+/// profile matches or there are no leaves
+pub fn diagnose(leaves: &[Vec<u8>], expected_root: &[u8]) -> Vec<CompatMatch> {
+    if leaves.is_empty() {
+        return Vec::new();
+    }
+    let reversed_leaves: Vec<Vec<u8>> = leaves.iter().map(|leaf| leaf.iter().rev().copied().collect()).collect();
+    let reversed_root: Vec<u8> = expected_root.iter().rev().copied().collect();
+
+    let mut exact = Vec::new();
+    let mut near = Vec::new();
+    for profile in profiles() {
+        let attempts = [
+            (leaves, expected_root, Transform::None),
+            (leaves, &reversed_root[..], Transform::ReversedRoot),
+            (&reversed_leaves[..], expected_root, Transform::ReversedLeaves),
+            (&reversed_leaves[..], &reversed_root[..], Transform::ReversedLeavesAndRoot),
+        ];
+        for (input, root, transform) in attempts {
+            if (profile.root)(input).as_deref() == Some(root) {
+                let found = CompatMatch { profile: profile.name.clone(), libraries: profile.libraries.clone(), transform };
+                if transform == Transform::None {
+                    exact.push(found)
+                } else {
+                    near.push(found)
+                }
+                break;
+            }
+        }
+    }
+    if exact.is_empty() { near } else { exact }
+}
+
+/// Builds every known profile
+fn profiles() -> Vec<Profile> {
+    type DigestRoot = fn(&[Vec<u8>], bool, OddNode) -> Option<Vec<u8>>;
+    let backends: [(&str, DigestRoot, DigestRoot); 2] = [
+        ("sha256", digest_root::<Sha256>, digest_root::<Sorted<Sha256>>),
+        ("keccak256", digest_root::<Keccak256>, digest_root::<Sorted<Keccak256>>),
+    ];
+    let odd_nodes = [(OddNode::RepeatLast, "repeat last leaf"), (OddNode::Duplicate, "duplicate odd"), (OddNode::Promote, "promote odd")];
+
+    let mut profiles = Vec::new();
+    for (hash, positional, sorted) in backends {
+        for (hash_leaves, leaves_name) in [(true, "hashed leaves"), (false, "leaves as given")] {
+            for (root, pairs_name) in [(positional, "positional pairs"), (sorted, "sorted pairs")] {
+                for (odd, odd_name) in odd_nodes {
+                    profiles.push(Profile {
+                        name: format!("{hash}, {leaves_name}, {pairs_name}, {odd_name}"),
+                        libraries: libraries_of(hash, hash_leaves, pairs_name == "sorted pairs", odd),
+                        root: Box::new(move |leaves| root(leaves, hash_leaves, odd)),
+                    });
+                }
+            }
+        }
+    }
+
+    profiles.push(Profile {
+        name: "sha256d, leaves as given, positional pairs, duplicate odd".to_string(),
+        libraries: vec!["Bitcoin"],
+        root: Box::new(|leaves| digest_root::<Double<Sha256>>(leaves, false, OddNode::Duplicate)),
+    });
+    profiles.push(Profile {
+        name: "sha256 with 0x00 and 0x01 prefixes, hashed leaves, positional pairs, promote odd".to_string(),
+        libraries: vec!["RFC 6962", "Certificate Transparency"],
+        root: Box::new(|leaves| digest_root::<Rfc6962>(leaves, true, OddNode::Promote)),
+    });
+    profiles.push(Profile {
+        name: "keccak256 twice, leaves sorted by hash, sorted pairs, heap layout".to_string(),
+        libraries: vec!["OpenZeppelin StandardMerkleTree"],
+        root: Box::new(|leaves| Some(standard_merkle_tree_root(leaves).to_vec())),
+    });
+
+    type U64Root = fn(Vec<Vec<u8>>) -> Option<u64>;
+    let u64_trees: [(&str, U64Root); 4] = [
+        ("MerkleTree::new", |leaves| MerkleTree::new(leaves).root()),
+        ("MerkleTree::new_portable", |leaves| MerkleTree::new_portable(leaves).root()),
+        ("MerkleTree::new_sorted", |leaves| MerkleTree::new_sorted(leaves).root()),
+        ("MerkleTree::new_rfc6962", |leaves| MerkleTree::new_rfc6962(leaves).root()),
+    ];
+    for (name, root) in u64_trees {
+        profiles.push(Profile {
+            name: format!("u64 hashes, big endian root, {name}"),
+            libraries: vec![name],
+            root: Box::new(move |leaves| root(leaves.to_vec()).map(|root| root.to_be_bytes().to_vec())),
+        });
+    }
+    profiles
+}
+
+/// Names the libraries and options known to build trees with a profile of
+/// the matrix. merkletreejs hashes with sha256 unless it is given another
+/// function, takes the leaves as given unless `hashLeaves` is set, and
+/// promotes the odd nodes.
+fn libraries_of(hash: &str, hash_leaves: bool, sorted: bool, odd: OddNode) -> Vec<&'static str> {
+    match (hash, hash_leaves, sorted, odd) {
+        ("sha256", false, false, OddNode::Promote) => vec!["merkletreejs defaults"],
+        ("sha256", true, false, OddNode::Promote) => vec!["merkletreejs with hashLeaves"],
+        ("sha256", true, false, OddNode::RepeatLast) => vec!["DigestTree<Sha256>"],
+        ("keccak256", false, false, OddNode::Promote) => vec!["merkletreejs with keccak256"],
+        ("keccak256", true, false, OddNode::Promote) => vec!["merkletreejs with keccak256 and hashLeaves"],
+        ("keccak256", true, true, OddNode::Promote) => {
+            vec!["merkletreejs with keccak256, hashLeaves and sortPairs", "OpenZeppelin MerkleProof"]
+        }
+        ("keccak256", true, false, OddNode::RepeatLast) => vec!["KeccakTree"],
+        _ => Vec::new(),
+    }
+}
+
+/// Calculates the root of a DigestTree-like profile. Leaves taken as given
+/// must already be 32 byte hashes, otherwise the profile can not be used.
+fn digest_root<D: Digest>(leaves: &[Vec<u8>], hash_leaves: bool, odd: OddNode) -> Option<Vec<u8>> {
+    let leaf_hashes = if hash_leaves {
+        leaves.iter().map(|leaf| D::digest(leaf)).collect()
+    } else {
+        leaves.iter().map(|leaf| Bytes32::try_from(leaf.as_slice()).ok()).collect::<Option<Vec<_>>>()?
+    };
+    root_of::<D>(leaf_hashes, odd).ok().map(|root| root.to_vec())
+}
+
+/// Calculates the root OpenZeppelin's StandardMerkleTree gives: every leaf
+/// is keccak256 applied twice to its data, the leaves are sorted by their
+/// hash, and the tree is an array where the children of the node `i` are
+/// `2i + 1` and `2i + 2`, with the leaves at the end in reverse order.
+fn standard_merkle_tree_root(leaves: &[Vec<u8>]) -> Bytes32 {
+    let mut leaf_hashes: Vec<Bytes32> = leaves.iter().map(|leaf| keccak256(&keccak256(leaf))).collect();
+    leaf_hashes.sort();
+    let len = leaf_hashes.len();
+    let mut tree = vec![[0u8; 32]; 2 * len - 1];
+    for (i, leaf) in leaf_hashes.into_iter().enumerate() {
+        tree[2 * len - 2 - i] = leaf;
+    }
+    for i in (0..len - 1).rev() {
+        tree[i] = Sorted::<Keccak256>::combine(&tree[2 * i + 1], &tree[2 * i + 2]);
+    }
+    tree[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads a hash written as hex
+    fn from_hex(text: &str) -> Vec<u8> {
+        (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Six 32 byte leaves, a quantity with which every way of completing
+    /// odd levels gives a different root
+    fn leaves() -> Vec<Vec<u8>> {
+        ["Crypto", "Merkle", "Rust", "Tree", "Test", "Hash"].iter().map(|leaf| keccak256(leaf.as_bytes()).to_vec()).collect()
+    }
+
+    #[test]
+    /// Test if the root generated with each profile is identified as that
+    /// profile, and only as it.
+    fn identifies_every_profile() {
+        let leaves = leaves();
+        for profile in profiles() {
+            let root = (profile.root)(&leaves).unwrap();
+            let matches = diagnose(&leaves, &root);
+            assert_eq!(matches.len(), 1, "{}: {matches:?}", profile.name);
+            assert_eq!(matches[0].profile, profile.name);
+            assert_eq!(matches[0].transform, Transform::None);
+        }
+    }
+
+    #[test]
+    /// Test if known roots of other libraries are identified with the
+    /// libraries that give them
+    fn identifies_known_roots() {
+        let abcd: Vec<Vec<u8>> = ["a", "b", "c", "d"].iter().map(|leaf| leaf.as_bytes().to_vec()).collect();
+        let merkletreejs = from_hex("68203f90e9d07dc5859259d7536e87a6ba9d345f2552b5b9de2999ddce9ce1bf");
+        let matches = diagnose(&abcd, &merkletreejs);
+        assert!(matches.iter().any(|found| found.libraries.contains(&"merkletreejs with keccak256 and hashLeaves")));
+        assert!(matches.iter().all(|found| found.transform == Transform::None));
+
+        let rfc6962: Vec<Vec<u8>> = vec![vec![], vec![0x00], vec![0x10]];
+        let root = from_hex("aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77");
+        assert_eq!(diagnose(&rfc6962, &root)[0].libraries, vec!["RFC 6962", "Certificate Transparency"]);
+    }
+
+    #[test]
+    /// Test if the txids and root of block 100000, in the reversed order
+    /// Bitcoin shows them, are a near-miss of the Bitcoin profile
+    fn bitcoin_display_order_is_a_near_miss() {
+        let txids: Vec<Vec<u8>> = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ]
+        .iter()
+        .map(|txid| from_hex(txid))
+        .collect();
+        let root = from_hex("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
+
+        let matches = diagnose(&txids, &root);
+        assert_eq!(matches.len(), 1, "{matches:?}");
+        assert_eq!(matches[0].libraries, vec!["Bitcoin"]);
+        assert_eq!(matches[0].transform, Transform::ReversedLeavesAndRoot);
+    }
+
+    #[test]
+    /// Test if a root no profile gives and a tree without leaves have no matches
+    fn unknown_roots_have_no_matches() {
+        assert!(diagnose(&leaves(), &[0u8; 32]).is_empty());
+        assert!(diagnose(&[], &[0u8; 32]).is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use std::marker::PhantomData;
+
+use crate::error::MerkleError;
+use crate::merkle::extend_elements;
+
+/// A 32 byte hash, the `bytes32` type of Solidity.
+pub type Bytes32 = [u8; 32];
+
+/// A cryptographic hash function with a 32 byte output, used by a DigestTree.
+pub trait Digest {
+    /// How a DigestTree completes the levels with an odd quantity of
+    /// nodes. By default the base level is padded like in `MerkleTree`.
+    const ODD_NODE: OddNode = OddNode::RepeatLast;
+
+    /// Hashes the raw bytes of an element into a leaf
+    fn digest(data: &[u8]) -> Bytes32;
+
+    /// Hashes two nodes into their parent. By default it is the digest
+    /// of the 64 bytes of the children, left first.
+    fn combine(left: &Bytes32, right: &Bytes32) -> Bytes32 {
+        let mut concatenated = [0u8; 64];
+        concatenated[..32].copy_from_slice(left);
+        concatenated[32..].copy_from_slice(right);
+        Self::digest(&concatenated)
+    }
+}
+
+/// Applies a Digest twice at every step, like Bitcoin's SHA256d: a leaf is
+/// the digest of the digest of its bytes, and a parent is the digest of the
+/// digest of its two children.
+pub struct Double<D>(PhantomData<D>);
+
+impl<D: Digest> Digest for Double<D> {
+    const ODD_NODE: OddNode = D::ODD_NODE;
+
+    fn digest(data: &[u8]) -> Bytes32 {
+        D::digest(&D::digest(data))
+    }
+}
+
+/// Sorts the two children of every node before combining them, like
+/// OpenZeppelin's MerkleProof, so a proof can be checked with the leaf
+/// alone, without its index. Leaves are hashed as with the inner Digest.
+pub struct Sorted<D>(PhantomData<D>);
+
+impl<D: Digest> Digest for Sorted<D> {
+    const ODD_NODE: OddNode = D::ODD_NODE;
+
+    fn digest(data: &[u8]) -> Bytes32 {
+        D::digest(data)
+    }
+
+    fn combine(left: &Bytes32, right: &Bytes32) -> Bytes32 {
+        if left <= right { D::combine(left, right) } else { D::combine(right, left) }
+    }
+}
+
+/// A Merkle Tree over 32 byte hashes computed with a cryptographic hash
+/// function, instead of the u64 hashes of `MerkleTree`.
+///
+/// Each leaf is the digest of the raw bytes of an element and each parent is
+/// `D::combine` of its children. The levels with an odd quantity of nodes
+/// are completed as `D::ODD_NODE` says: by default the base level is padded
+/// just like in `MerkleTree`, by repeating the last elements.
+/// - `levels`: Every level of the tree, from the base level to the root.
+/// - `leaf_count`: Quantity of different elements in the base level.
+pub struct DigestTree<D> {
+    levels: Vec<Vec<Bytes32>>,
+    leaf_count: usize,
+    digest: PhantomData<D>,
+}
+
+impl<D: Digest> DigestTree<D> {
+    /// Creates a new DigestTree
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaves`: The raw bytes of every element of the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the DigestTree instance, or an Error if there are no leaves
+    pub fn new<B: AsRef<[u8]>>(leaves: &[B]) -> Result<Self, MerkleError> {
+        let base_level = leaves.iter().map(|leaf| D::digest(leaf.as_ref())).collect();
+        Self::from_leaf_hashes(base_level)
+    }
+
+    /// Creates a new DigestTree from leaves that were already hashed.
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaf_hashes`: The digest of every element of the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the DigestTree instance, or an Error if there are no
+    /// leaves. It is UnsupportedPadding if the Digest promotes odd nodes,
+    /// since the proofs of the tree have a hash for every level.
+    pub fn from_leaf_hashes(leaf_hashes: Vec<Bytes32>) -> Result<Self, MerkleError> {
+        if leaf_hashes.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        if D::ODD_NODE == OddNode::Promote {
+            return Err(MerkleError::UnsupportedPadding);
+        }
+        let leaf_count = leaf_hashes.len();
+        let levels = build_levels::<D>(leaf_hashes, D::ODD_NODE);
+        Ok(Self { levels, leaf_count, digest: PhantomData })
+    }
+
+    /// Gets the root of the tree
+    pub fn root(&self) -> Bytes32 {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Gets the leaf of a different element in the base level
+    pub fn leaf(&self, index: usize) -> Option<Bytes32> {
+        if index >= self.leaf_count {
+            return None;
+        }
+        Some(self.levels[0][index])
+    }
+
+    /// Gets the quantity of different elements in the tree
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Checks if the tree has no elements. It is always false, since an
+    /// empty DigestTree can not be created.
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Generates the proof for a specific element. The first hash is the
+    /// sibling of the leaf and the last one is the sibling of a child of the root.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the original input array
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the hashes of the proof, or an Error if the index is invalid
+    pub fn generate_proof(&self, mut index: usize) -> Result<Vec<Bytes32>, MerkleError> {
+        if index >= self.leaf_count {
+            return Err(MerkleError::InvalidIndex { index, len: self.leaf_count });
+        }
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            // The last node of an odd level is duplicated, so it is its own sibling
+            proof.push(*level.get(index ^ 1).unwrap_or(&level[index]));
+            index /= 2;
+        }
+        Ok(proof)
+    }
+
+    /// Checks if the root of the tree can be obtained with a proof, a leaf
+    /// and its index on the input array.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The hashes that make up the proof to get to the root.
+    /// - `index`: The index in the input array of the received leaf.
+    /// - `leaf`: The digest of one of the elements on the input array.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &[Bytes32], index: usize, leaf: Bytes32) -> bool {
+        index < self.leaf_count && verify_proof::<D>(self.root(), proof, index, leaf)
+    }
+}
+
+/// How a level with an odd quantity of nodes is completed before the next
+/// one is hashed. It is where the trees of most libraries differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OddNode {
+    /// The base level is padded to a power of 2 by repeating its last
+    /// element, like `MerkleTree` and `DigestTree` by default do.
+    RepeatLast,
+    /// The last node of every odd level is combined with itself, like Bitcoin.
+    Duplicate,
+    /// The last node of every odd level goes up unchanged, like RFC 6962
+    /// and merkletreejs.
+    Promote,
+}
+
+/// Builds every level of a tree, from the base level to the root.
+///
+/// ### Arguments
+///
+/// - `leaf_hashes`: The digest of every element of the tree, at least one.
+/// - `odd`: How levels with an odd quantity of nodes are completed.
+///
+/// ### Returns
+///
+/// A vector with every level of the tree
+fn build_levels<D: Digest>(mut leaf_hashes: Vec<Bytes32>, odd: OddNode) -> Vec<Vec<Bytes32>> {
+    if odd == OddNode::RepeatLast {
+        extend_elements(&mut leaf_hashes);
+    }
+    let mut levels = vec![leaf_hashes];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => D::combine(left, right),
+                [last] if odd == OddNode::Promote => *last,
+                _ => D::combine(&pair[0], &pair[0]),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Calculates the root of the leaves of a tree without keeping its levels,
+/// completing the odd levels in any of the ways other libraries do.
+///
+/// ### Arguments
+///
+/// - `leaf_hashes`: The digest of every element of the tree.
+/// - `odd`: How levels with an odd quantity of nodes are completed.
+///
+/// ### Returns
+///
+/// A Result with the root, or an Error if there are no leaves
+pub fn root_of<D: Digest>(leaf_hashes: Vec<Bytes32>, odd: OddNode) -> Result<Bytes32, MerkleError> {
+    if leaf_hashes.is_empty() {
+        return Err(MerkleError::EmptyTree);
+    }
+    let levels = build_levels::<D>(leaf_hashes, odd);
+    Ok(levels[levels.len() - 1][0])
+}
+
+/// Checks a proof against a root without having the tree: the bits of the
+/// index tell on which side each hash of the proof goes.
+///
+/// ### Arguments
+///
+/// - `root`: The trusted root.
+/// - `proof`: The hashes that make up the proof to get to the root.
+/// - `index`: The index in the input array of the received leaf.
+/// - `leaf`: The digest of one of the elements on the input array.
+///
+/// ### Returns
+///
+/// A bool that is true if the proof leads to the root, false otherwise
+pub fn verify_proof<D: Digest>(root: Bytes32, proof: &[Bytes32], mut index: usize, leaf: Bytes32) -> bool {
+    let mut hash = leaf;
+    for sibling in proof {
+        hash = if index.is_multiple_of(2) { D::combine(&hash, sibling) } else { D::combine(sibling, &hash) };
+        index /= 2;
+    }
+    hash == root
+}
+
+/// Writes a hash as lowercase hex, the way it is usually shown
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A digest that is not cryptographic at all, so the tree can be checked by hand
+    struct Xor;
+
+    impl Digest for Xor {
+        fn digest(data: &[u8]) -> Bytes32 {
+            let mut output = [0u8; 32];
+            for (i, byte) in data.iter().enumerate() {
+                output[i % 32] ^= byte.rotate_left((i / 32) as u32);
+            }
+            output[31] = output[31].wrapping_add(1);
+            output
+        }
+    }
+
+    #[test]
+    /// Test if every proof verifies, with and without the tree, and if a
+    /// proof does not verify for another leaf or index.
+    fn proofs_verify() {
+        let leaves = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = DigestTree::<Xor>::new(&leaves).unwrap();
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            let leaf = Xor::digest(leaf.as_bytes());
+            assert_eq!(merkle.leaf(index), Some(leaf));
+            assert_eq!(proof.len(), 3);
+            assert!(merkle.verify(&proof, index, leaf));
+            assert!(verify_proof::<Xor>(merkle.root(), &proof, index, leaf));
+            assert!(!merkle.verify(&proof, index ^ 1, leaf));
+        }
+        assert_eq!(merkle.leaf(5), None);
+        assert_eq!(merkle.generate_proof(5), Err(MerkleError::InvalidIndex { index: 5, len: 5 }));
+        assert!(DigestTree::<Xor>::new::<&str>(&[]).is_err());
+    }
+
+    #[test]
+    /// Test if Double applies the digest twice both to leaves and to parents
+    fn double_digest_hashes_twice() {
+        let merkle = DigestTree::<Double<Xor>>::new(&["Crypto", "Merkle"]).unwrap();
+        let left = Xor::digest(&Xor::digest(b"Crypto"));
+        let right = Xor::digest(&Xor::digest(b"Merkle"));
+
+        assert_eq!(merkle.leaf(0), Some(left));
+        assert_eq!(merkle.root(), Xor::digest(&Xor::combine(&left, &right)));
+    }
+
+    #[test]
+    /// Test if Sorted combines the smaller child first, so every proof
+    /// verifies with any index
+    fn sorted_proofs_do_not_need_the_index() {
+        let leaves = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = DigestTree::<Sorted<Xor>>::new(&leaves).unwrap();
+        let (left, right) = (Xor::digest(b"Crypto"), Xor::digest(b"Merkle"));
+        assert_eq!(Sorted::<Xor>::combine(&left, &right), Sorted::<Xor>::combine(&right, &left));
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(verify_proof::<Sorted<Xor>>(merkle.root(), &proof, 0, Xor::digest(leaf.as_bytes())));
+        }
+    }
+
+    #[test]
+    /// Test if each way of completing odd levels gives the root it should,
+    /// and if repeating the last elements gives the root of a DigestTree.
+    fn odd_levels_are_completed_as_asked() {
+        let leaves: Vec<Bytes32> = ["Crypto", "Merkle", "Rust"].iter().map(|leaf| Xor::digest(leaf.as_bytes())).collect();
+        let left = Xor::combine(&leaves[0], &leaves[1]);
+
+        let promoted = root_of::<Xor>(leaves.clone(), OddNode::Promote).unwrap();
+        assert_eq!(promoted, Xor::combine(&left, &leaves[2]));
+        let duplicated = root_of::<Xor>(leaves.clone(), OddNode::Duplicate).unwrap();
+        assert_eq!(duplicated, Xor::combine(&left, &Xor::combine(&leaves[2], &leaves[2])));
+        let repeated = root_of::<Xor>(leaves.clone(), OddNode::RepeatLast).unwrap();
+        assert_eq!(repeated, DigestTree::<Xor>::from_leaf_hashes(leaves).unwrap().root());
+        assert_eq!(root_of::<Xor>(Vec::new(), OddNode::Promote), Err(MerkleError::EmptyTree));
+    }
+}
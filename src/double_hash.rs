@@ -0,0 +1,78 @@
+use std::hash::{BuildHasher, Hasher};
+
+/// A BuildHasher that hashes everything twice: the u64 given by the inner
+/// hasher is hashed again with a new inner hasher. Used as the hasher of a
+/// MerkleTree, both the elements and the parents are hashed twice, like
+/// Bitcoin does with SHA-256.
+///
+/// ```
+/// use tree::double_hash::DoubleHash;
+/// use tree::{DefaultBuildHasher, MerkleTree};
+///
+/// let merkle = MerkleTree::with_hasher(vec!["Crypto", "Merkle"], DoubleHash(DefaultBuildHasher::default()));
+/// assert_ne!(merkle.root(), MerkleTree::new(vec!["Crypto", "Merkle"]).root());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DoubleHash<B>(pub B);
+
+/// The Hasher built by DoubleHash.
+/// - `first`: Receives the bytes being hashed.
+/// - `second`: A new hasher that receives the output of `first`.
+pub struct DoubleHasher<H> {
+    first: H,
+    second: H,
+}
+
+impl<B: BuildHasher> BuildHasher for DoubleHash<B>
+where
+    B::Hasher: Clone,
+{
+    type Hasher = DoubleHasher<B::Hasher>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        DoubleHasher { first: self.0.build_hasher(), second: self.0.build_hasher() }
+    }
+}
+
+impl<H: Hasher + Clone> Hasher for DoubleHasher<H> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.first.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        // finish can not change the hasher, so the second pass uses a copy
+        let mut second = self.second.clone();
+        second.write_u64(self.first.finish());
+        second.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{hash_with, DefaultBuildHasher};
+    use crate::MerkleTree;
+
+    #[test]
+    /// Test if the output is the hash of the hash given by the inner hasher
+    fn hashes_twice() {
+        let inner = DefaultBuildHasher::default();
+        let once = inner.hash_one("Crypto");
+
+        assert_eq!(DoubleHash(inner.clone()).hash_one("Crypto"), inner.hash_one(once));
+    }
+
+    #[test]
+    /// Test if a tree that hashes twice verifies its proofs, also after adding elements
+    fn tree_verifies_double_hashed_proofs() {
+        let hasher = DoubleHash(DefaultBuildHasher::default());
+        let mut merkle = MerkleTree::with_hasher(vec!["Crypto", "Merkle", "Rust"], hasher.clone());
+        merkle.add_element("Tree");
+        merkle.add_element("Test");
+
+        for (index, element) in ["Crypto", "Merkle", "Rust", "Tree", "Test"].iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify(&proof, hash_with(&hasher, element)));
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::error::MerkleError;
+
+/// Reads little-endian values from a slice of bytes, returning an Error
+/// instead of panicking when the input ends early.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Takes the next `len` bytes
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], MerkleError> {
+        if self.remaining() < len {
+            return Err(MerkleError::InvalidEncoding(format!("Expected {len} more bytes at byte {}", self.pos)));
+        }
+        let taken = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(taken)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, MerkleError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, MerkleError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("took 4 bytes")))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, MerkleError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("took 8 bytes")))
+    }
+
+    /// Reads an u64 that is a len or an index in memory
+    pub(crate) fn usize(&mut self) -> Result<usize, MerkleError> {
+        let value = self.u64()?;
+        usize::try_from(value).map_err(|_| MerkleError::InvalidEncoding(format!("{value} does not fit in memory")))
+    }
+
+    /// Checks that the input still has room for `count` items of `item_size`
+    /// bytes, so a declared count can be trusted before allocating for it.
+    pub(crate) fn ensure_items(&self, count: usize, item_size: usize) -> Result<(), MerkleError> {
+        match count.checked_mul(item_size) {
+            Some(needed) if needed <= self.remaining() => Ok(()),
+            _ => Err(MerkleError::InvalidEncoding(format!("{count} items do not fit in the remaining bytes"))),
+        }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    /// Checks that every byte was read
+    pub(crate) fn finish(self) -> Result<(), MerkleError> {
+        if self.remaining() != 0 {
+            return Err(MerkleError::InvalidEncoding(format!("{} unexpected trailing bytes", self.remaining())));
+        }
+        Ok(())
+    }
+}
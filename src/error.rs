@@ -0,0 +1,248 @@
+use std::fmt;
+use std::io;
+
+use crate::limits::Limit;
+
+/// Errors returned by the fallible operations of the Merkle Tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    /// The index is outside of the elements of the tree.
+    /// - `index`: The index that was received.
+    /// - `len`: Quantity of elements in the tree.
+    InvalidIndex { index: usize, len: usize },
+    /// The index is a slot of the base level that only holds a copy of the
+    /// last element, added as padding. See `MerkleTree::generate_proof_padded`.
+    /// - `index`: The index that was received.
+    PaddedIndex { index: usize },
+    /// The tree would not have any element.
+    EmptyTree,
+    /// The requested element or tree does not exist.
+    NotFound,
+    /// The buffer received to write a proof into can not hold all of it.
+    /// - `needed`: The quantity of hashes in the proof.
+    /// - `got`: The len of the buffer.
+    BufferTooSmall { needed: usize, got: usize },
+    /// Some encoded bytes or text can not be decoded. The message explains
+    /// what was wrong with them.
+    InvalidEncoding(String),
+    /// A string is not a valid hex encoded hash.
+    InvalidHex(String),
+    /// An input declared more of something than the configured `Limits` allow.
+    /// - `limit`: The limit that was exceeded.
+    /// - `max`: The maximum allowed by that limit.
+    /// - `got`: The value that was received.
+    LimitExceeded { limit: Limit, max: usize, got: usize },
+    /// More distinct elements were requested than the tree has.
+    SelectionTooLarge { k: usize, len: usize },
+    /// Adding up the amounts of a sum tree overflowed an u64.
+    SumOverflow,
+    /// The slot at `index` already holds its final hash and can not be filled again.
+    SlotAlreadyFilled { index: usize },
+    /// A rebuild task was applied to a tree before it finished hashing
+    /// every level. Keep calling `RebuildTask::run_for` until it reports
+    /// `RebuildProgress::Complete`.
+    RebuildInProgress,
+    /// The base level of the tree changed after the rebuild task was started,
+    /// so applying the task would discard those changes.
+    StaleRebuild,
+    /// The operation only supports trees that repeat their last elements
+    /// as padding, not the ones that promote odd nodes.
+    UnsupportedPadding,
+    /// The leaves of the tree are not sorted, so it can not prove that an
+    /// element is absent. See `MerkleTree::new_sorted_leaves`.
+    UnsortedLeaves,
+    /// The element is in the tree at `index`, so its absence can not be proven.
+    ElementPresent { index: usize },
+    /// The levels of a saved tree do not match its base level, so some of
+    /// its hashes were changed after it was saved.
+    CorruptFile,
+    /// A file could not be read or written.
+    Io(io::ErrorKind),
+    /// Some options given to a `MerkleTreeBuilder` can not be used
+    /// together. The message explains which ones and why.
+    IncompatibleOptions(String),
+    /// An SSZ list has more elements than the limit of its type.
+    ListTooLong { len: usize, limit: usize },
+    /// The tree of an SSZ value has no node at the generalized index.
+    InvalidGindex { gindex: u64 },
+    /// The hash function of the tree can not be computed by a Solidity
+    /// contract. The message says which one it is.
+    UnsupportedOnChain(String),
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::InvalidIndex { index, len } => {
+                write!(f, "Invalid index {index} for a tree with {len} elements")
+            }
+            MerkleError::PaddedIndex { index } => write!(f, "The index {index} is padding, not an element of the tree"),
+            MerkleError::EmptyTree => write!(f, "The tree has no elements"),
+            MerkleError::NotFound => write!(f, "The element was not found"),
+            MerkleError::BufferTooSmall { needed, got } => {
+                write!(f, "The proof needs a buffer of {needed} hashes but got {got}")
+            }
+            MerkleError::InvalidEncoding(message) => write!(f, "Invalid encoding: {message}"),
+            MerkleError::InvalidHex(input) => write!(f, "'{input}' is not a valid hex encoded hash"),
+            MerkleError::LimitExceeded { limit, max, got } => {
+                write!(f, "The limit of {limit} is {max} but got {got}")
+            }
+            MerkleError::SelectionTooLarge { k, len } => {
+                write!(f, "Can not select {k} distinct elements out of {len}")
+            }
+            MerkleError::SumOverflow => write!(f, "The sum of the amounts overflowed"),
+            MerkleError::SlotAlreadyFilled { index } => write!(f, "The slot {index} was already filled"),
+            MerkleError::RebuildInProgress => write!(f, "The rebuild has not finished yet"),
+            MerkleError::StaleRebuild => write!(f, "The tree changed after the rebuild was started"),
+            MerkleError::UnsupportedPadding => write!(f, "The operation does not support the padding of the tree"),
+            MerkleError::UnsortedLeaves => write!(f, "The leaves of the tree are not sorted"),
+            MerkleError::ElementPresent { index } => write!(f, "The element is in the tree at index {index}"),
+            MerkleError::CorruptFile => write!(f, "The hashes of the saved tree do not match"),
+            MerkleError::Io(kind) => write!(f, "Could not access the file: {kind}"),
+            MerkleError::IncompatibleOptions(message) => write!(f, "Incompatible options: {message}"),
+            MerkleError::ListTooLong { len, limit } => write!(f, "The list has {len} elements but its limit is {limit}"),
+            MerkleError::InvalidGindex { gindex } => write!(f, "There is no node at the generalized index {gindex}"),
+            MerkleError::UnsupportedOnChain(message) => write!(f, "Can not be verified on-chain: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for MerkleError {}
+
+/// The reasons why a proof can fail to verify, see `MerkleTree::verify_detailed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The index of the proof is outside of the elements of the tree.
+    /// - `index`: The index of the proof.
+    /// - `len`: Quantity of elements in the tree.
+    InvalidIndex { index: usize, len: usize },
+    /// The proof does not have one hash for each level where the path of
+    /// its leaf has a sibling.
+    /// - `expected`: The quantity of hashes a proof for that index has.
+    /// - `got`: The quantity of hashes in the proof.
+    WrongProofLength { expected: usize, got: usize },
+    /// A hash of the proof is not on the side the index of the proof gives
+    /// it on its level, so the proof is not one of the leaf at that index.
+    /// - `index`: The index of the proof.
+    WrongSide { index: usize },
+    /// Combining the leaf with the proof does not give the root of the tree.
+    /// - `computed`: The hash obtained from the leaf and the proof.
+    /// - `expected`: The root of the tree.
+    RootMismatch { computed: u64, expected: u64 },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::InvalidIndex { index, len } => {
+                write!(f, "Invalid index {index} for a tree with {len} elements")
+            }
+            VerifyError::WrongProofLength { expected, got } => {
+                write!(f, "The proof should have {expected} hashes but has {got}")
+            }
+            VerifyError::WrongSide { index } => {
+                write!(f, "The sides of the proof are not the ones of index {index}")
+            }
+            VerifyError::RootMismatch { computed, expected } => {
+                write!(f, "The proof leads to {computed:016x} instead of the root {expected:016x}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// The reasons why a PolicyVerifier rejects a receipt, see `PolicyVerifier::verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The hasher of the tree is not one the policy allows.
+    AlgorithmNotAllowed { algorithm: String },
+    /// The policy requires the root to commit to the tree size, and the
+    /// receipt does not have it.
+    TreeSizeRequired,
+    /// The proof has more hashes than the policy allows.
+    /// - `max_depth`: The maximum quantity of hashes.
+    /// - `depth`: The quantity of hashes in the proof.
+    ProofTooDeep { max_depth: usize, depth: usize },
+    /// The root does not commit to the domain tag the policy requires.
+    /// - `expected`: The tag of the policy.
+    /// - `got`: The tag of the receipt, if it has one.
+    DomainTagMismatch { expected: String, got: Option<String> },
+    /// The proof is of a slot of the padding, which the policy does not allow.
+    /// - `index`: The index of the proof.
+    /// - `tree_size`: The quantity of different elements of the tree.
+    PaddedSlot { index: usize, tree_size: usize },
+    /// The receipt follows the policy, but its hasher is not a known one.
+    UnknownAlgorithm { algorithm: String },
+    /// The receipt follows the policy, but its proof does not lead to its root.
+    InvalidProof,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyViolation::AlgorithmNotAllowed { algorithm } => write!(f, "The algorithm '{algorithm}' is not allowed"),
+            PolicyViolation::TreeSizeRequired => write!(f, "The root must commit to the tree size"),
+            PolicyViolation::ProofTooDeep { max_depth, depth } => {
+                write!(f, "The proof has {depth} hashes but at most {max_depth} are allowed")
+            }
+            PolicyViolation::DomainTagMismatch { expected, got: Some(got) } => {
+                write!(f, "The domain tag must be '{expected}' but is '{got}'")
+            }
+            PolicyViolation::DomainTagMismatch { expected, got: None } => {
+                write!(f, "The domain tag must be '{expected}' but there is none")
+            }
+            PolicyViolation::PaddedSlot { index, tree_size } => {
+                write!(f, "The index {index} is padding of a tree with {tree_size} elements")
+            }
+            PolicyViolation::UnknownAlgorithm { algorithm } => write!(f, "Unknown algorithm '{algorithm}'"),
+            PolicyViolation::InvalidProof => write!(f, "The proof does not lead to the root"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+/// The first inconsistency found in the levels of a tree, see `MerkleTree::validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The base level does not hold one hash for each different element.
+    /// The padding is never stored, so both quantities must be equal.
+    /// - `diff_elements`: Quantity of different elements of the tree.
+    /// - `base_len`: Quantity of hashes in the base level.
+    ElementCount { diff_elements: usize, base_len: usize },
+    /// A level does not have half the nodes of the level below it,
+    /// rounding up, or it is above a level with a single node.
+    /// - `level`: The level with the wrong len.
+    /// - `expected`: The len it should have.
+    /// - `got`: The len it has.
+    LevelLength { level: usize, expected: usize, got: usize },
+    /// The last level does not have exactly one node, the root.
+    /// - `len`: Quantity of nodes in the last level.
+    RootLevel { len: usize },
+    /// A node is not the hash of its children.
+    /// - `level`: The level of the node.
+    /// - `index`: The index of the node in its level.
+    /// - `expected`: The hash re-calculated from the base level.
+    /// - `got`: The hash stored in the tree.
+    NodeMismatch { level: usize, index: usize, expected: u64, got: u64 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::ElementCount { diff_elements, base_len } => {
+                write!(f, "The tree has {diff_elements} elements but its base level has {base_len} hashes")
+            }
+            ValidationError::LevelLength { level, expected, got } => {
+                write!(f, "The level {level} should have {expected} nodes but has {got}")
+            }
+            ValidationError::RootLevel { len } => write!(f, "The last level should only have the root but has {len} nodes"),
+            ValidationError::NodeMismatch { level, index, expected, got } => {
+                write!(f, "The node {index} of level {level} is {got:016x} instead of {expected:016x}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
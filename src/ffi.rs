@@ -0,0 +1,312 @@
+//! C functions to build trees and check their proofs from other languages.
+//!
+//! A tree is an opaque pointer created by `merkle_new` and released by
+//! `merkle_free`. Every other function returns one of the `MERKLE_*` codes
+//! and writes its results through the pointers it receives. A panic never
+//! crosses the boundary, it is returned as `MERKLE_PANIC` instead.
+//!
+//! The signatures, as declared in `include/merkle.h`:
+//!
+//! ```c
+//! typedef struct MerkleTree MerkleTree;
+//!
+//! int32_t merkle_new(const uint8_t *const *buffers, const size_t *lens, size_t count, MerkleTree **out);
+//! int32_t merkle_root(const MerkleTree *tree, uint64_t *out);
+//! int32_t merkle_proof(const MerkleTree *tree, size_t index, uint64_t *hashes, size_t capacity, size_t *len);
+//! int32_t merkle_verify(uint64_t root, size_t index, const uint64_t *hashes, size_t len,
+//!                       const uint8_t *leaf, size_t leaf_len, bool *valid);
+//! void merkle_free(MerkleTree *tree);
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use crate::error::MerkleError;
+use crate::merkle::{fold_proof, hash_element, MerkleTree};
+
+/// The function did what was asked.
+pub const MERKLE_OK: i32 = 0;
+/// A pointer that has to point to something was null.
+pub const MERKLE_NULL_POINTER: i32 = 1;
+/// The tree has no elements, so it has no root.
+pub const MERKLE_EMPTY_TREE: i32 = 2;
+/// The index is not one of a different element of the tree.
+pub const MERKLE_INVALID_INDEX: i32 = 3;
+/// The buffer can not hold the whole proof. The needed len was written.
+pub const MERKLE_BUFFER_TOO_SMALL: i32 = 4;
+/// The function panicked, which is a bug of this crate.
+pub const MERKLE_PANIC: i32 = 5;
+
+/// Runs the body of a function, turning a panic into `MERKLE_PANIC`
+fn guard<F: FnOnce() -> i32>(body: F) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(MERKLE_PANIC)
+}
+
+/// Gets a slice from a pointer and a len, allowing a null pointer for an empty slice
+///
+/// # Safety
+///
+/// If `len` is not 0, `data` has to point to `len` valid values of T.
+unsafe fn slice_or_empty<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if len == 0 {
+        return Some(&[]);
+    }
+    if data.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees `data` points to `len` values
+    Some(unsafe { slice::from_raw_parts(data, len) })
+}
+
+/// Creates a tree with a leaf for each byte buffer.
+///
+/// ### Arguments
+///
+/// - `buffers`: The pointers to the bytes of each element.
+/// - `lens`: The len of each buffer.
+/// - `count`: Quantity of buffers, and of lens.
+/// - `out`: Where the pointer to the new tree is written. It has to be released with `merkle_free`.
+///
+/// ### Returns
+///
+/// `MERKLE_OK`, or `MERKLE_NULL_POINTER` if `out` or a needed pointer is null
+///
+/// # Safety
+///
+/// `buffers` and `lens` have to point to `count` values each, and every
+/// buffer to as many bytes as its len. `out` has to be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn merkle_new(buffers: *const *const u8, lens: *const usize, count: usize, out: *mut *mut MerkleTree) -> i32 {
+    guard(|| {
+        if out.is_null() {
+            return MERKLE_NULL_POINTER;
+        }
+        // SAFETY: the caller guarantees both arrays have `count` values
+        let (Some(buffers), Some(lens)) = (unsafe { slice_or_empty(buffers, count) }, unsafe { slice_or_empty(lens, count) }) else {
+            return MERKLE_NULL_POINTER;
+        };
+        let mut elements = Vec::with_capacity(count);
+        for (buffer, len) in buffers.iter().zip(lens) {
+            // SAFETY: the caller guarantees each buffer has its len
+            match unsafe { slice_or_empty(*buffer, *len) } {
+                Some(element) => elements.push(element),
+                None => return MERKLE_NULL_POINTER,
+            }
+        }
+        let tree = Box::new(MerkleTree::new(elements));
+        // SAFETY: `out` is not null and the caller guarantees it is valid for writes
+        unsafe { *out = Box::into_raw(tree) };
+        MERKLE_OK
+    })
+}
+
+/// Gets the root of a tree.
+///
+/// ### Returns
+///
+/// `MERKLE_OK`, `MERKLE_NULL_POINTER` or `MERKLE_EMPTY_TREE`
+///
+/// # Safety
+///
+/// `tree` has to be a pointer given by `merkle_new` that was not freed,
+/// and `out` has to be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn merkle_root(tree: *const MerkleTree, out: *mut u64) -> i32 {
+    guard(|| {
+        if out.is_null() {
+            return MERKLE_NULL_POINTER;
+        }
+        // SAFETY: the caller guarantees the tree is alive
+        let Some(tree) = (unsafe { tree.as_ref() }) else {
+            return MERKLE_NULL_POINTER;
+        };
+        match tree.root() {
+            Some(root) => {
+                // SAFETY: `out` is not null and the caller guarantees it is valid for writes
+                unsafe { *out = root };
+                MERKLE_OK
+            }
+            None => MERKLE_EMPTY_TREE,
+        }
+    })
+}
+
+/// Writes the proof of an element into a buffer of the caller, see
+/// `MerkleTree::generate_proof_into`.
+///
+/// ### Arguments
+///
+/// - `tree`: The tree the element belongs to.
+/// - `index`: The index of the element.
+/// - `hashes`: The buffer the hashes of the proof are written into.
+/// - `capacity`: The quantity of hashes the buffer can hold.
+/// - `len`: Where the quantity of hashes of the proof is written, also when the buffer is too small.
+///
+/// ### Returns
+///
+/// `MERKLE_OK`, `MERKLE_NULL_POINTER`, `MERKLE_INVALID_INDEX` or `MERKLE_BUFFER_TOO_SMALL`
+///
+/// # Safety
+///
+/// `tree` has to be a pointer given by `merkle_new` that was not freed,
+/// `hashes` has to be valid for `capacity` writes and `len` for one.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn merkle_proof(tree: *const MerkleTree, index: usize, hashes: *mut u64, capacity: usize, len: *mut usize) -> i32 {
+    guard(|| {
+        if len.is_null() || (hashes.is_null() && capacity > 0) {
+            return MERKLE_NULL_POINTER;
+        }
+        // SAFETY: the caller guarantees the tree is alive
+        let Some(tree) = (unsafe { tree.as_ref() }) else {
+            return MERKLE_NULL_POINTER;
+        };
+        let buffer: &mut [u64] = if capacity == 0 {
+            &mut []
+        } else {
+            // SAFETY: the caller guarantees `hashes` can hold `capacity` hashes
+            unsafe { slice::from_raw_parts_mut(hashes, capacity) }
+        };
+        let (code, written) = match tree.generate_proof_into(index, buffer) {
+            Ok(written) => (MERKLE_OK, written),
+            Err(MerkleError::BufferTooSmall { needed, .. }) => (MERKLE_BUFFER_TOO_SMALL, needed),
+            Err(_) => return MERKLE_INVALID_INDEX,
+        };
+        // SAFETY: `len` is not null and the caller guarantees it is valid for writes
+        unsafe { *len = written };
+        code
+    })
+}
+
+/// Checks the proof of an element against the root of a tree built with
+/// `merkle_new`, without the tree.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree.
+/// - `index`: The index of the element.
+/// - `hashes`: The hashes of the proof, as `merkle_proof` wrote them.
+/// - `len`: The quantity of hashes of the proof.
+/// - `leaf`: The bytes of the element.
+/// - `leaf_len`: The quantity of bytes of the element.
+/// - `valid`: Where the result is written, true if the proof leads to the root.
+///
+/// ### Returns
+///
+/// `MERKLE_OK` or `MERKLE_NULL_POINTER`
+///
+/// # Safety
+///
+/// `hashes` has to point to `len` hashes, `leaf` to `leaf_len` bytes, and
+/// `valid` has to be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn merkle_verify(
+    root: u64,
+    index: usize,
+    hashes: *const u64,
+    len: usize,
+    leaf: *const u8,
+    leaf_len: usize,
+    valid: *mut bool,
+) -> i32 {
+    guard(|| {
+        if valid.is_null() {
+            return MERKLE_NULL_POINTER;
+        }
+        // SAFETY: the caller guarantees the lens of both buffers
+        let (Some(hashes), Some(leaf)) = (unsafe { slice_or_empty(hashes, len) }, unsafe { slice_or_empty(leaf, leaf_len) }) else {
+            return MERKLE_NULL_POINTER;
+        };
+        // SAFETY: `valid` is not null and the caller guarantees it is valid for writes
+        unsafe { *valid = fold_proof(hashes, index, hash_element(leaf)) == root };
+        MERKLE_OK
+    })
+}
+
+/// Releases a tree created by `merkle_new`. A null pointer is ignored.
+///
+/// # Safety
+///
+/// `tree` has to be null or a pointer given by `merkle_new` that was not
+/// freed yet. It can not be used after this call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn merkle_free(tree: *mut MerkleTree) {
+    if !tree.is_null() {
+        // SAFETY: the caller guarantees the pointer came from `merkle_new`
+        drop(unsafe { Box::from_raw(tree) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    /// Builds a tree through the C functions, the way a foreign caller would
+    fn new_tree(elements: &[&[u8]]) -> *mut MerkleTree {
+        let buffers: Vec<*const u8> = elements.iter().map(|element| element.as_ptr()).collect();
+        let lens: Vec<usize> = elements.iter().map(|element| element.len()).collect();
+        let mut tree = ptr::null_mut();
+        assert_eq!(unsafe { merkle_new(buffers.as_ptr(), lens.as_ptr(), elements.len(), &mut tree) }, MERKLE_OK);
+        tree
+    }
+
+    #[test]
+    /// Test if the proofs written by merkle_proof verify with merkle_verify
+    /// only for their own element, and the root is the one of the library.
+    fn prove_and_verify_through_ffi() {
+        let elements: [&[u8]; 5] = [b"Crypto", b"Merkle", b"Rust", b"Tree", b"Test"];
+        let tree = new_tree(&elements);
+        let mut root = 0;
+        assert_eq!(unsafe { merkle_root(tree, &mut root) }, MERKLE_OK);
+        assert_eq!(Some(root), MerkleTree::new(elements.to_vec()).root());
+
+        for (index, element) in elements.iter().enumerate() {
+            let mut hashes = [0u64; 8];
+            let mut len = 0;
+            assert_eq!(unsafe { merkle_proof(tree, index, hashes.as_mut_ptr(), hashes.len(), &mut len) }, MERKLE_OK);
+            assert_eq!(len, 3);
+
+            let mut valid = false;
+            let code = unsafe { merkle_verify(root, index, hashes.as_ptr(), len, element.as_ptr(), element.len(), &mut valid) };
+            assert_eq!(code, MERKLE_OK);
+            assert!(valid);
+            let other = b"Other";
+            unsafe { merkle_verify(root, index, hashes.as_ptr(), len, other.as_ptr(), other.len(), &mut valid) };
+            assert!(!valid);
+        }
+        unsafe { merkle_free(tree) };
+    }
+
+    #[test]
+    /// Test if wrong arguments are reported with their codes instead of
+    /// panicking or writing out of the buffers.
+    fn errors_are_codes() {
+        let tree = new_tree(&[b"Crypto", b"Merkle", b"Rust"]);
+        let mut hashes = [0u64; 1];
+        let mut len = 0;
+
+        assert_eq!(unsafe { merkle_proof(tree, 0, hashes.as_mut_ptr(), hashes.len(), &mut len) }, MERKLE_BUFFER_TOO_SMALL);
+        assert_eq!(len, 2);
+        assert_eq!(unsafe { merkle_proof(tree, 3, hashes.as_mut_ptr(), hashes.len(), &mut len) }, MERKLE_INVALID_INDEX);
+        assert_eq!(unsafe { merkle_proof(tree, 0, ptr::null_mut(), 4, &mut len) }, MERKLE_NULL_POINTER);
+        assert_eq!(unsafe { merkle_root(ptr::null(), &mut 0) }, MERKLE_NULL_POINTER);
+        assert_eq!(unsafe { merkle_new(ptr::null(), ptr::null(), 2, &mut ptr::null_mut()) }, MERKLE_NULL_POINTER);
+        unsafe { merkle_free(tree) };
+        unsafe { merkle_free(ptr::null_mut()) };
+
+        let empty = new_tree(&[]);
+        assert_eq!(unsafe { merkle_root(empty, &mut 0) }, MERKLE_EMPTY_TREE);
+        unsafe { merkle_free(empty) };
+    }
+
+    #[test]
+    /// Test if a panic inside a function is returned as a code
+    fn panics_do_not_cross_the_boundary() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let code = guard(|| panic!("Unexpected"));
+        panic::set_hook(hook);
+
+        assert_eq!(code, MERKLE_PANIC);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use crate::error::MerkleError;
+use crate::merkle::{fold_proof, hash_element};
+use crate::{MerkleTree, RootHash};
+
+/// The proof that a leaf is part of a tree of the forest, and that the
+/// tree is part of the forest.
+/// - `tree_index`: The position of the tree among the trees sorted by name.
+/// - `tree_root`: The root of the tree the leaf is in.
+/// - `leaf_proof`: The proof that goes from the leaf to the root of its tree.
+/// - `tree_proof`: The proof that goes from the tree to the super-root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForestProof {
+    pub tree_index: usize,
+    pub tree_root: u64,
+    pub leaf_proof: Vec<u64>,
+    pub tree_proof: Vec<u64>,
+}
+
+/// A set of named trees committed together under a single super-root.
+///
+/// The super-root is the root of a Merkle Tree whose leaves are the
+/// `(name, root)` pairs of the trees, sorted by name. It is computed when
+/// it is asked for, so changes made through `tree_mut` are always reflected.
+#[derive(Default)]
+pub struct Forest {
+    trees: BTreeMap<String, MerkleTree>,
+}
+
+impl Forest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tree with a name, replacing the tree that had that name
+    ///
+    /// ### Returns
+    ///
+    /// The tree that was replaced, if there was one
+    pub fn insert_tree(&mut self, name: impl Into<String>, tree: MerkleTree) -> Option<MerkleTree> {
+        self.trees.insert(name.into(), tree)
+    }
+
+    /// Removes the tree with that name, returning it
+    pub fn remove_tree(&mut self, name: &str) -> Option<MerkleTree> {
+        self.trees.remove(name)
+    }
+
+    /// Gets the tree with that name
+    pub fn tree(&self, name: &str) -> Option<&MerkleTree> {
+        self.trees.get(name)
+    }
+
+    /// Gets the tree with that name so it can be changed. The super-root
+    /// will include the changes the next time it is computed.
+    pub fn tree_mut(&mut self, name: &str) -> Option<&mut MerkleTree> {
+        self.trees.get_mut(name)
+    }
+
+    /// Gets the super-root of the forest
+    ///
+    /// ### Returns
+    ///
+    /// The RootHash over every tree, or None if the forest has no trees
+    pub fn super_root(&self) -> Option<RootHash> {
+        self.super_tree()?.root_hash()
+    }
+
+    /// Generates the proof that a leaf is in a tree of the forest
+    ///
+    /// ### Arguments
+    ///
+    /// - `name`: The name of the tree the leaf is in.
+    /// - `leaf_index`: The index of the leaf in the input array of that tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the ForestProof, or an Error if there is no tree with
+    /// that name or the index is invalid in it
+    pub fn prove(&self, name: &str, leaf_index: usize) -> Result<ForestProof, MerkleError> {
+        let tree = self.trees.get(name).ok_or(MerkleError::NotFound)?;
+        let leaf_proof = tree.generate_proof(leaf_index)?.hashes();
+        let tree_root = tree.root_hash().ok_or(MerkleError::EmptyTree)?.into_raw();
+
+        let tree_index = self.trees.keys().position(|key| key == name).ok_or(MerkleError::NotFound)?;
+        let super_tree = self.super_tree().ok_or(MerkleError::EmptyTree)?;
+        let tree_proof = super_tree.generate_proof(tree_index)?.hashes();
+        Ok(ForestProof { tree_index, tree_root, leaf_proof, tree_proof })
+    }
+
+    /// Builds the tree over the `(name, root)` pairs of every tree
+    fn super_tree(&self) -> Option<MerkleTree> {
+        if self.trees.is_empty() {
+            return None;
+        }
+        let leaves = self.trees.iter().map(|(name, tree)| tree_leaf(name, tree.root_hash())).collect();
+        Some(MerkleTree::new(leaves))
+    }
+}
+
+/// The leaf that commits a tree in the super tree
+fn tree_leaf(name: &str, root: Option<RootHash>) -> (String, Option<u64>) {
+    (name.to_string(), root.map(RootHash::into_raw))
+}
+
+/// Checks if a leaf is in a tree of the forest with the given super-root.
+///
+/// ### Arguments
+///
+/// - `super_root`: The super-root of the forest.
+/// - `name`: The name of the tree the leaf is in.
+/// - `leaf_index`: The index of the leaf in the input array of that tree.
+/// - `leaf`: The hash of the leaf.
+/// - `proof`: The proof generated by `Forest::prove`.
+///
+/// ### Returns
+///
+/// A bool that is true if the super-root can be obtained with that information, false otherwise
+pub fn verify_nested(super_root: RootHash, name: &str, leaf_index: usize, leaf: u64, proof: &ForestProof) -> bool {
+    let tree_root = fold_proof(&proof.leaf_proof, leaf_index, leaf);
+    if tree_root != proof.tree_root {
+        return false;
+    }
+    let tree_leaf = hash_element(tree_leaf(name, Some(RootHash::from_raw(tree_root))));
+    fold_proof(&proof.tree_proof, proof.tree_index, tree_leaf) == super_root.into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forest() -> Forest {
+        let mut forest = Forest::new();
+        forest.insert_tree("carol", MerkleTree::new(vec!["Rust", "Tree"]));
+        forest.insert_tree("alice", MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]));
+        forest.insert_tree("bob", MerkleTree::new(vec!["Test"]));
+        forest
+    }
+
+    #[test]
+    /// Test if changing the tree of one tenant changes the super-root
+    fn mutating_a_tree_changes_super_root() {
+        let mut forest = forest();
+        let before = forest.super_root().unwrap();
+        forest.tree_mut("bob").unwrap().add_element("Another test");
+
+        assert_ne!(forest.super_root().unwrap(), before);
+    }
+
+    #[test]
+    /// Test if a nested proof verifies with only the super-root, and fails
+    /// under another name or with another leaf.
+    fn verifies_nested_proof() {
+        let forest = forest();
+        let super_root = forest.super_root().unwrap();
+        let leaf = hash_element("Merkle");
+        let proof = forest.prove("alice", 1).unwrap();
+
+        assert!(verify_nested(super_root, "alice", 1, leaf, &proof));
+        assert!(!verify_nested(super_root, "carol", 1, leaf, &proof));
+        assert!(!verify_nested(super_root, "alice", 1, hash_element("Rust"), &proof));
+    }
+
+    #[test]
+    /// Test if removing a tenant changes the super-root to the one of a
+    /// forest that never had it, no matter the insertion order.
+    fn removing_a_tree_is_deterministic() {
+        let mut forest = forest();
+        let with_bob = forest.super_root().unwrap();
+        forest.remove_tree("bob").unwrap();
+
+        let mut expected = Forest::new();
+        expected.insert_tree("alice", MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]));
+        expected.insert_tree("carol", MerkleTree::new(vec!["Rust", "Tree"]));
+
+        assert_ne!(forest.super_root().unwrap(), with_bob);
+        assert_eq!(forest.super_root(), expected.super_root());
+    }
+
+    #[test]
+    /// Test if proving in a missing tree or an invalid index fails
+    fn prove_errors() {
+        let forest = forest();
+
+        assert_eq!(forest.prove("dave", 0), Err(MerkleError::NotFound));
+        assert_eq!(forest.prove("alice", 3), Err(MerkleError::PaddedIndex { index: 3 }));
+        assert_eq!(forest.prove("alice", 4), Err(MerkleError::InvalidIndex { index: 4, len: 3 }));
+        assert_eq!(Forest::new().super_root(), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::hash::{BuildHasher, Hash};
+
+use crate::merkle::{combine_with, hash_with, DefaultBuildHasher};
+use crate::verify::{PairOrdering, Proof, Side};
+
+/// Abstraction of a Merkle Tree that only keeps what it needs to append
+/// leaves, get the root and prove the most recent leaf: the frontier and
+/// the quantity of leaves. It takes O(log n) memory no matter how many
+/// leaves were appended.
+///
+/// The frontier is the path of the last leaf. For every level where that
+/// path is a right child, it holds the left sibling, which is a complete
+/// subtree that no append will change. Where the path is a left child the
+/// sibling only holds padding, so it is computed from the last leaf.
+///
+/// The padding repeats the last leaf like `MerkleTree::new`, so both trees
+/// have the same root and the same proof of the last leaf.
+/// - `frontier`: The left sibling of the path of the last leaf at each level. Levels where the path is a left child hold stale hashes.
+/// - `last_leaf`: The hash of the most recent leaf.
+/// - `leaf_count`: Quantity of leaves appended.
+/// - `hasher`: Builds the hasher used for the parents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncrementalMerkleTree<H = DefaultBuildHasher> {
+    frontier: Vec<u64>,
+    last_leaf: u64,
+    leaf_count: usize,
+    hasher: H,
+}
+
+impl IncrementalMerkleTree {
+    /// Creates an IncrementalMerkleTree without leaves
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H: BuildHasher> IncrementalMerkleTree<H> {
+    /// Creates an IncrementalMerkleTree without leaves that hashes the
+    /// parents with a custom hasher, like `MerkleTree::with_hasher`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hasher`: Builds the hasher used for the parents. The leaves must be hashed with it too.
+    pub fn with_hasher(hasher: H) -> Self {
+        Self { frontier: Vec::new(), last_leaf: 0, leaf_count: 0, hasher }
+    }
+
+    /// Appends a leaf to the tree
+    ///
+    /// The previous leaf closes the subtrees that end with it, so their
+    /// roots are hashed up to the first level where the new leaf is a right
+    /// child, and that root becomes the frontier of the level. This takes
+    /// O(log n) hashes.
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaf`: The hash of the new leaf.
+    pub fn append(&mut self, leaf: u64) {
+        let index = self.leaf_count;
+        if index > 0 {
+            let closed_level = index.trailing_zeros() as usize;
+            if self.frontier.len() <= closed_level {
+                self.frontier.resize(closed_level + 1, 0);
+            }
+            let mut node = self.last_leaf;
+            for level in 0..closed_level {
+                node = self.combine(self.frontier[level], node);
+            }
+            self.frontier[closed_level] = node;
+        }
+        self.last_leaf = leaf;
+        self.leaf_count += 1;
+    }
+
+    /// Hashes an element the way a MerkleTree with the same hasher hashes
+    /// its leaves, and appends it to the tree
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The new element.
+    pub fn append_element<T: Hash>(&mut self, element: T) {
+        self.append(hash_with(&self.hasher, element));
+    }
+
+    /// Gets the quantity of leaves appended
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Checks if no leaf was appended
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Gets the root of the tree, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        let last_proof = self.prove_last()?;
+        Some(self.fold_path(&last_proof))
+    }
+
+    /// Generates the proof of the most recent leaf, which is the only one
+    /// the frontier can prove
+    ///
+    /// ### Returns
+    ///
+    /// The Proof, with the same hashes and sides as the one of
+    /// `MerkleTree::generate_proof`, or None if the tree is empty
+    pub fn prove_last(&self) -> Option<Proof> {
+        let last_index = self.leaf_count.checked_sub(1)?;
+        let height = self.leaf_count.next_power_of_two().ilog2() as usize;
+        let mut padding = self.last_leaf;
+        let mut siblings = Vec::with_capacity(height);
+        for level in 0..height {
+            if (last_index >> level).is_multiple_of(2) {
+                siblings.push((padding, Side::Right));
+            } else {
+                siblings.push((self.frontier[level], Side::Left));
+            }
+            padding = self.combine(padding, padding);
+        }
+        Some(Proof::new(last_index, siblings))
+    }
+
+    /// Hashes the last leaf with the siblings of its proof
+    fn fold_path(&self, proof: &Proof) -> u64 {
+        proof.siblings.iter().fold(self.last_leaf, |node, (sibling, side)| match side {
+            Side::Left => self.combine(*sibling, node),
+            Side::Right => self.combine(node, *sibling),
+        })
+    }
+
+    /// Hashes the concatenation of two nodes, like the parents of a MerkleTree
+    fn combine(&self, left: u64, right: u64) -> u64 {
+        combine_with(&self.hasher, PairOrdering::Positional, left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+    use crate::MerkleTree;
+
+    /// A xorshift generator, so the tests go over many sequences without a crate
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    /// Test if after every append of random sequences the root and the
+    /// proof of the last leaf are the ones of a MerkleTree with the same
+    /// elements.
+    fn agrees_with_merkle_tree() {
+        let mut state = 0x9e3779b97f4a7c15;
+        for _ in 0..20 {
+            let len = (next_random(&mut state) % 150) as usize + 1;
+            let elements: Vec<u64> = (0..len).map(|_| next_random(&mut state)).collect();
+            let mut incremental = IncrementalMerkleTree::new();
+            for count in 1..=len {
+                incremental.append(hash_element(elements[count - 1]));
+                let merkle = MerkleTree::new(elements[..count].to_vec());
+
+                assert_eq!(incremental.root(), merkle.root(), "len {count}");
+                let proof = incremental.prove_last().unwrap();
+                assert_eq!(proof, merkle.generate_proof(count - 1).unwrap());
+                assert!(merkle.verify(&proof, hash_element(elements[count - 1])));
+            }
+        }
+    }
+
+    #[test]
+    /// Test if the frontier keeps a single hash per level
+    fn frontier_is_logarithmic() {
+        let mut incremental = IncrementalMerkleTree::new();
+        assert_eq!((incremental.root(), incremental.prove_last()), (None, None));
+        for element in 0..100_000usize {
+            incremental.append(hash_element(element));
+        }
+        assert_eq!(incremental.len(), 100_000);
+        assert_eq!(incremental.frontier.len(), 100_000usize.next_power_of_two().ilog2() as usize);
+    }
+}
@@ -0,0 +1,153 @@
+use std::hash::{BuildHasher, Hash};
+
+use crate::merkle::DefaultBuildHasher;
+use crate::{IncrementalMerkleTree, MerkleTree, RootHash};
+
+/// Builds Merkle Trees directly from iterators, without naming the tree type.
+///
+/// ```
+/// use tree::{MerkleRootExt, MerkleTree, PortableHash};
+///
+/// let text = "Crypto\nMerkle\nRust";
+/// let root = text.lines().merkle_root();
+///
+/// assert_eq!(root, MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]).root_hash());
+///
+/// let portable = text.lines().merkle_root_with(PortableHash::default());
+/// assert_eq!(portable, MerkleTree::new_portable(vec!["Crypto", "Merkle", "Rust"]).root_hash());
+/// ```
+///
+/// The hasher is a type parameter of the `_with` methods, which the
+/// methods without the suffix fix to `DefaultBuildHasher`, since the type
+/// parameters of a method can not have a default. There is no counterpart
+/// for parallel iterators: it needs the rayon crate, which can not be
+/// fetched where this crate is built. `MerkleTree::new_par` hashes the
+/// elements of a vector on several threads instead.
+pub trait MerkleRootExt: Iterator + Sized
+where
+    Self::Item: Hash + Clone,
+{
+    /// Gets the root of the tree that `MerkleTree::new` would build from the
+    /// elements of the iterator, without building the tree.
+    ///
+    /// ### Returns
+    ///
+    /// The RootHash of the elements, or None if the iterator was empty
+    fn merkle_root(self) -> Option<RootHash> {
+        self.merkle_root_with(DefaultBuildHasher::default())
+    }
+
+    /// Gets the root of the tree that `MerkleTree::with_hasher` would build
+    /// from the elements of the iterator, without building the tree.
+    ///
+    /// Each element is appended to an IncrementalMerkleTree as soon as it
+    /// is yielded, so only its frontier is kept in memory: O(log n) hashes
+    /// no matter how many elements there are.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hasher`: Builds the hasher used for the elements and the parents.
+    ///
+    /// ### Returns
+    ///
+    /// The RootHash of the elements, or None if the iterator was empty
+    fn merkle_root_with<H: BuildHasher>(self, hasher: H) -> Option<RootHash> {
+        let mut frontier = IncrementalMerkleTree::with_hasher(hasher);
+        for element in self {
+            frontier.append_element(element);
+        }
+        frontier.root().map(RootHash::from_raw)
+    }
+
+    /// Builds the whole tree from the elements of the iterator.
+    ///
+    /// ```
+    /// use tree::MerkleRootExt;
+    ///
+    /// let merkle = (0..5).map(|i| i * 10).merkle_tree();
+    /// let proof = merkle.generate_proof(2).unwrap();
+    ///
+    /// assert_eq!(proof.len(), 3);
+    /// ```
+    ///
+    /// ### Returns
+    ///
+    /// The same MerkleTree that `MerkleTree::new` returns for the collected elements
+    fn merkle_tree(self) -> MerkleTree {
+        MerkleTree::new(self.collect())
+    }
+
+    /// Builds the whole tree from the elements of the iterator with a custom hasher.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hasher`: Builds the hasher used for the elements and the parents.
+    ///
+    /// ### Returns
+    ///
+    /// The same MerkleTree that `MerkleTree::with_hasher` returns for the collected elements
+    fn merkle_tree_with<H: BuildHasher>(self, hasher: H) -> MerkleTree<H> {
+        MerkleTree::with_hasher(self.collect(), hasher)
+    }
+}
+
+impl<I> MerkleRootExt for I
+where
+    I: Iterator,
+    I::Item: Hash + Clone,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortableHash;
+
+    #[test]
+    /// Test if the root of the iterator is the root of MerkleTree::new for
+    /// lens that are and are not a power of 2.
+    fn merkle_root_matches_new() {
+        for len in [1, 2, 3, 4, 5, 7, 8, 9, 100] {
+            let data: Vec<String> = (0..len).map(|i| format!("Element {i}")).collect();
+            let expected = MerkleTree::new(data.clone()).root_hash();
+
+            assert_eq!(data.iter().merkle_root(), expected);
+            assert_eq!(data.into_iter().merkle_root(), expected);
+        }
+    }
+
+    #[test]
+    /// Test if the tree of the iterator has the same root and proofs as MerkleTree::new
+    fn merkle_tree_matches_new() {
+        for len in [1, 3, 4, 6] {
+            let data: Vec<&[u8]> = (0..len).map(|i| &b"Crypto Merkle Rust"[i..]).collect();
+            let expected = MerkleTree::new(data.clone());
+            let merkle = data.into_iter().merkle_tree();
+
+            assert_eq!(merkle.root_hash(), expected.root_hash());
+            for index in 0..len {
+                assert_eq!(merkle.generate_proof(index), expected.generate_proof(index));
+            }
+        }
+    }
+
+    #[test]
+    /// Test if the root and the tree with a custom hasher are the ones of
+    /// MerkleTree::with_hasher, for lens that are and are not a power of 2.
+    fn with_hasher_matches_with_hasher() {
+        for len in [1, 2, 3, 5, 8, 13, 100] {
+            let data: Vec<String> = (0..len).map(|i| format!("Element {i}")).collect();
+            let expected = MerkleTree::new_portable(data.clone());
+
+            assert_eq!(data.iter().merkle_root_with(PortableHash), expected.root_hash());
+            assert_eq!(data.into_iter().merkle_tree_with(PortableHash), expected);
+        }
+        assert_eq!(std::iter::empty::<u8>().merkle_root_with(PortableHash), None);
+    }
+
+    #[test]
+    /// Test if an empty iterator has no root
+    fn empty_iterator_has_no_root() {
+        assert_eq!(std::iter::empty::<u8>().merkle_root(), None);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::digest_tree::{Bytes32, Digest, DigestTree};
+
+/// Bytes absorbed by each permutation when the output has 256 bits.
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+/// Rotation of each lane visited by the rho and pi steps, in visiting order.
+const ROTATIONS: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+
+/// Lanes visited by the rho and pi steps, starting after lane 1.
+const PI_LANES: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+/// Hashes some bytes with Keccak-256, the hash Ethereum and Solidity's
+/// `keccak256` use. It is not SHA3-256, which pads the input differently.
+///
+/// ### Arguments
+///
+/// - `data`: The bytes to hash.
+///
+/// ### Returns
+///
+/// The 32 bytes of the hash
+pub fn keccak256(data: &[u8]) -> Bytes32 {
+    sponge(data, 0x01)
+}
+
+/// Absorbs the data into the Keccak sponge and squeezes 32 bytes out of it.
+///
+/// ### Arguments
+///
+/// - `data`: The bytes to hash.
+/// - `domain`: The first padding byte. 0x01 for Keccak and 0x06 for SHA3.
+fn sponge(data: &[u8], domain: u8) -> Bytes32 {
+    let mut state = [0u64; 25];
+    let mut chunks = data.chunks_exact(RATE);
+    for block in &mut chunks {
+        absorb(&mut state, block);
+    }
+    let remainder = chunks.remainder();
+    let mut last = [0u8; RATE];
+    last[..remainder.len()].copy_from_slice(remainder);
+    last[remainder.len()] ^= domain;
+    last[RATE - 1] ^= 0x80;
+    absorb(&mut state, &last);
+
+    let mut output = [0u8; 32];
+    for (bytes, lane) in output.chunks_exact_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    output
+}
+
+/// XORs a block of `RATE` bytes into the state and permutes it
+fn absorb(state: &mut [u64; 25], block: &[u8]) {
+    for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+        *lane ^= u64::from_le_bytes(bytes.try_into().expect("chunks of 8 bytes"));
+    }
+    keccak_f(state);
+}
+
+/// The Keccak-f[1600] permutation. Lane (x, y) is at index x + 5 * y.
+fn keccak_f(state: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS {
+        // Theta
+        let mut columns = [0u64; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+        // Rho and pi
+        let mut carried = state[1];
+        for (lane, rotation) in PI_LANES.iter().zip(ROTATIONS) {
+            let next = state[*lane];
+            state[*lane] = carried.rotate_left(rotation);
+            carried = next;
+        }
+        // Chi
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&state[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        // Iota
+        state[0] ^= round_constant;
+    }
+}
+
+/// The Keccak-256 backend of a DigestTree
+pub struct Keccak256;
+
+impl Digest for Keccak256 {
+    fn digest(data: &[u8]) -> Bytes32 {
+        keccak256(data)
+    }
+}
+
+/// A Merkle Tree whose proofs can be checked by a Solidity contract.
+///
+/// Each leaf is keccak256 of the raw bytes of an element and each parent is
+/// keccak256 of the 64 bytes of its children, left first. Proofs are the
+/// `bytes32[]` a Solidity verifier receives, and the bits of the index tell
+/// on which side each hash goes. When the quantity of elements is a power
+/// of 2 the root is the same one merkletreejs gives with `hashLeaves` and
+/// keccak256.
+pub type KeccakTree = DigestTree<Keccak256>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest_tree::{to_hex as hex, verify_proof};
+
+    #[test]
+    /// Test Keccak-256 against published vectors, and the permutation
+    /// against SHA3-256, which only changes the padding.
+    fn keccak_matches_known_vectors() {
+        assert_eq!(hex(&keccak256(b"")), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(hex(&keccak256(b"abc")), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+        assert_eq!(hex(&sponge(b"abc", 0x06)), "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532");
+        // Longer than one block, so more than one permutation is absorbed
+        assert_eq!(hex(&sponge(&[b'x'; 200], 0x06)), "9dcc53f665ce704a221fcd3e25e53e88b5a41e8ab3deeeab8a5f2602e0316896");
+    }
+
+    #[test]
+    /// Test the root against the one merkletreejs gives for the leaves
+    /// a, b, c and d with `hashLeaves` and keccak256.
+    fn root_matches_merkletreejs_fixture() {
+        let merkle = KeccakTree::new(&["a", "b", "c", "d"]).unwrap();
+
+        assert_eq!(hex(&merkle.leaf(0).unwrap()), "3ac225168df54212a25c1c01fd35bebfea408fdac2e31ddd6f80a4bbf9a5f1cb");
+        assert_eq!(hex(&merkle.root()), "68203f90e9d07dc5859259d7536e87a6ba9d345f2552b5b9de2999ddce9ce1bf");
+    }
+
+    #[test]
+    /// Test if every proof verifies, with and without the tree, and if a
+    /// proof does not verify for another leaf or index.
+    fn proofs_verify() {
+        let leaves = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = KeccakTree::new(&leaves).unwrap();
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            let leaf = keccak256(leaf.as_bytes());
+            assert_eq!(proof.len(), 3);
+            assert!(merkle.verify(&proof, index, leaf));
+            assert!(verify_proof::<Keccak256>(merkle.root(), &proof, index, leaf));
+            assert!(!merkle.verify(&proof, index ^ 1, leaf));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher};
+
+use crate::merkle::{hash_with, verify_against_root_with, MerkleTree, PairOrdering, Proof};
+
+/// Byte hashed before the key by the inner hasher, like the inner pad of HMAC
+const INNER_PAD: u8 = 0x36;
+/// Byte hashed before the key by the outer hasher, like the outer pad of HMAC
+const OUTER_PAD: u8 = 0x5c;
+
+/// A BuildHasher that mixes a secret key in every hash, like HMAC does:
+/// the bytes are hashed after the key, and that hash is hashed again after
+/// the key with another pad. Used as the hasher of a MerkleTree, both the
+/// elements and the parents depend on the key, so the root can not be
+/// recomputed without it even knowing every element.
+///
+/// ```
+/// use tree::MerkleTree;
+///
+/// let merkle = MerkleTree::new_keyed(b"secret", vec!["Crypto", "Merkle"]);
+/// assert_ne!(merkle.root(), MerkleTree::new(vec!["Crypto", "Merkle"]).root());
+/// ```
+/// - `inner`: A hasher that already received the inner pad and the key.
+/// - `outer`: A hasher that already received the outer pad and the key.
+#[derive(Debug, Clone)]
+pub struct KeyedHash {
+    inner: DefaultHasher,
+    outer: DefaultHasher,
+}
+
+/// The Hasher built by KeyedHash.
+/// - `inner`: Receives the bytes being hashed.
+/// - `outer`: Receives the output of `inner`.
+pub struct KeyedHasher {
+    inner: DefaultHasher,
+    outer: DefaultHasher,
+}
+
+impl KeyedHash {
+    /// Creates a KeyedHash for a key. The key is hashed with its len, so
+    /// keys of any len can be used.
+    pub fn new(key: &[u8]) -> Self {
+        let mut inner = DefaultHasher::new();
+        (INNER_PAD, key).hash(&mut inner);
+        let mut outer = DefaultHasher::new();
+        (OUTER_PAD, key).hash(&mut outer);
+        Self { inner, outer }
+    }
+}
+
+impl BuildHasher for KeyedHash {
+    type Hasher = KeyedHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        KeyedHasher { inner: self.inner.clone(), outer: self.outer.clone() }
+    }
+}
+
+impl Hasher for KeyedHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        // finish can not change the hasher, so the outer pass uses a copy
+        let mut outer = self.outer.clone();
+        outer.write_u64(self.inner.finish());
+        outer.finish()
+    }
+}
+
+impl MerkleTree<KeyedHash> {
+    /// Creates a new MerkleTree that mixes a secret key in the hash of
+    /// every element and every parent, see `KeyedHash`. The proofs have
+    /// the same shape as the ones of `new`, but they only verify with
+    /// leaves hashed with the same key.
+    ///
+    /// ### Arguments
+    ///
+    /// - `key`: The secret key of the tree.
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn new_keyed<T: Hash + Clone>(key: &[u8], elements: Vec<T>) -> Self {
+        Self::with_hasher(elements, KeyedHash::new(key))
+    }
+}
+
+/// Hashes an element with a key, the way a tree created with
+/// `MerkleTree::new_keyed` hashes its leaves
+pub fn keyed_leaf<T: Hash>(key: &[u8], element: T) -> u64 {
+    hash_with(&KeyedHash::new(key), element)
+}
+
+/// Checks if a root can be obtained with the use of a proof and a leaf,
+/// without having the tree, for a tree created with `MerkleTree::new_keyed`
+///
+/// ### Arguments
+///
+/// - `key`: The secret key of the tree.
+/// - `root`: The root of the tree the element should belong to.
+/// - `len`: The quantity of different elements of the tree.
+/// - `proof`: The Proof of the element, as `generate_proof` returns it.
+/// - `leaf`: The hash of the element, as `keyed_leaf` returns it.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_keyed_against_root(key: &[u8], root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    verify_against_root_with(&KeyedHash::new(key), PairOrdering::Positional, root, len, proof, leaf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Test if the same elements under two keys give different roots, and
+    /// neither of them is the root of the tree without a key.
+    fn different_keys_give_different_roots() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let first = MerkleTree::new_keyed(b"first key", data.clone());
+        let second = MerkleTree::new_keyed(b"second key", data.clone());
+
+        assert_ne!(first.root(), second.root());
+        assert_ne!(first.root(), MerkleTree::new(data.clone()).root());
+        assert_eq!(first.root(), MerkleTree::new_keyed(b"first key", data).root());
+        // The len of the key is hashed too
+        assert_ne!(KeyedHash::new(b"ab").hash_one("c"), KeyedHash::new(b"a").hash_one("bc"));
+    }
+
+    #[test]
+    /// Test if a proof only verifies with leaves hashed with the key of its
+    /// tree, also after adding elements.
+    fn proof_verifies_only_with_matching_key() {
+        let mut merkle = MerkleTree::new_keyed(b"secret", vec!["Crypto", "Merkle", "Rust"]);
+        merkle.add_element("Tree");
+        let root = merkle.root().unwrap();
+
+        for (index, element) in ["Crypto", "Merkle", "Rust", "Tree"].iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify(&proof, keyed_leaf(b"secret", element)));
+            assert!(verify_keyed_against_root(b"secret", root, 4, &proof, keyed_leaf(b"secret", element)));
+
+            assert!(!merkle.verify(&proof, keyed_leaf(b"guess", element)));
+            assert!(!verify_keyed_against_root(b"guess", root, 4, &proof, keyed_leaf(b"secret", element)));
+            assert!(!verify_keyed_against_root(b"guess", root, 4, &proof, keyed_leaf(b"guess", element)));
+        }
+    }
+}
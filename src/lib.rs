@@ -0,0 +1,109 @@
+//! Merkle Trees over hashable elements.
+//!
+//! Everything is available with the default `std` feature. Without it the
+//! crate is `no_std` and only needs `alloc`, keeping the `verify` module so
+//! proofs can still be checked, with a hasher that does not need `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "blake3")]
+pub mod blake3;
+#[cfg(all(feature = "sha256", feature = "keccak"))]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod digest_tree;
+#[cfg(feature = "std")]
+pub mod double_hash;
+#[cfg(feature = "std")]
+mod encoding;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod forest;
+#[cfg(feature = "std")]
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod iter_ext;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "keccak")]
+pub mod keccak;
+#[cfg(feature = "std")]
+pub mod keyed;
+#[cfg(feature = "std")]
+pub mod light_tree;
+#[cfg(feature = "std")]
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod merkle;
+#[cfg(feature = "std")]
+pub mod mmr;
+#[cfg(feature = "std")]
+pub mod nary;
+#[cfg(feature = "std")]
+pub mod policy;
+#[cfg(feature = "std")]
+pub mod portable;
+#[cfg(feature = "std")]
+pub mod root_hash;
+#[cfg(feature = "std")]
+pub mod salted;
+#[cfg(feature = "std")]
+pub mod selection;
+#[cfg(feature = "sha256")]
+pub mod sha256;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod solidity;
+#[cfg(feature = "std")]
+pub mod sparse;
+#[cfg(feature = "sha256")]
+pub mod ssz;
+#[cfg(feature = "async-stream")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod sum_tree;
+pub mod verify;
+#[cfg(feature = "std")]
+pub mod vectors;
+#[cfg(feature = "std")]
+pub mod with_data;
+
+#[cfg(feature = "std")]
+pub use error::{MerkleError, PolicyViolation, ValidationError, VerifyError};
+#[cfg(feature = "std")]
+pub use forest::Forest;
+#[cfg(feature = "std")]
+pub use incremental::IncrementalMerkleTree;
+#[cfg(feature = "std")]
+pub use iter_ext::MerkleRootExt;
+#[cfg(feature = "std")]
+pub use light_tree::LightMerkleTree;
+#[cfg(feature = "std")]
+pub use limits::Limits;
+#[cfg(feature = "std")]
+pub use merkle::{DefaultBuildHasher, MerkleTree, MerkleTreeBuilder, PaddingStrategy};
+#[cfg(feature = "std")]
+pub use mmr::Mmr;
+#[cfg(feature = "std")]
+pub use nary::NaryMerkleTree;
+#[cfg(feature = "std")]
+pub use portable::PortableHash;
+#[cfg(feature = "std")]
+pub use root_hash::RootHash;
+#[cfg(feature = "std")]
+pub use salted::SaltedMerkleTree;
+#[cfg(feature = "std")]
+pub use shared::SharedMerkleTree;
+#[cfg(feature = "std")]
+pub use sparse::SparseMerkleTree;
+pub use verify::{PairOrdering, Proof, Side};
+#[cfg(feature = "std")]
+pub use with_data::MerkleTreeWithData;
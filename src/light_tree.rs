@@ -0,0 +1,264 @@
+use std::hash::Hash;
+
+use crate::error::MerkleError;
+use crate::merkle::{combine_with, hash_element, verify_against_root, DefaultBuildHasher, PairOrdering, Proof, Side};
+
+/// A Merkle Tree that only stores the hashes of its different elements.
+/// The root and the proofs are computed from them each time they are
+/// needed, at the cost of an O(n) pass. A `MerkleTree` also stores every
+/// node above the elements, close to one more hash for each of them, so
+/// this tree takes about half its memory.
+///
+/// It hashes and pads its elements exactly like `MerkleTree::new`, so both
+/// trees have the same root and give the same proofs for the same elements.
+/// The padding is never stored: the nodes that only cover copies of the
+/// last element are computed from it.
+/// - `leaves`: The hashes of the different elements.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LightMerkleTree {
+    leaves: Vec<u64>,
+}
+
+impl LightMerkleTree {
+    /// Creates a new LightMerkleTree
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the base level.
+    ///
+    /// ### Returns
+    ///
+    /// A LightMerkleTree instance
+    pub fn new<T: Hash>(elements: Vec<T>) -> Self {
+        Self { leaves: elements.iter().map(hash_element).collect() }
+    }
+
+    /// Gets the quantity of different elements in the tree
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Adds an element at the end of the tree. From then on it is the
+    /// element repeated as padding, like in `MerkleTree::add_element`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `new_elem`: The element that will be added.
+    pub fn add_element<T: Hash>(&mut self, new_elem: T) {
+        self.leaves.push(hash_element(new_elem));
+    }
+
+    /// Gets the len of the base level counting the padding
+    fn padded_len(&self) -> usize {
+        self.leaves.len().next_power_of_two()
+    }
+
+    /// Computes the root of the tree from its leaves
+    ///
+    /// ### Returns
+    ///
+    /// The hash of the root, or None if the tree is empty
+    pub fn root(&self) -> Option<u64> {
+        (!self.leaves.is_empty()).then(|| self.subtree_root(0, self.padded_len()))
+    }
+
+    /// Generates the proof for an element, computing the root of the
+    /// subtree of each sibling from the leaves below it.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hash_index`: The index of the element in the original input array.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof of the element, or an Error if the index
+    /// is not one of a different element, the same one `MerkleTree` gives
+    pub fn generate_proof(&self, hash_index: usize) -> Result<Proof, MerkleError> {
+        if self.leaves.is_empty() || hash_index >= self.padded_len() {
+            return Err(MerkleError::InvalidIndex { index: hash_index, len: self.leaves.len() });
+        }
+        if hash_index >= self.leaves.len() {
+            return Err(MerkleError::PaddedIndex { index: hash_index });
+        }
+        let height = self.padded_len().ilog2();
+        let siblings = (0..height)
+            .map(|level| {
+                let node_index = hash_index >> level;
+                let sibling = node_index ^ 1;
+                let side = if node_index.is_multiple_of(2) { Side::Right } else { Side::Left };
+                (self.subtree_root(sibling << level, 1 << level), side)
+            })
+            .collect();
+        Ok(Proof::new(hash_index, siblings))
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a
+    /// proof and a leaf
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The Proof of the element, as `generate_proof` returned it.
+    /// - `leaf`: The hash of the element.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root of the tree can be obtained with
+    /// that information, false otherwise
+    pub fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+        self.root().is_some_and(|root| verify_against_root(root, self.leaves.len(), proof, leaf))
+    }
+
+    /// Gets the root of the subtree over `width` leaves of the padded base
+    /// level, a power of 2, starting at `start`. Only the subtrees that
+    /// cover both elements and padding are split, one for each level.
+    fn subtree_root(&self, start: usize, width: usize) -> u64 {
+        let len = self.leaves.len();
+        if start + width <= len {
+            return leaves_root(&self.leaves[start..start + width]);
+        }
+        if start >= len {
+            // Every leaf is a copy of the last element
+            let mut node = self.leaves[len - 1];
+            for _ in 0..width.ilog2() {
+                node = combine(node, node);
+            }
+            return node;
+        }
+        let half = width / 2;
+        combine(self.subtree_root(start, half), self.subtree_root(start + half, half))
+    }
+}
+
+/// Hashes two nodes into their parent, like a MerkleTree created with `new`
+fn combine(left: u64, right: u64) -> u64 {
+    combine_with(&DefaultBuildHasher::default(), PairOrdering::Positional, left, right)
+}
+
+/// Gets the root of the subtree over some leaves whose len is a power of 2.
+/// Only the pending left nodes are kept, at most one for each level.
+fn leaves_root(leaves: &[u64]) -> u64 {
+    let mut pending: Vec<(u32, u64)> = Vec::new();
+    for &leaf in leaves {
+        let mut node = (0, leaf);
+        while let Some(&(level, left)) = pending.last()
+            && level == node.0
+        {
+            pending.pop();
+            node = (level + 1, combine(left, node.1));
+        }
+        pending.push(node);
+    }
+    pending[0].1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::MerkleTree;
+
+    /// The operations both trees share, so the same checks run on both.
+    trait SharedTree {
+        fn create(elements: Vec<usize>) -> Self;
+        fn add(&mut self, element: usize);
+        fn root(&self) -> Option<u64>;
+        fn proof(&self, index: usize) -> Result<Proof, MerkleError>;
+        fn verify(&self, proof: &Proof, leaf: u64) -> bool;
+    }
+
+    impl SharedTree for MerkleTree {
+        fn create(elements: Vec<usize>) -> Self {
+            MerkleTree::new(elements)
+        }
+        fn add(&mut self, element: usize) {
+            self.add_element(element);
+        }
+        fn root(&self) -> Option<u64> {
+            MerkleTree::root(self)
+        }
+        fn proof(&self, index: usize) -> Result<Proof, MerkleError> {
+            self.generate_proof(index)
+        }
+        fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+            MerkleTree::verify(self, proof, leaf)
+        }
+    }
+
+    impl SharedTree for LightMerkleTree {
+        fn create(elements: Vec<usize>) -> Self {
+            LightMerkleTree::new(elements)
+        }
+        fn add(&mut self, element: usize) {
+            self.add_element(element);
+        }
+        fn root(&self) -> Option<u64> {
+            LightMerkleTree::root(self)
+        }
+        fn proof(&self, index: usize) -> Result<Proof, MerkleError> {
+            self.generate_proof(index)
+        }
+        fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+            LightMerkleTree::verify(self, proof, leaf)
+        }
+    }
+
+    /// Gets the root and every proof of a tree created with some elements
+    /// and then grown one element at a time, checking that the proofs verify.
+    fn run_suite<T: SharedTree>(initial: usize, added: usize) -> Vec<(Option<u64>, Vec<Proof>)> {
+        let mut tree = T::create((0..initial).collect());
+        let mut states = Vec::new();
+        for len in initial..=initial + added {
+            if len > initial {
+                tree.add(len - 1);
+            }
+            let proofs: Vec<Proof> = (0..len).map(|index| tree.proof(index).unwrap()).collect();
+            for (index, proof) in proofs.iter().enumerate() {
+                assert!(tree.verify(proof, hash_element(index)));
+                assert!(!tree.verify(proof, hash_element(len + 1)));
+            }
+            let error = if len == 0 || len.is_power_of_two() {
+                MerkleError::InvalidIndex { index: len, len }
+            } else {
+                MerkleError::PaddedIndex { index: len }
+            };
+            assert_eq!(tree.proof(len), Err(error));
+            states.push((tree.root(), proofs));
+        }
+        states
+    }
+
+    #[test]
+    /// Test if the light tree has the same roots and proofs as the full
+    /// tree, for trees created at once and grown with add_element.
+    fn light_tree_matches_full_tree() {
+        for (initial, added) in [(0, 20), (1, 10), (3, 6), (5, 12), (8, 9), (13, 0)] {
+            assert_eq!(run_suite::<LightMerkleTree>(initial, added), run_suite::<MerkleTree>(initial, added));
+        }
+    }
+
+    #[test]
+    /// Test if the padding is not stored: the tree holds one hash for each
+    /// different element, however far the len is from a power of 2.
+    fn padding_is_not_stored() {
+        let mut light = LightMerkleTree::new((0..9usize).collect());
+        assert_eq!(light.leaves.len(), 9);
+        assert_eq!(light.root(), MerkleTree::new((0..9usize).collect()).root());
+        light.add_element(9usize);
+        assert_eq!(light.leaves.len(), 10);
+        assert_eq!(light.generate_proof(15), Err(MerkleError::PaddedIndex { index: 15 }));
+        assert_eq!(light.generate_proof(16), Err(MerkleError::InvalidIndex { index: 16, len: 10 }));
+    }
+
+    #[test]
+    /// Test if an empty light tree has no root, and verifies nothing
+    fn empty_light_tree() {
+        let light = LightMerkleTree::default();
+        assert!(light.is_empty());
+        assert_eq!(light.root(), None);
+        assert!(!light.verify(&Proof::new(0, Vec::new()), hash_element(0usize)));
+    }
+}
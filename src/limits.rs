@@ -0,0 +1,167 @@
+use std::fmt;
+
+use crate::error::MerkleError;
+
+/// Each of the limits that can be configured in `Limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Leaves,
+    Height,
+    ProofEntries,
+    SerializedSize,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Leaves => write!(f, "leaves"),
+            Limit::Height => write!(f, "height"),
+            Limit::ProofEntries => write!(f, "proof entries"),
+            Limit::SerializedSize => write!(f, "serialized size"),
+        }
+    }
+}
+
+/// Bounds for inputs that come from untrusted sources, like trees and
+/// proofs received over the network. The declared sizes of an input are
+/// checked against them before anything is allocated, so a hostile
+/// payload can not make us reserve an absurd amount of memory.
+/// - `max_leaves`: Maximum quantity of leaves in the base level.
+/// - `max_height`: Maximum quantity of levels in a tree.
+/// - `max_proof_entries`: Maximum quantity of hashes in a proof.
+/// - `max_serialized_size`: Maximum quantity of bytes of a serialized input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_leaves: usize,
+    pub max_height: usize,
+    pub max_proof_entries: usize,
+    pub max_serialized_size: usize,
+}
+
+impl Default for Limits {
+    /// Limits that fit any tree with up to 2^24 leaves. Every level above
+    /// the leaves adds one hash to a proof, so proofs of those trees have
+    /// at most 24 entries.
+    fn default() -> Self {
+        Self {
+            max_leaves: 1 << 24,
+            max_height: 25,
+            max_proof_entries: 24,
+            max_serialized_size: 512 << 20,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits that accept any input. They must be asked for explicitly,
+    /// and should only be used with inputs that are trusted.
+    pub fn unlimited() -> Self {
+        Self {
+            max_leaves: usize::MAX,
+            max_height: usize::MAX,
+            max_proof_entries: usize::MAX,
+            max_serialized_size: usize::MAX,
+        }
+    }
+
+    /// Sets the maximum quantity of leaves
+    pub fn max_leaves(mut self, max: usize) -> Self {
+        self.max_leaves = max;
+        self
+    }
+
+    /// Sets the maximum quantity of levels
+    pub fn max_height(mut self, max: usize) -> Self {
+        self.max_height = max;
+        self
+    }
+
+    /// Sets the maximum quantity of hashes in a proof
+    pub fn max_proof_entries(mut self, max: usize) -> Self {
+        self.max_proof_entries = max;
+        self
+    }
+
+    /// Sets the maximum quantity of bytes of a serialized input
+    pub fn max_serialized_size(mut self, max: usize) -> Self {
+        self.max_serialized_size = max;
+        self
+    }
+
+    /// Checks a declared quantity of leaves
+    pub fn check_leaves(&self, got: usize) -> Result<(), MerkleError> {
+        check(Limit::Leaves, self.max_leaves, got)
+    }
+
+    /// Checks a declared quantity of levels
+    pub fn check_height(&self, got: usize) -> Result<(), MerkleError> {
+        check(Limit::Height, self.max_height, got)
+    }
+
+    /// Checks a declared quantity of hashes in a proof
+    pub fn check_proof_entries(&self, got: usize) -> Result<(), MerkleError> {
+        check(Limit::ProofEntries, self.max_proof_entries, got)
+    }
+
+    /// Checks the quantity of bytes of a serialized input
+    pub fn check_serialized_size(&self, got: usize) -> Result<(), MerkleError> {
+        check(Limit::SerializedSize, self.max_serialized_size, got)
+    }
+}
+
+/// Returns a LimitExceeded error if `got` is larger than `max`
+fn check(limit: Limit, max: usize, got: usize) -> Result<(), MerkleError> {
+    if got > max {
+        return Err(MerkleError::LimitExceeded { limit, max, got });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    /// Test if every check identifies the limit that was exceeded
+    fn checks_identify_the_limit() {
+        let limits = Limits::default().max_leaves(4).max_height(3).max_proof_entries(2).max_serialized_size(10);
+
+        assert!(limits.check_leaves(4).is_ok());
+        assert_eq!(limits.check_leaves(5), Err(MerkleError::LimitExceeded { limit: Limit::Leaves, max: 4, got: 5 }));
+        assert_eq!(limits.check_height(4), Err(MerkleError::LimitExceeded { limit: Limit::Height, max: 3, got: 4 }));
+        assert_eq!(
+            limits.check_proof_entries(3),
+            Err(MerkleError::LimitExceeded { limit: Limit::ProofEntries, max: 2, got: 3 })
+        );
+        assert_eq!(
+            limits.check_serialized_size(11),
+            Err(MerkleError::LimitExceeded { limit: Limit::SerializedSize, max: 10, got: 11 })
+        );
+    }
+
+    #[test]
+    /// Test if unlimited accepts even the largest declared counts
+    fn unlimited_accepts_everything() {
+        let limits = Limits::unlimited();
+
+        assert!(limits.check_leaves(usize::MAX).is_ok());
+        assert!(limits.check_proof_entries(usize::MAX).is_ok());
+    }
+
+    #[test]
+    /// Test if verifying with limits rejects a proof that is too long before
+    /// hashing it, and still verifies a valid proof.
+    fn verify_with_limits_rejects_long_proofs() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]);
+        let proof = merkle.generate_proof(1).unwrap();
+        let leaf = crate::merkle::hash_element("Merkle");
+        let limits = Limits::default().max_proof_entries(1);
+
+        assert_eq!(merkle.verify_with_limits(&proof, leaf, &Limits::default()), Ok(true));
+        assert_eq!(
+            merkle.verify_with_limits(&proof, leaf, &limits),
+            Err(MerkleError::LimitExceeded { limit: Limit::ProofEntries, max: 1, got: 2 })
+        );
+    }
+}
@@ -1,15 +1,158 @@
-use merkle::MerkleTree;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
 
-mod merkle;
+use tree::merkle::{hash_leaf, PairOrdering};
+use tree::policy::{PolicyVerifier, Receipt, VerifierPolicy};
+use tree::vectors::{self, Profile, VectorConfig};
+use tree::{MerkleTree, PolicyViolation, Proof, RootHash};
 
+const USAGE: &str = "Usage:
+    merkle build <leaves> [<tree>]
+        Builds a tree with a leaf for each line of <leaves>, prints its root
+        and saves it to <tree> (<leaves> with the .tree extension by default).
+    merkle prove <tree> <index>
+        Prints the proof of the leaf at <index> of a saved tree, as hex.
+    merkle verify <root> <proof> <index> <leaf> [--policy <policy>]
+        Checks that <leaf> is at <index> of the tree with that <root>.
+        Exits with 0 if the proof is valid and with 1 if it is not. With a
+        <policy> file, the proof must also follow its rules, see
+        VerifierPolicy::from_json.
+    merkle vectors <leaves> [--profile <profile>] [--domain-tag <tag>]
+        Prints the test vectors of a tree with a leaf for each line of
+        <leaves>, with the proof of every leaf. The profile is one of
+        default, rfc6962, sorted or committed-len (default by default).
+    merkle vectors --check <vectors>
+        Re-runs a vector file and prints every divergence found. Exits with
+        0 if there are none and with 1 if there are.";
 
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Runs the subcommand in the arguments
+///
+/// ### Returns
+///
+/// A Result with the code the program exits with, or an Error with the
+/// message to show if the arguments or the files are not valid
+fn run(args: &[String]) -> Result<ExitCode, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["build", leaves] => build(leaves, &Path::new(leaves).with_extension("tree")),
+        ["build", leaves, tree] => build(leaves, Path::new(tree)),
+        ["prove", tree, index] => prove(tree, index),
+        ["verify", root, proof, index, leaf] => verify(root, proof, index, leaf, None),
+        ["verify", root, proof, index, leaf, "--policy", policy] => verify(root, proof, index, leaf, Some(policy)),
+        ["vectors", "--check", document] => check_vectors(document),
+        ["vectors", leaves, options @ ..] => generate_vectors(leaves, options),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Builds a tree with a leaf for each line of a file, prints its root and saves it
+fn build(leaves: &str, tree: &Path) -> Result<ExitCode, String> {
+    let content = fs::read_to_string(leaves).map_err(|error| format!("Could not read {leaves}: {error}"))?;
+    let merkle = MerkleTree::new(content.lines().collect());
+    let root = merkle.root_hash().ok_or(format!("{leaves} has no leaves"))?;
+    merkle.save(tree).map_err(|error| format!("Could not write {}: {error}", tree.display()))?;
+    println!("{root}");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the proof of a leaf of a saved tree as hex
+fn prove(tree: &str, index: &str) -> Result<ExitCode, String> {
+    let merkle = MerkleTree::load(tree).map_err(|error| format!("Could not load {tree}: {error}"))?;
+    let proof = merkle.generate_proof(parse_index(index)?).map_err(|error| error.to_string())?;
+    println!("{}", proof.to_hex());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Checks a proof against a root, without the tree, and against the rules
+/// of a policy file if there is one
+fn verify(root: &str, proof: &str, index: &str, leaf: &str, policy: Option<&str>) -> Result<ExitCode, String> {
+    let root: RootHash = root.parse().map_err(|error| format!("Invalid root: {error}"))?;
+    let proof = Proof::from_hex(proof).map_err(|error| format!("Invalid proof: {error}"))?;
+    let index = parse_index(index)?;
+    let policy = match policy {
+        Some(policy) => {
+            let content = fs::read_to_string(policy).map_err(|error| format!("Could not read {policy}: {error}"))?;
+            VerifierPolicy::from_json(&content).map_err(|error| format!("Invalid policy: {error}"))?
+        }
+        None => VerifierPolicy::new(),
+    };
+    // The trees of the build command use the default hasher and do not
+    // commit to their size or to a domain tag
+    let receipt = Receipt {
+        algorithm: "default".to_string(),
+        ordering: PairOrdering::Positional,
+        root: root.into_raw(),
+        tree_size: None,
+        domain_tag: None,
+        leaf: hash_leaf(leaf),
+        proof,
+    };
+    let verdict =
+        if receipt.proof.leaf_index() == index { PolicyVerifier::new(policy).verify(&receipt) } else { Err(PolicyViolation::InvalidProof) };
+    match verdict {
+        Ok(()) => {
+            println!("valid");
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(PolicyViolation::InvalidProof) => {
+            println!("invalid");
+            Ok(ExitCode::FAILURE)
+        }
+        Err(violation) => {
+            println!("invalid: {violation}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Prints the test vectors of a tree with a leaf for each line of a file
+fn generate_vectors(leaves: &str, mut options: &[&str]) -> Result<ExitCode, String> {
+    let mut config = VectorConfig::new(Profile::Default);
+    loop {
+        options = match options {
+            [] => break,
+            ["--profile", name, rest @ ..] => {
+                config.profile = Profile::from_name(name).ok_or(format!("Unknown profile: {name}"))?;
+                rest
+            }
+            ["--domain-tag", tag, rest @ ..] => {
+                config.domain_tag = Some(tag.to_string());
+                rest
+            }
+            _ => return Err(USAGE.to_string()),
+        };
+    }
+    let content = fs::read_to_string(leaves).map_err(|error| format!("Could not read {leaves}: {error}"))?;
+    let leaves: Vec<Vec<u8>> = content.lines().map(|line| line.as_bytes().to_vec()).collect();
+    let indices: Vec<usize> = (0..leaves.len()).collect();
+    println!("{}", vectors::generate(&config, &leaves, &indices).map_err(|error| error.to_string())?);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Re-runs a vector file and prints every divergence found
+fn check_vectors(document: &str) -> Result<ExitCode, String> {
+    let content = fs::read_to_string(document).map_err(|error| format!("Could not read {document}: {error}"))?;
+    let divergences = vectors::check(&content).map_err(|error| format!("Invalid vectors: {error}"))?;
+    for divergence in &divergences {
+        println!("{divergence}");
+    }
+    Ok(if divergences.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
 
-fn main() {
-    let strings = vec!["Crypto", "Merkle", "Rust"];
-    let mut merkle = MerkleTree::new(strings);
-    merkle.add_element("Test");
-    let proof = merkle.generate_proof(0).unwrap();
-    let elem0_hash = 18444331223197392467;
-    let verification = merkle.verify(proof, 0, elem0_hash);
-    println!("Verification was succesful: {:?}", verification);
+/// Parses the index of a leaf
+fn parse_index(index: &str) -> Result<usize, String> {
+    index.parse().map_err(|_| format!("Invalid index: {index}"))
 }
@@ -1,20 +1,121 @@
-use std::hash::{DefaultHasher, Hash, Hasher};
+use std::fmt;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash};
+
+use crate::error::{MerkleError, VerifyError};
+use crate::limits::Limits;
+use crate::root_hash::{to_hex, RootHash};
+pub(crate) use crate::verify::{combine_with, concatenate_elements, hash_leaf_with as hash_with, hash_node_with};
+pub use crate::verify::{commit_len_with, expected_proof_len_for, verify_against_root_with, verify_committed_against_root_with, PairOrdering};
+
+mod absence;
+mod audit;
+mod batch;
+mod builder;
+mod diff;
+mod files;
+mod history;
+mod levels;
+mod multiproof;
+mod parallel;
+mod placeholders;
+mod proof;
+mod proof_pack;
+mod rebuild;
+mod render;
+mod retention;
+mod small;
+mod storage;
+mod streaming;
+mod traversal;
+mod validate;
+
+use history::History;
+use levels::Levels;
+use placeholders::PlaceholderSlots;
+use small::{create_small_levels, SMALL_TREE_MAX_LEAVES};
+pub use absence::{AbsenceProof, Neighbor};
+pub use audit::AuditMismatch;
+pub use batch::Batch;
+pub use builder::MerkleTreeBuilder;
+pub use history::TreeVersion;
+pub use multiproof::MultiProof;
+pub use placeholders::RootStatus;
+pub use proof::{Proof, Side};
+pub use proof_pack::{EntryStatus, PackEntry, PackReport, ProofPack};
+pub use rebuild::{RebuildProgress, RebuildTask};
+pub use retention::{GcReport, MerkleTreeSnapshot, Retention};
+pub use traversal::{BfsIter, DfsIter, NodeVisitor, Position};
+
+type TreeStructure = Levels;
+
+/// How a level with an odd quantity of nodes is completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaddingStrategy {
+    /// The last element is repeated until the base level has a len that
+    /// is a power of 2, so every node of the tree has a sibling. Every slot
+    /// after the different elements holds a copy of the last one, so
+    /// adding an element replaces the first copy and the new element is
+    /// the one repeated from then on.
+    #[default]
+    RepeatLast,
+    /// Nothing is repeated. The last node of a level with an odd len has no
+    /// sibling and is promoted to the next level as it is, like the trees
+    /// of Certificate Transparency (RFC 6962) do.
+    PromoteOdd,
+    /// The base level is completed with `ZERO_LEAF` until its len is a
+    /// power of 2, like the trees of many smart contracts do. The padding
+    /// does not depend on any element, so a tree over [a, b, c] never has
+    /// the root of a tree over [a, b, c, c]. Adding an element still takes
+    /// the first slot of padding, since it is not an element of the tree.
+    ZeroHash,
+}
 
-const BASE: i32 = 2;
+/// The hash of each slot of padding of the base level when the tree pads
+/// with `PaddingStrategy::ZeroHash`. The nodes above them are hashed from
+/// it like any other node.
+pub const ZERO_LEAF: u64 = 0;
 
-type TreeStructure = Vec<Vec<u64>>;
+/// The hasher used by `MerkleTree::new`. Every tree built with it gives the
+/// same hashes, so it is the one used by proofs checked without a tree.
+///
+/// Its hashes come from the `DefaultHasher` of the standard library, which
+/// may change between Rust releases. Roots that are published or stored for
+/// verifiers built with another toolchain should come from
+/// `MerkleTree::new_portable`, see `PortableHash`.
+pub type DefaultBuildHasher = BuildHasherDefault<DefaultHasher>;
 
 /// Abstraction of a Merkle Tree. The structure is represented
-/// as a vector of vectors. Each vector contains hashes and represents
-/// a level in the tree. This structure is used so as to follow
+/// as a list of levels. Each level contains hashes, and every level is
+/// stored in the same vector. This structure is used so as to follow
 /// the simple verification algorithm in this video:
 /// https://www.youtube.com/watch?v=n6nEPaE7KZ8
-/// - `arr`: A vector of vectors will be the structure of our tree. Each vector is a level on it.
-/// - `diff_elements`:  Quantity of different elements in the base level. In the base level we could have repeated
-///                     elements that where pushed so it could reach a len that is a power of 2.
-pub struct MerkleTree {
-    arr: TreeStructure,     // A vector of vectors will be the structure of our tree. Each vector is a level on it.
+/// - `arr`: The levels of our tree, stored one after the other in a single vector.
+/// - `diff_elements`:  Quantity of different elements in the base level. The base level is padded with
+///   copies of the element at `diff_elements - 1` so it reaches a len that is a power of 2, but those
+///   copies are not stored, see `repeated`.
+/// - `placeholders`: Slots that still hold a placeholder hash when the tree was created with
+///   `with_placeholders`. It is None once every slot was filled.
+/// - `hasher`: Builds the hasher used for the elements and for every node above them.
+/// - `ordering`: How the children of each node are ordered before hashing them.
+/// - `padding`: How the levels with an odd quantity of nodes are completed.
+/// - `history`: The previous roots of the tree and its shared snapshots, when it was
+///   created with `with_history`.
+/// - `commit_len`: If the root is the hash of the last level together with `diff_elements`,
+///   see `with_committed_len`.
+/// - `repeated`: The hash of a node that only covers copies of the last element, for every level
+///   below the root. Each level only stores the nodes that cover a different element, the ones
+///   after them are all this hash. It is empty when the tree promotes odd nodes.
+#[derive(Clone)]
+pub struct MerkleTree<H = DefaultBuildHasher> {
+    arr: TreeStructure,     // The levels of our tree, stored one after the other in a single vector.
     diff_elements: usize,   // Quantity of different elemn
+    placeholders: Option<PlaceholderSlots>,
+    hasher: H,
+    ordering: PairOrdering,
+    padding: PaddingStrategy,
+    history: Option<History<H>>,
+    commit_len: bool,
+    repeated: Vec<u64>,
 }
 
 impl MerkleTree {
@@ -29,470 +130,1653 @@ impl MerkleTree {
     /// 
     /// A MerkleTree instance 
     pub fn new<T: Hash + Clone>(elements: Vec<T>) -> Self {
-        // Hash every element of the array
-        let elements_len = elements.len();
-        let hashed_elements = create_first_level(elements);
-        let arr = create_remaining_levels(hashed_elements);
-        Self { arr, diff_elements: elements_len }
+        Self::with_hasher(elements, DefaultBuildHasher::default())
     }
 
-    /// Checks if the hash received is equal to the root of the tree
+    /// Creates a new MerkleTree that sorts the children of every node before
+    /// hashing them, so its proofs can be checked with `verify_sorted`
+    /// without the index of the leaf.
     /// 
     /// ### Arguments
     /// 
-    /// - `hash_to_check`: A hash that will be compared with the root
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
     /// 
     /// ### Returns
     /// 
-    /// If the hash is equal to the one of the root, then it returns true,
-    /// else false.
-    fn is_root(&self, hash_to_check: u64) -> bool {
-        let root_level = match self.arr.last() {
-            Some(root_level) => root_level,
-            None => return false,
-        };
-
-        match root_level.last() {
-            Some(root) => *root == hash_to_check,
-            None => false,
-        }
+    /// A MerkleTree instance 
+    pub fn new_sorted<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        Self::build(elements, DefaultBuildHasher::default(), PairOrdering::Sorted, PaddingStrategy::RepeatLast)
     }
 
-    /// Checks if the root of the tree can be obtained with the use of a proof, 
-    /// a leaf and its index on the input array.
+    /// Creates a new MerkleTree with the shape RFC 6962 defines for
+    /// Certificate Transparency logs. No element is repeated: the last node
+    /// of a level with an odd len is promoted to the next level, so the
+    /// proofs of some elements have less hashes than others.
     /// 
     /// ### Arguments
     /// 
-    /// - `proofs`: A vector of hashes that make up the proof to get to the root.
-    /// - `leaf_index`: The index in the input array of the received leaf.
-    /// - `leaf`: The hash of one of the elements on the input array.
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
     /// 
     /// ### Returns
     /// 
-    /// A bool that is true if the root can be obtained with that information, false otherwise
-    pub fn verify(&self, proofs: Vec<u64>, leaf_index: usize, leaf: u64) -> bool {
-        // If the index is equal or larger than the quantity of different elements
-        // it means that the index is invalid.
-        if leaf_index >= self.diff_elements {
-            return false;
-        }
-        let mut hash_index = leaf_index;
-        let mut hash = leaf;
-        let mut concatenation: String;
-        for proof in &proofs {
-
-            if hash_index % 2 == 0 {
-                // We know that if the index is even, the proof is on the right: hash + proof
-                concatenation = concatenate_elements(hash, *proof);
-            } else {
-                // We know that if the index is odd, the proof is on the left: proof + hash
-                concatenation = concatenate_elements(*proof, hash);
-            }
-
-            // Get the new hash and update the index for the next level 
-            hash = hash_element(concatenation);
-            hash_index /= 2;
-        }
-
-        self.is_root(hash)
+    /// A MerkleTree instance 
+    pub fn new_rfc6962<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        Self::build(elements, DefaultBuildHasher::default(), PairOrdering::Positional, PaddingStrategy::PromoteOdd)
     }
 
-    /// Generates a Vector containing the hashes that together form the proof
-    /// for a specific element in the tree. With this proof and the hash of the element,
-    /// the tree's root can be obtained.
+    /// Creates a new MerkleTree whose root is the hash of the last level
+    /// together with the quantity of different elements. The padding
+    /// repeats the last element, so without it a tree over [a, b, c] has
+    /// the same root as a tree over [a, b, c, c], and a verifier could be
+    /// convinced the second "c" was included on purpose.
+    /// 
+    /// The levels are the same ones `new` creates, so the proofs are the
+    /// same too. They verify with `verify_committed_against_root` and the
+    /// len of the tree, see `committed_len`.
     /// 
     /// ### Arguments
     /// 
-    /// - `hash_index`: The index of the element in the original input array
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
     /// 
     /// ### Returns
     /// 
-    /// A Result that contains the vector with the hashes or an Error explaining
-    /// what was the problem 
-    pub fn generate_proof(&self, mut hash_index: usize) -> Result<Vec<u64>, String> {
-        // If the index is equal or larger than the quantity of different elements
-        // it means that the index is invalid.
-        if hash_index >= self.diff_elements {
-            return Err(String::from("Invalid index"));
-        }
-        let mut proof_hash: u64;
-        let mut proof = Vec::new();
-        for level in &self.arr {
-            // If we reach the root level we dont continue
-            // since the root does not go on the proof.
-            if level.len() == 1 {
-                break;
-            }
-
-            if hash_index % 2 == 0 {
-                proof_hash = level[hash_index + 1];
-            } else {
-                proof_hash = level[hash_index - 1];
-            }
-            proof.push(proof_hash);
-            hash_index /= 2;
-        }
-        Ok(proof)
-    }
-
-    /// Adds an element to the tree
-    /// 
-    /// There are 2 cases to handle when adding an element to the tree.
-    /// First is the case when we add an element to a tree that already
-    /// has a base level that are all different elements. In this case we 
-    /// add the element and add other repeated elements to the base level
-    /// so it keeps a len that is a power of 2. By adding all this elements
-    /// we create a new subtree that will have the same width and height
-    /// as the original one. So all we have to do is create a new hash from
-    /// the old root and the new subtree root to create the new original
-    /// root.
-    /// 
-    /// The other possible case is when the base level has repeated values. 
-    /// This case is handled by replacing the first repeated value with 
-    /// the new element and re-calculating the part of the tree affected 
-    /// by this change.
-    pub fn add_element<T: Hash + Clone>(&mut self, new_elem: T) {
-        // Get how many different elements we have on the base level
-        let curr_base_len = self.diff_elements;
-        if diff_to_power_of_2(curr_base_len as f64) == 0 { // The base level has 2^n different elements.
-            self.create_new_base_level(new_elem);
-            // Now we get the base level for the subtree
-            // and create it. This base level has the new 
-            // value added and then a bunch of repeated values.
-            let new_base_section = self.arr[0][curr_base_len..].to_vec();
-            let subtree = create_remaining_levels(new_base_section);
-            // After creating the new subtree, we unify it with 
-            // our original tree. This is done by combinating
-            // each level. (We start from level 1 since level 0
-            // was already compleated at the beginning)
-            for i in 1..self.arr.len() {
-                self.arr[i].extend(subtree[i].clone());
-            }
-
-            // Create the new root.
-            // This is done by concatenating the roots of the new subtree and
-            // the one from the original tree.
-            let last_level = self.arr.last().unwrap();
-            let concatenated_roots = concatenate_elements(last_level[0], last_level[1]);
-            let new_root = hash_element(concatenated_roots);
-            // Add the new root level
-            let new_root_level = vec![new_root];
-            self.arr.push(new_root_level);
-        } else {
-            // We need to replace a repeated element with the new one
-            // and re-calculate the hashes that it affects.
-            let new_hash = hash_element(new_elem);
-            self.replace_repeated_value(new_hash);
-        }
+    /// A MerkleTree instance 
+    pub fn with_committed_len<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        let mut merkle = Self::new(elements);
+        merkle.commit_len = true;
+        merkle
     }
 
-    /// Creates a new base level by adding a new element.
+    /// Creates an empty MerkleTree with every level already allocated for
+    /// `capacity` elements, so adding them one by one with `add_element`
+    /// does not reallocate the levels. The capacity does not change the
+    /// hashes of the tree. Only the room for the different elements and
+    /// the nodes above them is taken, the padding is never stored.
     /// 
-    /// By adding a new element to a level that has already
-    /// a len that is a power of 2, we lose that quality. So
-    /// we also have to add repeated values so we can get that
-    /// quality again.
-    fn create_new_base_level<T: Hash + Clone>(&mut self, new_elem: T) {
-        self.arr[0].push(hash_element(new_elem));
-        self.diff_elements += 1;
-        extend_elements(&mut self.arr[0]);
-    }
-
-    /// Replaces the first repeated value in the base level with
-    /// a new value.
+    /// ### Arguments
     /// 
-    /// The first repeated value is at self.diff_elements. In that position
-    /// we insert the new value. This makes it necessary to update some
-    /// hashes in the tree. That is why we iterate through each level
-    /// creating new hashes with the updated values.
+    /// - `capacity`: The quantity of elements the tree will hold without reallocating.
     /// 
-    /// ### Arguments
+    /// ### Returns
     /// 
-    /// - `new_hash`: The hash of the new value to be inserted in the place of the repeated value.
-    fn replace_repeated_value(&mut self, mut new_hash: u64) {
-        let mut index = self.diff_elements; // Index of the first repeated value
-        self.diff_elements += 1;
-        let mut right_node: u64;
-        let mut left_node: u64;
-        for level in &mut self.arr {
-            // Update the node with the new hash.
-            level[index] = new_hash;
-
-            // If we reached the root level and we already
-            // updated its value, we should not continue.
-            if level.len() == 1 {
-                break;
-            }
-
-            if index % 2 == 0 { // We are on the left node
-                left_node = level[index];
-                right_node = level[index + 1];
-            } else { // We are on the right node
-                right_node = level[index];
-                left_node = level[index - 1];
-            }
-
-            // Create the new hash for the parent node
-            // that will be updated in the next iteration.
-            let concatenated = concatenate_elements(left_node, right_node);
-            new_hash = hash_element(concatenated);
-            // Update the index for the next iteration
-            index /= 2;
-
+    /// An empty MerkleTree instance
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut merkle = Self::default();
+        if capacity > 0 {
+            let height = capacity.next_power_of_two().ilog2() as usize + 1;
+            merkle.arr = TreeStructure::with_capacity((0..height).map(|level| capacity.div_ceil(1 << level)));
         }
+        merkle
     }
 }
 
-/// Concatenates to elements into one
-/// 
-/// ### Arguments
-/// 
-/// - `elem1`: An u64 that will be the first part of the concatenation.
-/// - `elem2`: An u64 that will be the second part of the concatenation.
-/// 
-/// ### Returns
-/// 
-/// A String thats the result of the concatenation fo the 2 elements
-fn concatenate_elements(elem1: u64, elem2: u64) -> String {// TODO: Check if this way of concatenating the hashes is correct
-    elem1.to_string() + &elem2.to_string()
-}
-
-/// Hashes an element
-/// 
-/// ### Arguments
-/// 
-/// - `element`: An element that implements the trait Hash
-/// 
-/// ### Returns
-/// 
-/// An u64 that represents the hash of the element
-fn hash_element<T: Hash>(element: T) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    element.hash(&mut hasher);
-    hasher.finish()
+impl Default for MerkleTree {
+    /// Creates an empty MerkleTree, see `add_element`
+    fn default() -> Self {
+        Self::new(Vec::<u64>::new())
+    }
 }
 
-/// Gets the difference between 'num' and the next closest number that is
-/// a power of 2
-/// 
-/// ### Arguments
-/// 
-/// - `num`: The number that we will use to get the next power of 2
-/// 
-/// ### Returns
-/// 
-/// An i32 that represents the difference that needs to be added so 'num'
-/// can reach the closes power of 2 (that is bigger than 'num')
-fn diff_to_power_of_2(num: f64) -> i32 {
-    // Find the exponent that would get us close to the len of the elements vector 
-    let exp = num.log2().ceil() as u32;
-    // Get how much more elements we need to get to a power of 2 len
-    let diff = BASE.pow(exp) - num as i32;
-    diff
+impl<T: Hash + Clone> FromIterator<T> for MerkleTree {
+    /// Creates a new MerkleTree from the elements of an iterator, the same
+    /// way `new` does
+    fn from_iter<I: IntoIterator<Item = T>>(elements: I) -> Self {
+        Self::new(elements.into_iter().collect())
+    }
 }
 
-/// Extends the elements vector so it has a len of
-/// equal to a power of 2, if necessary
-/// 
-/// First we need to find the exponent that would give us
-/// a close value to the elements len. Once we have this, we
-/// can get the difference between the closest power of 2 and
-/// the current len. That difference is the amount of repeated
-/// cells we have to add again to make the len of to be a
-/// power of 2.
-/// 
-/// ### Arguments
-/// 
-/// - `elements`: A vector with the elements that will be hashed and form the first level in the tree
-fn extend_elements<T: Hash + Clone>(elements: &mut Vec<T>) { // TODO: Check if this function should be inside the impl
-    let diff = diff_to_power_of_2(elements.len() as f64);
-    if diff != 0 {
-        // Add the last 'diff' elements to the elements vector
-        let index = elements.len() - diff as usize;
-        let elements_slice = elements[index..].to_vec();
-        elements.extend(elements_slice);
+impl<H: BuildHasher, T: Hash + Clone> Extend<T> for MerkleTree<H> {
+    /// Adds the elements of an iterator to the tree, see `add_elements`
+    fn extend<I: IntoIterator<Item = T>>(&mut self, new_elems: I) {
+        self.add_elements(new_elems.into_iter().collect());
     }
 }
 
-/// Creates the first level of the Merkle Tree.
-/// 
-/// Hashes all the input elements and adding repeated hashes 
-/// if the len is not equal to a power of 2.
-/// 
-/// ### Arguments
-/// 
-/// - `elements`: A vector with the elements that will be hashed and form the first level in the tree
-/// 
-/// ### Returns
-/// 
-/// A vector full of the hashes of the elements. This vector represents the first
-/// level of the Merkle Tree
-fn create_first_level<T: Hash + Clone>(mut elements: Vec<T>) -> Vec<u64> { // TODO: Check if this function should be inside the impl
-    extend_elements(&mut elements);
-    elements.iter().map(|elem| {
-        hash_element(elem)
-    }).collect()
+impl<H: BuildHasher> fmt::Debug for MerkleTree<H> {
+    /// Shows the size of the tree and its root instead of every hash,
+    /// use `render` to see all the levels
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MerkleTree")
+            .field("height", &self.height())
+            .field("len", &self.diff_elements)
+            .field("root", &self.root_hex())
+            .finish()
+    }
 }
 
-/// Uses the first level of the tree to create the remaining levels.
-/// Each new level uses the one before.
-/// 
-/// ### Arguments
-/// 
-/// - `hashed_elements`: A vector full of hashes representing the first level of the tree
-/// 
-/// ### Returns
-/// 
-/// A vector of vectors with hashes. Each vector represents a level on the tree, 
-/// starting from the first to the last (the root).
-fn create_remaining_levels(hashed_elements: Vec<u64>) -> TreeStructure { // TODO: Check if this function should be inside the impl
-    // We create the vec that will contain each level of the tree.
-    // Then we add the first level (the already hashed elements we have).
-    let mut tree_structure = Vec::new();
-    tree_structure.push(hashed_elements.clone());
-
-    // Each level creates the next level. So we iter each level by taking
-    // chunks of size 2, concatenating this chunks and hashing the concatenation.
-    // This process creates the next level.
-    let mut hashes = hashed_elements;
-    while hashes.len() != 1 {
-        hashes = hashes.chunks(2).map(|chunk| {
-            let concatenated = concatenate_elements(chunk[0], chunk[1]);
-            hash_element(concatenated)
-        }).collect();
-        tree_structure.push(hashes.clone());
+impl<H> PartialEq for MerkleTree<H> {
+    /// Two trees are equal if they have the same levels, the same
+    /// quantity of different elements and their roots commit to it or not
+    /// alike, whatever hasher built them
+    fn eq(&self, other: &Self) -> bool {
+        self.arr == other.arr && self.diff_elements == other.diff_elements && self.commit_len == other.commit_len
     }
-    tree_structure
 }
 
+impl<H> Eq for MerkleTree<H> {}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    const LEVEL_0: usize = 0;
-    const LEVEL_1: usize = 1;
-    const LEVEL_2: usize = 2;
+impl<H: BuildHasher> MerkleTree<H> {
 
+    /// Creates a new MerkleTree that hashes the elements and its nodes
+    /// with the hasher received instead of the default one.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    /// - `hasher`: Builds the hasher used for every hash of the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn with_hasher<T: Hash + Clone>(elements: Vec<T>, hasher: H) -> Self {
+        Self::build(elements, hasher, PairOrdering::Positional, PaddingStrategy::RepeatLast)
+    }
 
-    /// Manually generates the tree structure while also generating a tree.
-    /// All with the same input array, which has 4 elements in total.
-    /// The structure should look like this:
-    /// 
-    ///             [
-    /// LEVEL 0         [elem0_hash, elem1_hash, elem2_hash, elem3_haash],
-    /// LEVEL 1         [elem01_hash, elem23_hash],
-    /// LEVEL 2         [elem0123_hash],
-    ///             ]
-    /// From this we can see how the level 0 contains every hash of the elements
-    /// while level 2 has the root.
-    /// 
-    fn manually_create_tree_hashes() -> (TreeStructure, MerkleTree) {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
-        let mut tree = Vec::new();
-        let merkle = MerkleTree::new(data.clone());
-        // Get the hashes of the elements and manually create the tree structure
-        // Level 0. It has the hashes of every element
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
-        let elem2_hash = hash_element(data[2]);
-        let elem3_hash = hash_element(data[3]);
+    /// Creates the levels of a new MerkleTree
+    fn build<T: Hash + Clone>(elements: Vec<T>, hasher: H, ordering: PairOrdering, padding: PaddingStrategy) -> Self {
+        // Hash every element of the array
+        let elements_len = elements.len();
+        let arr = match padding {
+            // An empty tree has no levels at all, not even a root
+            _ if elements_len == 0 => TreeStructure::new(),
+            // Small trees are built on the stack, avoiding the intermediate vectors
+            PaddingStrategy::RepeatLast if (1..=SMALL_TREE_MAX_LEAVES).contains(&elements_len) => {
+                create_small_levels(&hasher, ordering, &elements)
+            }
+            _ => {
+                let hashed_elements = create_first_level(&hasher, elements);
+                create_remaining_levels_with(&hasher, ordering, padding, hashed_elements)
+            }
+        };
+        Self::from_levels(arr, elements_len, hasher, ordering, padding)
+    }
 
-        let level_0 = vec![elem0_hash, elem1_hash, elem2_hash, elem3_hash];
+    /// Creates a MerkleTree from levels that were already hashed, with
+    /// the default settings for everything else
+    ///
+    /// ### Arguments
+    ///
+    /// - `arr`: The levels of the tree, each one without its padding.
+    /// - `diff_elements`: Quantity of different elements in the base level.
+    /// - `hasher`: Builds the hasher the levels were hashed with.
+    /// - `ordering`: How the children of each node were ordered before hashing them.
+    /// - `padding`: How the levels with an odd quantity of nodes were completed.
+    pub(crate) fn from_levels(arr: TreeStructure, diff_elements: usize, hasher: H, ordering: PairOrdering, padding: PaddingStrategy) -> Self {
+        let mut merkle = Self { arr, diff_elements, placeholders: None, hasher, ordering, padding, history: None, commit_len: false, repeated: Vec::new() };
+        merkle.refresh_repeated();
+        merkle
+    }
 
-        // Level 1. It has the hashes of:
-        // (elem0_hash + elem1_hash) = elem01_hash 
-        // (elem2_hash + elem3_hash) = elem23_hash
-        let elem01 = concatenate_elements(elem0_hash, elem1_hash);
-        let elem01_hash = hash_element(elem01);
+    /// Hashes the node that only covers copies of the last element, once
+    /// for every level below the root. It has to be called every time the
+    /// last element or the len of the tree change, before any node above
+    /// the base level is hashed again.
+    fn refresh_repeated(&mut self) {
+        self.repeated.clear();
+        if self.diff_elements == 0 {
+            return;
+        }
+        let mut repeated = match self.padding {
+            PaddingStrategy::RepeatLast => self.arr[0][self.diff_elements - 1],
+            PaddingStrategy::ZeroHash => ZERO_LEAF,
+            PaddingStrategy::PromoteOdd => return,
+        };
+        for _ in 0..self.padded_len().ilog2() {
+            self.repeated.push(repeated);
+            repeated = combine_with(&self.hasher, self.ordering, repeated, repeated);
+        }
+    }
 
-        let elem23 = concatenate_elements(elem2_hash, elem3_hash);
-        let elem23_hash = hash_element(elem23);
+    /// Hashes the parent of the last node of a level with an odd len,
+    /// which has no sibling stored. When the padding repeats the last
+    /// element the sibling only covers copies of it, and when the odd
+    /// nodes are promoted the parent is the node as it is. With zero
+    /// padding the sibling only covers `ZERO_LEAF`s.
+    ///
+    /// ### Arguments
+    ///
+    /// - `level`: The level of the node.
+    /// - `node`: The hash of the node.
+    fn lone_parent(&self, level: usize, node: u64) -> u64 {
+        match self.padding {
+            PaddingStrategy::RepeatLast | PaddingStrategy::ZeroHash => combine_with(&self.hasher, self.ordering, node, self.repeated[level]),
+            PaddingStrategy::PromoteOdd => node,
+        }
+    }
 
-        let level_1 = vec![elem01_hash, elem23_hash];
+    /// Gets how the children of each node are ordered before hashing them
+    pub fn ordering(&self) -> PairOrdering {
+        self.ordering
+    }
 
-        // Level 2. It only contains one hash which will be the root:
-        // (elem01_hash + elem23_hash) = root_hash
-        let root = concatenate_elements(elem01_hash, elem23_hash);
-        let root_hash = hash_element(root);
+    /// Gets how the levels with an odd quantity of nodes are completed
+    pub fn padding(&self) -> PaddingStrategy {
+        self.padding
+    }
 
-        let level_2 = vec![root_hash];
+    /// Gets the root of the tree, the only hash in the last level. If the
+    /// tree was created with `with_committed_len`, that hash is hashed
+    /// together with the quantity of different elements.
+    ///
+    /// ### Returns
+    ///
+    /// The hash of the root, or None if the tree has no root.
+    pub fn root(&self) -> Option<u64> {
+        let root = match self.arr.last() {
+            Some(root_level) if root_level.len() == 1 => root_level[0],
+            _ => return None,
+        };
+        Some(self.commit_root(root, self.diff_elements))
+    }
 
-        // Push every level so we can have a manually created tree
-        tree.push(level_0);
-        tree.push(level_1);
-        tree.push(level_2);
+    /// Hashes the root of the levels together with a len if the tree
+    /// commits to it, see `with_committed_len`. Otherwise it is the root
+    /// as it is.
+    ///
+    /// ### Arguments
+    ///
+    /// - `root`: The hash of the only node in the last level, or the one a proof leads to.
+    /// - `len`: The quantity of different elements the tree has, or had.
+    pub(super) fn commit_root(&self, root: u64, len: usize) -> u64 {
+        if self.commit_len {
+            commit_len_with(&self.hasher, root, len)
+        } else {
+            root
+        }
+    }
 
-        (tree, merkle)
+    /// Gets the quantity of different elements the root commits to, so a
+    /// verifier can check the len it was told the tree has.
+    ///
+    /// ### Returns
+    ///
+    /// The len of the tree, or None if its root does not commit to it
+    pub fn committed_len(&self) -> Option<usize> {
+        self.commit_len.then_some(self.diff_elements)
     }
 
-    #[test]
-    /// Test if the concatenation differs when changing order of elements
-    fn hash_depends_on_concat_order() {
-        // Declare our elements
-        let elem1 = String::from("Crypto");
-        let elem2 = String::from("Rust");
-        // Hash our elements
-        let hash_1  = hash_element(elem1);
-        let hash_2  = hash_element(elem2);
+    /// Gets the root of the tree as a RootHash, so it can not be mistaken
+    /// with the hash of a leaf.
+    ///
+    /// ### Returns
+    ///
+    /// The RootHash of the tree, or None if the tree has no root.
+    pub fn root_hash(&self) -> Option<RootHash> {
+        self.root().map(RootHash::from_raw)
+    }
 
-        // Create the hash of the concatenation hash_1 + hash_2
-        let concat_12 = concatenate_elements(hash_1, hash_2);
-        let hash_12 = hash_element(concat_12);
-        // Create the hash of the concatenation hash_2 + hash_1
-        let concat_21 = concatenate_elements(hash_2, hash_1);
-        let hash_21 = hash_element(concat_21);
+    /// Gets the root of the tree as 16 lowercase hex digits, the way
+    /// `RootHash` is displayed.
+    ///
+    /// ### Returns
+    ///
+    /// The hex string of the root, or None if the tree has no root.
+    pub fn root_hex(&self) -> Option<String> {
+        self.root().map(to_hex)
+    }
 
-        assert_ne!(hash_12, hash_21);
+    /// Gets the hash of a different element as 16 lowercase hex digits
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the input array.
+    ///
+    /// ### Returns
+    ///
+    /// The hex string of the leaf, or None if the index is not one of a different element.
+    pub fn leaf_hex(&self, index: usize) -> Option<String> {
+        self.get_leaf(index).map(to_hex)
     }
 
-    #[test]
-    /// Test if by passing an input array of 3 items we get one of 4
-    /// items with the last 2 being equal.
-    fn extend_elements_repeats_last_one() {
-        let mut data = vec!["Crypto", "Merkle", "Rust"];
-        let expected_result = vec!["Crypto", "Merkle", "Rust", "Rust"];
-        extend_elements(&mut data);
+    /// Gets the quantity of different elements in the tree, without
+    /// counting the repeated ones used as padding.
+    pub fn len(&self) -> usize {
+        self.diff_elements
+    }
 
-        assert_eq!(data, expected_result);
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.diff_elements == 0
     }
 
-    #[test]
-    /// Test the case where the input array has only value
-    /// 
-    /// The creation of the Merkle Tree with an input array of only one value
-    /// should just contain the hash of that value and nothing else.
-    fn creation_from_arrray_one_value() {
-        let data = vec!["Crypto"];
-        let merkle = MerkleTree::new(data.clone());
+    /// Gets the quantity of elements in the base level, counting the
+    /// repeated ones or the `ZERO_LEAF`s used as padding. It is always a
+    /// power of 2, unless the tree promotes odd nodes instead of padding
+    /// the base level. The padding is not stored, so this is the len the base level
+    /// has for the hashes, not the one it takes in memory.
+    pub fn padded_len(&self) -> usize {
+        padded_len_of(self.padding, self.diff_elements)
+    }
 
-        assert_eq!(merkle.arr.len(), 1);
+    /// Gets the quantity of levels in the tree, counting the base level
+    /// and the root level.
+    pub fn height(&self) -> usize {
+        self.arr.len()
     }
 
-    #[test]
-    /// Test the creation of a Merkle Tree
-    /// 
-    /// We check if the hashes are correct and also if the number of
-    /// levels is the expected when creating a tree from an array of
-    /// 4 initial elements.
-    fn creation_from_arrray() {
-        // We know that when we use an input array of 4 elements
-        // the quantity of levels should be 3.
-        let desired_level_quantity = 3;
-       
-        let (manual_tree, merkle) = manually_create_tree_hashes();
+    /// Gets the quantity of levels above the base level, 0 when the tree
+    /// has a single element or none
+    pub fn depth(&self) -> usize {
+        self.height().saturating_sub(1)
+    }
 
-        // Test every level
-        assert_eq!(merkle.arr[LEVEL_0], manual_tree[LEVEL_0]);
-        assert_eq!(merkle.arr[LEVEL_1], manual_tree[LEVEL_1]);
-        assert_eq!(merkle.arr[LEVEL_2], manual_tree[LEVEL_2]);
-        // Test quantity of levels
-        assert_eq!(merkle.arr.len(), desired_level_quantity);
+    /// Gets the quantity of hashes in the proofs of the tree, one for each
+    /// level above the base level. It is the len of every proof unless the
+    /// tree promotes odd nodes, whose last elements can have shorter ones,
+    /// see `verify_detailed`.
+    pub fn expected_proof_len(&self) -> usize {
+        self.depth()
     }
 
-    #[test]
-    /// Test the creation of a Merkle Tree with an input array that
-    /// has a len that is not a power of 2.
-    /// 
+    /// Checks if two trees have the same root, so they commit to the same
+    /// elements even if they were built in a different way
+    ///
+    /// ### Arguments
+    ///
+    /// - `other`: The tree to compare with.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if both trees have a root and it is the same, false otherwise
+    pub fn same_root(&self, other: &Self) -> bool {
+        self.root().is_some() && self.root() == other.root()
+    }
+
+    /// Gets the hash of a different element in the base level, the one a
+    /// proof of that element is verified with
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the input array.
+    ///
+    /// ### Returns
+    ///
+    /// The hash of the element, or None if the index is not one of a different element
+    pub fn get_leaf(&self, index: usize) -> Option<u64> {
+        if index >= self.diff_elements {
+            return None;
+        }
+        self.arr.first()?.get(index).copied()
+    }
+
+    /// Gets the hash of any element in the base level, the repeated ones
+    /// used as padding included
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the base level.
+    ///
+    /// ### Returns
+    ///
+    /// The hash of the element, or None if the index is outside of the base level
+    pub fn get_padded_leaf(&self, index: usize) -> Option<u64> {
+        self.get_node(0, index)
+    }
+
+    /// Gets the hash of any node of the tree. Level 0 is the base level and
+    /// the last level, `height() - 1`, is the one of the root. The nodes
+    /// that only cover padding are not stored, so their hash is the one
+    /// repeated for their level.
+    ///
+    /// ### Arguments
+    ///
+    /// - `level`: The level of the node.
+    /// - `index`: The index of the node in its level, from left to right.
+    ///
+    /// ### Returns
+    ///
+    /// The hash of the node, or None if there is no node at that position
+    pub fn get_node(&self, level: usize, index: usize) -> Option<u64> {
+        self.arr.node_at(level, index).or_else(|| {
+            let padded_level_len = self.padded_len() >> level;
+            self.repeated.get(level).copied().filter(|_| index < padded_level_len)
+        })
+    }
+
+    /// Gets every node of a level, the ones that only cover padding included
+    ///
+    /// ### Arguments
+    ///
+    /// - `level`: The index of the level, 0 being the base level and `height() - 1` the root level.
+    pub(crate) fn padded_level(&self, level: usize) -> Vec<u64> {
+        (0..).map_while(|index| self.get_node(level, index)).collect()
+    }
+
+    /// Gets the quantity of nodes stored in every level of the tree. The
+    /// nodes that only cover padding are not stored, so they are not counted.
+    pub fn node_count(&self) -> usize {
+        self.arr.iter().map(<[u64]>::len).sum()
+    }
+
+    /// Gets the bytes of the heap taken by the levels of the tree, counting
+    /// the room they have to grow without moving
+    pub fn memory_usage(&self) -> usize {
+        self.arr.memory_usage()
+    }
+
+    /// Checks if an element is one of the different elements of the tree.
+    /// The repeated elements used as padding are not taken into account.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element to look for. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the element is in the tree, false otherwise
+    pub fn contains<T: Hash>(&self, element: &T) -> bool {
+        let hash = hash_with(&self.hasher, element);
+        self.leaves().contains(&hash)
+    }
+
+    /// Checks if a hash is the leaf at an index of the tree, by generating
+    /// the proof of that index and verifying it with the hash
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaf_hash`: The hash of the element, as `get_leaf` returns it.
+    /// - `index`: The index of the element in the input array.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the proof of the index verifies with the
+    /// hash, false otherwise or if the index is not one of a different element
+    pub fn contains_hash(&self, leaf_hash: u64, index: usize) -> bool {
+        self.generate_proof(index).is_ok_and(|proof| self.verify(&proof, leaf_hash))
+    }
+
+    /// Gets the index of the first occurrence of an element in the tree.
+    /// The index never points to a repeated element used as padding, so
+    /// it can be used directly with `generate_proof`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element to look for. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// The index of the element in the base level, or None if it is not in the tree
+    pub fn index_of<T: Hash>(&self, element: &T) -> Option<usize> {
+        let hash = hash_with(&self.hasher, element);
+        self.leaves().iter().position(|leaf| *leaf == hash)
+    }
+
+    /// Gets the indexes of every occurrence of an element in the tree,
+    /// leaving out the repeated elements used as padding.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element to look for. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// A vector with the indexes in ascending order, empty if the element is not in the tree
+    pub fn indices_of<T: Hash>(&self, element: &T) -> Vec<usize> {
+        let hash = hash_with(&self.hasher, element);
+        self.leaves()
+            .iter()
+            .enumerate()
+            .filter(|(_, leaf)| **leaf == hash)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Gets the hashes of the different elements in the base level,
+    /// leaving out the repeated ones used as padding.
+    fn leaves(&self) -> &[u64] {
+        match self.arr.first() {
+            Some(base_level) => &base_level[..self.diff_elements],
+            None => &[],
+        }
+    }
+
+    /// Checks if the hash received is equal to the root of the tree
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `hash_to_check`: A hash that will be compared with the root of
+    ///   the levels, such as the one a proof leads to
+    /// 
+    /// ### Returns
+    /// 
+    /// If the hash is equal to the one of the root, after committing it to
+    /// the len when the tree does, then it returns true, else false.
+    fn is_root(&self, hash_to_check: u64) -> bool {
+        self.root() == Some(self.commit_root(hash_to_check, self.diff_elements))
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a proof
+    /// and a leaf. Every hash of the proof carries the side it goes on.
+    /// 
+    /// The len of the proof is checked before hashing anything, so a proof
+    /// with a hash too many or too few is rejected even if it leads to the
+    /// root, like the proof of an inner node passed as a leaf.
+    /// The side of every hash has to be the one the index of the proof
+    /// gives it, so a proof relabelled with another index is rejected.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `proof`: The Proof of the element, as `generate_proof` returns it.
+    /// - `leaf`: The hash of one of the elements on the input array.
+    /// 
+    /// ### Returns
+    /// 
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+        self.verify_detailed(proof, leaf).is_ok()
+    }
+
+    /// Same as `verify`, telling why the proof failed when it does.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The Proof of the element, as `generate_proof` returns it.
+    /// - `leaf`: The hash of one of the elements on the input array.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is Ok if the root can be obtained with that information,
+    /// or a VerifyError with the first check that failed
+    pub fn verify_detailed(&self, proof: &Proof, leaf: u64) -> Result<(), VerifyError> {
+        let index = proof.leaf_index();
+        // If the index is equal or larger than the quantity of different elements
+        // it means that the index is invalid.
+        let root = match self.root() {
+            Some(root) if index < self.diff_elements => root,
+            _ => return Err(VerifyError::InvalidIndex { index, len: self.diff_elements }),
+        };
+        let levels = self.sibling_levels(index);
+        if proof.len() != levels.len() {
+            return Err(VerifyError::WrongProofLength { expected: levels.len(), got: proof.len() });
+        }
+        let Some(folded) = proof.fold_at_levels(&self.hasher, self.ordering, &levels, leaf) else {
+            return Err(VerifyError::WrongSide { index });
+        };
+        let computed = self.commit_root(folded, self.diff_elements);
+        if computed != root {
+            return Err(VerifyError::RootMismatch { computed, expected: root });
+        }
+        Ok(())
+    }
+
+    /// Gets the levels where the path of an element has a sibling, one for
+    /// each hash of its proof. Levels where the node is promoted have no
+    /// sibling, so they add no hash.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the original input array.
+    fn sibling_levels(&self, index: usize) -> Vec<usize> {
+        (0..self.height().saturating_sub(1)).filter(|level| self.get_node(*level, (index >> level) ^ 1).is_some()).collect()
+    }
+
+    /// Gets the quantity of hashes in the proof of an element, without
+    /// collecting its levels like `sibling_levels` does.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the original input array.
+    fn proof_len(&self, index: usize) -> usize {
+        (0..self.height().saturating_sub(1)).filter(|level| self.get_node(*level, (index >> level) ^ 1).is_some()).count()
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of the
+    /// hashes of a proof, a leaf and its index on the input array. The side
+    /// of each hash is taken from the parity of the index.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `proofs`: A vector of hashes that make up the proof to get to the root.
+    /// - `leaf_index`: The index in the input array of the received leaf.
+    /// - `leaf`: The hash of one of the elements on the input array.
+    /// 
+    /// ### Returns
+    /// 
+    /// A bool that is true if the root can be obtained with that information,
+    /// false otherwise or if the proof does not have one hash for each level
+    /// where the path of the leaf has a sibling
+    #[deprecated(note = "use `verify` with the Proof returned by `generate_proof`")]
+    pub fn verify_hashes(&self, proofs: Vec<u64>, leaf_index: usize, leaf: u64) -> bool {
+        // If the index is equal or larger than the quantity of different elements
+        // it means that the index is invalid.
+        if leaf_index >= self.diff_elements {
+            return false;
+        }
+        match (self.ordering, self.padding) {
+            (PairOrdering::Positional, PaddingStrategy::RepeatLast | PaddingStrategy::ZeroHash) => {
+                proofs.len() == self.proof_len(leaf_index) && self.is_root(fold_proof_with(&self.hasher, &proofs, leaf_index, leaf))
+            }
+            (PairOrdering::Positional, PaddingStrategy::PromoteOdd) => {
+                fold_promoted_proof_with(&self.hasher, &proofs, leaf_index, self.diff_elements, leaf)
+                    .is_some_and(|root| self.is_root(root))
+            }
+            (PairOrdering::Sorted, _) => {
+                proofs.len() == self.proof_len(leaf_index) && self.is_root(fold_sorted_proof_with(&self.hasher, &proofs, leaf))
+            }
+        }
+    }
+
+    /// Checks if the root of a tree created with `new_sorted` can be obtained
+    /// with a proof and a leaf. Since the children of every node are sorted,
+    /// the index of the leaf is not needed to know where each hash goes.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `proofs`: A vector of hashes that make up the proof to get to the root.
+    /// - `leaf`: The hash of one of the elements on the input array.
+    /// 
+    /// ### Returns
+    /// 
+    /// A bool that is true if the root can be obtained with that information, false
+    /// otherwise, if the proof has more hashes than levels above the leaves or if
+    /// the tree does not sort the children of its nodes
+    pub fn verify_sorted(&self, proofs: Vec<u64>, leaf: u64) -> bool {
+        // Without the index the exact len is only known when every level has a sibling
+        let len_fits = match self.padding {
+            PaddingStrategy::PromoteOdd => proofs.len() <= self.expected_proof_len(),
+            _ => proofs.len() == self.expected_proof_len(),
+        };
+        self.ordering == PairOrdering::Sorted && len_fits && self.is_root(fold_sorted_proof_with(&self.hasher, &proofs, leaf))
+    }
+
+    /// Checks a proof like `verify`, but first checks it against some
+    /// limits so a proof received from an untrusted source is rejected
+    /// before any hashing happens.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The Proof of the element, as `generate_proof` returns it.
+    /// - `leaf`: The hash of one of the elements on the input array.
+    /// - `limits`: The limits the proof has to respect.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the same bool `verify` returns, or an Error if the
+    /// proof exceeds the limits
+    pub fn verify_with_limits(&self, proof: &Proof, leaf: u64, limits: &Limits) -> Result<bool, MerkleError> {
+        limits.check_proof_entries(proof.len())?;
+        Ok(self.verify(proof, leaf))
+    }
+
+    /// Generates the Proof for a specific element in the tree: the hashes
+    /// that together form the path to the root, each one with the side it
+    /// goes on. With this proof and the hash of the element, the tree's root
+    /// can be obtained.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `hash_index`: The index of the element in the original input array
+    /// 
+    /// ### Returns
+    /// 
+    /// A Result that contains the Proof or an Error explaining
+    /// what was the problem. The Error is PaddedIndex if the index is
+    /// one of the copies of the last element added as padding.
+    pub fn generate_proof(&self, hash_index: usize) -> Result<Proof, MerkleError> {
+        self.check_element_index(hash_index)?;
+        Ok(self.proof_at(hash_index))
+    }
+
+    /// Generates the Proof for any slot of the base level, including the
+    /// copies of the last element added as padding. A proof of a copy
+    /// verifies with the hash of the last element, so this is only for
+    /// callers that really want to prove the duplicate.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `hash_index`: The index of the slot in the base level.
+    /// 
+    /// ### Returns
+    /// 
+    /// A Result that contains the Proof, or an Error if the index is
+    /// outside of the base level
+    pub fn generate_proof_padded(&self, hash_index: usize) -> Result<Proof, MerkleError> {
+        if hash_index >= self.padded_len() {
+            return Err(MerkleError::InvalidIndex { index: hash_index, len: self.diff_elements });
+        }
+        Ok(self.proof_at(hash_index))
+    }
+
+    /// Generates the proof of a whole subtree: the hashes of the path
+    /// from one of its nodes up to the root. The node is the root of an
+    /// aligned block of 2^level elements, and it takes the place of the
+    /// leaf when the proof is verified, see `verify_subtree`.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `level`: The level of the node, 0 being the base level.
+    /// - `index`: The index of the node in its level.
+    /// 
+    /// ### Returns
+    /// 
+    /// A Result with the hashes from the level of the node up to the root,
+    /// or an Error. It is PaddedIndex if the node only covers padding, and
+    /// UnsupportedPadding if the tree promotes odd nodes, since then some
+    /// levels have no sibling.
+    pub fn generate_subtree_proof(&self, level: usize, index: usize) -> Result<Vec<u64>, MerkleError> {
+        if self.padding == PaddingStrategy::PromoteOdd {
+            return Err(MerkleError::UnsupportedPadding);
+        }
+        let level_len = self.arr.level_slice(level).map_or(0, <[u64]>::len);
+        if index >= level_len {
+            if level < self.height() && index < self.padded_len() >> level {
+                return Err(MerkleError::PaddedIndex { index });
+            }
+            return Err(MerkleError::InvalidIndex { index, len: level_len });
+        }
+        Ok((level..self.height() - 1).filter_map(|i| self.get_node(i, (index >> (i - level)) ^ 1)).collect())
+    }
+
+    /// Collects the siblings of the path from a slot of the base level
+    /// up to the root, the index must be inside the base level
+    fn proof_at(&self, mut hash_index: usize) -> Proof {
+        let leaf_index = hash_index;
+        let mut proof_index: usize;
+        let mut side: Side;
+        let mut siblings = Vec::new();
+        // The root level does not go on the proof
+        for level in 0..self.height().saturating_sub(1) {
+            if hash_index.is_multiple_of(2) {
+                proof_index = hash_index + 1;
+                side = Side::Right;
+            } else {
+                proof_index = hash_index - 1;
+                side = Side::Left;
+            }
+            // The last node of a level with an odd len has no sibling when
+            // the odd nodes are promoted, it goes to the next level as it is.
+            if let Some(proof_hash) = self.get_node(level, proof_index) {
+                siblings.push((proof_hash, side));
+            }
+            hash_index /= 2;
+        }
+        Proof::new(leaf_index, siblings)
+    }
+
+    /// Generates the Proof for the first occurrence of an element in the
+    /// tree, so the caller does not need to keep track of its index.
+    /// Repeated elements used as padding are never matched.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element to prove. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof, whose `leaf_index` is the index of the
+    /// element, or a NotFound Error if the element is not in the tree
+    pub fn generate_proof_for<T: Hash>(&self, element: &T) -> Result<Proof, MerkleError> {
+        let index = self.index_of(element).ok_or(MerkleError::NotFound)?;
+        self.generate_proof(index)
+    }
+
+    /// Same as `generate_proof`, with the hashes of the proof as 16
+    /// lowercase hex digits each and without their sides.
+    pub fn proof_hex(&self, hash_index: usize) -> Result<Vec<String>, MerkleError> {
+        Ok(self.generate_proof(hash_index)?.siblings().iter().map(|(hash, _)| to_hex(*hash)).collect())
+    }
+
+    /// Generates the hashes of the proof for a specific element in the
+    /// tree, without their sides.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `hash_index`: The index of the element in the original input array
+    /// 
+    /// ### Returns
+    /// 
+    /// A Result that contains the vector with the hashes or an Error explaining
+    /// what was the problem 
+    #[deprecated(note = "use `generate_proof`, which returns a Proof")]
+    pub fn generate_proof_hashes(&self, hash_index: usize) -> Result<Vec<u64>, MerkleError> {
+        self.generate_proof(hash_index).map(|proof| proof.hashes())
+    }
+
+    /// Adds an element to the tree
+    /// 
+    /// Only the different elements are stored, so the new element is
+    /// pushed at the end of the base level and the last node of every
+    /// level above it is re-calculated. The copies of the new element that
+    /// pad the base level are never written: a node that only covers them
+    /// has the hash repeated for its level. This way adding elements one
+    /// by one takes O(log n) hashes per element.
+    /// 
+    /// When the base level already had 2^n different elements the new one
+    /// starts its right half, so a new root level is added on top.
+    /// 
+    /// Adding an element to an empty tree gives the same tree `new` gives
+    /// for that single element. A tree with a single element has 2^0
+    /// different elements and its only level is also the root level, so
+    /// the second element makes it a base level of 2 and a root
+    /// level above it.
+    pub fn add_element<T: Hash + Clone>(&mut self, new_elem: T) {
+        self.record_root();
+        let new_hash = hash_with(&self.hasher, new_elem);
+        self.push_leaf(new_hash);
+    }
+
+    /// Adds many elements to the tree at once
+    /// 
+    /// The base level is updated exactly like calling `add_element` for
+    /// each element would, but none of the levels above it is hashed until
+    /// every element is in place. Then only the nodes to the right of the
+    /// first changed element are re-calculated, once. The resulting tree is
+    /// the same one the repeated calls would give.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `new_elems`: A vector with the elements to add, in order.
+    pub fn add_elements<T: Hash + Clone>(&mut self, new_elems: Vec<T>) {
+        if new_elems.is_empty() {
+            return;
+        }
+        self.record_root();
+        let first_changed = self.diff_elements;
+        if self.arr.is_empty() {
+            self.arr.push([]);
+        }
+        for new_elem in new_elems {
+            let new_hash = hash_with(&self.hasher, new_elem);
+            self.arr.push_node(0, new_hash);
+            self.diff_elements += 1;
+        }
+        self.refresh_repeated();
+        self.rehash_from(first_changed);
+    }
+
+    /// Adds every element of another tree after the elements of this one,
+    /// like building a tree per shard and joining them in order
+    /// 
+    /// A node of `other` covers the same elements in the merged tree when
+    /// the len of this tree is a multiple of the elements it covers. So
+    /// the levels of `other` are appended to the ones of this tree up to
+    /// the highest level where that happens, and only the nodes above
+    /// them are re-calculated. When the len of this tree is a power of 2
+    /// every level of `other` is reused and only its root is glued to this
+    /// tree, like `add_element` does. The resulting tree is the one `new`
+    /// gives for all the elements.
+    /// 
+    /// The hashes of `other` are taken as they are, so both trees have to
+    /// hash with the same hasher. Its nodes are only reused if it orders and
+    /// pads them like this tree does, otherwise only its leaves are.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `other`: The tree whose elements go after the ones of this tree.
+    pub fn merge(&mut self, other: Self) {
+        if other.is_empty() {
+            return;
+        }
+        self.record_root();
+        let offset = self.diff_elements;
+        let reused_top = if other.ordering == self.ordering && other.padding == self.padding {
+            (other.height() - 1).min(offset.trailing_zeros() as usize)
+        } else {
+            0
+        };
+        for (level, nodes) in other.arr.iter().take(reused_top + 1).enumerate() {
+            if level == self.arr.len() {
+                self.arr.push([]);
+            }
+            for node in nodes {
+                self.arr.push_node(level, *node);
+            }
+        }
+        self.diff_elements += other.diff_elements;
+        self.refresh_repeated();
+        self.rehash_above(reused_top, offset >> reused_top);
+    }
+
+    /// Removes the last different element of the tree, undoing `add_element`
+    /// 
+    /// The element is removed from the base level and the last node of
+    /// every level above it is re-calculated, now padded with copies of the
+    /// element before it. If the element was the first one of the right
+    /// half of the base level, that half only had padding left. So the root
+    /// is dropped, and the tree goes back to the height it had before the
+    /// element was added.
+    /// 
+    /// ### Returns
+    /// 
+    /// The hash of the removed element, or None if the tree is empty
+    pub fn pop_element(&mut self) -> Option<u64> {
+        let removed = self.get_leaf(self.diff_elements.checked_sub(1)?)?;
+        self.record_root();
+        self.diff_elements -= 1;
+        self.forget_slot(self.diff_elements);
+        if self.diff_elements == 0 {
+            self.arr.clear();
+            self.refresh_repeated();
+            return Some(removed);
+        }
+        self.pop_leaf();
+        Some(removed)
+    }
+
+    /// Removes the last hash of the base level, and re-calculates the last
+    /// node of every level above it. The levels that are no longer needed
+    /// are dropped.
+    fn pop_leaf(&mut self) {
+        self.arr.pop_node(0);
+        self.refresh_repeated();
+        let mut level = 0;
+        while self.arr[level].len() > 1 {
+            let len = self.arr[level].len();
+            let last = self.arr[level][len - 1];
+            let parent = if len.is_multiple_of(2) {
+                combine_with(&self.hasher, self.ordering, self.arr[level][len - 2], last)
+            } else {
+                self.lone_parent(level, last)
+            };
+            self.arr.truncate_level(level + 1, len.div_ceil(2));
+            self.arr[level + 1][len.div_ceil(2) - 1] = parent;
+            level += 1;
+        }
+        self.arr.truncate(level + 1);
+    }
+
+    /// Replaces an element of the tree with a new one
+    /// 
+    /// Only the nodes in the path from the element to the root are
+    /// re-calculated. If the element is the last one, the padding is made
+    /// of copies of it, so their hashes are re-calculated too.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `index`: The index of the element in the input array.
+    /// - `new_elem`: The element that replaces the old one.
+    /// 
+    /// ### Returns
+    /// 
+    /// A Result that is an Error if the index is not one of a different element
+    pub fn update_element<T: Hash>(&mut self, index: usize, new_elem: T) -> Result<(), MerkleError> {
+        if index >= self.diff_elements {
+            return Err(MerkleError::InvalidIndex { index, len: self.diff_elements });
+        }
+        self.record_root();
+        let new_hash = hash_with(&self.hasher, new_elem);
+        self.write_leaf(index, new_hash);
+        Ok(())
+    }
+
+    /// Writes the hash of a different element in the base level and
+    /// re-calculates the path from it to the root.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `index`: The index of the element in the input array.
+    /// - `leaf_hash`: The new hash of the element.
+    fn write_leaf(&mut self, index: usize, leaf_hash: u64) {
+        self.arr[0][index] = leaf_hash;
+        // The padding may be made of copies of the last element
+        if index + 1 == self.diff_elements {
+            self.refresh_repeated();
+        }
+        self.rehash_path(index);
+    }
+
+    /// Pushes a new hash at the end of the base level, and re-calculates
+    /// the last node of every level above it.
+    /// 
+    /// The last node of each level is either the parent of the last two
+    /// nodes of the level below or, if that level has an odd len, the
+    /// parent of its last node and the padding, see `lone_parent`. When it
+    /// does not exist yet it is pushed, which also creates a new root level
+    /// once the old root gets a sibling.
+    /// 
+    /// ### Arguments
+    /// 
+    /// - `new_hash`: The hash of the new element.
+    fn push_leaf(&mut self, new_hash: u64) {
+        if self.arr.is_empty() {
+            self.arr.push([]);
+        }
+        self.arr.push_node(0, new_hash);
+        self.diff_elements += 1;
+        self.refresh_repeated();
+        let mut level = 0;
+        while self.arr[level].len() > 1 {
+            let len = self.arr[level].len();
+            let last = self.arr[level][len - 1];
+            let parent = if len.is_multiple_of(2) {
+                combine_with(&self.hasher, self.ordering, self.arr[level][len - 2], last)
+            } else {
+                self.lone_parent(level, last)
+            };
+            if level + 1 == self.arr.len() {
+                self.arr.push([]);
+            }
+            let parent_index = (len - 1) / 2;
+            if parent_index < self.arr[level + 1].len() {
+                self.arr[level + 1][parent_index] = parent;
+            } else {
+                self.arr.push_node(level + 1, parent);
+            }
+            level += 1;
+        }
+    }
+
+    /// Re-calculates every node that is above or to the right of the path
+    /// that goes from a base level node up to the root.
+    ///
+    /// The nodes to the left of the path are kept, and each level is
+    /// completed from the level below it. So the levels grow or shrink to
+    /// fit the base level, adding or dropping levels at the top if needed.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index in the base level of the first node that was changed.
+    fn rehash_from(&mut self, index: usize) {
+        self.rehash_above(0, index);
+    }
+
+    /// Re-calculates every node that is above a level and to the right of
+    /// the path that goes from one of its nodes up to the root. The nodes
+    /// of that level and the ones below it are kept.
+    ///
+    /// ### Arguments
+    ///
+    /// - `level`: The level that is already up to date.
+    /// - `index`: The index in that level of the first node that was changed.
+    fn rehash_above(&mut self, level: usize, mut index: usize) {
+        let mut i = level + 1;
+        while self.arr[i - 1].len() > 1 {
+            index /= 2;
+            if i == self.arr.len() {
+                self.arr.push([]);
+            }
+            self.arr.truncate_level(i, index);
+            for parent_index in index..self.arr[i - 1].len().div_ceil(2) {
+                let children = &self.arr[i - 1];
+                let parent = match children.get(2 * parent_index + 1) {
+                    Some(right) => combine_with(&self.hasher, self.ordering, children[2 * parent_index], *right),
+                    None => self.lone_parent(i - 1, children[2 * parent_index]),
+                };
+                self.arr.push_node(i, parent);
+            }
+            i += 1;
+        }
+        self.arr.truncate(i);
+    }
+
+    /// Re-calculates every node in the path that goes from a base level
+    /// node up to the root.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index in the base level of the node that was changed.
+    fn rehash_path(&mut self, mut index: usize) {
+        for i in 1..self.arr.len() {
+            let left_index = index - index % 2;
+            let parent = match self.arr[i - 1].get(left_index + 1) {
+                Some(right) => combine_with(&self.hasher, self.ordering, self.arr[i - 1][left_index], *right),
+                None => self.lone_parent(i - 1, self.arr[i - 1][left_index]),
+            };
+            index /= 2;
+            self.arr[i][index] = parent;
+        }
+    }
+}
+
+/// Checks if a root can be obtained with the use of a proof and a leaf,
+/// without having the tree. This is all a verifier needs: the trusted
+/// root, the quantity of elements of the tree and the proof that came
+/// along with the element. The hashing is the one of a tree created with
+/// `MerkleTree::new`.
+///
+/// The len tells how many hashes the proof must have, so the hash of an
+/// inner node passed as a leaf, with the rest of its path as the proof, is
+/// rejected, and the index of the proof has to be one of the elements. It
+/// has to come from the same trusted source as the root.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the element should belong to.
+/// - `len`: The quantity of different elements of the tree.
+/// - `proof`: The Proof of the element, as `generate_proof` returns it.
+/// - `leaf`: The hash of the element.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_against_root(root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    verify_against_root_with(&DefaultBuildHasher::default(), PairOrdering::Positional, root, len, proof, leaf)
+}
+
+/// Checks if a root can be obtained with the use of a subtree proof and
+/// the root of that subtree, without having the tree. The hashing is the
+/// one of a tree created with `MerkleTree::new`.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the subtree should belong to.
+/// - `proof`: The hashes of the path from the subtree up to the root, as `generate_subtree_proof` returns them.
+/// - `level`: The level of the root of the subtree, 0 being the base level.
+/// - `index`: The index of the root of the subtree in its level.
+/// - `subtree_root`: The hash of the root of the subtree.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_subtree(root: u64, proof: &[u64], level: usize, index: usize, subtree_root: u64) -> bool {
+    // The index has to be one of a node in a tree with that height
+    let height = level + proof.len();
+    height < usize::BITS as usize && index >> proof.len() == 0 && fold_proof(proof, index, subtree_root) == root
+}
+
+/// Same as `verify_against_root` for a tree created with
+/// `MerkleTree::with_committed_len`, whose root commits to the len. Proofs
+/// of trees that promote odd nodes have no hash on some levels, so they
+/// only verify with the tree.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the element should belong to.
+/// - `len`: The quantity of different elements the tree claims to have.
+/// - `proof`: The Proof of the element, as `generate_proof` returns it.
+/// - `leaf`: The hash of the element.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information
+/// and that len, false otherwise
+pub fn verify_committed_against_root(root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    verify_committed_against_root_with(&DefaultBuildHasher::default(), PairOrdering::Positional, root, len, proof, leaf)
+}
+
+/// Hashes an element the way the trees with the default hasher hash their
+/// leaves, so the hash can be checked against a proof without the tree.
+///
+/// ### Arguments
+///
+/// - `element`: The element of the tree.
+///
+/// ### Returns
+///
+/// The hash of the leaf of the element
+pub fn hash_leaf<T: Hash>(element: T) -> u64 {
+    hash_element(element)
+}
+
+/// Gets the root that a proof leads to when starting from a leaf.
+///
+/// ### Arguments
+///
+/// - `proofs`: The hashes that make up the proof to get to the root.
+/// - `leaf_index`: The index in the input array of the received leaf.
+/// - `leaf`: The hash of one of the elements on the input array.
+///
+/// ### Returns
+///
+/// The hash obtained after combining the leaf with every hash in the proof
+pub(crate) fn fold_proof(proofs: &[u64], leaf_index: usize, leaf: u64) -> u64 {
+    fold_proof_with(&DefaultBuildHasher::default(), proofs, leaf_index, leaf)
+}
+
+/// Same as `fold_proof`, hashing with the hasher received.
+pub(crate) fn fold_proof_with<H: BuildHasher>(hasher: &H, proofs: &[u64], leaf_index: usize, leaf: u64) -> u64 {
+    let mut hash_index = leaf_index;
+    let mut hash = leaf;
+    let mut concatenation: [u8; 16];
+    for proof in proofs {
+
+        if hash_index.is_multiple_of(2) {
+            // We know that if the index is even, the proof is on the right: hash + proof
+            concatenation = concatenate_elements(hash, *proof);
+        } else {
+            // We know that if the index is odd, the proof is on the left: proof + hash
+            concatenation = concatenate_elements(*proof, hash);
+        }
+
+        // Get the new hash and update the index for the next level 
+        hash = hash_node_with(hasher, concatenation);
+        hash_index /= 2;
+    }
+    hash
+}
+
+/// Same as `fold_proof_with` for a tree that promotes the last node of the
+/// levels with an odd len. The quantity of leaves tells on which levels the
+/// path of the leaf is promoted, and so takes no hash from the proof.
+///
+/// ### Returns
+///
+/// The hash obtained after combining the leaf with every hash in the proof,
+/// or None if the proof does not have exactly one hash for each level where
+/// the path of the leaf has a sibling
+pub(crate) fn fold_promoted_proof_with<H: BuildHasher>(hasher: &H, proofs: &[u64], leaf_index: usize, leaf_count: usize, leaf: u64) -> Option<u64> {
+    let mut proofs = proofs.iter();
+    let mut hash_index = leaf_index;
+    let mut level_len = leaf_count;
+    let mut hash = leaf;
+    while level_len > 1 {
+        if !hash_index.is_multiple_of(2) {
+            hash = combine_with(hasher, PairOrdering::Positional, *proofs.next()?, hash);
+        } else if hash_index + 1 < level_len {
+            hash = combine_with(hasher, PairOrdering::Positional, hash, *proofs.next()?);
+        }
+        // Otherwise the node is the last one of a level with an odd len and it is promoted
+        hash_index /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+    proofs.next().is_none().then_some(hash)
+}
+
+/// Same as `fold_proof` for a tree that sorts the children of its nodes,
+/// where the leaf index is not needed.
+pub(crate) fn fold_sorted_proof_with<H: BuildHasher>(hasher: &H, proofs: &[u64], leaf: u64) -> u64 {
+    proofs.iter().fold(leaf, |hash, proof| combine_with(hasher, PairOrdering::Sorted, hash, *proof))
+}
+
+/// Hashes an element
+/// 
+/// The element is prefixed with `LEAF_PREFIX`, while the parents are
+/// prefixed with `NODE_PREFIX`. This way the concatenation of two children
+/// can not be presented as an element to forge a proof.
+/// 
+/// ### Arguments
+/// 
+/// - `element`: An element that implements the trait Hash
+/// 
+/// ### Returns
+/// 
+/// An u64 that represents the hash of the element
+pub(crate) fn hash_element<T: Hash>(element: T) -> u64 {
+    hash_with(&DefaultBuildHasher::default(), element)
+}
+
+/// Hashes the concatenation of two children into their parent
+/// 
+/// ### Arguments
+/// 
+/// - `concatenated`: The result of `concatenate_elements` with the two children.
+/// 
+/// ### Returns
+/// 
+/// An u64 that represents the hash of the parent
+pub(crate) fn hash_node(concatenated: [u8; 16]) -> u64 {
+    hash_node_with(&DefaultBuildHasher::default(), concatenated)
+}
+
+/// Gets the difference between 'num' and the next closest number that is
+/// a power of 2
+/// 
+/// ### Arguments
+/// 
+/// - `num`: The number that we will use to get the next power of 2
+/// 
+/// ### Returns
+/// 
+/// An usize that represents the difference that needs to be added so 'num'
+/// can reach the closes power of 2 (that is bigger than 'num'). It is 0
+/// when 'num' already is a power of 2.
+/// 
+/// ### Panics
+/// 
+/// If the next power of 2 does not fit in an usize
+pub(crate) fn diff_to_power_of_2(num: usize) -> usize {
+    let power = num.checked_next_power_of_two().expect("The len can not be padded to a power of 2");
+    power - num
+}
+
+/// Gets the len of a base level with `diff_elements` different elements,
+/// counting the padding
+/// 
+/// ### Arguments
+/// 
+/// - `padding`: How the levels with an odd quantity of nodes are completed.
+/// - `diff_elements`: Quantity of different elements in the base level.
+pub(crate) fn padded_len_of(padding: PaddingStrategy, diff_elements: usize) -> usize {
+    match padding {
+        PaddingStrategy::RepeatLast | PaddingStrategy::ZeroHash if diff_elements > 0 => diff_elements + diff_to_power_of_2(diff_elements),
+        _ => diff_elements,
+    }
+}
+
+/// Extends the elements vector so it has a len of
+/// equal to a power of 2, if necessary
+/// 
+/// First we need to find the exponent that would give us
+/// a close value to the elements len. Once we have this, we
+/// can get the difference between the closest power of 2 and
+/// the current len. That difference is the amount of copies
+/// of the last element we have to add to make the len of to be a
+/// power of 2. Only the last element is repeated, so every
+/// slot after the different elements holds the same padding.
+/// 
+/// ### Arguments
+/// 
+/// - `elements`: A vector with the elements that will be hashed and form the first level in the tree
+pub(crate) fn extend_elements<T: Hash + Clone>(elements: &mut Vec<T>) { // TODO: Check if this function should be inside the impl
+    let diff = diff_to_power_of_2(elements.len());
+    if let Some(last) = elements.last().cloned() {
+        // Add 'diff' copies of the last element to the elements vector
+        elements.resize(elements.len() + diff, last);
+    }
+}
+
+/// Creates the first level of the Merkle Tree.
+/// 
+/// Hashes all the input elements, once each. The copies of the last one
+/// that pad the level are not added, see `create_remaining_levels`.
+/// 
+/// ### Arguments
+/// 
+/// - `hasher`: Builds the hasher used for the elements.
+/// - `elements`: A vector with the elements that will be hashed and form the first level in the tree
+/// 
+/// ### Returns
+/// 
+/// A vector full of the hashes of the elements. This vector represents the first
+/// level of the Merkle Tree
+fn create_first_level<H: BuildHasher, T: Hash + Clone>(hasher: &H, elements: Vec<T>) -> Vec<u64> { // TODO: Check if this function should be inside the impl
+    elements.iter().map(|elem| {
+        hash_with(hasher, elem)
+    }).collect()
+}
+
+/// Uses the first level of the tree to create the remaining levels.
+/// Each new level uses the one before. When a level has an odd len its
+/// last node has no sibling, because the one it would have only covers
+/// copies of the last element. That sibling is not stored: it is the
+/// hash of the copies, which is the same for every node like it in the
+/// level and is combined with itself to get the one of the level above.
+/// 
+/// ### Arguments
+/// 
+/// - `hashed_elements`: A vector full of hashes representing the first level of the tree
+/// 
+/// ### Returns
+/// 
+/// The levels of the tree, each one with the exact room it needs,
+/// starting from the first to the last (the root).
+pub(crate) fn create_remaining_levels(hashed_elements: Vec<u64>) -> TreeStructure { // TODO: Check if this function should be inside the impl
+    create_remaining_levels_with(&DefaultBuildHasher::default(), PairOrdering::Positional, PaddingStrategy::RepeatLast, hashed_elements)
+}
+
+/// Same as `create_remaining_levels`, hashing with the hasher received.
+/// When the padding promotes odd nodes, the last node of a level with an
+/// odd len goes to the next level as it is, and with zero padding it is
+/// paired with the node that only covers `ZERO_LEAF`s.
+pub(crate) fn create_remaining_levels_with<H: BuildHasher>(hasher: &H, ordering: PairOrdering, padding: PaddingStrategy, hashed_elements: Vec<u64>) -> TreeStructure {
+    // We create the structure that will contain each level of the tree,
+    // with the exact room every level needs.
+    // Then we add the first level (the already hashed elements we have).
+    let mut level_lens = vec![hashed_elements.len()];
+    while let Some(&len) = level_lens.last().filter(|len| **len > 1) {
+        level_lens.push(len.div_ceil(2));
+    }
+    let mut tree_structure = TreeStructure::with_capacity(level_lens);
+    // The hash of a node that only covers padding
+    let mut repeated = match padding {
+        PaddingStrategy::ZeroHash => ZERO_LEAF,
+        _ => hashed_elements.last().copied().unwrap_or_default(),
+    };
+    tree_structure.push(hashed_elements);
+
+    // Each level creates the next level. So we iter each level by taking
+    // chunks of size 2, concatenating this chunks and hashing the concatenation.
+    // Each parent is written right into the structure, reading its children
+    // from the level below by reference.
+    while tree_structure.last().is_some_and(|level| level.len() > 1) {
+        let level = tree_structure.len() - 1;
+        tree_structure.push([]);
+        for parent_index in 0..tree_structure[level].len().div_ceil(2) {
+            let children = &tree_structure[level];
+            let parent = match (children.get(2 * parent_index + 1), padding) {
+                (Some(right), _) => combine_with(hasher, ordering, children[2 * parent_index], *right),
+                (None, PaddingStrategy::RepeatLast | PaddingStrategy::ZeroHash) => combine_with(hasher, ordering, children[2 * parent_index], repeated),
+                (None, PaddingStrategy::PromoteOdd) => children[2 * parent_index],
+            };
+            tree_structure.push_node(level + 1, parent);
+        }
+        repeated = combine_with(hasher, ordering, repeated, repeated);
+    }
+    tree_structure
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const LEVEL_0: usize = 0;
+    const LEVEL_1: usize = 1;
+    const LEVEL_2: usize = 2;
+
+
+    /// Manually generates the tree structure while also generating a tree.
+    /// All with the same input array, which has 4 elements in total.
+    /// The structure should look like this:
+    /// 
+    ///             [
+    /// LEVEL 0         [elem0_hash, elem1_hash, elem2_hash, elem3_haash],
+    /// LEVEL 1         [elem01_hash, elem23_hash],
+    /// LEVEL 2         [elem0123_hash],
+    ///             ]
+    /// From this we can see how the level 0 contains every hash of the elements
+    /// while level 2 has the root.
+    /// 
+    fn manually_create_tree_hashes() -> (TreeStructure, MerkleTree) {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let mut tree = TreeStructure::new();
+        let merkle = MerkleTree::new(data.clone());
+        // Get the hashes of the elements and manually create the tree structure
+        // Level 0. It has the hashes of every element
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+        let elem2_hash = hash_element(data[2]);
+        let elem3_hash = hash_element(data[3]);
+
+        let level_0 = vec![elem0_hash, elem1_hash, elem2_hash, elem3_hash];
+
+        // Level 1. It has the hashes of:
+        // (elem0_hash + elem1_hash) = elem01_hash 
+        // (elem2_hash + elem3_hash) = elem23_hash
+        let elem01 = concatenate_elements(elem0_hash, elem1_hash);
+        let elem01_hash = hash_node(elem01);
+
+        let elem23 = concatenate_elements(elem2_hash, elem3_hash);
+        let elem23_hash = hash_node(elem23);
+
+        let level_1 = vec![elem01_hash, elem23_hash];
+
+        // Level 2. It only contains one hash which will be the root:
+        // (elem01_hash + elem23_hash) = root_hash
+        let root = concatenate_elements(elem01_hash, elem23_hash);
+        let root_hash = hash_node(root);
+
+        let level_2 = vec![root_hash];
+
+        // Push every level so we can have a manually created tree
+        tree.push(level_0);
+        tree.push(level_1);
+        tree.push(level_2);
+
+        (tree, merkle)
+    }
+
+    #[test]
+    /// Test if the concatenation differs when changing order of elements
+    fn hash_depends_on_concat_order() {
+        // Declare our elements
+        let elem1 = String::from("Crypto");
+        let elem2 = String::from("Rust");
+        // Hash our elements
+        let hash_1  = hash_element(elem1);
+        let hash_2  = hash_element(elem2);
+
+        // Create the hash of the concatenation hash_1 + hash_2
+        let concat_12 = concatenate_elements(hash_1, hash_2);
+        let hash_12 = hash_node(concat_12);
+        // Create the hash of the concatenation hash_2 + hash_1
+        let concat_21 = concatenate_elements(hash_2, hash_1);
+        let hash_21 = hash_node(concat_21);
+
+        assert_ne!(hash_12, hash_21);
+    }
+
+    #[test]
+    /// Test if an internal node can not be presented as an element.
+    ///
+    /// Without domain separation, the concatenation of the two children of a
+    /// node is an element whose hash is that node, so it verifies with the
+    /// proof of the node. Now leaves and parents are hashed with different
+    /// prefixes and the forged element does not verify.
+    fn internal_node_does_not_verify_as_leaf() {
+        let (manual_tree, merkle) = manually_create_tree_hashes();
+        let forged_element = concatenate_elements(manual_tree[LEVEL_0][0], manual_tree[LEVEL_0][1]);
+        let forged_proof = Proof::new(0, vec![(manual_tree[LEVEL_1][1], Side::Right)]);
+
+        assert_eq!(hash_node(forged_element), manual_tree[LEVEL_1][0]);
+        assert_ne!(hash_element(forged_element), manual_tree[LEVEL_1][0]);
+        assert!(!merkle.verify(&forged_proof, hash_element(forged_element)));
+    }
+
+    #[test]
+    /// Test if two different pairs that gave the same decimal concatenation
+    /// now give different parents. Both (1, 23) and (12, 3) used to be "123".
+    fn ambiguous_pairs_do_not_collide() {
+        assert_ne!(concatenate_elements(1, 23), concatenate_elements(12, 3));
+        assert_ne!(hash_node(concatenate_elements(1, 23)), hash_node(concatenate_elements(12, 3)));
+    }
+
+    #[test]
+    /// Test if by passing an input array of 3, 5 or 6 items we get one with
+    /// a len that is a power of 2, where every added item is a copy of the
+    /// last one.
+    fn extend_elements_repeats_last_one() {
+        let mut data = vec!["Crypto", "Merkle", "Rust"];
+        extend_elements(&mut data);
+        assert_eq!(data, vec!["Crypto", "Merkle", "Rust", "Rust"]);
+
+        let mut data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        extend_elements(&mut data);
+        assert_eq!(data, vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Test", "Test", "Test"]);
+
+        let mut data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof"];
+        extend_elements(&mut data);
+        assert_eq!(data, vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof", "Proof", "Proof"]);
+
+        let mut data: Vec<&str> = Vec::new();
+        extend_elements(&mut data);
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    /// Test if every slot of the base level after the different elements
+    /// holds the hash of the last one, for 5 and 6 elements, whether the
+    /// tree is created at once, element by element or with `add_elements`.
+    fn padding_repeats_last_element() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof"];
+        for len in [5, 6] {
+            let merkle = MerkleTree::new(data[..len].to_vec());
+            let last = hash_element(data[len - 1]);
+            assert_eq!(merkle.padded_len(), 8);
+            assert!((len..8).all(|index| merkle.get_padded_leaf(index) == Some(last)));
+
+            let expected = create_remaining_levels(create_first_level(&DefaultBuildHasher::default(), data[..len].to_vec()));
+            assert_eq!(merkle.arr, expected);
+
+            let mut one_by_one = MerkleTree::default();
+            for element in &data[..len] {
+                one_by_one.add_element(element);
+            }
+            assert_eq!(one_by_one, merkle);
+
+            let mut added = MerkleTree::new(data[..3].to_vec());
+            added.add_elements(data[3..len].to_vec());
+            assert_eq!(added, merkle);
+        }
+    }
+
+    #[test]
+    /// Test if updating and popping the last element keeps the padding as
+    /// copies of the new last element.
+    fn padding_follows_last_element() {
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof"]);
+        merkle.update_element(5, "Other").unwrap();
+        assert_eq!(merkle, MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Other"]));
+        merkle.update_element(4, "Tree").unwrap();
+        assert_eq!(merkle, MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Tree", "Other"]));
+
+        merkle.pop_element();
+        assert_eq!(merkle, MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Tree"]));
+        merkle.pop_element();
+        assert_eq!(merkle, MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]));
+        merkle.pop_element();
+        assert_eq!(merkle, MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]));
+    }
+
+    #[test]
+    /// Test the difference to the next power of 2 right at and after every
+    /// power of 2 an usize can hold.
+    fn diff_to_power_of_2_boundaries() {
+        assert_eq!(diff_to_power_of_2(0), 1);
+        for k in 0..usize::BITS - 1 {
+            let power = 1usize << k;
+            assert_eq!(diff_to_power_of_2(power), 0);
+            assert_eq!(diff_to_power_of_2(power + 1), power - 1);
+            assert_eq!(diff_to_power_of_2(2 * power - 1), usize::from(k > 0));
+        }
+        assert_eq!(diff_to_power_of_2(1 << (usize::BITS - 1)), 0);
+    }
+
+    #[test]
+    /// Test lens an f64 can not hold exactly. 2^53 + 1 was rounded down to
+    /// 2^53, which looked like a power of 2, and 2^31 did not fit the i32
+    /// the power was calculated in.
+    fn diff_to_power_of_2_above_f64_precision() {
+        let len = (1usize << 53) + 1;
+        assert_eq!(len as f64, (1u64 << 53) as f64);
+        assert_eq!(diff_to_power_of_2(len), (1 << 53) - 1);
+        assert_eq!(diff_to_power_of_2((1 << 31) + 1), (1 << 31) - 1);
+        assert_eq!(diff_to_power_of_2(usize::MAX >> 1), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The len can not be padded to a power of 2")]
+    /// Test if a len above the biggest power of 2 panics instead of wrapping
+    fn diff_to_power_of_2_overflow() {
+        diff_to_power_of_2((1 << (usize::BITS - 1)) + 1);
+    }
+
+    #[test]
+    /// Test the case where the input array has only value
+    /// 
+    /// The creation of the Merkle Tree with an input array of only one value
+    /// should just contain the hash of that value and nothing else.
+    fn creation_from_arrray_one_value() {
+        let data = vec!["Crypto"];
+        let merkle = MerkleTree::new(data.clone());
+
+        assert_eq!(merkle.arr.len(), 1);
+    }
+
+    #[test]
+    /// Test the creation of a Merkle Tree
+    /// 
+    /// We check if the hashes are correct and also if the number of
+    /// levels is the expected when creating a tree from an array of
+    /// 4 initial elements.
+    fn creation_from_arrray() {
+        // We know that when we use an input array of 4 elements
+        // the quantity of levels should be 3.
+        let desired_level_quantity = 3;
+       
+        let (manual_tree, merkle) = manually_create_tree_hashes();
+
+        // Test every level
+        assert_eq!(merkle.arr[LEVEL_0], manual_tree[LEVEL_0]);
+        assert_eq!(merkle.arr[LEVEL_1], manual_tree[LEVEL_1]);
+        assert_eq!(merkle.arr[LEVEL_2], manual_tree[LEVEL_2]);
+        // Test quantity of levels
+        assert_eq!(merkle.arr.len(), desired_level_quantity);
+    }
+
+    #[test]
+    /// Test the creation of a Merkle Tree with an input array that
+    /// has a len that is not a power of 2.
+    /// 
     /// With an input array of 5 elements, the Merkle Tree should
     /// copy repeated elements to have a first level with a
     /// quantity of 8 elements. Then with 8 elements the tree
@@ -501,265 +1785,1540 @@ mod tests {
         let desired_level_quantity = 4;
         let desired_quantity_in_first_level = 8;
         let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
-        let merkle = MerkleTree::new(data);
+        let merkle = MerkleTree::new(data);
+
+        assert_eq!(merkle.padded_len(), desired_quantity_in_first_level);
+        assert_eq!(merkle.arr.len(), desired_level_quantity);
+    }
+
+    #[test]
+    /// Test if the creation of a Merkle Tree with an input array that
+    /// has a len that is not power of 2, has the correct hash values
+    /// on the first level.
+    /// 
+    /// With 3 elements, the creation should copy the last element so
+    /// the first level has 4 elements. The last and penultimate hashes
+    /// in the first level should be the same.
+    fn creation_from_array_3_elements() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let merkle = MerkleTree::new(data);
+        let last_i = 3;
+        let penultimate_i = 2;
+
+        assert_eq!(merkle.get_padded_leaf(last_i), merkle.get_padded_leaf(penultimate_i));
+    }
+
+    #[test]
+    /// Test if the expected hash to be the root is actually the root 
+    /// of the tree.
+    fn is_root_returns_true() {
+        let data = vec!["Crypto", "Merkle"];
+        let merkle = MerkleTree::new(data.clone());
+
+        // We manually get the root
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+
+        let root_concatenation = concatenate_elements(elem0_hash, elem1_hash);
+        let desired_root = hash_node(root_concatenation);
+
+        assert!(merkle.is_root(desired_root));
+    }
+
+    #[test]
+    /// Test if a random hash is the root of the tree.
+    fn is_root_returns_false() {
+        let data = vec!["Crypto", "Merkle"];
+        let merkle = MerkleTree::new(data.clone());
+
+        // We manually get the root
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+        // We add garbage to the concatenation so the hash changes
+        let garbage = "x";
+        let root_concatenation = (concatenate_elements(elem0_hash, elem1_hash), garbage);
+        let wrong_root = hash_element(root_concatenation);
+
+        assert!(!merkle.is_root(wrong_root));
+    }
+
+    #[test]
+    /// Test if the tree can verify a correct proof
+    fn tree_verifies_proof() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let merkle = MerkleTree::new(data.clone());
+
+        // Get the hashes of the elements manually.
+        // Level 0. It has the hashes of every element.
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+        let elem2_hash = hash_element(data[2]);
+        let elem3_hash = hash_element(data[3]);
+
+        // Create one of the proof hashes that we will be using:
+        // (elem2_hash + elem3_hash) = elem23_hash
+        let elem23 = concatenate_elements(elem2_hash, elem3_hash);
+        let elem23_hash = hash_node(elem23);
+
+        // Creation of the proof. The element is on the right of its sibling
+        // and its parent is on the left of elem23_hash.
+        let elem1_index = 1;
+        let proof = Proof::new(elem1_index, vec![(elem0_hash, Side::Left), (elem23_hash, Side::Right)]);
+         
+        assert!(merkle.verify(&proof, elem1_hash));
+    }
+
+    #[test]
+    /// Test if the tree can verify an incorrect proof
+    fn tree_cant_verify_wrong_proof() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let merkle = MerkleTree::new(data.clone());
+
+        // Get the hashes of the elements manually.
+        // Level 0. It has the hashes of every element
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+        let elem2_hash = hash_element(data[2]);
+        let elem3_hash = hash_element(data[3]);
+
+        // Create one of the proofs that we will be using:
+        // (elem2_hash + elem3_hash) = elem23_hash
+        let garbage = "X";
+        let elem23 = (concatenate_elements(elem2_hash, elem3_hash), garbage);
+        let elem23_hash = hash_element(elem23);
+
+        let elem1_index = 1;
+        let proof = Proof::new(elem1_index, vec![(elem0_hash, Side::Left), (elem23_hash, Side::Right)]);
+         
+        assert!(!merkle.verify(&proof, elem1_hash));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    /// Test if passing the wrong index makes the verifying to fail
+    fn verify_with_wrong_index() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let merkle = MerkleTree::new(data.clone());
+
+        // Get the hashes of the elements and manually create the tree structure
+        // Level 0. It has the hashes of every element
+        let elem0_hash = hash_element(data[0]);
+        let elem1_hash = hash_element(data[1]);
+        let elem2_hash = hash_element(data[2]);
+        let elem3_hash = hash_element(data[3]);
+
+        // Create one of the proofs that we will be using:
+        // (elem2_hash + elem3_hash) = elem23_hash
+        let garbage = "X";
+        let elem23 = (concatenate_elements(elem2_hash, elem3_hash), garbage);
+        let elem23_hash = hash_element(elem23);
+
+        let proof = vec![elem0_hash, elem23_hash];
+        let elem1_wrong_index = 2;
+         
+        assert!(!merkle.verify_hashes(proof, elem1_wrong_index, elem1_hash));
+    }
+
+    #[test]
+    /// Test if the generation of proof works
+    /// 
+    /// By getting the manually created tree and the tree created with
+    /// our abstraction, we manually create what would be the correct proof
+    /// for the first element in the input array. We then check if the 
+    /// generated proof is equal to the one we manually created.  
+    fn generate_right_proof() {
+        let (manual_tree, merkle) = manually_create_tree_hashes();
+
+        let elem1_hash = manual_tree[LEVEL_0][1];
+        let elem23_hash = manual_tree[LEVEL_1][1];
+
+        let desired_proof = Proof::new(0, vec![(elem1_hash, Side::Right), (elem23_hash, Side::Right)]);
+        let proof = merkle.generate_proof(0).unwrap();
+
+        assert_eq!(proof, desired_proof);
+    }
+
+    #[test]
+    /// Test if adding a new element in a tree that already has a base
+    /// level of 2^n different elements creates a new level on the tree
+    /// 
+    /// If we start a Merkle Tree with an input array of 4 elements,
+    /// this will create a tree with 3 levels. If we add an element
+    /// the base level grows, creating a new level on the tree.
+    fn add_element_creates_new_level() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let mut desired_merkle_levels = 3;
+        let mut merkle = MerkleTree::new(data);
+
+        assert_eq!(merkle.arr.len(), desired_merkle_levels);
+
+        merkle.add_element("Test");
+        desired_merkle_levels = 4;
+
+        assert_eq!(merkle.arr.len(), desired_merkle_levels);
+    }
+
+    #[test]
+    /// Test if adding a new element in a tree that already has a base
+    /// level of 2^n different elements, doubles the quantity of
+    /// base elements.
+    /// 
+    /// If we start a Merkle Tree with an input array of 4 elements,
+    /// by adding an element we no longer have a base level with
+    /// a quantity that is a power of 2. So to have that again
+    /// we need to have repeated values. In this case, we should
+    /// end up having a base level with 8 elements.
+    fn add_element_doubles_base_elements() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let mut desired_base_level_quantity = data.len();
+        let mut merkle = MerkleTree::new(data);
+
+        assert_eq!(merkle.padded_len(), desired_base_level_quantity);
+
+        merkle.add_element("Test");
+        desired_base_level_quantity *= 2;
+
+        assert_eq!(merkle.padded_len(), desired_base_level_quantity);
+    }
+
+    #[test]
+    /// Test if the base level elements are correct when adding a new element
+    /// in a tree that already has a base level of 2^n different elements
+    fn add_element_creates_correct_hashes() {
+        let data = vec!["Crypto", "Merkle"];
+        let new_elem = "Rust";
+        let mut merkle = MerkleTree::new(data);
+        let old_root = merkle.arr[1][0];
+
+        merkle.add_element(new_elem);
+        let new_elem_hash = hash_element(new_elem);
+
+        assert_eq!(merkle.get_padded_leaf(2), Some(new_elem_hash));
+        assert_eq!(merkle.get_padded_leaf(3), Some(new_elem_hash));
+        assert!(!merkle.is_root(old_root));
+    }
+
+    #[test]
+    /// Test if adding an element when having repeated values on the base level
+    /// replaces the first repeated level to the new element and re-calculates
+    /// the necessary nodes.
+    /// 
+    /// When creating a tree with an input array of 3 elements, the last element will
+    /// be repeated on the base level so it can have a len that is a power of 2. However,
+    /// when adding a new element in this case it should replace the element that is
+    /// repeated and re-calculate a whole half of the tree, even the root.
+    fn add_element_replaces_repeated_element() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let mut merkle = MerkleTree::new(data);
+        let last_base_level_index = 3;
+        let last_hash_before_add = merkle.get_padded_leaf(last_base_level_index);
+
+        let new_element = String::from("Tree");
+        let new_element_hash = hash_element(new_element.clone());
+        merkle.add_element(new_element);
+        let last_hash_after_add = merkle.get_padded_leaf(last_base_level_index);
+
+        assert_eq!(last_hash_after_add, Some(new_element_hash));
+        assert_ne!(last_hash_after_add, last_hash_before_add);
+    }
+
+    #[test]
+    /// Test if adding two elements and using both cases works as expected
+    /// 
+    /// We will have a tree that will be created from an input array of 3 elements.
+    /// This means that to have a base level of 2^n elements, the last one should
+    /// be repeated (so there aren't 2^n different elements). So when we add the 
+    /// first element, the repeated value located at the end of the base 
+    /// level should be replaced with the new value. After that we should have
+    /// a base level of 2^n different elements. So when we add a second element,
+    /// the other case should occur and we should end up with a base level that
+    /// will have 2 times the quantity of elements it has before.
+    fn add_2_elements() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let mut merkle = MerkleTree::new(data);
+        let desired_levels = 4;
+        let replaced_element_index = 3; // We had 3 initial elements. The fourth (index 3) should be the repeated one
+
+        let new_element_1 = String::from("Tree");
+        let new_element_1_hash = hash_element(new_element_1.clone());
+        let new_element_2 = String::from("Test");
+        merkle.add_element(new_element_1);
+        merkle.add_element(new_element_2);
+
+        assert_eq!(merkle.arr.len(), desired_levels);
+        assert_eq!(merkle.get_padded_leaf(replaced_element_index), Some(new_element_1_hash));
+    }
+
+    #[test]
+    /// Test if adding three elements in a row to a tree of 4 elements leaves
+    /// the new right half with copies of the last added element, and every
+    /// slot of the base level and the root match a tree created from scratch.
+    /// 
+    /// The first element doubles the base level, so slots 5, 6 and 7 are
+    /// copies of it. Each of the next two replaces the first copy, and
+    /// the copies after it become copies of the new element.
+    fn add_3_elements_to_4_element_tree() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof", "Hash"];
+        let mut merkle = MerkleTree::new(data[..4].to_vec());
+
+        for len in 5..=data.len() {
+            merkle.add_element(data[len - 1]);
+            let rebuilt = MerkleTree::new(data[..len].to_vec());
+            let last_hash = hash_element(data[len - 1]);
+
+            for index in 0..8 {
+                let expected = hash_element(data[index.min(len - 1)]);
+                assert_eq!(merkle.get_padded_leaf(index), Some(expected), "slot {index} with {len} elements");
+                assert_eq!(merkle.get_padded_leaf(index), rebuilt.get_padded_leaf(index));
+            }
+            assert!((len..8).all(|index| merkle.get_padded_leaf(index) == Some(last_hash)));
+            assert_eq!(merkle.root(), rebuilt.root());
+            assert_eq!(merkle, rebuilt);
+        }
+    }
+
+    #[test]
+    /// Test if a tree created from a single element grows into the tree
+    /// `new` gives at every step, up to 8 elements, with the same root,
+    /// height and proofs. The first add turns the single level, which is
+    /// also the root level, into a base level of 2 and a root above it.
+    fn single_element_tree_grows_one_by_one() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof", "Hash", "Node"];
+        for new_tree in [MerkleTree::new::<&str>, MerkleTree::new_sorted::<&str>] {
+            let mut merkle = new_tree(vec![data[0]]);
+            assert_eq!((merkle.height(), merkle.padded_len()), (1, 1));
+
+            for len in 2..=data.len() {
+                merkle.add_element(data[len - 1]);
+                let expected = new_tree(data[..len].to_vec());
+                assert_eq!(merkle, expected, "{len} elements");
+                assert_eq!(merkle.root(), expected.root());
+                assert_eq!(merkle.height(), expected.height());
+
+                for (index, element) in data[..len].iter().enumerate() {
+                    let proof = merkle.generate_proof(index).unwrap();
+                    assert_eq!(proof, expected.generate_proof(index).unwrap());
+                    assert!(merkle.verify(&proof, hash_element(element)));
+                }
+            }
+            assert_eq!(merkle.height(), 4);
+        }
+    }
+
+    #[test]
+    /// Test if the root is the only hash of the last level, and if it is
+    /// None when the tree has no levels instead of panicking.
+    fn root_is_last_level_hash() {
+        let (manual_tree, merkle) = manually_create_tree_hashes();
+        assert_eq!(merkle.root(), Some(manual_tree[LEVEL_2][0]));
+
+        let empty = MerkleTree { arr: TreeStructure::new(), diff_elements: 0, placeholders: None, hasher: DefaultBuildHasher::default(), ordering: PairOrdering::Positional, padding: PaddingStrategy::RepeatLast, history: None, commit_len: false, repeated: Vec::new() };
+        assert_eq!(empty.root(), None);
+    }
+
+    #[test]
+    /// Test the whole lifecycle of a tree that starts empty: it has no root
+    /// nor proofs, and adding elements gives the same tree `new` gives for
+    /// every element added so far, with both padding strategies.
+    fn lifecycle_starting_from_empty() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        for new_tree in [MerkleTree::new::<&str>, MerkleTree::new_rfc6962::<&str>] {
+            let mut merkle = new_tree(Vec::new());
+            assert!(merkle.is_empty());
+            assert_eq!((merkle.len(), merkle.padded_len(), merkle.height()), (0, 0, 0));
+            assert_eq!(merkle.root(), None);
+            assert_eq!(merkle.root_hash(), None);
+            assert!(!merkle.is_root(0));
+            assert_eq!(merkle.generate_proof(0), Err(MerkleError::InvalidIndex { index: 0, len: 0 }));
+            assert!(!merkle.verify(&Proof::new(0, Vec::new()), hash_element(data[0])));
+            assert!(!merkle.contains(&data[0]));
+            assert_eq!(merkle.iter_bfs().count(), 0);
+            assert_eq!(merkle.iter_dfs().count(), 0);
+
+            for len in 1..=data.len() {
+                merkle.add_element(data[len - 1]);
+                let expected = new_tree(data[..len].to_vec());
+                assert_eq!(merkle.arr, expected.arr);
+                assert_eq!(merkle.len(), len);
+                assert_eq!(merkle.root(), expected.root());
+
+                let proof = merkle.generate_proof(len - 1).unwrap();
+                assert!(merkle.verify(&proof, hash_element(data[len - 1])));
+            }
+        }
+    }
+
+    #[test]
+    /// Test if a tree over [a, b, c] and a tree over [a, b, c, c] have the
+    /// same root, unless their roots commit to their len. Then the proofs
+    /// only verify with the len the tree has.
+    fn committed_len_separates_padding() {
+        let three = vec!["Crypto", "Merkle", "Rust"];
+        let four = vec!["Crypto", "Merkle", "Rust", "Rust"];
+        assert_eq!(MerkleTree::new(three.clone()).root(), MerkleTree::new(four.clone()).root());
+        assert_eq!(MerkleTree::new(three.clone()).committed_len(), None);
+
+        let committed_three = MerkleTree::with_committed_len(three.clone());
+        let committed_four = MerkleTree::with_committed_len(four);
+        assert_ne!(committed_three.root(), committed_four.root());
+        assert_eq!(committed_three.committed_len(), Some(3));
+        assert_eq!(committed_four.committed_len(), Some(4));
+        assert_ne!(committed_three, MerkleTree::new(three.clone()));
+
+        let root = committed_three.root().unwrap();
+        let proof = committed_three.generate_proof(2).unwrap();
+        assert!(committed_three.verify(&proof, hash_element("Rust")));
+        assert!(verify_committed_against_root(root, 3, &proof, hash_element("Rust")));
+        assert!(!verify_committed_against_root(root, 4, &proof, hash_element("Rust")));
+        assert!(!verify_against_root(root, 3, &proof, hash_element("Rust")));
+        // The copy at index 3 can be proven, but it is not one of the 3 elements
+        let padded = committed_three.generate_proof_padded(3).unwrap();
+        assert!(!committed_three.verify(&padded, hash_element("Rust")));
+        assert!(!verify_committed_against_root(root, 3, &padded, hash_element("Rust")));
+
+        let multiproof = committed_three.generate_multiproof(&[0, 2]).unwrap();
+        assert!(committed_three.verify_multiproof(&multiproof, &[(0, hash_element("Crypto")), (2, hash_element("Rust"))]));
+    }
+
+    #[test]
+    /// Test if the root of a tree that commits to its len follows the len
+    /// when elements are added and popped.
+    fn committed_len_follows_mutations() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let mut merkle = MerkleTree::with_committed_len(data[..1].to_vec());
+        for len in 2..=data.len() {
+            merkle.add_element(data[len - 1]);
+            assert_eq!(merkle.root(), MerkleTree::with_committed_len(data[..len].to_vec()).root());
+        }
+        merkle.pop_element();
+        assert_eq!(merkle, MerkleTree::with_committed_len(data[..4].to_vec()));
+        assert_eq!(merkle.root(), MerkleTree::with_committed_len(data[..4].to_vec()).root());
+    }
+
+    #[test]
+    /// Test if the default tree is the empty one
+    fn default_tree_is_empty() {
+        let merkle = MerkleTree::default();
+        assert!(merkle.is_empty());
+        assert_eq!(merkle.root(), None);
+        assert_eq!(merkle.arr, MerkleTree::new(Vec::<&str>::new()).arr);
+    }
+
+    #[test]
+    /// Test if adding n elements to a tree created with capacity for n gives
+    /// the same tree `new` gives, without reallocating any level.
+    fn with_capacity_matches_new() {
+        for capacity in [1, 2, 3, 4, 5, 8, 16] {
+            let mut merkle = MerkleTree::with_capacity(capacity);
+            assert!(merkle.is_empty());
+            assert_eq!(merkle.root(), None);
+            let memory = merkle.memory_usage();
+
+            merkle.add_element(0usize);
+            let base_level_ptr = merkle.arr[LEVEL_0].as_ptr();
+            for element in 1..capacity {
+                merkle.add_element(element);
+            }
+            let expected = MerkleTree::new((0..capacity).collect());
+            assert_eq!(merkle.arr, expected.arr);
+            assert_eq!(merkle.len(), expected.len());
+            assert_eq!(merkle.root(), expected.root());
+            assert_eq!(merkle.arr[LEVEL_0].as_ptr(), base_level_ptr);
+            assert_eq!(merkle.memory_usage(), memory);
+        }
+    }
+
+    #[test]
+    /// Test if the capacity never changes the hashes of the tree, comparing
+    /// it with a tree that grows from empty without any capacity.
+    fn capacity_does_not_change_hashes() {
+        let mut with_capacity = MerkleTree::with_capacity(13);
+        let mut without_capacity = MerkleTree::default();
+        for element in 0..20 {
+            with_capacity.add_element(element);
+            without_capacity.add_element(element);
+            assert_eq!(with_capacity.arr, without_capacity.arr);
+            assert_eq!(with_capacity.len(), without_capacity.len());
+        }
+    }
+
+    #[test]
+    /// Test if updating an element gives the same tree as building it again
+    /// with the new element, including the repeated copies of the element
+    /// and with both padding strategies.
+    fn update_element_matches_new() {
+        let mut data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        for new_tree in [MerkleTree::new::<&str>, MerkleTree::new_rfc6962::<&str>] {
+            let mut merkle = new_tree(data.clone());
+            for index in 0..data.len() {
+                data[index] = "Updated";
+                merkle.update_element(index, "Updated").unwrap();
+                assert_eq!(merkle.arr, new_tree(data.clone()).arr);
+
+                let proof = merkle.generate_proof(index).unwrap();
+                assert!(merkle.verify(&proof, hash_element("Updated")));
+            }
+            assert_eq!(merkle.update_element(5, "Test"), Err(MerkleError::InvalidIndex { index: 5, len: 5 }));
+        }
+    }
+
+    #[test]
+    /// Test if updating an element of one half of the tree leaves the proofs
+    /// of the other half untouched, except for the hash of the updated half.
+    ///
+    /// A proof issued before the update still leads to the old root, and once
+    /// its last hash is replaced with the new root of the updated half it
+    /// verifies against the new root.
+    fn update_element_keeps_proofs_of_other_half() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Hash", "Leaf", "Root"];
+        let mut merkle = MerkleTree::new(data.clone());
+        let old_root = merkle.root().unwrap();
+        let old_proofs: Vec<Proof> = (0..4).map(|index| merkle.generate_proof(index).unwrap()).collect();
+
+        merkle.update_element(6, "Updated").unwrap();
+        assert_ne!(merkle.root(), Some(old_root));
+        let updated_half = merkle.arr[2][1];
+        for (index, old_proof) in old_proofs.into_iter().enumerate() {
+            let leaf = hash_element(data[index]);
+            assert_eq!(fold_proof(&old_proof.hashes(), index, leaf), old_root);
+
+            let new_proof = merkle.generate_proof(index).unwrap();
+            assert_eq!(new_proof.siblings()[..2], old_proof.siblings()[..2]);
+            assert_eq!(new_proof.siblings()[2], (updated_half, Side::Right));
+            assert!(merkle.verify(&new_proof, leaf));
+        }
+    }
+
+    #[test]
+    /// Test if popping the last element of a tree gives the same tree `new`
+    /// gives without that element, down to the empty tree, with both
+    /// padding strategies.
+    fn pop_element_matches_new() {
+        let data: Vec<usize> = (0..17).collect();
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+            let mut merkle = new_tree(data.clone());
+            for len in (0..data.len()).rev() {
+                assert_eq!(merkle.pop_element(), Some(hash_element(data[len])));
+                let expected = new_tree(data[..len].to_vec());
+                assert_eq!(merkle.arr, expected.arr);
+                assert_eq!(merkle.len(), len);
+                assert_eq!(merkle.root(), expected.root());
+            }
+            assert!(merkle.is_empty());
+            assert_eq!(merkle.pop_element(), None);
+        }
+    }
+
+    #[test]
+    /// Test if adding an element and popping it gives back the original root,
+    /// also when the add crosses a power of 2 and creates a new level.
+    ///
+    /// A tree of 4 elements grows to 8 slots and 4 levels when the fifth
+    /// element is added, and popping it has to drop that level again.
+    fn add_then_pop_restores_root() {
+        for len in 1..=17 {
+            for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+                let mut merkle = new_tree((0..len).collect());
+                let original_arr = merkle.arr.clone();
+                let original_root = merkle.root();
+
+                merkle.add_element(len);
+                assert_eq!(merkle.pop_element(), Some(hash_element(len)));
+                assert_eq!(merkle.root(), original_root);
+                assert_eq!(merkle.arr, original_arr);
+                assert_eq!(merkle.len(), len);
+            }
+        }
+
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]);
+        merkle.add_element("Test");
+        assert_eq!((merkle.padded_len(), merkle.height()), (8, 4));
+        merkle.pop_element();
+        assert_eq!((merkle.padded_len(), merkle.height()), (4, 3));
+        let proof = merkle.generate_proof(3).unwrap();
+        assert!(merkle.verify(&proof, hash_element("Tree")));
+    }
+
+    #[test]
+    /// Test if adding many elements at once gives exactly the same tree as
+    /// adding them one by one, for every combination of sizes up to 20 and
+    /// with both padding strategies.
+    fn add_elements_matches_repeated_add_element() {
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+            for initial_len in 0..=20 {
+                for added in 0..=20 {
+                    let new_elems: Vec<usize> = (initial_len..initial_len + added).collect();
+                    let mut batch = new_tree((0..initial_len).collect());
+                    let mut one_by_one = new_tree((0..initial_len).collect());
+
+                    batch.add_elements(new_elems.clone());
+                    for new_elem in new_elems {
+                        one_by_one.add_element(new_elem);
+                    }
+                    assert_eq!(batch.arr, one_by_one.arr);
+                    assert_eq!(batch.len(), one_by_one.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Test if the level 1 node 1 of a tree of 8 elements is proven with
+    /// the hashes above it and verified with only the root, and if nodes
+    /// that only cover padding or are outside of the tree are rejected.
+    fn subtree_proof_verifies_with_root() {
+        let merkle = MerkleTree::new((0..8usize).collect());
+        let root = merkle.root().unwrap();
+        let subtree_root = hash_node(concatenate_elements(hash_element(2usize), hash_element(3usize)));
+
+        let proof = merkle.generate_subtree_proof(1, 1).unwrap();
+        assert_eq!(proof, vec![merkle.get_node(1, 0).unwrap(), merkle.get_node(2, 1).unwrap()]);
+        assert!(verify_subtree(root, &proof, 1, 1, subtree_root));
+        assert!(!verify_subtree(root, &proof, 1, 0, subtree_root));
+        assert!(!verify_subtree(root, &proof, 1, 5, subtree_root));
+        assert!(!verify_subtree(root, &proof, 1, 1, hash_element(2usize)));
+
+        // A leaf is a subtree of level 0 and the root one of the last level
+        assert_eq!(merkle.generate_subtree_proof(0, 5).unwrap(), merkle.generate_proof(5).unwrap().hashes());
+        assert!(verify_subtree(root, &merkle.generate_subtree_proof(3, 0).unwrap(), 3, 0, root));
+
+        let padded = MerkleTree::new((0..5usize).collect());
+        assert_eq!(padded.generate_subtree_proof(1, 3), Err(MerkleError::PaddedIndex { index: 3 }));
+        assert_eq!(padded.generate_subtree_proof(1, 4), Err(MerkleError::InvalidIndex { index: 4, len: 3 }));
+        assert_eq!(padded.generate_subtree_proof(4, 0), Err(MerkleError::InvalidIndex { index: 0, len: 0 }));
+        let proof = padded.generate_subtree_proof(1, 2).unwrap();
+        assert!(verify_subtree(padded.root().unwrap(), &proof, 1, 2, padded.get_node(1, 2).unwrap()));
+        assert_eq!(MerkleTree::new_rfc6962(vec![0usize]).generate_subtree_proof(0, 0), Err(MerkleError::UnsupportedPadding));
+    }
+
+    #[test]
+    /// Test if merging two trees gives the tree `new` gives for their
+    /// elements in order, for every combination of sizes up to 20, some
+    /// larger ones and both padding strategies.
+    fn merge_matches_new_over_concatenated_elements() {
+        let sizes = (0..=20).chain([64, 100, 1000, 1024]);
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+            for own_len in sizes.clone() {
+                for other_len in sizes.clone() {
+                    let mut merged = new_tree((0..own_len).collect());
+                    merged.merge(new_tree((own_len..own_len + other_len).collect()));
+                    let expected = new_tree((0..own_len + other_len).collect());
+
+                    assert_eq!(merged, expected, "{own_len} + {other_len}");
+                    assert_eq!(merged.root(), expected.root());
+                }
+            }
+        }
+
+        // A tree padded in another way only gives its leaves
+        let mut merged = MerkleTree::new(vec![0usize, 1, 2]);
+        merged.merge(MerkleTree::new_rfc6962(vec![3usize, 4, 5]));
+        assert_eq!(merged, MerkleTree::new((0..6usize).collect()));
+    }
+
+    #[test]
+    /// Test if collecting an iterator gives the same tree `new` gives, and if
+    /// extending a tree gives the same tree as adding each element, also
+    /// with empty iterators.
+    fn collect_and_extend_match_new_and_add_element() {
+        for len in 0..=17 {
+            let collected: MerkleTree = (0..len).collect();
+            let expected = MerkleTree::new((0..len).collect());
+            assert_eq!(collected.arr, expected.arr);
+            assert_eq!(collected.len(), len);
+
+            let mut extended = MerkleTree::new(vec![100, 101, 102]);
+            let mut one_by_one = MerkleTree::new(vec![100, 101, 102]);
+            extended.extend(0..len);
+            for new_elem in 0..len {
+                one_by_one.add_element(new_elem);
+            }
+            assert_eq!(extended.root(), one_by_one.root());
+            assert_eq!(extended.arr, one_by_one.arr);
+        }
+
+        let mut merkle: MerkleTree = std::iter::empty::<&str>().collect();
+        assert!(merkle.is_empty());
+        merkle.extend(["Crypto", "Merkle", "Rust"]);
+        assert_eq!(merkle.root(), MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]).root());
+    }
+
+    #[test]
+    /// Test if appending 10k elements one by one keeps every level in line
+    /// with its base level, right after each time the tree doubles and at
+    /// the end, and gives the same tree as adding them in a single batch.
+    fn append_one_by_one_matches_rebuild() {
+        let mut merkle = MerkleTree::default();
+        for new_elem in 0..10_000usize {
+            merkle.add_element(new_elem);
+            if new_elem.is_power_of_two() {
+                assert_eq!(merkle.arr, create_remaining_levels(merkle.arr[0].to_vec()));
+            }
+        }
+        assert_eq!(merkle.arr, create_remaining_levels(merkle.arr[0].to_vec()));
+        assert_eq!(merkle.get_leaf(9_999), Some(hash_element(9_999usize)));
+
+        let mut batch = MerkleTree::default();
+        batch.add_elements((0..10_000usize).collect());
+        assert_eq!(merkle, batch);
+    }
+
+    #[test]
+    #[ignore]
+    /// Benchmark adding 2^16 elements to a tree one by one against adding
+    /// them in a single batch.
+    ///
+    /// Run it with `cargo test --release -- --ignored add_elements_benchmark`
+    fn add_elements_benchmark() {
+        let new_elems: Vec<usize> = (0..1 << 16).collect();
+
+        let start = std::time::Instant::now();
+        let mut one_by_one = MerkleTree::default();
+        for new_elem in new_elems.clone() {
+            one_by_one.add_element(new_elem);
+        }
+        let repeated = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let mut batch = MerkleTree::default();
+        batch.add_elements(new_elems);
+        let batched = start.elapsed();
+
+        assert_eq!(batch.root(), one_by_one.root());
+        assert!(batched < repeated, "add_elements took {batched:?}, add_element {repeated:?}");
+    }
+
+    #[test]
+    /// Test if the root changes after every element added, in both cases
+    /// of add_element, and if it is the root that verify checks against.
+    fn root_changes_after_each_add() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let mut merkle = MerkleTree::new(data);
+        let mut roots = vec![merkle.root().unwrap()];
+
+        // Replaces the repeated value, then creates a new root level
+        for new_element in ["Tree", "Test"] {
+            merkle.add_element(new_element);
+            let root = merkle.root().unwrap();
+            assert!(!roots.contains(&root));
+            roots.push(root);
+
+            let index = merkle.diff_elements - 1;
+            let proof = merkle.generate_proof(index).unwrap();
+            assert_eq!(fold_proof(&proof.hashes(), index, hash_element(new_element)), root);
+            assert!(merkle.verify(&proof, hash_element(new_element)));
+        }
+    }
+
+    #[test]
+    /// Test if len, padded_len and height stay consistent after adding elements
+    /// in both cases of add_element.
+    ///
+    /// Starting from 3 elements, the first add replaces a repeated value and
+    /// the second one creates a new subtree, so every case is covered.
+    fn accessors_stay_consistent_after_adding() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let mut merkle = MerkleTree::new(data);
+        assert!(!merkle.is_empty());
+        assert_eq!((merkle.len(), merkle.padded_len(), merkle.height()), (3, 4, 3));
+
+        let expected = [(4, 4, 3), (5, 8, 4), (6, 8, 4), (7, 8, 4), (8, 8, 4), (9, 16, 5)];
+        for (i, (len, padded_len, height)) in expected.into_iter().enumerate() {
+            merkle.add_element(i);
+            assert_eq!((merkle.len(), merkle.padded_len(), merkle.height()), (len, padded_len, height));
+            assert_eq!(merkle.padded_len(), merkle.len().next_power_of_two());
+            assert_eq!(merkle.padded_len(), 1 << (merkle.height() - 1));
+        }
+    }
+
+    #[test]
+    /// Test if contains finds every element, including repeated ones in the input
+    /// and the ones added later, but not the ones that are only padding.
+    ///
+    /// With 5 elements the base level is padded with copies of the last 3 elements,
+    /// so with a hash that only appears as padding we check it is ignored.
+    fn contains_ignores_padding() {
+        let data = vec!["Crypto", "Merkle", "Crypto", "Rust", "Tree"];
+        let mut merkle = MerkleTree::new(data.clone());
+
+        for element in &data {
+            assert!(merkle.contains(element));
+        }
+        assert!(!merkle.contains(&"Test"));
+
+        merkle.add_element("Test");
+        assert!(merkle.contains(&"Test"));
+
+        // Pretend the last different element was only padding
+        merkle.diff_elements -= 1;
+        assert!(merkle.arr[LEVEL_0][merkle.diff_elements..].contains(&hash_element("Test")));
+        assert!(!merkle.contains(&"Test"));
+    }
+
+    #[test]
+    /// Test if contains_hash is true for the leaf at each index and false
+    /// for a perturbed hash, another index or an invalid one.
+    fn contains_hash_checks_leaf_at_index() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        for index in 0..merkle.len() {
+            let leaf = merkle.get_leaf(index).unwrap();
+            assert!(merkle.contains_hash(leaf, index));
+            assert!(!merkle.contains_hash(leaf ^ 1, index));
+            assert!(!merkle.contains_hash(leaf, (index + 1) % merkle.len()));
+        }
+        // The padding slots and the indexes outside of the tree are not errors
+        assert!(!merkle.contains_hash(merkle.get_leaf(4).unwrap(), 5));
+        assert!(!merkle.contains_hash(merkle.get_leaf(4).unwrap(), 100));
+        assert!(!MerkleTree::new(Vec::<u8>::new()).contains_hash(0, 0));
+    }
+
+    #[test]
+    /// Test if index_of and indices_of find the positions of an element and
+    /// never return an index of the padding.
+    ///
+    /// With 5 elements the last 3 are repeated as padding, so "Rust" and "Tree"
+    /// appear in the padded region too.
+    fn index_of_skips_padding() {
+        let data = vec!["Crypto", "Merkle", "Crypto", "Rust", "Tree"];
+        let mut merkle = MerkleTree::new(data);
+
+        assert_eq!(merkle.index_of(&"Crypto"), Some(0));
+        assert_eq!(merkle.indices_of(&"Crypto"), vec![0, 2]);
+        assert_eq!(merkle.indices_of(&"Rust"), vec![3]);
+        assert_eq!(merkle.indices_of(&"Tree"), vec![4]);
+        assert_eq!(merkle.index_of(&"Test"), None);
+        assert!(merkle.indices_of(&"Test").is_empty());
+
+        merkle.add_element("Test");
+        let index = merkle.index_of(&"Test").unwrap();
+        assert_eq!(index, 5);
+        let proof = merkle.index_of(&"Test").map(|i| merkle.generate_proof(i)).unwrap().unwrap();
+        assert!(merkle.verify(&proof, hash_element("Test")));
+    }
+
+    /// A FNV-1a hasher, only used to build trees with a hasher that is not the default one
+    #[derive(Default)]
+    struct Fnv1a(u64);
+
+    impl std::hash::Hasher for Fnv1a {
+        fn write(&mut self, bytes: &[u8]) {
+            if self.0 == 0 {
+                self.0 = 0xcbf29ce484222325;
+            }
+            for byte in bytes {
+                self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    /// Test if two trees with different hashers over the same data have
+    /// different roots, and each one verifies its own proofs but not the
+    /// proofs of the other one.
+    fn different_hashers_give_different_roots() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let default_merkle = MerkleTree::new(data.clone());
+        let mut fnv_merkle = MerkleTree::with_hasher(data.clone(), BuildHasherDefault::<Fnv1a>::default());
+
+        assert_ne!(default_merkle.root(), fnv_merkle.root());
+        for (index, element) in data.iter().enumerate() {
+            let default_proof = default_merkle.generate_proof(index).unwrap();
+            let fnv_proof = fnv_merkle.generate_proof(index).unwrap();
+            let fnv_leaf = hash_with(&BuildHasherDefault::<Fnv1a>::default(), element);
+
+            assert!(default_merkle.verify(&default_proof, hash_element(element)));
+            assert!(fnv_merkle.verify(&fnv_proof, fnv_leaf));
+            assert!(!fnv_merkle.verify(&default_proof, hash_element(element)));
+        }
 
-        assert_eq!(merkle.arr[LEVEL_0].len(), desired_quantity_in_first_level);
-        assert_eq!(merkle.arr.len(), desired_level_quantity);
+        // Elements added later use the hasher of the tree too
+        fnv_merkle.add_element("Hasher");
+        let index = fnv_merkle.index_of(&"Hasher").unwrap();
+        let proof = fnv_merkle.generate_proof(index).unwrap();
+        let leaf = hash_with(&BuildHasherDefault::<Fnv1a>::default(), "Hasher");
+        assert!(fnv_merkle.verify(&proof, leaf));
     }
 
     #[test]
-    /// Test if the creation of a Merkle Tree with an input array that
-    /// has a len that is not power of 2, has the correct hash values
-    /// on the first level.
-    /// 
-    /// With 3 elements, the creation should copy the last element so
-    /// the first level has 4 elements. The last and penultimate hashes
-    /// in the first level should be the same.
-    fn creation_from_array_3_elements() {
+    /// Test if a proof of a tree with sorted pairs verifies with the leaf alone,
+    /// also after adding elements in both cases of add_element.
+    fn sorted_proof_verifies_without_index() {
         let data = vec!["Crypto", "Merkle", "Rust"];
-        let merkle = MerkleTree::new(data);
-        let last_i = 3;
-        let penultimate_i = 2;
+        let mut merkle = MerkleTree::new_sorted(data);
+        assert_eq!(merkle.ordering(), PairOrdering::Sorted);
+
+        let proof = merkle.generate_proof(2).unwrap();
+        assert!(merkle.verify_sorted(proof.hashes(), hash_element("Rust")));
+        assert!(merkle.verify(&proof, hash_element("Rust")));
+        assert!(!merkle.verify_sorted(proof.hashes(), hash_element("Crypto")));
+
+        // Replaces the repeated value, then creates a new root level
+        for new_element in ["Tree", "Test"] {
+            merkle.add_element(new_element);
+            let index = merkle.index_of(&new_element).unwrap();
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify_sorted(proof.hashes(), hash_element(new_element)));
+        }
+        let proof = merkle.generate_proof(0).unwrap();
+        assert!(merkle.verify_sorted(proof.hashes(), hash_element("Crypto")));
+    }
 
-        assert_eq!(merkle.arr[LEVEL_0][last_i], merkle.arr[LEVEL_0][penultimate_i]);
+    /// Computes the root of some leaves with the recursive definition of
+    /// RFC 6962: the first subtree has the largest power of 2 leaves that is
+    /// smaller than the quantity of leaves, and the second one the rest.
+    fn rfc6962_root(leaves: &[u64]) -> u64 {
+        if leaves.len() == 1 {
+            return leaves[0];
+        }
+        let split = leaves.len().next_power_of_two() / 2;
+        hash_node(concatenate_elements(rfc6962_root(&leaves[..split]), rfc6962_root(&leaves[split..])))
     }
 
     #[test]
-    /// Test if the expected hash to be the root is actually the root 
-    /// of the tree.
-    fn is_root_returns_true() {
-        let data = vec!["Crypto", "Merkle"];
-        let merkle = MerkleTree::new(data.clone());
+    /// Test if trees of 5 and 7 leaves that promote odd nodes have the shape
+    /// RFC 6962 defines, and if every proof has one hash for each level where
+    /// its path has a sibling.
+    ///
+    /// With 5 leaves the last one is promoted twice and joins the root with
+    /// a proof of one hash. With 7 leaves the last one is promoted once.
+    fn rfc6962_shape_with_5_and_7_leaves() {
+        let cases = [
+            (5, vec![5, 3, 2, 1], vec![3, 3, 3, 3, 1]),
+            (7, vec![7, 4, 2, 1], vec![3, 3, 3, 3, 3, 3, 2]),
+        ];
+        for (len, level_lens, proof_lens) in cases {
+            let data: Vec<usize> = (0..len).collect();
+            let leaves: Vec<u64> = data.iter().map(hash_element).collect();
+            let merkle = MerkleTree::new_rfc6962(data);
+
+            assert_eq!(merkle.padding(), PaddingStrategy::PromoteOdd);
+            assert_eq!(merkle.arr.iter().map(<[u64]>::len).collect::<Vec<_>>(), level_lens);
+            assert_eq!(merkle.arr[LEVEL_0], leaves);
+            assert_eq!(merkle.root(), Some(rfc6962_root(&leaves)));
+            assert_eq!((merkle.len(), merkle.padded_len()), (len, len));
+            assert_eq!(ProofPack::export(&merkle, &[0], true).err(), Some(MerkleError::UnsupportedPadding));
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle.generate_proof(index).unwrap();
+                assert_eq!(proof.len(), proof_lens[index]);
+                assert!(merkle.verify(&proof, *leaf));
+                assert!(!merkle.verify(&proof, leaves[(index + 1) % len]));
+
+                // A proof with a hash too many or too few never verifies
+                let mut longer = proof.siblings().to_vec();
+                longer.push((*leaf, Side::Right));
+                assert!(!merkle.verify(&Proof::new(index, longer), *leaf));
+                assert!(!merkle.verify(&Proof::new(index, proof.siblings()[1..].to_vec()), *leaf));
+
+                // The old proofs without sides still know where each hash goes
+                #[allow(deprecated)]
+                let hashes = merkle.generate_proof_hashes(index).unwrap();
+                assert_eq!(hashes, proof.hashes());
+                #[allow(deprecated)]
+                let verified = merkle.verify_hashes(hashes, index, *leaf);
+                assert!(verified);
+
+                let mut buffer = [0u64; 4];
+                let written = merkle.generate_proof_into(index, &mut buffer).unwrap();
+                assert_eq!(buffer[..written], proof.hashes());
+            }
+        }
+    }
 
-        // We manually get the root
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
+    #[test]
+    /// Test if adding elements one by one to a tree that promotes odd nodes
+    /// gives the same tree as building it from every element at once.
+    fn rfc6962_add_element_matches_new() {
+        let mut merkle = MerkleTree::new_rfc6962(vec![0usize]);
+        for len in 2..=17 {
+            merkle.add_element(len - 1);
+            let expected = MerkleTree::new_rfc6962((0..len).collect());
+
+            assert_eq!(merkle.arr, expected.arr);
+            assert_eq!(merkle.len(), len);
+            let proof = merkle.generate_proof(len - 1).unwrap();
+            assert!(merkle.verify(&proof, hash_element(len - 1)));
+        }
+    }
 
-        let root_concatenation = concatenate_elements(elem0_hash, elem1_hash);
-        let desired_root = hash_element(root_concatenation);
+    #[test]
+    /// Test if the same 5 elements padded with each strategy give the
+    /// expected padded base level, proof lens and distinct roots.
+    fn padding_strategies_with_5_elements() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let leaves: Vec<u64> = data.iter().map(hash_element).collect();
+        let with_padding = |padding| MerkleTree::builder().padding(padding).build(data.clone()).unwrap();
+        let repeat_last = with_padding(PaddingStrategy::RepeatLast);
+        let zero_hash = with_padding(PaddingStrategy::ZeroHash);
+        let promote_odd = with_padding(PaddingStrategy::PromoteOdd);
+        assert_eq!(repeat_last, MerkleTree::new(data.clone()));
+
+        let cases = [
+            (&repeat_last, [leaves[4]; 3].to_vec(), [3, 3, 3, 3, 3]),
+            (&zero_hash, [ZERO_LEAF; 3].to_vec(), [3, 3, 3, 3, 3]),
+            (&promote_odd, Vec::new(), [3, 3, 3, 3, 1]),
+        ];
+        for (merkle, padding, proof_lens) in cases {
+            assert_eq!(merkle.arr.iter().map(<[u64]>::len).collect::<Vec<_>>(), [5, 3, 2, 1]);
+            let padded_leaves: Vec<u64> = leaves.iter().copied().chain(padding).collect();
+            assert_eq!(merkle.padded_level(LEVEL_0), padded_leaves);
+            assert_eq!(merkle.padded_len(), padded_leaves.len());
+            assert_eq!(merkle.get_padded_leaf(5), padded_leaves.get(5).copied());
+            assert_eq!(merkle.get_leaf(5), None);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle.generate_proof(index).unwrap();
+                assert_eq!(proof.len(), proof_lens[index]);
+                assert!(merkle.verify(&proof, *leaf));
+                #[allow(deprecated)]
+                let verified = merkle.verify_hashes(proof.hashes(), index, *leaf);
+                assert!(verified);
+            }
+        }
 
-        assert!(merkle.is_root(desired_root));
+        // The zero padding is hashed like any leaf, so it is the root of 8 leaves
+        let explicit = MerkleTree::from_hashes(leaves.iter().copied().chain([ZERO_LEAF; 3]));
+        assert_eq!(zero_hash.root(), explicit.root());
+        assert_ne!(zero_hash.root(), repeat_last.root());
+        assert_ne!(zero_hash.root(), promote_odd.root());
+        assert_ne!(repeat_last.root(), promote_odd.root());
     }
 
     #[test]
-    /// Test if a random hash is the root of the tree.
-    fn is_root_returns_false() {
-        let data = vec!["Crypto", "Merkle"];
-        let merkle = MerkleTree::new(data.clone());
+    /// Test if a tree padded with zeros keeps the root `new` would give
+    /// after adding, updating and removing elements, and repeating the last
+    /// element changes its root.
+    fn zero_padding_follows_mutations() {
+        let zero_padded = |len: usize| MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build((0..len).collect()).unwrap();
+        let mut merkle = zero_padded(1);
+        for len in 2..=17 {
+            merkle.add_element(len - 1);
+            assert_eq!(merkle.arr, zero_padded(len).arr);
+        }
+        merkle.update_element(16, 16usize).unwrap();
+        while merkle.len() > 1 {
+            merkle.pop_element();
+            assert_eq!(merkle.arr, zero_padded(merkle.len()).arr);
+        }
 
-        // We manually get the root
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
-        // We add garbage to the concatenation so the hash changes
-        let garbage = "x";
-        let root_concatenation = concatenate_elements(elem0_hash, elem1_hash) + garbage;
-        let wrong_root = hash_element(root_concatenation);
+        let mut batch = zero_padded(3);
+        batch.add_elements(vec![3usize, 4, 5]);
+        assert_eq!(batch.arr, zero_padded(6).arr);
 
-        assert!(!merkle.is_root(wrong_root));
+        let repeated = MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build(vec![0usize, 1, 2, 2]).unwrap();
+        assert_ne!(zero_padded(3).root(), repeated.root());
     }
 
     #[test]
-    /// Test if the tree can verify a correct proof
-    fn tree_verifies_proof() {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+    /// Test if a Proof verifies without the caller supplying any index, since
+    /// every hash carries its side, and if flipping any side makes it fail.
+    fn proof_sides_replace_the_index() {
+        // With 6 elements no sibling is a copy of its node, which would give
+        // the same hash on either side
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof"];
         let merkle = MerkleTree::new(data.clone());
 
-        // Get the hashes of the elements manually.
-        // Level 0. It has the hashes of every element.
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
-        let elem2_hash = hash_element(data[2]);
-        let elem3_hash = hash_element(data[3]);
+        for (index, element) in data.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert_eq!(proof.leaf_index(), index);
+            assert!(merkle.verify(&proof, hash_element(element)));
+
+            for flipped in 0..proof.len() {
+                let mut siblings = proof.siblings().to_vec();
+                siblings[flipped].1 = match siblings[flipped].1 {
+                    Side::Left => Side::Right,
+                    Side::Right => Side::Left,
+                };
+                assert!(!merkle.verify(&Proof::new(index, siblings), hash_element(element)));
+            }
+        }
+    }
 
-        // Create one of the proof hashes that we will be using:
-        // (elem2_hash + elem3_hash) = elem23_hash
-        let elem23 = concatenate_elements(elem2_hash, elem3_hash);
-        let elem23_hash = hash_element(elem23);
+    #[test]
+    /// Test if each reason for a proof to fail is reported, including a
+    /// proof that is one hash too short.
+    fn verify_detailed_errors() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        let leaf = hash_element("Rust");
+        let proof = merkle.generate_proof(2).unwrap();
+        let root = merkle.root().unwrap();
+        assert_eq!(merkle.verify_detailed(&proof, leaf), Ok(()));
+
+        let out_of_range = Proof::new(5, proof.siblings().to_vec());
+        assert_eq!(merkle.verify_detailed(&out_of_range, leaf), Err(VerifyError::InvalidIndex { index: 5, len: 5 }));
+
+        let short = Proof::new(2, proof.siblings()[..2].to_vec());
+        assert_eq!(merkle.verify_detailed(&short, leaf), Err(VerifyError::WrongProofLength { expected: 3, got: 2 }));
+        assert!(!merkle.verify(&short, leaf));
+
+        let wrong_leaf = hash_element("Other");
+        let computed = proof.fold_with(&merkle.hasher, merkle.ordering, wrong_leaf).unwrap();
+        assert_eq!(merkle.verify_detailed(&proof, wrong_leaf), Err(VerifyError::RootMismatch { computed, expected: root }));
+    }
 
-        // Creation of the proof and the necessary index 
-        let proof = vec![elem0_hash, elem23_hash];
-        let elem1_index = 1;
-         
-        assert!(merkle.verify(proof, elem1_index, elem1_hash));
+    #[test]
+    /// Test if the expected len of the proofs of a tree that promotes odd
+    /// nodes leaves out the levels without a sibling.
+    fn verify_detailed_promoted_proof_len() {
+        let merkle = MerkleTree::new_rfc6962((0..5usize).collect());
+        let proof = merkle.generate_proof(4).unwrap();
+        assert_eq!(proof.len(), 1);
+        assert_eq!(merkle.verify_detailed(&proof, hash_element(4usize)), Ok(()));
+
+        let padded = Proof::new(4, vec![(0, Side::Right), proof.siblings()[0]]);
+        assert_eq!(merkle.verify_detailed(&padded, hash_element(4usize)), Err(VerifyError::WrongProofLength { expected: 1, got: 2 }));
     }
 
     #[test]
-    /// Test if the tree can verify an incorrect proof
-    fn tree_cant_verify_wrong_proof() {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
-        let merkle = MerkleTree::new(data.clone());
+    /// Test if every proof has the expected len, for trees built at once
+    /// and grown with add_element, with and without the tree at hand.
+    fn proofs_have_expected_len() {
+        let empty = MerkleTree::new(Vec::<usize>::new());
+        assert_eq!((empty.depth(), empty.expected_proof_len(), expected_proof_len_for(0)), (0, 0, 0));
+
+        let mut grown = MerkleTree::default();
+        for len in 1..=33usize {
+            grown.add_element(len - 1);
+            let zero_padded = MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build((0..len).collect()).unwrap();
+            for merkle in [&MerkleTree::new((0..len).collect()), &grown, &zero_padded] {
+                assert_eq!(merkle.depth(), merkle.height() - 1);
+                assert_eq!(merkle.expected_proof_len(), expected_proof_len_for(len));
+                for index in 0..len {
+                    assert_eq!(merkle.generate_proof(index).unwrap().len(), merkle.expected_proof_len());
+                }
+            }
 
-        // Get the hashes of the elements manually.
-        // Level 0. It has the hashes of every element
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
-        let elem2_hash = hash_element(data[2]);
-        let elem3_hash = hash_element(data[3]);
+            // Promoted nodes only make proofs shorter
+            let promoted = MerkleTree::new_rfc6962((0..len).collect());
+            assert_eq!(promoted.expected_proof_len(), expected_proof_len_for(len));
+            assert!((0..len).all(|index| promoted.generate_proof(index).unwrap().len() <= promoted.expected_proof_len()));
+        }
+        assert_eq!(expected_proof_len_for(1), 0);
+        assert_eq!(expected_proof_len_for(5), 3);
+        assert_eq!(expected_proof_len_for(1 << 20), 20);
+    }
 
-        // Create one of the proofs that we will be using:
-        // (elem2_hash + elem3_hash) = elem23_hash
-        let garbage = "X";
-        let elem23 = concatenate_elements(elem2_hash, elem3_hash) + garbage;
-        let elem23_hash = hash_element(elem23);
+    #[test]
+    /// Test if a proof relabelled with another index is rejected by every
+    /// verifier, since its sides are not the ones of that index, and so is
+    /// the proof of a padded slot relabelled as one of the last element
+    fn relabelled_proof_is_rejected() {
+        let data: Vec<usize> = (0..6).collect();
+        for merkle in [MerkleTree::new(data.clone()), MerkleTree::new_sorted(data.clone()), MerkleTree::with_committed_len(data.clone())] {
+            let root = merkle.root().unwrap();
+            let proof = merkle.generate_proof(2).unwrap();
+            let relabelled = Proof::new(5, proof.siblings().to_vec());
+            assert!(merkle.verify(&proof, hash_element(2usize)));
+            assert_eq!(merkle.verify_detailed(&relabelled, hash_element(2usize)), Err(VerifyError::WrongSide { index: 5 }));
+            assert!(!verify_against_root_with(&merkle.hasher, merkle.ordering, root, 6, &relabelled, hash_element(2usize)));
+            assert!(!verify_committed_against_root_with(&merkle.hasher, merkle.ordering, root, 6, &relabelled, hash_element(2usize)));
+        }
 
-        let proof = vec![elem0_hash, elem23_hash];
-        let elem1_index = 1;
-         
-        assert!(!merkle.verify(proof, elem1_index, elem1_hash));
+        let merkle = MerkleTree::new(data[..5].to_vec());
+        let padded = merkle.generate_proof_padded(6).unwrap();
+        assert_eq!(padded.fold_with(&merkle.hasher, merkle.ordering, hash_element(4usize)), merkle.root());
+        let relabelled = Proof::new(4, padded.siblings().to_vec());
+        assert_eq!(merkle.verify_detailed(&relabelled, hash_element(4usize)), Err(VerifyError::WrongSide { index: 4 }));
+        assert!(!verify_against_root(merkle.root().unwrap(), 5, &relabelled, hash_element(4usize)));
     }
 
     #[test]
-    /// Test if passing the wrong index makes the verifying to fail
-    fn verify_with_wrong_index() {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
-        let merkle = MerkleTree::new(data.clone());
-
-        // Get the hashes of the elements and manually create the tree structure
-        // Level 0. It has the hashes of every element
-        let elem0_hash = hash_element(data[0]);
-        let elem1_hash = hash_element(data[1]);
-        let elem2_hash = hash_element(data[2]);
-        let elem3_hash = hash_element(data[3]);
+    /// Test if empty proofs, proofs with a hash too few and proofs with a
+    /// hash too many are rejected by every verifier, for several sizes.
+    #[allow(deprecated)]
+    fn wrong_length_proofs_are_rejected() {
+        for len in [1usize, 2, 3, 5, 8, 13] {
+            let data: Vec<usize> = (0..len).collect();
+            for merkle in [MerkleTree::new(data.clone()), MerkleTree::new_sorted(data.clone()), MerkleTree::new_rfc6962(data.clone())] {
+                for index in 0..len {
+                    let leaf = hash_element(index);
+                    let proof = merkle.generate_proof(index).unwrap();
+                    let expected = proof.len();
+
+                    let mut longer = proof.siblings().to_vec();
+                    longer.push((merkle.root().unwrap(), Side::Right));
+                    let mut wrong = vec![Proof::new(index, longer)];
+                    if expected > 0 {
+                        wrong.push(Proof::new(index, Vec::new()));
+                        wrong.push(Proof::new(index, proof.siblings()[..expected - 1].to_vec()));
+                    }
+                    for wrong_proof in wrong {
+                        assert_eq!(
+                            merkle.verify_detailed(&wrong_proof, leaf),
+                            Err(VerifyError::WrongProofLength { expected, got: wrong_proof.len() }),
+                            "len {len}, index {index}"
+                        );
+                        assert!(!merkle.verify(&wrong_proof, leaf));
+                        assert!(!merkle.verify_hashes(wrong_proof.hashes(), index, leaf));
+                        assert!(!merkle.verify_sorted(wrong_proof.hashes(), leaf));
+                    }
+                }
+            }
+        }
+    }
 
-        // Create one of the proofs that we will be using:
-        // (elem2_hash + elem3_hash) = elem23_hash
-        let garbage = "X";
-        let elem23 = concatenate_elements(elem2_hash, elem3_hash) + garbage;
-        let elem23_hash = hash_element(elem23);
+    #[test]
+    /// Test if an inner node passed as a leaf, with the rest of the path as
+    /// its proof, is rejected even if it leads to the root
+    #[allow(deprecated)]
+    fn inner_node_is_not_a_leaf() {
+        let data: Vec<usize> = (0..8).collect();
+        for merkle in [MerkleTree::new(data.clone()), MerkleTree::new_sorted(data)] {
+            let proof = merkle.generate_proof(2).unwrap();
+            let parent = merkle.get_node(LEVEL_1, 1).unwrap();
+            let shortened = Proof::new(1, proof.siblings()[1..].to_vec());
+            assert_eq!(shortened.fold_with(&merkle.hasher, merkle.ordering, parent), merkle.root());
+
+            assert!(!merkle.verify(&shortened, parent));
+            assert!(!merkle.verify_hashes(shortened.hashes(), 1, parent));
+            assert!(!merkle.verify_sorted(shortened.hashes(), parent));
+        }
+    }
 
-        let proof = vec![elem0_hash, elem23_hash];
-        let elem1_wrong_index = 2;
-         
-        assert!(!merkle.verify(proof, elem1_wrong_index, elem1_hash));
+    #[test]
+    /// Test if the free verifiers reject an inner node passed as a leaf,
+    /// with the rest of its path as the proof, whatever index it claims:
+    /// the len of the tree tells how many hashes the proof must have
+    fn free_verifiers_check_the_proof_len() {
+        let data: Vec<usize> = (0..8).collect();
+        let (merkle, committed) = (MerkleTree::new(data.clone()), MerkleTree::with_committed_len(data));
+        let (root, committed_root) = (merkle.root().unwrap(), committed.root().unwrap());
+        for index in 0..8 {
+            let (proof, committed_proof) = (merkle.generate_proof(index).unwrap(), committed.generate_proof(index).unwrap());
+            assert!(verify_against_root(root, 8, &proof, hash_element(index)));
+            assert!(verify_committed_against_root(committed_root, 8, &committed_proof, hash_element(index)));
+
+            let parent = merkle.get_node(LEVEL_1, index / 2).unwrap();
+            let shortened = Proof::new(index / 2, proof.siblings()[1..].to_vec());
+            assert_eq!(shortened.fold_with(&merkle.hasher, merkle.ordering, parent), Some(root));
+            assert!(!verify_against_root(root, 8, &shortened, parent));
+            let shortened = Proof::new(index / 2, committed_proof.siblings()[1..].to_vec());
+            assert!(!verify_committed_against_root(committed_root, 8, &shortened, parent));
+        }
     }
 
     #[test]
-    /// Test if the generation of proof works
+    /// Test if proving an index outside of the base level returns the index
+    /// and the quantity of elements, and proving a padded index says so.
     /// 
-    /// By getting the manually created tree and the tree created with
-    /// our abstraction, we manually create what would be the correct proof
-    /// for the first element in the input array. We then check if the 
-    /// generated proof is equal to the one we manually created.  
-    fn generate_right_proof() {
-        let (manual_tree, merkle) = manually_create_tree_hashes();
+    /// A tree of 5 elements has a base level of 8, so the indexes from 5
+    /// to 7 are padding and the ones from 8 are outside of the tree.
+    fn generate_proof_invalid_index() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+
+        assert_eq!(merkle.generate_proof(5), Err(MerkleError::PaddedIndex { index: 5 }));
+        assert_eq!(merkle.generate_proof(7), Err(MerkleError::PaddedIndex { index: 7 }));
+        assert_eq!(merkle.generate_proof(8), Err(MerkleError::InvalidIndex { index: 8, len: 5 }));
+        assert_eq!(merkle.generate_proof(100), Err(MerkleError::InvalidIndex { index: 100, len: 5 }));
+        #[allow(deprecated)]
+        let hashes = merkle.generate_proof_hashes(7);
+        assert_eq!(hashes, Err(MerkleError::PaddedIndex { index: 7 }));
+
+        // Nothing is padding when the odd nodes are promoted
+        let promoted = MerkleTree::new_rfc6962(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        assert_eq!(promoted.generate_proof(5), Err(MerkleError::InvalidIndex { index: 5, len: 5 }));
+    }
 
-        let elem1_hash = manual_tree[LEVEL_0][1];
-        let elem23_hash = manual_tree[LEVEL_1][1];
+    #[test]
+    /// Test if the padded indexes can be proven when asked for, and their
+    /// proofs verify with the hash of the last element they copy.
+    fn generate_proof_padded_proves_the_copies() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
 
-        let desired_proof = vec![elem1_hash, elem23_hash];
-        let proof = merkle.generate_proof(0).unwrap();
+        for index in 0..5 {
+            assert_eq!(merkle.generate_proof_padded(index), merkle.generate_proof(index));
+        }
+        for index in 5..8 {
+            let proof = merkle.generate_proof_padded(index).unwrap();
+            assert_eq!(proof.leaf_index(), index);
+            assert_eq!(proof.fold_with(&merkle.hasher, merkle.ordering, hash_element("Test")), merkle.root());
+            // They are not proofs of one of the elements
+            assert!(!verify_against_root(merkle.root().unwrap(), 5, &proof, hash_element("Test")));
+        }
+        assert_eq!(merkle.generate_proof_padded(8), Err(MerkleError::InvalidIndex { index: 8, len: 5 }));
+    }
 
-        assert_eq!(proof, desired_proof);
+    #[test]
+    /// Test if the hex helpers give 16 digits that parse back to the hashes
+    fn hex_helpers_round_trip() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        let root_hex = merkle.root_hex().unwrap();
+        assert_eq!(root_hex, merkle.root_hash().unwrap().to_string());
+        assert_eq!(crate::root_hash::parse_hex(&root_hex), Ok(merkle.root().unwrap()));
+
+        assert_eq!(merkle.leaf_hex(1), Some(format!("{:016x}", hash_element("Merkle"))));
+        assert_eq!(merkle.leaf_hex(5), None);
+        let hexes = merkle.proof_hex(2).unwrap();
+        let parsed: Vec<u64> = hexes.iter().map(|hash| crate::root_hash::parse_hex(hash).unwrap()).collect();
+        assert!(hexes.iter().all(|hash| hash.len() == 16));
+        assert_eq!(parsed, merkle.generate_proof(2).unwrap().hashes());
+        assert_eq!(merkle.proof_hex(5), Err(MerkleError::PaddedIndex { index: 5 }));
+        assert_eq!(MerkleTree::new(Vec::<u8>::new()).root_hex(), None);
     }
 
     #[test]
-    /// Test if adding a new element in a tree that already has a base
-    /// level of 2^n different elements creates a new level on the tree
-    /// 
-    /// If we start a Merkle Tree with an input array of 4 elements,
-    /// this will create a tree with 3 levels. If we add an element
-    /// the base level grows, creating a new level on the tree.
-    fn add_element_creates_new_level() {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
-        let mut desired_merkle_levels = 3;
-        let mut merkle = MerkleTree::new(data);
+    /// Test if proving by value finds the first occurrence of the element,
+    /// and the proofs of both occurrences of a repeated value verify.
+    fn generate_proof_for_element() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Merkle", "Tree"];
+        let merkle = MerkleTree::new(data);
 
-        assert_eq!(merkle.arr.len(), desired_merkle_levels);
+        let first = merkle.generate_proof_for(&"Merkle").unwrap();
+        assert_eq!(first.leaf_index(), 1);
+        assert!(merkle.verify(&first, hash_element("Merkle")));
 
-        merkle.add_element("Test");
-        desired_merkle_levels = 4;
+        let second = merkle.generate_proof(merkle.indices_of(&"Merkle")[1]).unwrap();
+        assert_eq!(second.leaf_index(), 3);
+        assert!(merkle.verify(&second, hash_element("Merkle")));
 
-        assert_eq!(merkle.arr.len(), desired_merkle_levels);
+        assert_eq!(merkle.generate_proof_for(&"Other"), Err(MerkleError::NotFound));
     }
 
     #[test]
-    /// Test if adding a new element in a tree that already has a base
-    /// level of 2^n different elements, doubles the quantity of
-    /// base elements.
-    /// 
-    /// If we start a Merkle Tree with an input array of 4 elements,
-    /// by adding an element we no longer have a base level with
-    /// a quantity that is a power of 2. So to have that again
-    /// we need to have repeated values. In this case, we should
-    /// end up having a base level with 8 elements.
-    fn add_element_doubles_base_elements() {
-        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
-        let mut desired_base_level_quantity = data.len();
-        let mut merkle = MerkleTree::new(data);
+    /// Test if the proof of an element that is repeated as padding points
+    /// to the element itself and not to one of its copies.
+    fn generate_proof_for_skips_padding() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        // The base level ends with Test repeated 4 times
+        let proof = merkle.generate_proof_for(&"Test").unwrap();
+
+        assert_eq!(proof.leaf_index(), 4);
+        assert!(merkle.verify(&proof, hash_element("Test")));
+        assert_eq!(merkle.indices_of(&"Test"), vec![4]);
+    }
 
-        assert_eq!(merkle.arr[LEVEL_0].len(), desired_base_level_quantity);
+    #[test]
+    /// Test if a verifier that only has the root and the proofs can check the
+    /// inclusion of every element, once the tree is gone.
+    fn verify_against_root_without_tree() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let (root, proofs) = {
+            let merkle = MerkleTree::new(data.clone());
+            let proofs: Vec<Proof> = (0..data.len()).map(|i| merkle.generate_proof(i).unwrap()).collect();
+            (merkle.root().unwrap(), proofs)
+        };
 
-        merkle.add_element("Test");
-        desired_base_level_quantity *= 2;
+        for (proof, element) in proofs.iter().zip(&data) {
+            assert!(verify_against_root(root, 5, proof, hash_element(element)));
+            assert!(!verify_against_root(root, 5, proof, hash_element("Other")));
+            assert!(!verify_against_root(root ^ 1, 5, proof, hash_element(element)));
+            assert!(!verify_against_root(root, 9, proof, hash_element(element)));
+        }
 
-        assert_eq!(merkle.arr[LEVEL_0].len(), desired_base_level_quantity);
+        // The hashes of these elements are not already sorted the way their positions are
+        let sorted = MerkleTree::new_sorted(vec!["Crypto", "Merkle", "Rust", "Tree", "Test", "Proof"]);
+        let proof = sorted.generate_proof(3).unwrap();
+        let root = sorted.root().unwrap();
+        assert!(verify_against_root_with(&DefaultBuildHasher::default(), PairOrdering::Sorted, root, 6, &proof, hash_element("Tree")));
+        assert!(!verify_against_root(root, 6, &proof, hash_element("Tree")));
     }
 
     #[test]
-    /// Test if the base level elements are correct when adding a new element
-    /// in a tree that already has a base level of 2^n different elements
-    fn add_element_creates_correct_hashes() {
-        let data = vec!["Crypto", "Merkle"];
-        let new_elem = "Rust";
-        let mut merkle = MerkleTree::new(data);
-        let old_root = merkle.arr[1][0];
+    /// Test if a tree with positional pairs does not accept proofs without an index
+    fn positional_tree_rejects_sorted_verification() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]);
+        let proof = merkle.generate_proof(2).unwrap();
 
-        merkle.add_element(new_elem);
-        let new_elem_hash = hash_element(new_elem);
+        assert_eq!(merkle.ordering(), PairOrdering::Positional);
+        assert!(!merkle.verify_sorted(proof.hashes(), hash_element("Rust")));
+    }
 
-        assert_eq!(merkle.arr[LEVEL_0][2], new_elem_hash);
-        assert_eq!(merkle.arr[LEVEL_0][3], new_elem_hash);
-        assert!(!merkle.is_root(old_root));
+    #[test]
+    /// Test if trees built from the same data are equal, and a clone can
+    /// change without affecting the original.
+    fn clone_and_equality() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = MerkleTree::new(data.clone());
+        assert_eq!(merkle, MerkleTree::new(data.clone()));
+        assert!(merkle.same_root(&MerkleTree::new(data.clone())));
+
+        let mut cloned = merkle.clone();
+        assert_eq!(cloned, merkle);
+        cloned.add_element("Other");
+        assert_ne!(cloned, merkle);
+        assert!(!cloned.same_root(&merkle));
+        assert_eq!(merkle, MerkleTree::new(data));
+        assert_eq!(merkle.len(), 5);
+
+        assert!(!MerkleTree::default().same_root(&MerkleTree::default()));
     }
 
     #[test]
-    /// Test if adding an element when having repeated values on the base level
-    /// replaces the first repeated level to the new element and re-calculates
-    /// the necessary nodes.
-    /// 
-    /// When creating a tree with an input array of 3 elements, the last element will
-    /// be repeated on the base level so it can have a len that is a power of 2. However,
-    /// when adding a new element in this case it should replace the element that is
-    /// repeated and re-calculate a whole half of the tree, even the root.
-    fn add_element_replaces_repeated_element() {
-        let data = vec!["Crypto", "Merkle", "Rust"];
-        let mut merkle = MerkleTree::new(data);
-        let last_base_level_index = 3;
-        let last_hash_before_add = merkle.arr[LEVEL_0][last_base_level_index];
+    /// Test if a tree is printed with its size and root, not with every hash
+    fn debug_shows_summary() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+        let expected = format!("MerkleTree {{ height: 3, len: 3, root: Some({:?}) }}", merkle.root_hex().unwrap());
+        assert_eq!(format!("{merkle:?}"), expected);
+        assert_eq!(format!("{:?}", MerkleTree::default()), "MerkleTree { height: 0, len: 0, root: None }");
+    }
 
-        let new_element = String::from("Tree");
-        let new_element_hash = hash_element(new_element.clone());
-        merkle.add_element(new_element);
-        let last_hash_after_add = merkle.arr[LEVEL_0][last_base_level_index];
+    #[test]
+    /// Test if get_leaf only gives the different elements, get_padded_leaf
+    /// also the padding, and none of them panics outside of the tree.
+    fn get_leaf_bounds() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = MerkleTree::new(data.clone());
+        for (index, element) in data.iter().enumerate() {
+            assert_eq!(merkle.get_leaf(index), Some(hash_element(element)));
+            assert!(merkle.verify(&merkle.generate_proof(index).unwrap(), merkle.get_leaf(index).unwrap()));
+        }
+        assert_eq!(merkle.get_leaf(5), None);
+        assert_eq!(merkle.get_padded_leaf(7), Some(hash_element("Test")));
+        assert_eq!(merkle.get_padded_leaf(8), None);
+        assert_eq!(merkle.get_leaf(usize::MAX), None);
+
+        let empty = MerkleTree::default();
+        assert_eq!(empty.get_leaf(0), None);
+        assert_eq!(empty.get_padded_leaf(0), None);
+    }
 
-        assert_eq!(last_hash_after_add, new_element_hash);
-        assert_ne!(last_hash_after_add, last_hash_before_add);
+    #[test]
+    /// Test if every node of the tree is reachable with get_node, with
+    /// level 0 being the leaves and the last level the root.
+    fn get_node_matches_manual_tree() {
+        let (manual_tree, merkle) = manually_create_tree_hashes();
+        for (level, hashes) in manual_tree.iter().enumerate() {
+            for (index, hash) in hashes.iter().enumerate() {
+                assert_eq!(merkle.get_node(level, index), Some(*hash));
+            }
+            assert_eq!(merkle.get_node(level, hashes.len()), None);
+        }
+        assert_eq!(merkle.get_node(merkle.height() - 1, 0), merkle.root());
+        assert_eq!(merkle.get_node(merkle.height(), 0), None);
+        assert_eq!(merkle.node_count(), 7);
+
+        // The nodes that only cover padding are not stored
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        assert_eq!(merkle.node_count(), 11);
+        assert_eq!(merkle.get_node(1, 3), merkle.get_node(1, 2));
+        assert_eq!(merkle.get_node(1, 4), None);
+        assert_eq!(MerkleTree::default().get_node(0, 0), None);
+        assert_eq!(MerkleTree::default().node_count(), 0);
+    }
+
+    /// The way `create_remaining_levels_with` created the levels before it
+    /// stopped copying each one, kept to check the new one against it.
+    fn create_remaining_levels_cloning(ordering: PairOrdering, hashed_elements: Vec<u64>) -> Vec<Vec<u64>> {
+        let hasher = DefaultBuildHasher::default();
+        let mut tree_structure = vec![hashed_elements.clone()];
+        let mut hashes = hashed_elements;
+        while hashes.len() > 1 {
+            hashes = hashes.chunks(2).map(|chunk| match chunk {
+                [left, right] => combine_with(&hasher, ordering, *left, *right),
+                _ => chunk[0],
+            }).collect();
+            tree_structure.push(hashes.clone());
+        }
+        tree_structure
     }
 
     #[test]
-    /// Test if adding two elements and using both cases works as expected
-    /// 
-    /// We will have a tree that will be created from an input array of 3 elements.
-    /// This means that to have a base level of 2^n elements, the last one should
-    /// be repeated (so there aren't 2^n different elements). So when we add the 
-    /// first element, the repeated value located at the end of the base 
-    /// level should be replaced with the new value. After that we should have
-    /// a base level of 2^n different elements. So when we add a second element,
-    /// the other case should occur and we should end up with a base level that
-    /// will have 2 times the quantity of elements it has before.
-    fn add_2_elements() {
-        let data = vec!["Crypto", "Merkle", "Rust"];
-        let mut merkle = MerkleTree::new(data);
-        let desired_levels = 4;
-        let replaced_element_index = 3; // We had 3 initial elements. The fourth (index 3) should be the repeated one
+    /// Test if the levels are the same ones the copying algorithm created,
+    /// for padded and odd lens and with both orderings.
+    fn remaining_levels_match_cloning_algorithm() {
+        for ordering in [PairOrdering::Positional, PairOrdering::Sorted] {
+            for len in 0..70u64 {
+                let hashed_elements: Vec<u64> = (0..len).map(hash_element).collect();
+                let levels = create_remaining_levels_with(&DefaultBuildHasher::default(), ordering, PaddingStrategy::PromoteOdd, hashed_elements.clone());
+                assert_eq!(levels, create_remaining_levels_cloning(ordering, hashed_elements).into());
+            }
+        }
+    }
 
-        let new_element_1 = String::from("Tree");
-        let new_element_1_hash = hash_element(new_element_1.clone());
-        let new_element_2 = String::from("Test");
-        merkle.add_element(new_element_1);
-        merkle.add_element(new_element_2);
+    #[test]
+    #[ignore]
+    /// Test if the levels of 2^20 leaves are the same ones the algorithm
+    /// that copied every level created.
+    ///
+    /// Run it with `cargo test --release -- --ignored remaining_levels_at_scale`
+    fn remaining_levels_at_scale() {
+        let hashed_elements: Vec<u64> = (0..1u64 << 20).map(hash_element).collect();
+        let cloning = create_remaining_levels_cloning(PairOrdering::Positional, hashed_elements.clone());
+        let levels = create_remaining_levels(hashed_elements);
+
+        assert_eq!(levels, cloning.into());
+    }
 
-        assert_eq!(merkle.arr.len(), desired_levels);
-        assert_eq!(merkle.arr[LEVEL_0][replaced_element_index], new_element_1_hash);
+    #[test]
+    /// Test if a sequence of mutations leaves the same levels and proofs as
+    /// one vector per level created from the base level after each one.
+    fn flat_levels_match_nested_vectors() {
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+            let mut elements: Vec<usize> = Vec::new();
+            let mut merkle = new_tree(Vec::new());
+            for step in 0..120usize {
+                match step % 7 {
+                    3 => {
+                        merkle.pop_element();
+                        elements.pop();
+                    }
+                    5 if !elements.is_empty() => {
+                        let index = step % elements.len();
+                        merkle.update_element(index, step * 31).unwrap();
+                        elements[index] = step * 31;
+                    }
+                    _ => {
+                        merkle.add_element(step);
+                        elements.push(step);
+                    }
+                }
+                let leaves: Vec<u64> = elements.iter().map(hash_element).collect();
+                assert_eq!(&merkle.arr[LEVEL_0][..elements.len()], leaves);
+                let nested = create_remaining_levels_cloning(PairOrdering::Positional, merkle.padded_level(LEVEL_0));
+                assert_eq!((0..merkle.height()).map(|level| merkle.padded_level(level)).collect::<Vec<_>>(), nested);
+                // The stored levels are the padded ones without the nodes that only cover padding
+                assert!(merkle.levels().zip(&nested).all(|(level, padded)| padded.starts_with(level)));
+                for index in 0..elements.len() {
+                    let mut proof_index = index;
+                    let expected: Vec<u64> = nested[..nested.len() - 1].iter().filter_map(|level| {
+                        let sibling = level.get(proof_index ^ 1).copied();
+                        proof_index /= 2;
+                        sibling
+                    }).collect();
+                    assert_eq!(merkle.generate_proof(index).unwrap().hashes(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Test if a tree of 2^20 + 1 elements stores nearly half the nodes of
+    /// its padded levels, and still has the root and proofs they give.
+    fn padding_is_not_stored() {
+        let len = (1 << 20) + 1;
+        let merkle = MerkleTree::new((0..len).collect::<Vec<usize>>());
+        let padded_nodes = 2 * merkle.padded_len() - 1;
+
+        // Every level but the root stores one node more than half of the padded one
+        assert_eq!(merkle.node_count(), (1 << 21) + 21);
+        assert_eq!(padded_nodes, (1 << 22) - 1);
+        assert_eq!((merkle.len(), merkle.padded_len()), (len, 1 << 21));
+
+        let padded = create_remaining_levels_cloning(PairOrdering::Positional, merkle.padded_level(LEVEL_0));
+        assert_eq!(merkle.root(), padded.last().map(|level| level[0]));
+        for index in [0, len / 2, len - 1] {
+            let mut proof_index = index;
+            let expected: Vec<u64> = padded[..padded.len() - 1].iter().map(|level| {
+                let sibling = level[proof_index ^ 1];
+                proof_index /= 2;
+                sibling
+            }).collect();
+            assert_eq!(merkle.generate_proof(index).unwrap().hashes(), expected);
+        }
+    }
+
+    #[test]
+    /// Test if the levels of a deep tree take less memory than one vector
+    /// per level did: the hashes plus two indexes per level.
+    fn flat_levels_memory_usage() {
+        let merkle = MerkleTree::new((0..1usize << 16).collect());
+        let nodes = merkle.node_count() * size_of::<u64>();
+        let nested = nodes + merkle.height() * size_of::<Vec<u64>>();
+        assert!(merkle.memory_usage() <= nodes + (2 * merkle.height() + 1) * size_of::<usize>());
+        assert!(merkle.memory_usage() < nested);
     }
 }
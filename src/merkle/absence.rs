@@ -0,0 +1,203 @@
+use std::hash::{BuildHasher, Hash};
+
+use super::{create_remaining_levels, hash_element, hash_with, DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering, Proof, TreeStructure};
+use crate::error::MerkleError;
+
+/// A leaf of the tree together with its inclusion proof.
+/// - `leaf`: The hash of the leaf.
+/// - `proof`: The Proof of the leaf, which carries its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    pub leaf: u64,
+    pub proof: Proof,
+}
+
+/// The proof that an element is not in a tree with sorted leaves: the two
+/// adjacent leaves whose hashes bracket the hash of the element.
+/// - `left`: The largest leaf smaller than the element, None if the element is smaller than every leaf.
+/// - `right`: The smallest leaf larger than the element, None if the element is larger than every leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsenceProof {
+    pub left: Option<Neighbor>,
+    pub right: Option<Neighbor>,
+}
+
+impl MerkleTree {
+    /// Creates a new MerkleTree whose leaves are sorted by their hash, so
+    /// `prove_absence` can show that an element is not in it. Repeated
+    /// elements are kept once, since their hashes are equal.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed, sorted and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn new_sorted_leaves<T: Hash>(elements: Vec<T>) -> Self {
+        let mut base_level: Vec<u64> = elements.iter().map(hash_element).collect();
+        base_level.sort_unstable();
+        base_level.dedup();
+        let diff_elements = base_level.len();
+        let arr = if base_level.is_empty() { TreeStructure::new() } else { create_remaining_levels(base_level) };
+        Self::from_levels(arr, diff_elements, DefaultBuildHasher::default(), PairOrdering::Positional, PaddingStrategy::RepeatLast)
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Checks if the different elements of the base level are in strictly
+    /// increasing order. Adding or updating elements of a tree created with
+    /// `new_sorted_leaves` can break it.
+    pub fn has_sorted_leaves(&self) -> bool {
+        self.leaves().windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    /// Generates the proof that an element is not in the tree, made of the
+    /// inclusion proofs of the two adjacent leaves that bracket its hash.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element that is not in the tree. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the AbsenceProof, or an Error if the tree is empty, its
+    /// leaves are not sorted or the element is in the tree
+    pub fn prove_absence<T: Hash>(&self, element: &T) -> Result<AbsenceProof, MerkleError> {
+        if self.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        if !self.has_sorted_leaves() {
+            return Err(MerkleError::UnsortedLeaves);
+        }
+        let hash = hash_with(&self.hasher, element);
+        let right_index = match self.leaves().binary_search(&hash) {
+            Ok(index) => return Err(MerkleError::ElementPresent { index }),
+            Err(index) => index,
+        };
+        let neighbor = |index: usize| -> Result<Neighbor, MerkleError> {
+            Ok(Neighbor { leaf: self.arr[0][index], proof: self.generate_proof(index)? })
+        };
+        let left = right_index.checked_sub(1).map(neighbor).transpose()?;
+        let right = (right_index < self.diff_elements).then(|| neighbor(right_index)).transpose()?;
+        Ok(AbsenceProof { left, right })
+    }
+
+    /// Checks if an AbsenceProof shows that an element is not in the tree:
+    /// both leaves are in the tree, they are adjacent, and the hash of the
+    /// element is strictly between them. A missing left leaf is only
+    /// accepted if the right one is the first leaf, and a missing right leaf
+    /// if the left one is the last.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The AbsenceProof, as `prove_absence` returns it.
+    /// - `element`: The element that is not in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the proof is valid for the element, false otherwise
+    pub fn verify_absence<T: Hash>(&self, proof: &AbsenceProof, element: &T) -> bool {
+        if !self.has_sorted_leaves() {
+            return false;
+        }
+        let hash = hash_with(&self.hasher, element);
+        let is_valid = |neighbor: &Neighbor| self.verify(&neighbor.proof, neighbor.leaf);
+        match (&proof.left, &proof.right) {
+            (Some(left), Some(right)) => {
+                is_valid(left)
+                    && is_valid(right)
+                    && right.proof.leaf_index() == left.proof.leaf_index() + 1
+                    && left.leaf < hash
+                    && hash < right.leaf
+            }
+            (None, Some(right)) => is_valid(right) && right.proof.leaf_index() == 0 && hash < right.leaf,
+            (Some(left), None) => is_valid(left) && left.proof.leaf_index() + 1 == self.diff_elements && left.leaf < hash,
+            (None, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    /// Test if every absent element of random sets gets a proof that
+    /// verifies, and every present one is rejected, checking against a HashSet.
+    fn absence_matches_hash_set() {
+        let mut state: u64 = 0x2545f4914f6cdd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % 200
+        };
+        for len in [1, 2, 5, 16, 33] {
+            let elements: Vec<u64> = (0..len).map(|_| next()).collect();
+            let oracle: HashSet<u64> = elements.iter().copied().collect();
+            let merkle = MerkleTree::new_sorted_leaves(elements);
+            assert_eq!(merkle.len(), oracle.len());
+
+            for candidate in 0..200u64 {
+                match merkle.prove_absence(&candidate) {
+                    Ok(proof) => {
+                        assert!(!oracle.contains(&candidate));
+                        assert!(merkle.verify_absence(&proof, &candidate));
+                    }
+                    Err(MerkleError::ElementPresent { index }) => {
+                        assert!(oracle.contains(&candidate));
+                        assert!(merkle.verify(&merkle.generate_proof(index).unwrap(), hash_element(candidate)));
+                    }
+                    Err(error) => panic!("Unexpected error {error}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Test if elements smaller than the first leaf or larger than the last
+    /// have a single neighbor, and it can not be used for other elements.
+    fn absence_at_the_edges() {
+        let merkle = MerkleTree::new_sorted_leaves((0..10u64).collect());
+        let leaves = merkle.leaves().to_vec();
+        let smallest = (10..).find(|candidate| hash_element(candidate) < leaves[0]).unwrap();
+        let largest = (10..).find(|candidate| hash_element(candidate) > leaves[9]).unwrap();
+
+        let proof = merkle.prove_absence(&smallest).unwrap();
+        assert!(proof.left.is_none());
+        assert_eq!(proof.right.as_ref().unwrap().proof.leaf_index(), 0);
+        assert!(merkle.verify_absence(&proof, &smallest));
+        assert!(!merkle.verify_absence(&proof, &largest));
+
+        let proof = merkle.prove_absence(&largest).unwrap();
+        assert!(proof.right.is_none());
+        assert_eq!(proof.left.as_ref().unwrap().proof.leaf_index(), 9);
+        assert!(merkle.verify_absence(&proof, &largest));
+        assert!(!merkle.verify_absence(&proof, &smallest));
+    }
+
+    #[test]
+    /// Test if proofs with neighbors that are not adjacent, or that hide a
+    /// present element, are rejected.
+    fn absence_rejects_forged_proofs() {
+        let merkle = MerkleTree::new_sorted_leaves((0..8u64).collect());
+        let neighbor = |index: usize| Neighbor { leaf: merkle.arr[0][index], proof: merkle.generate_proof(index).unwrap() };
+        let present = (0..8u64).find(|element| hash_element(element) == merkle.arr[0][3]).unwrap();
+
+        let skipping = AbsenceProof { left: Some(neighbor(2)), right: Some(neighbor(4)) };
+        assert!(!merkle.verify_absence(&skipping, &present));
+        assert!(!merkle.verify_absence(&AbsenceProof { left: None, right: None }, &present));
+        assert_eq!(merkle.prove_absence(&present), Err(MerkleError::ElementPresent { index: 3 }));
+    }
+
+    #[test]
+    /// Test if a tree with unsorted leaves can not prove absences
+    fn absence_needs_sorted_leaves() {
+        let merkle = MerkleTree::new((0..8u64).collect());
+        assert!(!merkle.has_sorted_leaves());
+        assert_eq!(merkle.prove_absence(&100u64), Err(MerkleError::UnsortedLeaves));
+        assert_eq!(MerkleTree::new_sorted_leaves(Vec::<u64>::new()).prove_absence(&1u64), Err(MerkleError::EmptyTree));
+    }
+}
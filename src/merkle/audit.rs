@@ -0,0 +1,126 @@
+use std::hash::{BuildHasher, Hash};
+
+use super::{hash_with, MerkleTree};
+
+/// The reason a dataset does not match the leaves of a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditMismatch {
+    /// The element at `index` does not hash to the leaf at that index.
+    Leaf { index: usize },
+    /// The dataset has a different quantity of elements than the tree.
+    /// - `expected`: Quantity of different elements in the tree.
+    /// - `got`: Quantity of elements in the dataset.
+    Count { expected: usize, got: usize },
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Checks if the tree commits to exactly the given elements, in order.
+    ///
+    /// Each element is hashed the same way `new` hashes them and compared
+    /// with the leaf at its index. The elements are consumed one at a time,
+    /// so the dataset never has to be fully in memory. The repeated values
+    /// at the end of the base level are not part of the dataset.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: The claimed original elements of the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is Ok if every element matches its leaf, or the
+    /// first AuditMismatch found otherwise
+    pub fn audit_against<T: Hash>(&self, elements: impl IntoIterator<Item = T>) -> Result<(), AuditMismatch> {
+        self.audit_leaves(elements.into_iter().map(|element| hash_with(&self.hasher, element)))
+    }
+
+    /// Same as `audit_against` for elements that are raw bytes, like the
+    /// lines of a file or the rows read from a database.
+    pub fn audit_against_bytes<B: AsRef<[u8]>>(&self, elements: impl IntoIterator<Item = B>) -> Result<(), AuditMismatch> {
+        self.audit_leaves(elements.into_iter().map(|element| hash_with(&self.hasher, element.as_ref())))
+    }
+
+    /// Same as `audit_against` for a dataset of leaf hashes that were already computed.
+    pub fn audit_against_hashes(&self, leaves: impl IntoIterator<Item = u64>) -> Result<(), AuditMismatch> {
+        self.audit_leaves(leaves.into_iter())
+    }
+
+    /// Compares the leaves of the dataset with the base level, one by one
+    fn audit_leaves(&self, mut leaves: impl Iterator<Item = u64>) -> Result<(), AuditMismatch> {
+        let expected = self.diff_elements;
+        for index in 0..expected {
+            match leaves.next() {
+                Some(leaf) if leaf == self.arr[0][index] => {}
+                Some(_) => return Err(AuditMismatch::Leaf { index }),
+                None => return Err(AuditMismatch::Count { expected, got: index }),
+            }
+        }
+        let extra = leaves.count();
+        if extra > 0 {
+            return Err(AuditMismatch::Count { expected, got: expected + extra });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    fn data() -> Vec<&'static str> {
+        vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]
+    }
+
+    #[test]
+    /// Test if the original input passes the audit, also after adding an element
+    fn original_input_passes() {
+        let mut merkle = MerkleTree::new(data());
+        assert_eq!(merkle.audit_against(data()), Ok(()));
+
+        merkle.add_element("Audit");
+        let mut grown = data();
+        grown.push("Audit");
+        assert_eq!(merkle.audit_against(grown.iter().copied()), Ok(()));
+    }
+
+    #[test]
+    /// Test if swapping two elements reports the first one that differs
+    fn swapped_element_reports_index() {
+        let merkle = MerkleTree::new(data());
+        let mut swapped = data();
+        swapped.swap(2, 4);
+
+        assert_eq!(merkle.audit_against(swapped), Err(AuditMismatch::Leaf { index: 2 }));
+    }
+
+    #[test]
+    /// Test if a truncated or extended dataset reports the count
+    fn different_len_reports_count() {
+        let merkle = MerkleTree::new(data());
+        let truncated = &data()[..3];
+        let extended = data().into_iter().chain(["Extra", "Extra"]);
+
+        assert_eq!(merkle.audit_against(truncated), Err(AuditMismatch::Count { expected: 5, got: 3 }));
+        assert_eq!(merkle.audit_against(extended), Err(AuditMismatch::Count { expected: 5, got: 7 }));
+    }
+
+    #[test]
+    /// Test if the padding copies of the last elements are not expected in the dataset
+    fn padding_is_not_part_of_the_dataset() {
+        let merkle = MerkleTree::new(data());
+        let with_padding = data().into_iter().chain(["Merkle", "Rust", "Tree"]);
+
+        assert_eq!(merkle.audit_against(with_padding), Err(AuditMismatch::Count { expected: 5, got: 8 }));
+    }
+
+    #[test]
+    /// Test the bytes and hashes variants against their own kind of tree
+    fn bytes_and_hashes_variants() {
+        let lines: Vec<Vec<u8>> = data().iter().map(|line| line.as_bytes().to_vec()).collect();
+        let merkle = MerkleTree::new(lines.iter().map(Vec::as_slice).collect());
+
+        assert_eq!(merkle.audit_against_bytes(&lines), Ok(()));
+        assert_eq!(merkle.audit_against_hashes(lines.iter().map(|line| hash_element(line.as_slice()))), Ok(()));
+        assert_eq!(merkle.audit_against_hashes([0, 1]), Err(AuditMismatch::Leaf { index: 0 }));
+    }
+}
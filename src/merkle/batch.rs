@@ -0,0 +1,205 @@
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+use super::{combine_with, hash_with, DefaultBuildHasher, MerkleTree};
+use crate::error::MerkleError;
+
+/// A group of mutations applied to a tree at once, see `MerkleTree::begin_batch`.
+///
+/// The mutations only write the base level. The nodes above it are
+/// re-calculated when the batch is committed, each one a single time no
+/// matter how many of its leaves changed. The batch borrows the tree, so
+/// the tree can not be read while it is open:
+///
+/// ```compile_fail
+/// use tree::MerkleTree;
+///
+/// let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle"]);
+/// let mut batch = merkle.begin_batch();
+/// batch.add_element("Rust");
+/// let root = merkle.root();
+/// batch.commit();
+/// ```
+///
+/// Dropping the batch commits it as well.
+/// - `tree`: The tree the mutations are applied to.
+/// - `dirty`: Indexes of the base level that changed since the batch was opened.
+/// - `first_added`: Index of the first element added since the batch was opened, every node to its right has to be created again.
+pub struct Batch<'a, H: BuildHasher = DefaultBuildHasher> {
+    tree: &'a mut MerkleTree<H>,
+    dirty: Vec<usize>,
+    first_added: Option<usize>,
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Opens a batch of mutations. Applying many of them through the batch
+    /// re-calculates the nodes they share once, instead of once per mutation.
+    ///
+    /// ### Returns
+    ///
+    /// A Batch that borrows the tree until it is committed or dropped
+    pub fn begin_batch(&mut self) -> Batch<'_, H> {
+        Batch { tree: self, dirty: Vec::new(), first_added: None }
+    }
+}
+
+impl<H: BuildHasher> Batch<'_, H> {
+    /// Replaces a different element of the tree, see `MerkleTree::update_element`.
+    /// Elements added in the same batch can be replaced too.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the input array.
+    /// - `new_elem`: The element that replaces the old one.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is an Error if the index is not one of a different element
+    pub fn update_element<T: Hash>(&mut self, index: usize, new_elem: T) -> Result<(), MerkleError> {
+        let len = self.tree.diff_elements;
+        if index >= len {
+            return Err(MerkleError::InvalidIndex { index, len });
+        }
+        self.tree.arr[0][index] = hash_with(&self.tree.hasher, new_elem);
+        self.dirty.push(index);
+        Ok(())
+    }
+
+    /// Adds an element at the end of the tree, see `MerkleTree::add_element`
+    ///
+    /// ### Arguments
+    ///
+    /// - `new_elem`: The element that will be added.
+    pub fn add_element<T: Hash>(&mut self, new_elem: T) {
+        let tree = &mut *self.tree;
+        let new_hash = hash_with(&tree.hasher, new_elem);
+        if tree.arr.is_empty() {
+            tree.arr.push([]);
+        }
+        self.first_added.get_or_insert(tree.diff_elements);
+        tree.arr.push_node(0, new_hash);
+        tree.diff_elements += 1;
+    }
+
+    /// Re-calculates every node above the leaves that changed, closing the batch
+    ///
+    /// ### Returns
+    ///
+    /// The new root of the tree, or None if it has no root
+    pub fn commit(mut self) -> Option<u64> {
+        self.apply();
+        self.tree.root()
+    }
+
+    /// Brings the levels above the base level up to date with it
+    fn apply(&mut self) {
+        if self.first_added.is_none() && self.dirty.is_empty() {
+            return;
+        }
+        let tree = &mut *self.tree;
+        tree.record_root();
+        // The padding copies the last element, which may have changed
+        tree.refresh_repeated();
+        if let Some(first_added) = self.first_added.take() {
+            tree.rehash_from(first_added);
+        }
+
+        let mut dirty = mem::take(&mut self.dirty);
+        for level in 1..tree.arr.len() {
+            dirty = dirty.into_iter().map(|index| index / 2).collect();
+            dirty.sort_unstable();
+            dirty.dedup();
+            for &index in &dirty {
+                let children = &tree.arr[level - 1];
+                let parent = match children.get(2 * index + 1) {
+                    Some(right) => combine_with(&tree.hasher, tree.ordering, children[2 * index], *right),
+                    None => tree.lone_parent(level - 1, children[2 * index]),
+                };
+                tree.arr[level][index] = parent;
+            }
+        }
+    }
+}
+
+impl<H: BuildHasher> Drop for Batch<'_, H> {
+    /// Commits the mutations that were not committed, so the tree is never
+    /// left with levels that do not match its base level
+    fn drop(&mut self) {
+        self.apply();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Test if a batch of random updates and additions leaves the same tree
+    /// as creating it from scratch with the final elements, with both
+    /// padding strategies.
+    fn batch_matches_rebuilt_tree() {
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for new_tree in [MerkleTree::new::<u64>, MerkleTree::new_rfc6962::<u64>] {
+            for (len, mutations) in [(0, 200), (1, 200), (3, 200), (8, 3), (13, 3), (13, 200)] {
+                let mut elements: Vec<u64> = (0..len).collect();
+                let mut merkle = new_tree(elements.clone());
+                let mut batch = merkle.begin_batch();
+                for _ in 0..mutations {
+                    let value = next();
+                    if value % 4 == 0 || elements.is_empty() {
+                        batch.add_element(value);
+                        elements.push(value);
+                    } else {
+                        let index = (value as usize) % elements.len();
+                        batch.update_element(index, value).unwrap();
+                        elements[index] = value;
+                    }
+                }
+                let root = batch.commit();
+                let expected = new_tree(elements);
+                assert_eq!(root, expected.root());
+                assert_eq!(merkle, expected);
+            }
+        }
+    }
+
+    #[test]
+    /// Test if updates that stay inside the padding only touch their paths,
+    /// including the repeated copies of the last element.
+    fn batch_updates_the_padding() {
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        let mut batch = merkle.begin_batch();
+        batch.update_element(4, "Other").unwrap();
+        batch.update_element(0, "First").unwrap();
+        batch.add_element("Added");
+        assert_eq!(batch.update_element(6, "Padded"), Err(MerkleError::InvalidIndex { index: 6, len: 6 }));
+        batch.commit();
+
+        assert_eq!(merkle, MerkleTree::new(vec!["First", "Merkle", "Rust", "Tree", "Other", "Added"]));
+    }
+
+    #[test]
+    /// Test if a dropped batch is committed, and the history gets a single
+    /// version for the whole batch.
+    fn dropped_batch_is_committed() {
+        let mut merkle = MerkleTree::with_history(vec!["Crypto", "Merkle"]);
+        let old_root = merkle.root();
+        {
+            let mut batch = merkle.begin_batch();
+            batch.add_element("Rust");
+            batch.update_element(0, "Tree").unwrap();
+        }
+        assert_eq!(merkle, MerkleTree::new(vec!["Tree", "Merkle", "Rust"]));
+        assert_eq!(merkle.root_history(), &[old_root.unwrap()]);
+
+        // An empty batch does not create a version
+        assert_eq!(merkle.begin_batch().commit(), merkle.root());
+        assert_eq!(merkle.root_history().len(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+use std::hash::{BuildHasher, Hash};
+
+use super::{create_first_level, create_remaining_levels_with, DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering, TreeStructure};
+use super::history::History;
+use crate::error::MerkleError;
+
+/// Creates a MerkleTree with any combination of its options, instead of
+/// a constructor for each of them. Every option starts as `MerkleTree::new`
+/// has it, so a builder without changes creates the same tree.
+///
+/// ```
+/// use tree::{MerkleTree, PaddingStrategy};
+///
+/// let merkle = MerkleTree::builder()
+///     .padding(PaddingStrategy::PromoteOdd)
+///     .track_history(true)
+///     .build(vec!["Crypto", "Merkle", "Rust"])
+///     .unwrap();
+/// assert_eq!(merkle.root(), MerkleTree::new_rfc6962(vec!["Crypto", "Merkle", "Rust"]).root());
+/// ```
+/// - `hasher`: Builds the hasher used for the elements and for every node above them.
+/// - `padding`: How the levels with an odd quantity of nodes are completed.
+/// - `sorted_pairs`: If the children of every node are sorted before hashing them.
+/// - `sorted_leaves`: If the leaves are sorted by their hash, like `new_sorted_leaves` does.
+/// - `track_history`: If the tree remembers every root it had, like `with_history` does.
+/// - `commit_len`: If the root commits to the quantity of different elements, like `with_committed_len` does.
+#[derive(Debug, Clone)]
+pub struct MerkleTreeBuilder<H = DefaultBuildHasher> {
+    hasher: H,
+    padding: PaddingStrategy,
+    sorted_pairs: bool,
+    sorted_leaves: bool,
+    track_history: bool,
+    commit_len: bool,
+}
+
+impl MerkleTree {
+    /// Creates a MerkleTreeBuilder with the options of `new`
+    pub fn builder() -> MerkleTreeBuilder {
+        MerkleTreeBuilder::new()
+    }
+}
+
+impl MerkleTreeBuilder {
+    /// Creates a MerkleTreeBuilder with the options of `MerkleTree::new`
+    pub fn new() -> Self {
+        Self {
+            hasher: DefaultBuildHasher::default(),
+            padding: PaddingStrategy::default(),
+            sorted_pairs: false,
+            sorted_leaves: false,
+            track_history: false,
+            commit_len: false,
+        }
+    }
+}
+
+impl Default for MerkleTreeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> MerkleTreeBuilder<H> {
+    /// Sets the hasher used for every hash of the tree, see `MerkleTree::with_hasher`
+    pub fn hasher<B: BuildHasher>(self, hasher: B) -> MerkleTreeBuilder<B> {
+        MerkleTreeBuilder {
+            hasher,
+            padding: self.padding,
+            sorted_pairs: self.sorted_pairs,
+            sorted_leaves: self.sorted_leaves,
+            track_history: self.track_history,
+            commit_len: self.commit_len,
+        }
+    }
+
+    /// Sets how the levels with an odd quantity of nodes are completed
+    pub fn padding(mut self, padding: PaddingStrategy) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets if the children of every node are sorted before hashing them,
+    /// see `MerkleTree::new_sorted`
+    pub fn sorted_pairs(mut self, sorted_pairs: bool) -> Self {
+        self.sorted_pairs = sorted_pairs;
+        self
+    }
+
+    /// Sets if the leaves are sorted by their hash and kept once, so the
+    /// tree can prove absences, see `MerkleTree::new_sorted_leaves`
+    pub fn sorted_leaves(mut self, sorted_leaves: bool) -> Self {
+        self.sorted_leaves = sorted_leaves;
+        self
+    }
+
+    /// Sets if the tree remembers every root it had, see `MerkleTree::with_history`
+    pub fn track_history(mut self, track_history: bool) -> Self {
+        self.track_history = track_history;
+        self
+    }
+
+    /// Sets if the root commits to the quantity of different elements,
+    /// see `MerkleTree::with_committed_len`
+    pub fn commit_len(mut self, commit_len: bool) -> Self {
+        self.commit_len = commit_len;
+        self
+    }
+
+    /// Checks that the options can be used together
+    fn check(&self) -> Result<(), MerkleError> {
+        // A sorted pair does not depend on the side of each child, so the
+        // proofs do not commit to the index of their leaf, and absence
+        // proofs need the indexes to show that two leaves are adjacent
+        if self.sorted_pairs && self.sorted_leaves {
+            return Err(MerkleError::IncompatibleOptions(
+                "sorted pairs do not bind the index of a leaf, which the absence proofs of sorted leaves need".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gets how the children of each node will be ordered
+    fn ordering(&self) -> PairOrdering {
+        if self.sorted_pairs { PairOrdering::Sorted } else { PairOrdering::Positional }
+    }
+}
+
+impl<H: BuildHasher> MerkleTreeBuilder<H> {
+    /// Creates the MerkleTree with the options of the builder
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if some options can not be used together
+    pub fn build<T: Hash + Clone>(self, elements: Vec<T>) -> Result<MerkleTree<H>, MerkleError> {
+        self.check()?;
+        if self.sorted_leaves {
+            let hashes = create_first_level(&self.hasher, elements);
+            return self.build_from_hashes(hashes);
+        }
+        let (track_history, commit_len) = (self.track_history, self.commit_len);
+        let ordering = self.ordering();
+        let merkle = MerkleTree::build(elements, self.hasher, ordering, self.padding);
+        Ok(finish(merkle, track_history, commit_len))
+    }
+
+    /// Creates the MerkleTree with the options of the builder from the
+    /// hashes of its elements, hashed with the same hasher the builder has
+    ///
+    /// ### Arguments
+    ///
+    /// - `hashes`: An iterator over the hashes that form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if some options can not be used together
+    pub fn build_from_hashes<I: IntoIterator<Item = u64>>(self, hashes: I) -> Result<MerkleTree<H>, MerkleError> {
+        self.check()?;
+        let mut base_level: Vec<u64> = hashes.into_iter().collect();
+        if self.sorted_leaves {
+            base_level.sort_unstable();
+            base_level.dedup();
+        }
+        let ordering = self.ordering();
+        let diff_elements = base_level.len();
+        let arr = if base_level.is_empty() {
+            TreeStructure::new()
+        } else {
+            create_remaining_levels_with(&self.hasher, ordering, self.padding, base_level)
+        };
+        let merkle = MerkleTree::from_levels(arr, diff_elements, self.hasher, ordering, self.padding);
+        Ok(finish(merkle, self.track_history, self.commit_len))
+    }
+}
+
+/// Sets the options of a tree that do not change its levels
+fn finish<H>(mut merkle: MerkleTree<H>, track_history: bool, commit_len: bool) -> MerkleTree<H> {
+    if track_history {
+        merkle.history = Some(History::new());
+    }
+    merkle.commit_len = commit_len;
+    merkle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+    use crate::PortableHash;
+
+    #[test]
+    /// Test if a builder without changes creates exactly the tree of `new`,
+    /// from the elements and from their hashes, for every small and large len.
+    fn default_builder_matches_new() {
+        for len in 0..40usize {
+            let data: Vec<usize> = (0..len).collect();
+            let merkle = MerkleTree::new(data.clone());
+
+            let built = MerkleTree::builder().build(data.clone()).unwrap();
+            assert_eq!(built, merkle);
+            assert_eq!(built.root(), merkle.root());
+            assert_eq!((built.ordering(), built.padding(), built.committed_len()), (merkle.ordering(), merkle.padding(), None));
+            assert_eq!(built.root_history(), merkle.root_history());
+
+            let from_hashes = MerkleTreeBuilder::default().build_from_hashes(data.iter().map(hash_element)).unwrap();
+            assert_eq!(from_hashes, merkle);
+            assert_eq!(from_hashes, MerkleTree::from_hashes(data.iter().map(hash_element)));
+        }
+    }
+
+    #[test]
+    /// Test if each option creates the same tree as the constructor that has it
+    fn options_match_constructors() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let build = |builder: MerkleTreeBuilder| builder.build(data.clone()).unwrap();
+
+        assert_eq!(build(MerkleTree::builder().sorted_pairs(true)), MerkleTree::new_sorted(data.clone()));
+        assert_eq!(build(MerkleTree::builder().padding(PaddingStrategy::PromoteOdd)), MerkleTree::new_rfc6962(data.clone()));
+        assert_eq!(build(MerkleTree::builder().commit_len(true)), MerkleTree::with_committed_len(data.clone()));
+        assert_eq!(build(MerkleTree::builder().sorted_leaves(true)), MerkleTree::new_sorted_leaves(data.clone()));
+
+        let mut tracked = build(MerkleTree::builder().track_history(true));
+        tracked.add_element("Hash");
+        let mut expected = MerkleTree::with_history(data.clone());
+        expected.add_element("Hash");
+        assert_eq!(tracked.root_history(), expected.root_history());
+
+        let portable = MerkleTree::builder().hasher(PortableHash).build(data.clone()).unwrap();
+        assert_eq!(portable.root(), MerkleTree::new_portable(data.clone()).root());
+    }
+
+    #[test]
+    /// Test if options that can not be used together fail when building
+    fn incompatible_options_fail() {
+        let builder = MerkleTree::builder().sorted_pairs(true).sorted_leaves(true);
+        assert!(matches!(builder.clone().build(vec!["Crypto", "Merkle"]), Err(MerkleError::IncompatibleOptions(_))));
+        assert!(matches!(builder.build_from_hashes([1, 2]), Err(MerkleError::IncompatibleOptions(_))));
+
+        let sorted = MerkleTree::builder().sorted_pairs(true).padding(PaddingStrategy::PromoteOdd).commit_len(true);
+        assert!(sorted.build(vec!["Crypto", "Merkle", "Rust"]).is_ok());
+    }
+}
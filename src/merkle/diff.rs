@@ -0,0 +1,117 @@
+use std::hash::BuildHasher;
+
+use super::MerkleTree;
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Finds the leaves that differ between two trees, like two snapshots
+    /// of mostly the same data. See `diff_with_hook`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `other`: The tree to compare with.
+    ///
+    /// ### Returns
+    ///
+    /// The sorted indexes of the leaves that differ or are only in one of the trees
+    pub fn diff(&self, other: &Self) -> Vec<usize> {
+        self.diff_with_hook(other, |_, _| {})
+    }
+
+    /// Finds the leaves that differ between two trees, calling a hook with
+    /// the level and index of every node visited.
+    ///
+    /// Both trees are descended at the same time from the highest root. A
+    /// subtree is skipped when it covers the same elements in both trees
+    /// and its hash is the same, so with d differences only O(d log n)
+    /// nodes are visited. The trees can have different heights, like after
+    /// adding elements to one of them, and the padding counts as absent:
+    /// the leaves that only one of the trees has are reported as extra.
+    ///
+    /// ### Arguments
+    ///
+    /// - `other`: The tree to compare with.
+    /// - `on_visit`: Called with the level and the index of each visited node.
+    ///
+    /// ### Returns
+    ///
+    /// The sorted indexes of the leaves that differ or are only in one of the trees
+    pub fn diff_with_hook(&self, other: &Self, mut on_visit: impl FnMut(usize, usize)) -> Vec<usize> {
+        let mut differences = Vec::new();
+        let top = self.height().max(other.height());
+        if top > 0 {
+            self.diff_node(other, top - 1, 0, &mut on_visit, &mut differences);
+        }
+        differences
+    }
+
+    /// Compares the subtrees of a node in both trees, pushing the indexes
+    /// of the leaves that differ in them
+    fn diff_node(&self, other: &Self, level: usize, index: usize, on_visit: &mut impl FnMut(usize, usize), differences: &mut Vec<usize>) {
+        on_visit(level, index);
+        let first = index << level;
+        let own_end = self.len().clamp(first, first + (1 << level));
+        let other_end = other.len().clamp(first, first + (1 << level));
+        if own_end == first || other_end == first {
+            // Only one of the trees has elements in the subtree
+            differences.extend(first..own_end.max(other_end));
+            return;
+        }
+        // The padding repeats the last element, so the hashes can only be
+        // compared when both subtrees cover the same elements
+        if own_end == other_end && level < self.height() && level < other.height() && self.get_node(level, index) == other.get_node(level, index) {
+            return;
+        }
+        if level == 0 {
+            differences.push(first);
+            return;
+        }
+        self.diff_node(other, level - 1, 2 * index, on_visit, differences);
+        self.diff_node(other, level - 1, 2 * index + 1, on_visit, differences);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MerkleTree;
+
+    #[test]
+    /// Test if 3 changes among 1M leaves are found visiting O(d log n) nodes
+    fn finds_few_changes_visiting_few_nodes() {
+        let data: Vec<usize> = (0..1_000_000).collect();
+        let before = MerkleTree::new(data.clone());
+        let mut after = before.clone();
+        for index in [17, 500_000, 999_999] {
+            after.update_element(index, index + 1).unwrap();
+        }
+
+        let mut visited = 0;
+        assert_eq!(before.diff_with_hook(&after, |_, _| visited += 1), vec![17, 500_000, 999_999]);
+        assert!(visited <= 3 * 2 * before.height(), "visited {visited}");
+        assert_eq!(after.diff(&before), vec![17, 500_000, 999_999]);
+        assert!(before.diff(&before.clone()).is_empty());
+    }
+
+    #[test]
+    /// Test if the elements added to a tree are reported as extra, also
+    /// when the tree has grown a level, and padding is not a difference.
+    fn reports_extra_leaves() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let short = MerkleTree::new(data[..3].to_vec());
+        let mut grown = short.clone();
+        grown.add_elements(data[3..].to_vec());
+        assert_ne!(short.height(), grown.height());
+
+        assert_eq!(short.diff(&grown), vec![3, 4]);
+        assert_eq!(grown.diff(&short), vec![3, 4]);
+
+        // The padding of [a, b, c] repeats c, so it hashes like [a, b, c, c]
+        let repeated = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Rust"]);
+        assert_eq!(short.root(), repeated.root());
+        assert_eq!(short.diff(&repeated), vec![3]);
+
+        let mut changed = grown.clone();
+        changed.update_element(1, "Hash").unwrap();
+        assert_eq!(short.diff(&changed), vec![1, 3, 4]);
+        assert_eq!(MerkleTree::new(Vec::<u8>::new()).diff(&short), vec![0, 1, 2]);
+    }
+}
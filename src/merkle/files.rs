@@ -0,0 +1,200 @@
+use std::fs;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use super::{hash_element, MerkleTree};
+
+impl MerkleTree {
+    /// Creates a new MerkleTree over the content of a reader split in pieces
+    /// of `chunk_size` bytes, the way torrent clients split files. Each chunk
+    /// is a leaf hashed with `hash_element` over its bytes, and the last one
+    /// may be shorter than the others. The content is read one chunk at a
+    /// time, so it is never fully held in memory.
+    ///
+    /// The quantity of chunks is the `len` of the tree, and the byte at
+    /// `offset` is in the leaf of index `offset / chunk_size`. A reader with
+    /// no content gives an empty tree.
+    ///
+    /// ### Arguments
+    ///
+    /// - `reader`: The reader whose content will form the first level in the tree.
+    /// - `chunk_size`: The quantity of bytes of each chunk.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if the reader fails or the
+    /// chunk size is 0
+    pub fn from_reader<R: Read>(mut reader: R, chunk_size: usize) -> io::Result<Self> {
+        if chunk_size == 0 {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "The chunk size can not be 0"));
+        }
+        let mut chunk = vec![0u8; chunk_size];
+        let mut chunk_hashes = Vec::new();
+        loop {
+            let len = read_chunk(&mut reader, &mut chunk)?;
+            if len == 0 {
+                break;
+            }
+            chunk_hashes.push(hash_element(&chunk[..len]));
+            if len < chunk_size {
+                break;
+            }
+        }
+        Ok(Self::from_hashes(chunk_hashes))
+    }
+
+    /// Creates a new MerkleTree over the files of a directory and of every
+    /// directory inside it. Each file is a leaf hashed with `hash_element`
+    /// over its content, and the leaves are ordered by path so the same
+    /// directory always gives the same tree.
+    ///
+    /// Symbolic links are skipped, whether they point to a file or to a
+    /// directory, so a link can not make a file be hashed twice or the walk
+    /// never end. A file or directory that can not be read makes the whole
+    /// walk fail, since skipping it would leave it out of the audit.
+    ///
+    /// ### Arguments
+    ///
+    /// - `path`: The directory whose files will form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree and the path of the file of each leaf,
+    /// or an Error if a file or directory can not be read
+    pub fn from_dir(path: &Path) -> io::Result<(Self, Vec<PathBuf>)> {
+        let mut paths = Vec::new();
+        collect_files(path, &mut paths)?;
+        paths.sort();
+        let mut file_hashes = Vec::with_capacity(paths.len());
+        for path in &paths {
+            file_hashes.push(hash_element(fs::read(path)?));
+        }
+        Ok((Self::from_hashes(file_hashes), paths))
+    }
+}
+
+/// Adds the paths of the files inside a directory, and inside every
+/// directory it has, skipping the symbolic links
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), paths)?;
+        } else if file_type.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Fills the chunk with the content of the reader, reading as many times as
+/// needed since a single read may return less bytes than there are.
+///
+/// ### Returns
+///
+/// The quantity of bytes read, which is less than the len of the chunk only
+/// when the reader has no more content
+fn read_chunk<R: Read>(reader: &mut R, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < chunk.len() {
+        match reader.read(&mut chunk[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A reader that never returns more than 3 bytes at once
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(3).min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    /// Test if a tree over the chunks of a file stops verifying the proof of
+    /// a chunk once that chunk is corrupted.
+    fn corrupted_chunk_fails_its_proof() {
+        let path = env::temp_dir().join(format!("merkle-from-reader-{}.bin", std::process::id()));
+        let mut content: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+        let merkle = MerkleTree::from_reader(fs::File::open(&path).unwrap(), 1024).unwrap();
+
+        // 9 full chunks and a short one with the last 784 bytes
+        assert_eq!(merkle.len(), 10);
+        assert_eq!(merkle.get_leaf(9), Some(hash_element(&content[9 * 1024..])));
+
+        let offset = 5000;
+        let index = offset / 1024;
+        let proof = merkle.generate_proof(index).unwrap();
+        assert!(merkle.verify(&proof, hash_element(&content[index * 1024..(index + 1) * 1024])));
+
+        content[offset] ^= 0xff;
+        fs::write(&path, &content).unwrap();
+        let corrupted = MerkleTree::from_reader(fs::File::open(&path).unwrap(), 1024).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_ne!(corrupted.root(), merkle.root());
+        assert!(!merkle.verify(&proof, corrupted.get_leaf(index).unwrap()));
+        assert_eq!(corrupted.get_leaf(index + 1), merkle.get_leaf(index + 1));
+    }
+
+    #[test]
+    /// Test if the files of a directory are ordered by path, and changing one
+    /// of them changes the root and fails only the proof of that file.
+    fn modified_file_fails_its_proof() {
+        let dir = env::temp_dir().join(format!("merkle-from-dir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("c.txt"), "Tree").unwrap();
+        fs::write(dir.join("b").join("z.txt"), "Rust").unwrap();
+        fs::write(dir.join("a.txt"), "Crypto").unwrap();
+        fs::write(dir.join("b").join("a.txt"), "Merkle").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("b"), dir.join("link")).unwrap();
+
+        let (merkle, paths) = MerkleTree::from_dir(&dir).unwrap();
+        let expected: Vec<PathBuf> = ["a.txt", "b/a.txt", "b/z.txt", "c.txt"].iter().map(|file| dir.join(file)).collect();
+        assert_eq!(paths, expected);
+        assert_eq!(merkle, MerkleTree::new(vec![b"Crypto".to_vec(), b"Merkle".to_vec(), b"Rust".to_vec(), b"Tree".to_vec()]));
+
+        fs::write(dir.join("b").join("z.txt"), "Rust!").unwrap();
+        let (modified, _) = MerkleTree::from_dir(&dir).unwrap();
+        assert_ne!(modified.root(), merkle.root());
+        for (index, path) in paths.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            let leaf = hash_element(fs::read(path).unwrap());
+            assert_eq!(merkle.verify(&proof, leaf), index != 2);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(MerkleTree::from_dir(&dir).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    /// Test if short reads do not split the chunks, and the edge cases of
+    /// the content len
+    fn chunks_ignore_short_reads() {
+        let content: Vec<u8> = (0..100).collect();
+        let chunks: Vec<&[u8]> = content.chunks(8).collect();
+
+        assert_eq!(MerkleTree::from_reader(Trickle(&content), 8).unwrap(), MerkleTree::new(chunks));
+        assert_eq!(MerkleTree::from_reader(Trickle(&content[..96]), 8).unwrap().len(), 12);
+        assert!(MerkleTree::from_reader(Trickle(&[]), 8).unwrap().is_empty());
+        assert_eq!(MerkleTree::from_reader(Trickle(&content), 0).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,255 @@
+use std::hash::{BuildHasher, Hash};
+
+use super::retention::{MerkleTreeSnapshot, Retention};
+use super::{MerkleTree, PaddingStrategy, Proof};
+use crate::verify::sibling_levels;
+
+/// The previous roots of a tree created with `with_history`.
+/// - `roots`: The roots `gc` has not dropped, from the oldest to the newest.
+/// - `versions`: The version of each root in `roots`.
+/// - `recorded`: Quantity of roots ever recorded, which is the version of the current root.
+/// - `snapshots`: The snapshots handed out by `share_snapshot`, from the oldest to the newest.
+/// - `retention`: The versions `gc` keeps.
+#[derive(Clone)]
+pub(super) struct History<H> {
+    pub(super) roots: Vec<u64>,
+    pub(super) versions: Vec<usize>,
+    pub(super) recorded: usize,
+    pub(super) snapshots: Vec<MerkleTreeSnapshot<H>>,
+    pub(super) retention: Retention,
+}
+
+impl<H> History<H> {
+    /// Creates a history without roots that keeps every version
+    pub(super) fn new() -> Self {
+        Self { roots: Vec::new(), versions: Vec::new(), recorded: 0, snapshots: Vec::new(), retention: Retention::default() }
+    }
+}
+
+/// A lightweight handle to a version of a tree, see `MerkleTree::snapshot`.
+/// It keeps what is needed to verify the proofs of that version, not its nodes.
+/// - `root`: The root of the tree at that version.
+/// - `leaf_count`: Quantity of different elements the tree had at that version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TreeVersion {
+    root: u64,
+    leaf_count: usize,
+}
+
+impl TreeVersion {
+    /// Gets the root of the tree at this version
+    pub fn root(&self) -> u64 {
+        self.root
+    }
+
+    /// Gets the quantity of different elements of the tree at this version
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Checks if the tree had no elements at this version. It never
+    /// happens, since a snapshot needs a root.
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+}
+
+impl MerkleTree {
+    /// Creates a new MerkleTree that remembers every root it had. Each
+    /// mutation stores the root the tree had before it, so the history only
+    /// grows by one hash per mutation.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn with_history<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        let mut merkle = Self::new(elements);
+        merkle.history = Some(History::new());
+        merkle
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Stores the current root in the history before a mutation changes it.
+    /// Trees without history and trees without a root store nothing.
+    pub(super) fn record_root(&mut self) {
+        if let Some(root) = self.root()
+            && let Some(history) = &mut self.history
+        {
+            history.roots.push(root);
+            history.versions.push(history.recorded);
+            history.recorded += 1;
+        }
+    }
+
+    /// Gets every root the tree had before its current one, from the oldest
+    /// to the newest, except the ones `gc` dropped. It is empty if the tree
+    /// was not created with `with_history`.
+    pub fn root_history(&self) -> &[u64] {
+        self.history.as_ref().map_or(&[], |history| &history.roots)
+    }
+
+    /// Gets the version of the current root. Version 0 is the first root
+    /// the tree had, and each mutation creates the next version. It is
+    /// always 0 if the tree was not created with `with_history`.
+    pub fn version(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.recorded)
+    }
+
+    /// Gets the root the tree had at a version.
+    ///
+    /// ### Arguments
+    ///
+    /// - `version`: The version of the tree, the current one being `version()`.
+    ///
+    /// ### Returns
+    ///
+    /// The root of that version, or None if the tree never reached it or
+    /// `gc` dropped it
+    pub fn root_at(&self, version: usize) -> Option<u64> {
+        if version == self.version() {
+            return self.root();
+        }
+        let history = self.history.as_ref()?;
+        let position = history.versions.binary_search(&version).ok()?;
+        Some(history.roots[position])
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Takes a snapshot of the current version of the tree, so the proofs
+    /// generated now can still be verified after the tree changes.
+    ///
+    /// ### Returns
+    ///
+    /// The TreeVersion of the tree, or None if the tree has no root
+    pub fn snapshot(&self) -> Option<TreeVersion> {
+        Some(TreeVersion { root: self.root()?, leaf_count: self.diff_elements })
+    }
+
+    /// Checks if the root of a version of the tree can be obtained with the
+    /// use of a proof generated at that version and a leaf.
+    ///
+    /// ### Arguments
+    ///
+    /// - `version`: The TreeVersion returned by `snapshot` when the proof was generated.
+    /// - `proof`: The Proof of the element, as `generate_proof` returned it.
+    /// - `leaf`: The hash of the element.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root of the version can be obtained with
+    /// that information, false otherwise
+    pub fn verify_at_version(&self, version: TreeVersion, proof: &Proof, leaf: u64) -> bool {
+        let index = proof.leaf_index();
+        let levels = sibling_levels(index, base_len_at(self.padding, version.leaf_count));
+        index < version.leaf_count
+            && proof
+                .fold_at_levels(&self.hasher, self.ordering, &levels, leaf)
+                .is_some_and(|hash| self.commit_root(hash, version.leaf_count) == version.root)
+    }
+}
+
+/// Gets the len of the base level of a tree that had `leaf_count`
+/// different elements, counting the padding when the tree has it, so the
+/// proofs of that version can be checked without having its levels.
+fn base_len_at(padding: PaddingStrategy, leaf_count: usize) -> usize {
+    match padding {
+        PaddingStrategy::RepeatLast | PaddingStrategy::ZeroHash => leaf_count.next_power_of_two(),
+        PaddingStrategy::PromoteOdd => leaf_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    #[test]
+    /// Test if a mixed sequence of mutations leaves every root in the
+    /// history, in the order the tree had them.
+    fn history_matches_captured_roots() {
+        let mut merkle = MerkleTree::with_history(vec!["Crypto", "Merkle", "Rust"]);
+        let mut roots = vec![merkle.root().unwrap()];
+
+        merkle.add_element("Tree");
+        roots.push(merkle.root().unwrap());
+        merkle.update_element(1, "Test").unwrap();
+        roots.push(merkle.root().unwrap());
+        merkle.add_elements(vec!["A", "B"]);
+        roots.push(merkle.root().unwrap());
+        merkle.pop_element();
+        roots.push(merkle.root().unwrap());
+        // A failed mutation does not create a version
+        assert!(merkle.update_element(10, "C").is_err());
+
+        assert_eq!(merkle.root_history(), &roots[..roots.len() - 1]);
+        for (version, root) in roots.iter().enumerate() {
+            assert_eq!(merkle.root_at(version), Some(*root));
+        }
+        assert_eq!(merkle.root_at(roots.len()), None);
+    }
+
+    #[test]
+    /// Test if proofs generated before adding elements fail against the
+    /// current root but pass against the snapshot of their version.
+    fn stale_proofs_verify_at_their_version() {
+        for new_tree in [MerkleTree::new::<&str>, MerkleTree::new_rfc6962::<&str>] {
+            let mut merkle = new_tree(vec!["Crypto", "Merkle", "Rust"]);
+            let version = merkle.snapshot().unwrap();
+            let proofs: Vec<Proof> = (0..3).map(|index| merkle.generate_proof(index).unwrap()).collect();
+
+            merkle.add_elements(vec!["Tree", "Test"]);
+            merkle.update_element(0, "Other").unwrap();
+            assert_ne!(merkle.root(), Some(version.root()));
+            for (proof, element) in proofs.iter().zip(["Crypto", "Merkle", "Rust"]) {
+                assert!(!merkle.verify(proof, hash_element(element)));
+                assert!(merkle.verify_at_version(version, proof, hash_element(element)));
+                assert!(!merkle.verify_at_version(version, proof, hash_element("Tree")));
+            }
+
+            // Proofs of the new version do not verify at the old one
+            let new_proof = merkle.generate_proof(4).unwrap();
+            assert!(merkle.verify_at_version(merkle.snapshot().unwrap(), &new_proof, hash_element("Test")));
+            assert!(!merkle.verify_at_version(version, &new_proof, hash_element("Test")));
+        }
+    }
+
+    #[test]
+    /// Test if the levels of the proofs of a version are computed like the
+    /// ones of the proofs the tree generates, for every index and size.
+    fn sibling_levels_match_generated_proofs() {
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>] {
+            for len in 1..20 {
+                let merkle = new_tree((0..len).collect());
+                for index in 0..len {
+                    let expected = merkle.generate_proof(index).unwrap().len();
+                    assert_eq!(sibling_levels(index, base_len_at(merkle.padding, len)).len(), expected);
+                }
+            }
+        }
+        assert_eq!(MerkleTree::new(Vec::<u8>::new()).snapshot(), None);
+    }
+
+    #[test]
+    /// Test if a tree starts recording once it has a root, and trees created
+    /// without history do not record anything.
+    fn history_starts_with_the_first_root() {
+        let mut merkle = MerkleTree::with_history(Vec::<&str>::new());
+        assert_eq!(merkle.root_at(0), None);
+        merkle.add_element("Crypto");
+        merkle.add_element("Merkle");
+
+        assert_eq!(merkle.root_history().len(), 1);
+        assert_eq!(merkle.root_at(0), MerkleTree::new(vec!["Crypto"]).root());
+
+        let mut plain = MerkleTree::new(vec!["Crypto"]);
+        plain.add_element("Merkle");
+        assert!(plain.root_history().is_empty());
+        assert_eq!(plain.root_at(0), plain.root());
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+use std::mem;
+use std::ops::{Index, IndexMut, Range};
+
+/// The levels of a tree stored in a single vector of hashes.
+///
+/// Each level owns a region of `nodes`, laid out one after the other from
+/// the base level up. A level grows inside its region without moving the
+/// others. Once a level outgrows its region, the regions are moved to a new
+/// vector where the full ones have twice the room, just like a vector
+/// doubles its capacity.
+/// - `nodes`: The regions of every level, one after another.
+/// - `starts`: Where the region of each level begins, plus where the last region ends.
+/// - `lens`: Quantity of nodes each level uses of its region. There are as
+///   many levels as lens, the remaining regions are room for new levels.
+#[derive(Clone)]
+pub(crate) struct Levels {
+    nodes: Vec<u64>,
+    starts: Vec<usize>,
+    lens: Vec<usize>,
+}
+
+impl Levels {
+    /// Creates a structure without levels and without room for them
+    pub(crate) fn new() -> Self {
+        Self { nodes: Vec::new(), starts: vec![0], lens: Vec::new() }
+    }
+
+    /// Creates a structure without levels but with room for them, so they
+    /// can be pushed and grow up to those lens without moving any hash.
+    ///
+    /// ### Arguments
+    ///
+    /// - `capacities`: The quantity of nodes each level will hold, from the base level up.
+    pub(crate) fn with_capacity(capacities: impl IntoIterator<Item = usize>) -> Self {
+        let capacities: Vec<usize> = capacities.into_iter().collect();
+        let mut levels = Self {
+            nodes: Vec::with_capacity(capacities.iter().sum()),
+            starts: Vec::with_capacity(capacities.len() + 1),
+            lens: Vec::with_capacity(capacities.len()),
+        };
+        levels.starts.push(0);
+        for capacity in capacities {
+            levels.add_region(capacity);
+        }
+        levels
+    }
+
+    /// Gets the quantity of levels
+    pub(crate) fn len(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// Checks if there are no levels
+    pub(crate) fn is_empty(&self) -> bool {
+        self.lens.is_empty()
+    }
+
+    /// Gets the nodes of a level, or None if there is no such level
+    pub(crate) fn level_slice(&self, level: usize) -> Option<&[u64]> {
+        let len = *self.lens.get(level)?;
+        let start = self.starts[level];
+        Some(&self.nodes[start..start + len])
+    }
+
+    /// Gets a node of a level, or None if there is no node at that position
+    pub(crate) fn node_at(&self, level: usize, index: usize) -> Option<u64> {
+        self.level_slice(level)?.get(index).copied()
+    }
+
+    /// Gets the base level, or None if there are no levels
+    pub(crate) fn first(&self) -> Option<&[u64]> {
+        self.level_slice(0)
+    }
+
+    /// Gets the top level, or None if there are no levels
+    pub(crate) fn last(&self) -> Option<&[u64]> {
+        self.level_slice(self.len().checked_sub(1)?)
+    }
+
+    /// Iterates over the levels, from the base level up
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        Iter { levels: self, range: 0..self.len() }
+    }
+
+    /// Adds a level on top of the others
+    pub(crate) fn push(&mut self, level: impl AsRef<[u64]>) {
+        let level = level.as_ref();
+        let index = self.len();
+        self.lens.push(0);
+        self.reserve(index, level.len());
+        let start = self.starts[index];
+        self.nodes[start..start + level.len()].copy_from_slice(level);
+        self.lens[index] = level.len();
+    }
+
+    /// Keeps only the first `len` levels
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.lens.truncate(len);
+    }
+
+    /// Removes every level, keeping their regions
+    pub(crate) fn clear(&mut self) {
+        self.lens.clear();
+    }
+
+    /// Adds a node at the end of a level
+    pub(crate) fn push_node(&mut self, level: usize, hash: u64) {
+        let len = self.lens[level];
+        self.reserve(level, len + 1);
+        self.nodes[self.starts[level] + len] = hash;
+        self.lens[level] = len + 1;
+    }
+
+    /// Removes the last node of a level
+    pub(crate) fn pop_node(&mut self, level: usize) -> Option<u64> {
+        let len = self.lens[level].checked_sub(1)?;
+        self.lens[level] = len;
+        Some(self.nodes[self.starts[level] + len])
+    }
+
+    /// Keeps only the first `len` nodes of a level
+    pub(crate) fn truncate_level(&mut self, level: usize, len: usize) {
+        self.lens[level] = self.lens[level].min(len);
+    }
+
+    /// Gets the bytes of the heap the levels take, the room for growing
+    /// and for new levels included
+    pub(crate) fn memory_usage(&self) -> usize {
+        (self.nodes.capacity() + self.starts.capacity() + self.lens.capacity()) * mem::size_of::<u64>()
+    }
+
+    /// Makes sure the region of a level can hold `len` nodes. A new level
+    /// gets a region at the end, and the last region grows in place. Any
+    /// other region that is too small makes every region move to a new
+    /// vector, where it and the regions that are full get twice the room.
+    fn reserve(&mut self, level: usize, len: usize) {
+        let regions = self.starts.len() - 1;
+        if level == regions {
+            self.add_region(len);
+            return;
+        }
+        let capacity = self.starts[level + 1] - self.starts[level];
+        if capacity >= len {
+            return;
+        }
+        if level + 1 == regions {
+            let end = self.starts[level] + len.max(2 * capacity);
+            self.nodes.resize(end, 0);
+            self.starts[regions] = end;
+            return;
+        }
+        let capacities: Vec<usize> = (0..regions)
+            .map(|region| {
+                let capacity = self.starts[region + 1] - self.starts[region];
+                let used = self.lens.get(region).copied().unwrap_or(0);
+                if region == level {
+                    len.max(2 * capacity)
+                } else if used == capacity {
+                    2 * capacity
+                } else {
+                    capacity
+                }
+            })
+            .collect();
+        let mut moved = Self::with_capacity(capacities);
+        for (region, level_nodes) in self.iter().enumerate() {
+            let start = moved.starts[region];
+            moved.nodes[start..start + level_nodes.len()].copy_from_slice(level_nodes);
+        }
+        moved.lens = mem::take(&mut self.lens);
+        *self = moved;
+    }
+
+    /// Adds room for one more level after the last region
+    fn add_region(&mut self, capacity: usize) {
+        let end = self.nodes.len() + capacity;
+        self.nodes.resize(end, 0);
+        self.starts.push(end);
+    }
+
+    /// Gets the range of `nodes` used by a level
+    fn range(&self, level: usize) -> Range<usize> {
+        let start = self.starts[level];
+        start..start + self.lens[level]
+    }
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Index<usize> for Levels {
+    type Output = [u64];
+
+    fn index(&self, level: usize) -> &[u64] {
+        &self.nodes[self.range(level)]
+    }
+}
+
+impl IndexMut<usize> for Levels {
+    fn index_mut(&mut self, level: usize) -> &mut [u64] {
+        let range = self.range(level);
+        &mut self.nodes[range]
+    }
+}
+
+impl PartialEq for Levels {
+    /// Two structures are equal if they have the same levels, no matter how
+    /// much room each one has
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Levels {}
+
+impl fmt::Debug for Levels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl From<Vec<Vec<u64>>> for Levels {
+    fn from(levels: Vec<Vec<u64>>) -> Self {
+        let mut flat = Self::with_capacity(levels.iter().map(Vec::len).collect::<Vec<usize>>());
+        for level in levels {
+            flat.push(level);
+        }
+        flat
+    }
+}
+
+impl<'a> IntoIterator for &'a Levels {
+    type Item = &'a [u64];
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the levels of a `Levels`, from the base level up.
+pub(crate) struct Iter<'a> {
+    levels: &'a Levels,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a [u64];
+
+    fn next(&mut self) -> Option<&'a [u64]> {
+        let level = self.range.next()?;
+        Some(&self.levels[level])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let level = self.range.next_back()?;
+        Some(&self.levels[level])
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Test if levels that grow past their regions keep every hash, and the
+    /// other levels are not changed.
+    fn levels_grow_without_losing_hashes() {
+        let mut levels = Levels::new();
+        let mut expected: Vec<Vec<u64>> = Vec::new();
+        for level in 0..4 {
+            levels.push([level]);
+            expected.push(vec![level]);
+        }
+        for hash in 0..100u64 {
+            let level = (hash % 4) as usize;
+            levels.push_node(level, hash);
+            expected[level].push(hash);
+            assert_eq!(levels, Levels::from(expected.clone()));
+        }
+        assert_eq!(levels.pop_node(3), expected[3].pop());
+        levels.truncate_level(1, 3);
+        expected[1].truncate(3);
+        levels.truncate(3);
+        expected.truncate(3);
+        assert_eq!(levels, Levels::from(expected.clone()));
+        assert_eq!(levels.node_at(1, 2), Some(expected[1][2]));
+        assert_eq!(levels.node_at(1, 3), None);
+        assert_eq!(levels.level_slice(3), None);
+    }
+
+    #[test]
+    /// Test if levels pushed into the room reserved for them do not move
+    /// the nodes to a new vector.
+    fn reserved_levels_are_not_moved() {
+        let mut levels = Levels::with_capacity([4, 2, 1]);
+        let memory = levels.memory_usage();
+        levels.push([1]);
+        for hash in [2, 3, 4] {
+            levels.push_node(0, hash);
+        }
+        levels.push([5, 6]);
+        levels.push([7]);
+        assert_eq!(levels.memory_usage(), memory);
+        assert_eq!(format!("{levels:?}"), "[[1, 2, 3, 4], [5, 6], [7]]");
+
+        levels.clear();
+        assert!(levels.is_empty());
+        assert_eq!(levels, Levels::new());
+    }
+}
@@ -0,0 +1,213 @@
+use std::hash::BuildHasher;
+
+use super::{combine_with, MerkleTree};
+use crate::error::MerkleError;
+
+/// The proof for several elements of a tree at once.
+///
+/// The proofs of the elements share many nodes, and some of the siblings
+/// in their paths are nodes that can be calculated from the other proven
+/// elements. Only the siblings that can not be calculated are stored.
+/// - `indices`: The indexes of the proven elements, sorted and without repeats.
+/// - `hashes`: The siblings that can not be calculated, level by level from
+///   the base level up, and from left to right inside each level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    indices: Vec<usize>,
+    hashes: Vec<u64>,
+}
+
+impl MultiProof {
+    /// Gets the indexes of the proven elements, sorted and without repeats
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Gets the hashes of the proof, in the order they are used
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Gets the quantity of hashes in the proof
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Checks if the proof has no hashes, which happens when every element
+    /// of the tree is proven
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Generates a single proof for several elements of the tree. A sibling
+    /// is only added to the proof if it can not be calculated from the
+    /// proven elements, so two adjacent elements need no hash for each other.
+    ///
+    /// ### Arguments
+    ///
+    /// - `indices`: The indexes of the elements in the original input array. Repeated indexes are proven once.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MultiProof, or an Error if an index is outside of
+    /// the elements of the tree, padded ones included
+    pub fn generate_multiproof(&self, indices: &[usize]) -> Result<MultiProof, MerkleError> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        if let Some(&index) = indices.last()
+            && index >= self.diff_elements
+        {
+            return Err(MerkleError::InvalidIndex { index, len: self.diff_elements });
+        }
+
+        let mut hashes = Vec::new();
+        let mut known = indices.clone();
+        for level in 0..self.height() - 1 {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                let sibling = index ^ 1;
+                if known.get(i + 1) == Some(&sibling) {
+                    // Both children are known, the sibling is calculated
+                    i += 1;
+                } else if let Some(hash) = self.get_node(level, sibling) {
+                    hashes.push(hash);
+                }
+                // Otherwise the node is promoted and has no sibling
+                parents.push(index / 2);
+                i += 1;
+            }
+            known = parents;
+        }
+        Ok(MultiProof { indices, hashes })
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a
+    /// multiproof and the leaves of every element it proves.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The MultiProof of the elements, as `generate_multiproof` returns it.
+    /// - `leaves`: The `(index, leaf hash)` pairs of the proven elements, in any order.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the leaves are exactly the ones of the proof and
+    /// the root can be obtained with them, false otherwise
+    pub fn verify_multiproof(&self, proof: &MultiProof, leaves: &[(usize, u64)]) -> bool {
+        let mut known = leaves.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        let indices: Vec<usize> = known.iter().map(|(index, _)| *index).collect();
+        // A repeated index with two different hashes is left twice by dedup
+        if indices != proof.indices || indices.last().is_none_or(|index| *index >= self.diff_elements) {
+            return false;
+        }
+
+        let mut hashes = proof.hashes.iter();
+        for level in 0..self.height() - 1 {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let (index, hash) = known[i];
+                let parent = match known.get(i + 1) {
+                    Some((next, right)) if *next == index ^ 1 && index.is_multiple_of(2) => {
+                        i += 1;
+                        combine_with(&self.hasher, self.ordering, hash, *right)
+                    }
+                    _ if self.get_node(level, index ^ 1).is_none() => hash,
+                    _ => {
+                        let Some(sibling) = hashes.next() else {
+                            return false;
+                        };
+                        if index.is_multiple_of(2) {
+                            combine_with(&self.hasher, self.ordering, hash, *sibling)
+                        } else {
+                            combine_with(&self.hasher, self.ordering, *sibling, hash)
+                        }
+                    }
+                };
+                parents.push((index / 2, parent));
+                i += 1;
+            }
+            known = parents;
+        }
+        hashes.next().is_none() && matches!(known[..], [(0, top)] if self.is_root(top))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    fn leaves(indices: &[usize]) -> Vec<(usize, u64)> {
+        indices.iter().map(|index| (*index, hash_element(index))).collect()
+    }
+
+    #[test]
+    /// Test if multiproofs of many sets of elements verify, with both
+    /// padding strategies and with sorted pairs.
+    fn multiproof_verifies() {
+        let sets: [&[usize]; 6] = [&[0], &[4], &[1, 2], &[0, 3, 4], &[2, 3, 5, 6], &[0, 1, 2, 3, 4, 5, 6]];
+        for new_tree in [MerkleTree::new::<usize>, MerkleTree::new_rfc6962::<usize>, MerkleTree::new_sorted::<usize>] {
+            let merkle = new_tree((0..7).collect());
+            for indices in sets {
+                let proof = merkle.generate_multiproof(indices).unwrap();
+                assert!(merkle.verify_multiproof(&proof, &leaves(indices)), "{indices:?}");
+            }
+        }
+    }
+
+    #[test]
+    /// Test if the multiproof of adjacent elements is strictly smaller than
+    /// their proofs together, and a pair of siblings needs no hash for each other.
+    fn multiproof_is_smaller_than_single_proofs() {
+        let merkle = MerkleTree::new((0..8usize).collect());
+        let pair = merkle.generate_multiproof(&[2, 3]).unwrap();
+        assert_eq!(pair.len(), 2);
+
+        let indices = [4, 5, 6];
+        let single_len: usize = indices.iter().map(|index| merkle.generate_proof(*index).unwrap().len()).sum();
+        let proof = merkle.generate_multiproof(&indices).unwrap();
+        assert!(proof.len() < single_len);
+        assert_eq!(proof.hashes(), &[merkle.arr[0][7], merkle.arr[2][0]]);
+
+        let everything = merkle.generate_multiproof(&(0..8).collect::<Vec<usize>>()).unwrap();
+        assert!(everything.is_empty());
+        assert!(merkle.verify_multiproof(&everything, &leaves(&(0..8).collect::<Vec<usize>>())));
+    }
+
+    #[test]
+    /// Test if repeated indexes are proven once
+    fn multiproof_deduplicates_indices() {
+        let merkle = MerkleTree::new((0..8usize).collect());
+        let proof = merkle.generate_multiproof(&[5, 1, 5, 1]).unwrap();
+
+        assert_eq!(proof, merkle.generate_multiproof(&[1, 5]).unwrap());
+        assert_eq!(proof.indices(), &[1, 5]);
+        assert!(merkle.verify_multiproof(&proof, &leaves(&[5, 1, 5])));
+    }
+
+    #[test]
+    /// Test if padded indexes are rejected, and wrong, missing or extra
+    /// leaves make the verification fail.
+    fn multiproof_rejects_invalid_input() {
+        let merkle = MerkleTree::new((0..5usize).collect());
+        assert_eq!(merkle.generate_multiproof(&[1, 6]), Err(MerkleError::InvalidIndex { index: 6, len: 5 }));
+
+        let proof = merkle.generate_multiproof(&[1, 4]).unwrap();
+        assert!(merkle.verify_multiproof(&proof, &leaves(&[1, 4])));
+        assert!(!merkle.verify_multiproof(&proof, &[(1, hash_element(1usize)), (4, hash_element(3usize))]));
+        assert!(!merkle.verify_multiproof(&proof, &leaves(&[1])));
+        assert!(!merkle.verify_multiproof(&proof, &leaves(&[1, 2, 4])));
+        assert!(!merkle.verify_multiproof(&proof, &[(1, hash_element(1usize)), (1, 0), (4, hash_element(4usize))]));
+
+        let empty = merkle.generate_multiproof(&[]).unwrap();
+        assert!(!merkle.verify_multiproof(&empty, &[]));
+    }
+}
@@ -0,0 +1,91 @@
+use std::hash::{BuildHasher, Hash};
+use std::num::NonZeroUsize;
+use std::thread;
+
+use super::{create_remaining_levels, hash_with, DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering};
+
+impl MerkleTree {
+    /// Creates a new MerkleTree hashing the elements on several threads.
+    /// It is worth it when the elements are large, since hashing them takes
+    /// most of the time. The tree is the same one `new` creates.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn new_par<T: Hash + Send + Sync>(elements: Vec<T>) -> Self {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let hashed_elements = hash_par(&DefaultBuildHasher::default(), &elements, threads);
+
+        let diff_elements = hashed_elements.len();
+        if diff_elements == 0 {
+            return Self::default();
+        }
+        let arr = create_remaining_levels(hashed_elements);
+        Self::from_levels(arr, diff_elements, DefaultBuildHasher::default(), PairOrdering::Positional, PaddingStrategy::RepeatLast)
+    }
+}
+
+/// Hashes the elements splitting them in one chunk per thread, keeping
+/// the order they had.
+fn hash_par<H: BuildHasher + Sync, T: Hash + Sync>(hasher: &H, elements: &[T], threads: usize) -> Vec<u64> {
+    let chunk_len = elements.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = elements
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|elem| hash_with(hasher, elem)).collect::<Vec<u64>>()))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("A hashing thread panicked")).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_strings(len: usize) -> Vec<String> {
+        (0..len).map(|i| format!("{i:0>4096}")).collect()
+    }
+
+    #[test]
+    /// Test if the tree hashed on several threads is the same as the serial
+    /// one, for sizes that do not split evenly between the threads.
+    fn parallel_matches_serial() {
+        for len in [0, 1, 2, 3, 5, 17, 100] {
+            let elements: Vec<usize> = (0..len).collect();
+            assert_eq!(MerkleTree::new_par(elements.clone()), MerkleTree::new(elements));
+        }
+        for threads in [1, 3, 8, 64] {
+            let elements = large_strings(50);
+            let expected: Vec<u64> = elements.iter().map(|elem| hash_with(&DefaultBuildHasher::default(), elem)).collect();
+            assert_eq!(hash_par(&DefaultBuildHasher::default(), &elements, threads), expected);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    /// Test if creating a tree of 100k elements of 4 KiB each on several
+    /// threads gives the same root and, when there is more than one core,
+    /// is faster than on one.
+    ///
+    /// Run it with `cargo test --release -- --ignored parallel_benchmark`
+    fn parallel_benchmark() {
+        let elements = large_strings(100_000);
+
+        let start = std::time::Instant::now();
+        let serial = MerkleTree::new(elements.clone());
+        let serial_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let parallel = MerkleTree::new_par(elements);
+        let parallel_time = start.elapsed();
+
+        assert_eq!(parallel.root(), serial.root());
+        if std::thread::available_parallelism().map_or(1, |threads| threads.get()) > 1 {
+            assert!(parallel_time < serial_time, "new_par took {parallel_time:?}, new {serial_time:?}");
+        }
+    }
+}
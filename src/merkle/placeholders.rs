@@ -0,0 +1,234 @@
+use std::hash::BuildHasher;
+
+use super::{create_remaining_levels, DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering};
+use crate::error::MerkleError;
+
+/// Keeps track of the base level slots that still hold a placeholder.
+/// - `filled`: One flag per slot, true once the slot received its final hash.
+/// - `missing`: Quantity of slots that were not filled yet.
+#[derive(Clone)]
+pub(super) struct PlaceholderSlots {
+    filled: Vec<bool>,
+    missing: usize,
+}
+
+/// The root of a tree together with whether it is final or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootStatus {
+    /// Every slot of the tree holds its final hash.
+    Final(u64),
+    /// Some slots still hold a placeholder, so the root will change.
+    /// - `root`: The current root.
+    /// - `missing`: Quantity of slots that were not filled yet.
+    Provisional { root: u64, missing: usize },
+}
+
+impl MerkleTree {
+    /// Creates a new MerkleTree with `n` slots that all hold the same placeholder
+    ///
+    /// This is useful when the quantity of elements is known up front but the
+    /// elements arrive out of order. Each slot is then replaced with `fill`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `n`: The quantity of elements the tree will have.
+    /// - `placeholder_hash`: The hash every slot will have until it is filled.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree instance, or an Error if `n` is 0
+    pub fn with_placeholders(n: usize, placeholder_hash: u64) -> Result<Self, MerkleError> {
+        if n == 0 {
+            return Err(MerkleError::EmptyTree);
+        }
+        let arr = create_remaining_levels(vec![placeholder_hash; n]);
+        let mut merkle = Self::from_levels(arr, n, DefaultBuildHasher::default(), PairOrdering::Positional, PaddingStrategy::RepeatLast);
+        merkle.placeholders = Some(PlaceholderSlots { filled: vec![false; n], missing: n });
+        Ok(merkle)
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Replaces the placeholder of a slot with the hash of its element
+    ///
+    /// Only the nodes in the path from the slot to the root are re-calculated.
+    /// If the slot is the last one, the padding made of copies of it is
+    /// re-calculated too.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the slot in the input array.
+    /// - `leaf_hash`: The hash of the element that goes in that slot.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is an Error if the index is invalid or if the slot was
+    /// already filled
+    pub fn fill(&mut self, index: usize, leaf_hash: u64) -> Result<(), MerkleError> {
+        if index >= self.diff_elements {
+            return Err(MerkleError::InvalidIndex { index, len: self.diff_elements });
+        }
+        let slots = match &mut self.placeholders {
+            // Elements added after the creation are never placeholders
+            Some(slots) if slots.filled.get(index) == Some(&false) => slots,
+            _ => return Err(MerkleError::SlotAlreadyFilled { index }),
+        };
+        slots.filled[index] = true;
+        slots.missing -= 1;
+        if slots.missing == 0 {
+            self.placeholders = None;
+        }
+
+        self.record_root();
+        self.write_leaf(index, leaf_hash);
+        Ok(())
+    }
+
+    /// Forgets the slot of an element that was removed from the tree, so
+    /// it is no longer counted as missing.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the removed element. It is always the last one.
+    pub(super) fn forget_slot(&mut self, index: usize) {
+        if let Some(slots) = &mut self.placeholders
+            && slots.filled.len() == index + 1
+        {
+            if slots.filled.pop() == Some(false) {
+                slots.missing -= 1;
+            }
+            if slots.missing == 0 {
+                self.placeholders = None;
+            }
+        }
+    }
+
+    /// Checks if every slot of the tree holds its final hash.
+    /// Trees that were not created with placeholders are always complete.
+    pub fn is_complete(&self) -> bool {
+        self.placeholders.is_none()
+    }
+
+    /// Gets the indexes of the slots that still hold a placeholder,
+    /// in increasing order.
+    pub fn missing_indices(&self) -> Vec<usize> {
+        match &self.placeholders {
+            Some(slots) => (0..slots.filled.len()).filter(|i| !slots.filled[*i]).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Gets the root of the tree flagged as provisional if there are still
+    /// slots that hold a placeholder.
+    ///
+    /// ### Returns
+    ///
+    /// The RootStatus of the tree, or None if the tree has no root.
+    pub fn root_status(&self) -> Option<RootStatus> {
+        let root = self.root()?;
+        match &self.placeholders {
+            Some(slots) => Some(RootStatus::Provisional { root, missing: slots.missing }),
+            None => Some(RootStatus::Final(root)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+    use std::sync::mpsc;
+    use std::thread;
+
+    const PLACEHOLDER: u64 = 0;
+
+    /// Fills a tree of `n` placeholders from several threads, each one
+    /// hashing a shuffled share of the elements.
+    fn fill_from_workers(n: usize, workers: usize) -> MerkleTree {
+        let mut merkle = MerkleTree::with_placeholders(n, PLACEHOLDER).unwrap();
+        // Multiplying by a number that is coprime with n gives us a shuffled order
+        let shuffled: Vec<usize> = (0..n).map(|i| (i * 7919) % n).collect();
+        let (sender, receiver) = mpsc::channel();
+
+        for worker in 0..workers {
+            let sender = sender.clone();
+            let indexes: Vec<usize> = shuffled.iter().skip(worker).step_by(workers).copied().collect();
+            thread::spawn(move || {
+                for index in indexes {
+                    sender.send((index, hash_element(format!("Element {index}")))).unwrap();
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, leaf_hash) in receiver {
+            merkle.fill(index, leaf_hash).unwrap();
+        }
+        merkle
+    }
+
+    #[test]
+    /// Test if filling every slot in a shuffled order from several workers
+    /// gives the same tree as creating it directly from the elements.
+    fn shuffled_fill_matches_direct_construction() {
+        for n in [1, 2, 5, 8, 1000] {
+            let data: Vec<String> = (0..n).map(|i| format!("Element {i}")).collect();
+            let expected = MerkleTree::new(data);
+            let merkle = fill_from_workers(n, 4);
+
+            assert!(merkle.is_complete());
+            assert_eq!(merkle.arr, expected.arr);
+            assert_eq!(merkle.root_status(), Some(RootStatus::Final(expected.arr.last().unwrap()[0])));
+        }
+    }
+
+    #[test]
+    /// Test if the missing slots are tracked and the root is flagged
+    /// while there are still placeholders.
+    fn tracks_missing_slots() {
+        let mut merkle = MerkleTree::with_placeholders(5, PLACEHOLDER).unwrap();
+        assert_eq!(merkle.missing_indices(), vec![0, 1, 2, 3, 4]);
+
+        merkle.fill(3, hash_element("Tree")).unwrap();
+        merkle.fill(0, hash_element("Crypto")).unwrap();
+
+        assert!(!merkle.is_complete());
+        assert_eq!(merkle.missing_indices(), vec![1, 2, 4]);
+        let root = merkle.arr.last().unwrap()[0];
+        assert_eq!(merkle.root_status(), Some(RootStatus::Provisional { root, missing: 3 }));
+    }
+
+    #[test]
+    /// Test if filling a slot twice or a slot outside of the tree fails
+    fn fill_rejects_invalid_slots() {
+        let mut merkle = MerkleTree::with_placeholders(3, PLACEHOLDER).unwrap();
+        merkle.fill(1, hash_element("Merkle")).unwrap();
+
+        assert_eq!(merkle.fill(1, hash_element("Rust")), Err(MerkleError::SlotAlreadyFilled { index: 1 }));
+        // Index 3 exists in the base level, but only as a repeated value
+        assert_eq!(merkle.fill(3, hash_element("Rust")), Err(MerkleError::InvalidIndex { index: 3, len: 3 }));
+        assert_eq!(MerkleTree::with_placeholders(0, PLACEHOLDER).err(), Some(MerkleError::EmptyTree));
+    }
+
+    #[test]
+    /// Test if popping a slot that was never filled stops counting it as missing
+    fn popped_slot_is_not_missing() {
+        let mut merkle = MerkleTree::with_placeholders(3, PLACEHOLDER).unwrap();
+        merkle.fill(0, hash_element("Crypto")).unwrap();
+        merkle.fill(1, hash_element("Merkle")).unwrap();
+
+        merkle.pop_element();
+        assert!(merkle.is_complete());
+        assert_eq!(merkle.arr, MerkleTree::new(vec!["Crypto", "Merkle"]).arr);
+    }
+
+    #[test]
+    /// Test if a tree created from elements has every slot filled
+    fn regular_tree_is_complete() {
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle"]);
+
+        assert!(merkle.is_complete());
+        assert!(merkle.missing_indices().is_empty());
+        assert_eq!(merkle.fill(0, hash_element("Rust")), Err(MerkleError::SlotAlreadyFilled { index: 0 }));
+    }
+}
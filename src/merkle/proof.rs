@@ -0,0 +1,264 @@
+use super::proof_pack::{array_field, hex_field, missing_field, usize_field};
+use crate::encoding::ByteReader;
+use crate::error::MerkleError;
+use crate::json::{self, Json};
+use crate::limits::Limits;
+use crate::digest_tree;
+use crate::root_hash::{parse_hex_bytes, to_hex};
+pub use crate::verify::{Proof, Side};
+
+/// Byte written for a hash that goes on the right, see `Proof::to_bytes`.
+const RIGHT: u8 = 0;
+/// Byte written for a hash that goes on the left, see `Proof::to_bytes`.
+const LEFT: u8 = 1;
+
+impl Proof {
+    /// Encodes the proof with a compact little-endian binary layout: the
+    /// leaf index as an u64 and the quantity of hashes as an u32, then every
+    /// hash as an u64 followed by a byte with its side (0 for right, 1 for left).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(12 + 9 * self.siblings.len());
+        bytes.extend_from_slice(&(self.leaf_index as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.siblings.len() as u32).to_le_bytes());
+        for (hash, side) in &self.siblings {
+            bytes.extend_from_slice(&hash.to_le_bytes());
+            bytes.push(match side {
+                Side::Right => RIGHT,
+                Side::Left => LEFT,
+            });
+        }
+        bytes
+    }
+
+    /// Decodes a proof encoded with `to_bytes`, using the default `Limits`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        Self::from_bytes_with_limits(bytes, &Limits::default())
+    }
+
+    /// Decodes a proof encoded with `to_bytes`. The declared quantity of
+    /// hashes is checked against the limits and against the len of the
+    /// input before anything is allocated for it.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof, or an Error if the bytes are not a valid
+    /// proof or exceed the limits
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(bytes.len())?;
+        let mut reader = ByteReader::new(bytes);
+        let leaf_index = reader.usize()?;
+        let sibling_count = reader.u32()? as usize;
+        limits.check_proof_entries(sibling_count)?;
+        reader.ensure_items(sibling_count, 9)?;
+
+        let mut siblings = Vec::with_capacity(sibling_count);
+        for _ in 0..sibling_count {
+            let hash = reader.u64()?;
+            let side = match reader.u8()? {
+                RIGHT => Side::Right,
+                LEFT => Side::Left,
+                other => return Err(MerkleError::InvalidEncoding(format!("Invalid side {other}"))),
+            };
+            siblings.push((hash, side));
+        }
+        reader.finish()?;
+        Ok(Self { leaf_index, siblings })
+    }
+
+    /// Encodes the proof with `to_bytes`, writing each byte as two
+    /// lowercase hex digits so it can be passed around as text
+    pub fn to_hex(&self) -> String {
+        digest_tree::to_hex(&self.to_bytes())
+    }
+
+    /// Decodes a proof encoded with `to_hex`, using the default `Limits`
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof, or an Error if the text is not hex or the
+    /// bytes are not a valid proof
+    pub fn from_hex(input: &str) -> Result<Self, MerkleError> {
+        Self::from_bytes(&parse_hex_bytes(input)?)
+    }
+
+    /// Encodes the proof as JSON. Hashes are written as 16 hex digits so
+    /// they do not lose precision in JSON numbers, and each side as
+    /// `"left"` or `"right"`.
+    pub fn to_json(&self) -> String {
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|(hash, side)| {
+                let side = match side {
+                    Side::Left => "left",
+                    Side::Right => "right",
+                };
+                Json::Object(vec![
+                    ("hash".to_string(), Json::from(to_hex(*hash))),
+                    ("side".to_string(), Json::from(side.to_string())),
+                ])
+            })
+            .collect();
+        let document = Json::Object(vec![
+            ("leaf_index".to_string(), Json::from(self.leaf_index)),
+            ("siblings".to_string(), Json::Array(siblings)),
+        ]);
+        document.to_string()
+    }
+
+    /// Decodes a proof encoded with `to_json`, using the default `Limits`
+    pub fn from_json(input: &str) -> Result<Self, MerkleError> {
+        Self::from_json_with_limits(input, &Limits::default())
+    }
+
+    /// Decodes a proof encoded with `to_json`, checking it against the limits
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof, or an Error if the text is not a valid
+    /// proof or exceeds the limits
+    pub fn from_json_with_limits(input: &str, limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(input.len())?;
+        let document = json::parse(input).map_err(MerkleError::InvalidEncoding)?;
+        let leaf_index = usize_field(&document, "leaf_index")?;
+        let siblings = array_field(&document, "siblings")?;
+        limits.check_proof_entries(siblings.len())?;
+        let siblings = siblings
+            .iter()
+            .map(|sibling| {
+                let side = match sibling.get("side").and_then(Json::as_str) {
+                    Some("left") => Side::Left,
+                    Some("right") => Side::Right,
+                    _ => return Err(missing_field("side")),
+                };
+                Ok((hex_field(sibling, "hash")?, side))
+            })
+            .collect::<Result<Vec<(u64, Side)>, MerkleError>>()?;
+        Ok(Self { leaf_index, siblings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+    use crate::MerkleTree;
+
+    /// A xorshift generator, so the tests go over many proofs without a crate
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    /// Test if many proofs with random hashes, sides and lens survive a round trip
+    fn bytes_round_trip() {
+        let mut state = 0x9e3779b97f4a7c15;
+        for _ in 0..200 {
+            let len = (next_random(&mut state) % 25) as usize;
+            let siblings = (0..len)
+                .map(|_| {
+                    let hash = next_random(&mut state);
+                    (hash, if hash.is_multiple_of(2) { Side::Right } else { Side::Left })
+                })
+                .collect();
+            let proof = Proof::new(next_random(&mut state) as usize, siblings);
+
+            let bytes = proof.to_bytes();
+            assert_eq!(bytes.len(), 12 + 9 * len);
+            assert_eq!(Proof::from_bytes(&bytes), Ok(proof));
+        }
+    }
+
+    #[test]
+    /// Test if a decoded proof still verifies against the root of its tree
+    fn decoded_proof_verifies() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = MerkleTree::new(data.clone());
+        for (index, element) in data.iter().enumerate() {
+            let bytes = merkle.generate_proof(index).unwrap().to_bytes();
+            let decoded = Proof::from_bytes(&bytes).unwrap();
+
+            assert_eq!(decoded.leaf_index(), index);
+            assert!(merkle.verify(&decoded, hash_element(element)));
+        }
+    }
+
+    #[test]
+    /// Test if a proof survives a hex round trip in either case, and text
+    /// that is not hex is an error.
+    fn hex_round_trip() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        let proof = merkle.generate_proof(4).unwrap();
+        let hex = proof.to_hex();
+
+        assert_eq!(hex.len(), 2 * proof.to_bytes().len());
+        assert_eq!(Proof::from_hex(&hex), Ok(proof.clone()));
+        assert_eq!(Proof::from_hex(&hex.to_uppercase()), Ok(proof));
+        for invalid in [&hex[1..], "zz", "0"] {
+            assert_eq!(Proof::from_hex(invalid), Err(MerkleError::InvalidHex(invalid.to_string())));
+        }
+    }
+
+    #[test]
+    /// Test if truncated input, trailing bytes and invalid sides are errors
+    /// instead of panics.
+    fn invalid_bytes_are_errors() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]);
+        let bytes = merkle.generate_proof(2).unwrap().to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(matches!(Proof::from_bytes(&bytes[..len]), Err(MerkleError::InvalidEncoding(_))));
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(Proof::from_bytes(&trailing), Err(MerkleError::InvalidEncoding(_))));
+
+        let mut wrong_side = bytes.clone();
+        wrong_side[12 + 8] = 2;
+        assert_eq!(Proof::from_bytes(&wrong_side), Err(MerkleError::InvalidEncoding("Invalid side 2".to_string())));
+
+        // A huge declared quantity of hashes is rejected before allocating
+        let mut huge = bytes;
+        huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Proof::from_bytes(&huge).is_err());
+    }
+
+    #[test]
+    /// Test if proofs of every element survive a JSON round trip and still
+    /// verify, including leaf indexes and hashes above 2^53.
+    fn json_round_trip() {
+        let data: Vec<usize> = (0..11).collect();
+        let merkle = MerkleTree::new(data.clone());
+        for (index, element) in data.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            let decoded = Proof::from_json(&proof.to_json()).unwrap();
+
+            assert_eq!(decoded, proof);
+            assert!(merkle.verify(&decoded, hash_element(element)));
+        }
+
+        let proof = Proof::new(usize::MAX, vec![(u64::MAX, Side::Left), ((1 << 53) + 1, Side::Right)]);
+        assert_eq!(Proof::from_json(&proof.to_json()), Ok(proof));
+    }
+
+    #[test]
+    /// Test if invalid JSON, negative indexes and hashes that are not hex
+    /// are errors instead of panics.
+    fn invalid_json_is_error() {
+        let hash = "00000000000000ff";
+        let documents = [
+            "{\"leaf_index\":0,\"siblings\":[".to_string(),
+            format!("{{\"leaf_index\":-1,\"siblings\":[{{\"hash\":\"{hash}\",\"side\":\"left\"}}]}}"),
+            "{\"leaf_index\":0,\"siblings\":[{\"hash\":\"not a hash\",\"side\":\"left\"}]}".to_string(),
+            "{\"leaf_index\":0,\"siblings\":[{\"hash\":255,\"side\":\"left\"}]}".to_string(),
+            format!("{{\"leaf_index\":0,\"siblings\":[{{\"hash\":\"{hash}\",\"side\":\"up\"}}]}}"),
+            "{\"siblings\":[]}".to_string(),
+        ];
+        for document in documents {
+            assert!(Proof::from_json(&document).is_err(), "{document}");
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+
+use super::{fold_proof, MerkleTree, PaddingStrategy};
+use crate::encoding::ByteReader;
+use crate::error::MerkleError;
+use crate::json::{self, Json};
+use crate::limits::Limits;
+use crate::root_hash::{parse_hex, to_hex, RootHash};
+
+/// Bytes every binary proof pack starts with.
+const MAGIC: &[u8; 4] = b"MKPK";
+const VERSION: u8 = 1;
+/// Flag set when the entries carry the hash of their leaf.
+const WITH_LEAF_DATA: u8 = 1;
+
+/// An element of the tree whose inclusion is proven by the pack.
+/// - `index`: The index of the element in the input array.
+/// - `leaf`: The hash of the element, if the pack was exported with leaf data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackEntry {
+    pub index: usize,
+    pub leaf: Option<u64>,
+}
+
+/// The result of verifying one entry of a pack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryStatus {
+    /// The leaf and the nodes of the pack lead to the trusted root.
+    Verified,
+    /// The entry does not lead to the trusted root.
+    Failed,
+    /// The pack has no leaf data for the entry and none was supplied.
+    MissingLeaf,
+}
+
+/// The per-entry results of verifying a pack, in the order of its entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackReport {
+    pub entries: Vec<(usize, EntryStatus)>,
+}
+
+impl PackReport {
+    /// Checks if every entry of the pack was verified
+    pub fn all_verified(&self) -> bool {
+        self.entries.iter().all(|(_, status)| *status == EntryStatus::Verified)
+    }
+}
+
+/// A self-contained bundle with the root of a tree and the proofs for some
+/// of its elements, that can be verified without the tree.
+///
+/// Instead of one proof per entry, the pack keeps a single table of the
+/// nodes the proofs need, addressed by level and index. Proofs of nearby
+/// elements share most of their nodes, so each node is stored only once.
+/// - `root`: The root of the tree the pack was exported from.
+/// - `leaf_count`: Quantity of different elements in that tree.
+/// - `height`: Quantity of levels in that tree.
+/// - `entries`: The proven elements, sorted by index.
+/// - `nodes`: The nodes needed by the proofs, by `(level, index)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofPack {
+    root: u64,
+    leaf_count: usize,
+    height: usize,
+    entries: Vec<PackEntry>,
+    nodes: BTreeMap<(usize, usize), u64>,
+}
+
+impl ProofPack {
+    /// Creates a pack with the proofs for some elements of a tree
+    ///
+    /// ### Arguments
+    ///
+    /// - `tree`: The tree the elements are in.
+    /// - `indices`: The indexes of the elements to prove. Repeated indexes are proven once.
+    /// - `include_leaf_data`: If the hashes of the elements are stored in the pack.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the ProofPack, or an Error if an index is invalid, the tree has no root
+    /// or it does not repeat its last element as padding
+    pub fn export(tree: &MerkleTree, indices: &[usize], include_leaf_data: bool) -> Result<Self, MerkleError> {
+        if tree.padding != PaddingStrategy::RepeatLast {
+            return Err(MerkleError::UnsupportedPadding);
+        }
+        let root = tree.root_hash().ok_or(MerkleError::EmptyTree)?.into_raw();
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let mut entries = Vec::with_capacity(indices.len());
+        let mut nodes = BTreeMap::new();
+        for index in indices {
+            if index >= tree.diff_elements {
+                return Err(MerkleError::InvalidIndex { index, len: tree.diff_elements });
+            }
+            let leaf = include_leaf_data.then(|| tree.arr[0][index]);
+            entries.push(PackEntry { index, leaf });
+
+            let mut node_index = index;
+            for level in 0..tree.height() - 1 {
+                if let Some(sibling) = tree.get_node(level, node_index ^ 1) {
+                    nodes.insert((level, node_index ^ 1), sibling);
+                }
+                node_index /= 2;
+            }
+        }
+        Ok(Self { root, leaf_count: tree.diff_elements, height: tree.arr.len(), entries, nodes })
+    }
+
+    /// Gets the root of the tree the pack claims to come from
+    pub fn root(&self) -> RootHash {
+        RootHash::from_raw(self.root)
+    }
+
+    /// Gets the proven elements, sorted by index
+    pub fn entries(&self) -> &[PackEntry] {
+        &self.entries
+    }
+
+    /// Verifies every entry of the pack with the leaf data it carries
+    ///
+    /// ### Arguments
+    ///
+    /// - `trusted_root`: The root obtained from a trusted source. The root stored in the pack is not trusted.
+    ///
+    /// ### Returns
+    ///
+    /// A PackReport with the status of every entry
+    pub fn verify_all(&self, trusted_root: RootHash) -> PackReport {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| match entry.leaf {
+                Some(leaf) => (entry.index, self.verify_entry(trusted_root, entry.index, leaf)),
+                None => (entry.index, EntryStatus::MissingLeaf),
+            })
+            .collect();
+        PackReport { entries }
+    }
+
+    /// Verifies every entry of the pack with leaf hashes supplied by the
+    /// verifier, which is needed for packs exported without leaf data.
+    ///
+    /// ### Arguments
+    ///
+    /// - `trusted_root`: The root obtained from a trusted source.
+    /// - `leaves`: The `(index, leaf hash)` pairs for the entries of the pack.
+    ///
+    /// ### Returns
+    ///
+    /// A PackReport with the status of every entry of the pack. Supplied
+    /// leaves for indexes that are not in the pack are ignored.
+    pub fn verify_leaves(&self, trusted_root: RootHash, leaves: &[(usize, u64)]) -> PackReport {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| match leaves.iter().find(|(index, _)| *index == entry.index) {
+                Some((_, leaf)) => (entry.index, self.verify_entry(trusted_root, entry.index, *leaf)),
+                None => (entry.index, EntryStatus::MissingLeaf),
+            })
+            .collect();
+        PackReport { entries }
+    }
+
+    /// Rebuilds the proof of an entry from the node table and checks it
+    fn verify_entry(&self, trusted_root: RootHash, index: usize, leaf: u64) -> EntryStatus {
+        if index >= self.leaf_count {
+            return EntryStatus::Failed;
+        }
+        let mut proof = Vec::with_capacity(self.height.saturating_sub(1));
+        for level in 0..self.height.saturating_sub(1) {
+            match self.nodes.get(&(level, (index >> level) ^ 1)) {
+                Some(hash) => proof.push(*hash),
+                None => return EntryStatus::Failed,
+            }
+        }
+        if fold_proof(&proof, index, leaf) == trusted_root.into_raw() {
+            EntryStatus::Verified
+        } else {
+            EntryStatus::Failed
+        }
+    }
+
+    /// Encodes the pack with a compact little-endian binary layout: the magic
+    /// bytes, the version, the flags, the root, the leaf count and the height,
+    /// then every entry and finally every node of the table.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let with_leaf_data = self.entries.iter().all(|entry| entry.leaf.is_some());
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(if with_leaf_data { WITH_LEAF_DATA } else { 0 });
+        bytes.extend_from_slice(&self.root.to_le_bytes());
+        bytes.extend_from_slice(&(self.leaf_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u32).to_le_bytes());
+
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            bytes.extend_from_slice(&(entry.index as u64).to_le_bytes());
+            if let (true, Some(leaf)) = (with_leaf_data, entry.leaf) {
+                bytes.extend_from_slice(&leaf.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        for ((level, index), hash) in &self.nodes {
+            bytes.extend_from_slice(&(*level as u32).to_le_bytes());
+            bytes.extend_from_slice(&(*index as u64).to_le_bytes());
+            bytes.extend_from_slice(&hash.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a pack encoded with `to_bytes`, using the default `Limits`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        Self::from_bytes_with_limits(bytes, &Limits::default())
+    }
+
+    /// Decodes a pack encoded with `to_bytes`. Every declared count is
+    /// checked against the limits and against the len of the input before
+    /// anything is allocated for it.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the ProofPack, or an Error if the bytes are not a valid
+    /// pack or exceed the limits
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(bytes.len())?;
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(MerkleError::InvalidEncoding("Not a proof pack".to_string()));
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(MerkleError::InvalidEncoding(format!("Unsupported version {version}")));
+        }
+        let with_leaf_data = reader.u8()? & WITH_LEAF_DATA != 0;
+        let root = reader.u64()?;
+        let leaf_count = reader.usize()?;
+        limits.check_leaves(leaf_count)?;
+        let height = reader.u32()? as usize;
+        limits.check_height(height)?;
+
+        let entry_count = reader.u32()? as usize;
+        limits.check_leaves(entry_count)?;
+        let entry_size = if with_leaf_data { 16 } else { 8 };
+        reader.ensure_items(entry_count, entry_size)?;
+        let mut entries = Vec::with_capacity(entry_count);
+        for _ in 0..entry_count {
+            let index = reader.usize()?;
+            let leaf = if with_leaf_data { Some(reader.u64()?) } else { None };
+            entries.push(PackEntry { index, leaf });
+        }
+
+        let node_count = reader.u32()? as usize;
+        reader.ensure_items(node_count, 20)?;
+        let mut nodes = BTreeMap::new();
+        for _ in 0..node_count {
+            let level = reader.u32()? as usize;
+            let index = reader.usize()?;
+            nodes.insert((level, index), reader.u64()?);
+        }
+        reader.finish()?;
+        Ok(Self { root, leaf_count, height, entries, nodes })
+    }
+
+    /// Encodes the pack as JSON. Hashes are written as 16 hex digits so
+    /// they do not lose precision in JSON numbers.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let leaf = entry.leaf.map_or(Json::Null, |leaf| Json::from(to_hex(leaf)));
+                Json::Object(vec![("index".to_string(), Json::from(entry.index)), ("leaf".to_string(), leaf)])
+            })
+            .collect();
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|((level, index), hash)| {
+                Json::Object(vec![
+                    ("level".to_string(), Json::from(*level)),
+                    ("index".to_string(), Json::from(*index)),
+                    ("hash".to_string(), Json::from(to_hex(*hash))),
+                ])
+            })
+            .collect();
+        let document = Json::Object(vec![
+            ("root".to_string(), Json::from(to_hex(self.root))),
+            ("leaf_count".to_string(), Json::from(self.leaf_count)),
+            ("height".to_string(), Json::from(self.height)),
+            ("entries".to_string(), Json::Array(entries)),
+            ("nodes".to_string(), Json::Array(nodes)),
+        ]);
+        document.to_string()
+    }
+
+    /// Decodes a pack encoded with `to_json`, using the default `Limits`
+    pub fn from_json(input: &str) -> Result<Self, MerkleError> {
+        Self::from_json_with_limits(input, &Limits::default())
+    }
+
+    /// Decodes a pack encoded with `to_json`, checking it against the limits
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the ProofPack, or an Error if the text is not a valid
+    /// pack or exceeds the limits
+    pub fn from_json_with_limits(input: &str, limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(input.len())?;
+        let document = json::parse(input).map_err(MerkleError::InvalidEncoding)?;
+        let root = hex_field(&document, "root")?;
+        let leaf_count = usize_field(&document, "leaf_count")?;
+        limits.check_leaves(leaf_count)?;
+        let height = usize_field(&document, "height")?;
+        limits.check_height(height)?;
+
+        let entries = array_field(&document, "entries")?;
+        limits.check_leaves(entries.len())?;
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let leaf = match entry.get("leaf") {
+                    Some(Json::Null) | None => None,
+                    Some(_) => Some(hex_field(entry, "leaf")?),
+                };
+                Ok(PackEntry { index: usize_field(entry, "index")?, leaf })
+            })
+            .collect::<Result<Vec<PackEntry>, MerkleError>>()?;
+        let nodes = array_field(&document, "nodes")?
+            .iter()
+            .map(|node| Ok(((usize_field(node, "level")?, usize_field(node, "index")?), hex_field(node, "hash")?)))
+            .collect::<Result<BTreeMap<(usize, usize), u64>, MerkleError>>()?;
+        Ok(Self { root, leaf_count, height, entries, nodes })
+    }
+}
+
+pub(super) fn missing_field(name: &str) -> MerkleError {
+    MerkleError::InvalidEncoding(format!("Missing or invalid field '{name}'"))
+}
+
+pub(super) fn hex_field(value: &Json, name: &str) -> Result<u64, MerkleError> {
+    parse_hex(value.get(name).and_then(Json::as_str).ok_or_else(|| missing_field(name))?)
+}
+
+pub(super) fn usize_field(value: &Json, name: &str) -> Result<usize, MerkleError> {
+    value
+        .get(name)
+        .and_then(Json::as_u64)
+        .and_then(|number| usize::try_from(number).ok())
+        .ok_or_else(|| missing_field(name))
+}
+
+pub(super) fn array_field<'a>(value: &'a Json, name: &str) -> Result<&'a [Json], MerkleError> {
+    value.get(name).and_then(Json::as_array).ok_or_else(|| missing_field(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_tree() -> MerkleTree {
+        let data: Vec<String> = (0..1000).map(|i| format!("Element {i}")).collect();
+        MerkleTree::new(data)
+    }
+
+    const INDICES: [usize; 10] = [3, 4, 5, 100, 101, 500, 640, 998, 999, 0];
+
+    #[test]
+    /// Test if a pack for 10 of 1000 leaves verifies without the tree
+    fn verifies_standalone() {
+        let merkle = big_tree();
+        let trusted_root = merkle.root_hash().unwrap();
+        let pack = ProofPack::export(&merkle, &INDICES, true).unwrap();
+        drop(merkle);
+
+        let report = pack.verify_all(trusted_root);
+        assert_eq!(report.entries.len(), 10);
+        assert!(report.all_verified());
+    }
+
+    #[test]
+    /// Test if the shared nodes are stored once, so the pack holds fewer
+    /// nodes than the 10 separate proofs together.
+    fn deduplicates_shared_nodes() {
+        let merkle = big_tree();
+        let pack = ProofPack::export(&merkle, &INDICES, true).unwrap();
+        let separate: usize = INDICES.iter().map(|i| merkle.generate_proof(*i).unwrap().len()).sum();
+
+        assert!(pack.nodes.len() < separate);
+    }
+
+    #[test]
+    /// Test if a corrupted entry fails while every other entry still verifies
+    fn detects_corrupted_entry() {
+        let merkle = big_tree();
+        let mut pack = ProofPack::export(&merkle, &INDICES, true).unwrap();
+        pack.entries[4].leaf = Some(pack.entries[4].leaf.unwrap() ^ 1);
+
+        let report = pack.verify_all(merkle.root_hash().unwrap());
+        let failed: Vec<usize> =
+            report.entries.iter().filter(|(_, status)| *status != EntryStatus::Verified).map(|(index, _)| *index).collect();
+        assert_eq!(failed, vec![pack.entries[4].index]);
+    }
+
+    #[test]
+    /// Test if a pack without leaf data needs the leaves from the verifier
+    fn verifies_supplied_leaves() {
+        let merkle = big_tree();
+        let pack = ProofPack::export(&merkle, &[7, 8], false).unwrap();
+        let root = merkle.root_hash().unwrap();
+
+        assert_eq!(pack.verify_all(root).entries, vec![(7, EntryStatus::MissingLeaf), (8, EntryStatus::MissingLeaf)]);
+        let leaves = [(7, merkle.arr[0][7]), (8, merkle.arr[0][7])];
+        assert_eq!(pack.verify_leaves(root, &leaves).entries, vec![(7, EntryStatus::Verified), (8, EntryStatus::Failed)]);
+    }
+
+    #[test]
+    /// Test if the pack survives a round trip through both encodings
+    fn binary_and_json_round_trips() {
+        let merkle = big_tree();
+        for include_leaf_data in [true, false] {
+            let pack = ProofPack::export(&merkle, &INDICES, include_leaf_data).unwrap();
+
+            assert_eq!(ProofPack::from_bytes(&pack.to_bytes()).unwrap(), pack);
+            assert_eq!(ProofPack::from_json(&pack.to_json()).unwrap(), pack);
+        }
+    }
+
+    #[test]
+    /// Test if truncated or hostile inputs are rejected with an Error
+    fn rejects_invalid_encodings() {
+        let merkle = big_tree();
+        let bytes = ProofPack::export(&merkle, &INDICES, true).unwrap().to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(ProofPack::from_bytes(&bytes[..len]).is_err());
+        }
+        // Declare a huge quantity of nodes at the end of a valid prefix
+        let nodes_count_at = bytes.len() - 4 - 20 * ProofPack::from_bytes(&bytes).unwrap().nodes.len();
+        let mut hostile = bytes[..nodes_count_at].to_vec();
+        hostile.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(ProofPack::from_bytes(&hostile), Err(MerkleError::InvalidEncoding(_))));
+
+        assert!(ProofPack::from_json(r#"{"root":"00"}"#).is_err());
+        assert!(ProofPack::from_json("not json").is_err());
+    }
+
+    #[test]
+    /// Test if an index outside the tree can not be exported
+    fn export_rejects_invalid_index() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+
+        assert_eq!(ProofPack::export(&merkle, &[3], true), Err(MerkleError::InvalidIndex { index: 3, len: 3 }));
+    }
+}
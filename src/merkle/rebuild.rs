@@ -0,0 +1,274 @@
+use std::hash::BuildHasher;
+use std::mem;
+
+use super::{combine_with, DefaultBuildHasher, MerkleTree, PairOrdering, TreeStructure};
+use crate::error::MerkleError;
+
+/// Progress reported by [`RebuildTask::run_for`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuildProgress {
+    /// There are still parent nodes left to hash.
+    /// - `hashed`: Parent nodes hashed so far by the task.
+    /// - `remaining`: Parent nodes that still have to be hashed.
+    InProgress { hashed: usize, remaining: usize },
+    /// Every level was hashed and the task can be applied with
+    /// [`MerkleTree::finish_rebuild`].
+    Complete,
+}
+
+/// A resumable rebuild of every level above the base level of a tree.
+///
+/// The task works on its own copy of the base level, so the tree it
+/// was started from keeps serving its old (and consistent) state until
+/// the finished task is applied with [`MerkleTree::finish_rebuild`].
+/// Dropping the task before that simply abandons the rebuild and leaves
+/// the tree untouched.
+/// - `levels`: The levels that were completely hashed. The first one is the base level.
+/// - `next_level`: The level that is currently being hashed.
+/// - `hashed`: Quantity of parent nodes hashed so far.
+/// - `total`: Quantity of parent nodes the whole rebuild has to hash.
+/// - `hasher`: A copy of the hasher of the tree the task was started from.
+/// - `ordering`: How the tree the task was started from orders the children of its nodes.
+/// - `repeated`: The hash of the padding of every level, empty when the tree promotes odd nodes.
+pub struct RebuildTask<H = DefaultBuildHasher> {
+    levels: TreeStructure,
+    next_level: Vec<u64>,
+    hashed: usize,
+    total: usize,
+    hasher: H,
+    ordering: PairOrdering,
+    repeated: Vec<u64>,
+}
+
+impl<H: BuildHasher> RebuildTask<H> {
+    /// Hashes at most `budget_nodes` parent nodes and then yields control
+    /// back to the caller.
+    ///
+    /// ### Arguments
+    ///
+    /// - `budget_nodes`: The maximum quantity of parent nodes that will be hashed in this call.
+    ///
+    /// ### Returns
+    ///
+    /// The progress of the task after spending the budget. Once it returns
+    /// `Complete` every following call will also return `Complete`.
+    pub fn run_for(&mut self, budget_nodes: usize) -> RebuildProgress {
+        let mut spent = 0;
+        while spent < budget_nodes && !self.is_complete() {
+            // The last complete level is the one we read the children from.
+            let level = self.levels.len() - 1;
+            let children = &self.levels[level];
+            let i = self.next_level.len() * 2;
+            // The last node of a level with an odd len is hashed with the
+            // padding, or promoted as it is if there is none
+            let parent = match (children.get(i + 1), self.repeated.get(level)) {
+                (Some(right), _) => combine_with(&self.hasher, self.ordering, children[i], *right),
+                (None, Some(repeated)) => combine_with(&self.hasher, self.ordering, children[i], *repeated),
+                (None, None) => children[i],
+            };
+            self.next_level.push(parent);
+            spent += 1;
+
+            // Once every pair of children was hashed, the level is complete
+            // and becomes the one we read from.
+            if self.next_level.len() == children.len().div_ceil(2) {
+                self.levels.push(mem::take(&mut self.next_level));
+            }
+        }
+        self.hashed += spent;
+
+        if self.is_complete() {
+            RebuildProgress::Complete
+        } else {
+            RebuildProgress::InProgress { hashed: self.hashed, remaining: self.total - self.hashed }
+        }
+    }
+
+    /// Checks if the task reached the root level
+    fn is_complete(&self) -> bool {
+        self.levels.last().is_none_or(|level| level.len() <= 1)
+    }
+}
+
+impl<H: BuildHasher + Clone> MerkleTree<H> {
+    /// Starts a rebuild of every level above the base level.
+    ///
+    /// The returned task has to be driven with [`RebuildTask::run_for`]
+    /// until it is complete and then applied with [`MerkleTree::finish_rebuild`].
+    /// While the task is running the tree is not borrowed, so reads keep
+    /// being served from the old state.
+    ///
+    /// ### Returns
+    ///
+    /// A RebuildTask that owns a copy of the base level
+    pub fn start_rebuild(&self) -> RebuildTask<H> {
+        // An empty tree has no base level either, so the task starts
+        // complete and finishing it keeps the tree empty
+        let mut levels = TreeStructure::new();
+        if let Some(base_level) = self.arr.first() {
+            levels.push(base_level);
+        }
+        // Each level has half the nodes of the one below it, rounding up,
+        // since the padding is not stored
+        let mut total = 0;
+        let mut level_len = levels.first().map_or(0, <[u64]>::len);
+        while level_len > 1 {
+            level_len = level_len.div_ceil(2);
+            total += level_len;
+        }
+        RebuildTask {
+            levels,
+            next_level: Vec::new(),
+            hashed: 0,
+            total,
+            hasher: self.hasher.clone(),
+            ordering: self.ordering,
+            repeated: self.repeated.clone(),
+        }
+    }
+
+    /// Replaces the levels of the tree with the ones built by a finished task.
+    ///
+    /// ### Arguments
+    ///
+    /// - `task`: A RebuildTask started from this tree that reported `Complete`.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is an Error if the task is still running, or if the base
+    /// level of the tree changed after the task was started. In both cases
+    /// the tree is left intact.
+    pub fn finish_rebuild(&mut self, task: RebuildTask<H>) -> Result<(), MerkleError> {
+        if !task.is_complete() {
+            return Err(MerkleError::RebuildInProgress);
+        }
+        if self.arr.first() != task.levels.first() {
+            return Err(MerkleError::StaleRebuild);
+        }
+        self.arr = task.levels;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::create_remaining_levels;
+
+    fn big_tree() -> MerkleTree {
+        let data: Vec<usize> = (0..1 << 16).collect();
+        MerkleTree::new(data)
+    }
+
+    #[test]
+    /// Test if rebuilding a 2^16 leaves tree in small slices gives the same
+    /// levels as building it in one shot.
+    fn rebuild_in_slices_matches_one_shot() {
+        let mut merkle = big_tree();
+        let one_shot = create_remaining_levels(merkle.arr[0].to_vec());
+
+        let mut task = merkle.start_rebuild();
+        let mut calls = 0;
+        while task.run_for(1000) != RebuildProgress::Complete {
+            calls += 1;
+        }
+        merkle.finish_rebuild(task).unwrap();
+
+        // 2^16 - 1 parent nodes hashed 1000 at a time
+        assert_eq!(calls, 65);
+        assert_eq!(merkle.arr, one_shot);
+    }
+
+    #[test]
+    /// Test if the task never hashes more nodes than the budget it receives
+    fn run_for_respects_budget() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]);
+        let mut task = merkle.start_rebuild();
+
+        assert_eq!(task.run_for(2), RebuildProgress::InProgress { hashed: 2, remaining: 1 });
+        assert_eq!(task.run_for(0), RebuildProgress::InProgress { hashed: 2, remaining: 1 });
+        assert_eq!(task.run_for(5), RebuildProgress::Complete);
+        assert_eq!(task.run_for(5), RebuildProgress::Complete);
+    }
+
+    #[test]
+    /// Test if abandoning a task mid-way or trying to apply it before it
+    /// finishes leaves the old tree intact.
+    fn abandoned_rebuild_leaves_tree_intact() {
+        let mut merkle = big_tree();
+        let old_arr = merkle.arr.clone();
+
+        let mut task = merkle.start_rebuild();
+        task.run_for(500);
+        // Reads keep working on the old state while the task is alive
+        let proof = merkle.generate_proof(7).unwrap();
+        assert!(merkle.verify(&proof, merkle.arr[0][7]));
+
+        assert_eq!(merkle.finish_rebuild(task), Err(MerkleError::RebuildInProgress));
+        assert_eq!(merkle.arr, old_arr);
+
+        let mut task = merkle.start_rebuild();
+        task.run_for(500);
+        drop(task);
+        assert_eq!(merkle.arr, old_arr);
+    }
+
+    #[test]
+    /// Test if a task started before a mutation can not overwrite it
+    fn stale_rebuild_is_rejected() {
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+        let mut task = merkle.start_rebuild();
+        while task.run_for(1) != RebuildProgress::Complete {}
+
+        merkle.add_element("Tree");
+        let arr_after_add = merkle.arr.clone();
+
+        assert_eq!(merkle.finish_rebuild(task), Err(MerkleError::StaleRebuild));
+        assert_eq!(merkle.arr, arr_after_add);
+    }
+
+    #[test]
+    /// Test if a tree that promotes odd nodes is rebuilt with the same levels,
+    /// counting each promoted node as one step.
+    fn rebuild_promoted_tree() {
+        let mut merkle = MerkleTree::new_rfc6962((0..7).collect());
+        let old_arr = merkle.arr.clone();
+        let mut task = merkle.start_rebuild();
+
+        // Levels of 4, 2 and 1 nodes above the base level
+        assert_eq!(task.run_for(3), RebuildProgress::InProgress { hashed: 3, remaining: 4 });
+        while task.run_for(1) != RebuildProgress::Complete {}
+        merkle.finish_rebuild(task).unwrap();
+        assert_eq!(merkle.arr, old_arr);
+    }
+
+    #[test]
+    /// Test if rebuilding a tree with only one element is immediately complete
+    fn rebuild_single_element_tree() {
+        let mut merkle = MerkleTree::new(vec!["Crypto"]);
+        let mut task = merkle.start_rebuild();
+
+        assert_eq!(task.run_for(0), RebuildProgress::Complete);
+        assert!(merkle.finish_rebuild(task).is_ok());
+        assert_eq!(merkle.arr.len(), 1);
+    }
+
+    #[test]
+    /// Test if rebuilding an empty tree is immediately complete and
+    /// leaves it empty, and a task from before it was emptied is stale
+    fn rebuild_empty_tree() {
+        let mut merkle = MerkleTree::default();
+        let mut task = merkle.start_rebuild();
+
+        assert_eq!(task.run_for(0), RebuildProgress::Complete);
+        assert_eq!(merkle.finish_rebuild(task), Ok(()));
+        assert!(merkle.is_empty());
+        assert_eq!(merkle.root(), None);
+        assert_eq!(merkle, MerkleTree::default());
+
+        let mut merkle = MerkleTree::new(vec!["Crypto"]);
+        let task = merkle.start_rebuild();
+        merkle.pop_element();
+        assert_eq!(merkle.finish_rebuild(task), Err(MerkleError::StaleRebuild));
+    }
+}
@@ -0,0 +1,113 @@
+use std::hash::BuildHasher;
+
+use super::{MerkleTree, TreeStructure};
+use crate::root_hash::to_hex;
+
+/// Hex digits of each hash shown by `render`.
+const LABEL_DIGITS: usize = 8;
+/// Columns taken by each element of the base level, the label with its
+/// markers and the space that separates it from the next one.
+const SLOT_WIDTH: usize = LABEL_DIGITS + 3;
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Draws the tree level by level, with the root on the first line and
+    /// the base level on the last one. Each hash is shown by its first 8 hex
+    /// digits. In the base level the different elements are between `[]` and
+    /// the repeated ones used as padding between `()`. Every node is centered
+    /// over the elements below it, so the columns line up for any size.
+    ///
+    /// ### Returns
+    ///
+    /// A String with one line per level, empty if the tree has no levels
+    pub fn render(&self) -> String {
+        let levels: Vec<Vec<u64>> = (0..self.height()).map(|level| self.padded_level(level)).collect();
+        render_levels(&levels.into(), self.diff_elements)
+    }
+}
+
+/// Draws the levels of a tree, see `MerkleTree::render`.
+fn render_levels(levels: &TreeStructure, diff_elements: usize) -> String {
+    let mut lines = Vec::with_capacity(levels.len());
+    for (level_index, level) in levels.iter().enumerate().rev() {
+        let base_len = levels[0].len();
+        let span = 1 << level_index;
+        let mut line = String::new();
+        for (index, hash) in level.iter().enumerate() {
+            let label = &to_hex(*hash)[..LABEL_DIGITS];
+            let cell = match level_index {
+                0 if index < diff_elements => format!("[{label}]"),
+                0 => format!("({label})"),
+                _ => format!(" {label} "),
+            };
+            // The node covers the elements below it, the last one can cover fewer
+            let first = index * span;
+            let covered = span.min(base_len - first);
+            let width = covered * SLOT_WIDTH - 1;
+            let column = first * SLOT_WIDTH + (width - cell.len()) / 2;
+            line.extend(std::iter::repeat_n(' ', column - line.len()));
+            line.push_str(&cell);
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Test the exact drawing of a tree with a single element
+    fn render_single_leaf() {
+        assert_eq!(render_levels(&vec![vec![0x1234_5678_9abc_def0]].into(), 1), "[12345678]");
+        assert_eq!(MerkleTree::new(Vec::<u8>::new()).render(), "");
+    }
+
+    #[test]
+    /// Test the exact drawing of a tree of 5 elements padded up to 8
+    fn render_padded_tree() {
+        let levels = vec![
+            vec![0x1000 << 48, 0x2000 << 48, 0x3000 << 48, 0x4000 << 48, 0x5000 << 48, 0x5000 << 48, 0x5000 << 48, 0x5000 << 48],
+            vec![0x1200 << 48, 0x3400 << 48, 0x5500 << 48, 0x5500 << 48],
+            vec![0x1234 << 48, 0x5555 << 48],
+            vec![0xabcd << 48],
+        ];
+        let expected = [
+            "                                       abcd0000",
+            "                 12340000                                    55550000",
+            "      12000000              34000000              55000000              55000000",
+            "[10000000] [20000000] [30000000] [40000000] [50000000] (50000000) (50000000) (50000000)",
+        ];
+        assert_eq!(render_levels(&levels.into(), 5), expected.join("\n"));
+    }
+
+    #[test]
+    /// Test the exact drawing of a tree that promotes odd nodes, where the
+    /// last nodes cover fewer elements.
+    fn render_promoted_tree() {
+        let levels = vec![vec![0x1000 << 48, 0x2000 << 48, 0x3000 << 48], vec![0x1200 << 48, 0x3000 << 48], vec![0x1230 << 48]];
+        let expected = ["            12300000", "      12000000         30000000", "[10000000] [20000000] [30000000]"];
+        assert_eq!(render_levels(&levels.into(), 3), expected.join("\n"));
+    }
+
+    #[test]
+    /// Test if a tree that grew with add_element is drawn like the same tree
+    /// created at once, with every parent centered between its children.
+    fn render_grown_tree() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let mut grown = MerkleTree::new(data[..2].to_vec());
+        for element in &data[2..] {
+            grown.add_element(*element);
+        }
+        let rendered = grown.render();
+        assert_eq!(rendered, MerkleTree::new(data).render());
+
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 4);
+        let base = lines[3];
+        assert_eq!(base.matches('[').count(), 5);
+        assert_eq!(base.matches('(').count(), 3);
+        // The root is centered over the whole base level
+        assert_eq!(lines[0].len() - LABEL_DIGITS - 1, (base.len() - LABEL_DIGITS - 2) / 2);
+    }
+}
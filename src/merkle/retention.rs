@@ -0,0 +1,337 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use super::{DefaultBuildHasher, MerkleError, MerkleTree, Proof};
+
+/// A rule that keeps the versions it returns true for, from their version
+/// and root. It is RefUnwindSafe so the tree that holds it still is.
+type KeepIf = Arc<dyn Fn(usize, u64) -> bool + Send + Sync + RefUnwindSafe>;
+
+// Holding a Retention must not take away any auto trait of the tree
+const _: () = {
+    fn assert_auto_traits<T: Send + Sync + UnwindSafe + RefUnwindSafe>() {}
+    let _ = assert_auto_traits::<MerkleTree>;
+    let _ = assert_auto_traits::<MerkleTreeSnapshot>;
+};
+
+/// The versions of a tree with history that `gc` keeps. A version is kept
+/// if any of the rules keeps it. By default every version is kept.
+///
+/// ```
+/// use tree::merkle::Retention;
+/// use tree::MerkleTree;
+///
+/// let mut merkle = MerkleTree::with_history(vec!["Crypto", "Merkle"]);
+/// merkle.set_retention(Retention::new().keep_last(1).pin(0));
+/// for element in ["Rust", "Tree", "Test"] {
+///     merkle.add_element(element);
+/// }
+///
+/// let report = merkle.gc();
+/// assert_eq!(report.roots, 1);
+/// assert!(merkle.root_at(0).is_some() && merkle.root_at(2).is_some());
+/// assert_eq!(merkle.root_at(1), None);
+/// ```
+/// - `keep_last`: Quantity of versions before the current one that are kept.
+/// - `keep_if`: The rule of the caller, if there is one.
+/// - `pinned`: The versions that are always kept.
+#[derive(Clone)]
+pub struct Retention {
+    keep_last: usize,
+    keep_if: Option<KeepIf>,
+    pinned: BTreeSet<usize>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self { keep_last: usize::MAX, keep_if: None, pinned: BTreeSet::new() }
+    }
+}
+
+impl fmt::Debug for Retention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retention")
+            .field("keep_last", &self.keep_last)
+            .field("keep_if", &self.keep_if.is_some())
+            .field("pinned", &self.pinned)
+            .finish()
+    }
+}
+
+impl Retention {
+    /// Creates a Retention that keeps every version
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps only the last versions before the current one, besides the
+    /// ones the other rules keep
+    ///
+    /// ### Arguments
+    ///
+    /// - `versions`: Quantity of versions kept. With 0 only the current one is.
+    pub fn keep_last(mut self, versions: usize) -> Self {
+        self.keep_last = versions;
+        self
+    }
+
+    /// Also keeps the versions a rule of the caller returns true for, like
+    /// the ones whose root was published
+    ///
+    /// ### Arguments
+    ///
+    /// - `keep_if`: Gets the version and its root.
+    pub fn keep_if(mut self, keep_if: impl Fn(usize, u64) -> bool + Send + Sync + RefUnwindSafe + 'static) -> Self {
+        self.keep_if = Some(Arc::new(keep_if));
+        self
+    }
+
+    /// Always keeps a version, whatever the other rules say
+    pub fn pin(mut self, version: usize) -> Self {
+        self.pinned.insert(version);
+        self
+    }
+
+    /// Checks if a version is kept when the tree is at `current`
+    fn keeps(&self, version: usize, root: u64, current: usize) -> bool {
+        self.pinned.contains(&version)
+            || current - version <= self.keep_last
+            || self.keep_if.as_ref().is_some_and(|keep_if| keep_if(version, root))
+    }
+}
+
+/// What a call to `gc` reclaimed.
+/// - `roots`: Quantity of roots dropped from the history.
+/// - `nodes`: Quantity of hashes freed from the snapshots the tree shared.
+///   The ones of a snapshot that is still held are freed when it is dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub roots: usize,
+    pub nodes: usize,
+}
+
+/// A snapshot of every node of a version of a tree, see
+/// `MerkleTree::share_snapshot`. Proofs of that version can be generated and
+/// verified with it no matter how the tree changes afterwards.
+///
+/// The nodes are shared with the history of the tree, so cloning the
+/// snapshot or taking it again at the same version copies no hash, and `gc`
+/// only drops the reference of the tree: a snapshot that is held is never
+/// invalidated.
+/// - `version`: The version of the tree the snapshot was taken at.
+/// - `tree`: The tree at that version, without history.
+pub struct MerkleTreeSnapshot<H = DefaultBuildHasher> {
+    version: usize,
+    tree: Arc<MerkleTree<H>>,
+}
+
+impl<H> Clone for MerkleTreeSnapshot<H> {
+    fn clone(&self) -> Self {
+        Self { version: self.version, tree: Arc::clone(&self.tree) }
+    }
+}
+
+impl<H: BuildHasher> fmt::Debug for MerkleTreeSnapshot<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MerkleTreeSnapshot").field("version", &self.version).field("tree", &self.tree).finish()
+    }
+}
+
+impl<H: BuildHasher> MerkleTreeSnapshot<H> {
+    /// Gets the version of the tree the snapshot was taken at
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Gets the root of the tree at that version
+    pub fn root(&self) -> Option<u64> {
+        self.tree.root()
+    }
+
+    /// Gets the quantity of different elements of the tree at that version
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Checks if the tree had no elements at that version
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Generates the proof of an element at that version, see `MerkleTree::generate_proof`
+    pub fn generate_proof(&self, index: usize) -> Result<Proof, MerkleError> {
+        self.tree.generate_proof(index)
+    }
+
+    /// Checks a proof against the root of that version, see `MerkleTree::verify`
+    pub fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+        self.tree.verify(proof, leaf)
+    }
+}
+
+impl<H: BuildHasher + Clone> MerkleTree<H> {
+    /// Takes a snapshot of every node of the current version. A tree with
+    /// history keeps it too, sharing its nodes, until `gc` drops the version.
+    ///
+    /// ### Returns
+    ///
+    /// The MerkleTreeSnapshot of the current version
+    pub fn share_snapshot(&mut self) -> MerkleTreeSnapshot<H> {
+        let version = self.version();
+        if let Some(history) = &self.history
+            && let Some(last) = history.snapshots.last()
+            && last.version == version
+        {
+            return last.clone();
+        }
+
+        // The history is taken out so it is not copied into the snapshot
+        let mut history = self.history.take();
+        let snapshot = MerkleTreeSnapshot { version, tree: Arc::new(self.clone()) };
+        if let Some(history) = &mut history {
+            history.snapshots.push(snapshot.clone());
+        }
+        self.history = history;
+        snapshot
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Sets the versions `gc` keeps. Trees without history keep no
+    /// versions, so it does nothing for them.
+    pub fn set_retention(&mut self, retention: Retention) {
+        if let Some(history) = &mut self.history {
+            history.retention = retention;
+        }
+    }
+
+    /// Gets the snapshot the tree shares for a version, if `share_snapshot`
+    /// was called at it and `gc` did not drop it
+    pub fn shared_snapshot(&self, version: usize) -> Option<MerkleTreeSnapshot<H>> {
+        let snapshots = &self.history.as_ref()?.snapshots;
+        let position = snapshots.binary_search_by_key(&version, |snapshot| snapshot.version).ok()?;
+        Some(snapshots[position].clone())
+    }
+
+    /// Drops the roots and the shared snapshots of the versions the
+    /// Retention of the tree does not keep. The current version is always
+    /// kept, and the nodes of a snapshot are only freed if nobody else
+    /// holds it.
+    ///
+    /// ### Returns
+    ///
+    /// A GcReport with what was reclaimed. It is empty for trees without history
+    pub fn gc(&mut self) -> GcReport {
+        let mut report = GcReport::default();
+        let Some(history) = &mut self.history else {
+            return report;
+        };
+        let (retention, current) = (&history.retention, history.recorded);
+
+        let before = history.roots.len();
+        let kept = history.versions.iter().copied().zip(history.roots.iter().copied());
+        let (versions, roots): (Vec<usize>, Vec<u64>) = kept.filter(|(version, root)| retention.keeps(*version, *root, current)).unzip();
+        report.roots = before - roots.len();
+        (history.versions, history.roots) = (versions, roots);
+
+        history.snapshots.retain(|snapshot| {
+            let root = snapshot.root().unwrap_or_default();
+            if snapshot.version == current || retention.keeps(snapshot.version, root, current) {
+                return true;
+            }
+            if Arc::strong_count(&snapshot.tree) == 1 {
+                report.nodes += snapshot.tree.node_count();
+            }
+            false
+        });
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    /// Builds a tree with history that went through 5 versions
+    fn five_versions() -> MerkleTree {
+        let mut merkle = MerkleTree::with_history(vec!["Crypto", "Merkle"]);
+        for element in ["Rust", "Tree", "Test", "Hash"] {
+            merkle.add_element(element);
+        }
+        merkle
+    }
+
+    #[test]
+    /// Test if gc keeps the last versions, the pinned ones and the ones of
+    /// the rule of the caller, keeping the numbers of the versions
+    fn gc_keeps_what_the_retention_says() {
+        let mut merkle = five_versions();
+        let roots: Vec<Option<u64>> = (0..=4).map(|version| merkle.root_at(version)).collect();
+        let published = roots[1].unwrap();
+        merkle.set_retention(Retention::new().keep_last(1).pin(0).keep_if(move |_, root| root == published));
+
+        assert_eq!(merkle.gc(), GcReport { roots: 1, nodes: 0 });
+        assert_eq!(merkle.root_history(), &[roots[0].unwrap(), roots[1].unwrap(), roots[3].unwrap()]);
+        for version in [0, 1, 3, 4] {
+            assert_eq!(merkle.root_at(version), roots[version]);
+        }
+        assert_eq!(merkle.root_at(2), None);
+        assert_eq!(merkle.version(), 4);
+
+        // Nothing else is dropped until the tree changes
+        assert_eq!(merkle.gc(), GcReport::default());
+        merkle.set_retention(Retention::new().keep_last(0));
+        assert_eq!(merkle.gc(), GcReport { roots: 3, nodes: 0 });
+        assert_eq!(merkle.root_at(4), merkle.root());
+    }
+
+    #[test]
+    /// Test if a snapshot that is held survives a gc that drops its version,
+    /// its proofs still verify, and its nodes are only reported once nobody
+    /// holds them.
+    fn held_snapshot_survives_gc() {
+        let elements = ["Crypto", "Merkle", "Rust"];
+        let mut merkle = MerkleTree::with_history(elements.to_vec());
+        let held = merkle.share_snapshot();
+        let proofs: Vec<Proof> = (0..3).map(|index| merkle.generate_proof(index).unwrap()).collect();
+        assert!(Arc::ptr_eq(&merkle.shared_snapshot(0).unwrap().tree, &held.tree));
+
+        merkle.set_retention(Retention::new().keep_last(0));
+        merkle.add_elements(vec!["Tree", "Test"]);
+        merkle.update_element(0, "Other").unwrap();
+        let dropped = merkle.share_snapshot();
+        merkle.add_element("Hash");
+
+        let nodes = dropped.tree.node_count();
+        drop(dropped);
+        assert_eq!(merkle.gc(), GcReport { roots: 3, nodes });
+        assert_eq!(merkle.root_at(0), None);
+        assert!(merkle.shared_snapshot(0).is_none() && merkle.shared_snapshot(2).is_none());
+
+        assert_eq!(held.version(), 0);
+        assert_eq!(held.root(), MerkleTree::new(elements.to_vec()).root());
+        for (index, (proof, element)) in proofs.iter().zip(elements).enumerate() {
+            assert!(!merkle.verify(proof, hash_element(element)));
+            assert!(held.verify(proof, hash_element(element)));
+            assert_eq!(held.generate_proof(index).as_ref(), Ok(proof));
+        }
+    }
+
+    #[test]
+    /// Test if trees without history hand out snapshots but keep nothing to reclaim
+    fn without_history_nothing_is_kept() {
+        let mut merkle = MerkleTree::new(vec!["Crypto", "Merkle"]);
+        let snapshot = merkle.share_snapshot();
+        merkle.add_element("Rust");
+        merkle.set_retention(Retention::new().keep_last(0));
+
+        assert_eq!(merkle.gc(), GcReport::default());
+        assert!(merkle.shared_snapshot(0).is_none());
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.verify(&snapshot.generate_proof(1).unwrap(), hash_element("Merkle")));
+    }
+}
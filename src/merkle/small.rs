@@ -0,0 +1,173 @@
+use std::hash::{BuildHasher, Hash};
+
+use super::{combine_with, hash_with, padded_len_of, MerkleTree, PairOrdering, TreeStructure};
+use crate::error::MerkleError;
+
+/// Largest quantity of elements that is built with `create_small_levels`.
+pub(super) const SMALL_TREE_MAX_LEAVES: usize = 8;
+
+/// Creates every level of a tree with at most `SMALL_TREE_MAX_LEAVES` elements.
+///
+/// It gives the same levels as `create_first_level` followed by
+/// `create_remaining_levels`, but each level is computed in an array
+/// on the stack and copied once into the room reserved for it. This way
+/// we skip allocating a vector for every level.
+///
+/// ### Arguments
+///
+/// - `hasher`: Builds the hasher used for every hash of the tree.
+/// - `ordering`: How the children of each node are ordered before hashing them.
+/// - `elements`: A slice with between 1 and `SMALL_TREE_MAX_LEAVES` elements.
+///
+/// ### Returns
+///
+/// The levels of the tree, each one with the exact room it needs,
+/// starting from the first to the last (the root).
+pub(super) fn create_small_levels<H: BuildHasher, T: Hash>(hasher: &H, ordering: PairOrdering, elements: &[T]) -> TreeStructure {
+    let len = elements.len();
+    let mut level = [0u64; SMALL_TREE_MAX_LEAVES];
+    for (node, element) in level.iter_mut().zip(elements) {
+        *node = hash_with(hasher, element);
+    }
+
+    let height = len.next_power_of_two().trailing_zeros() as usize + 1;
+    let mut tree_structure = TreeStructure::with_capacity((0..height).map(|i| len.div_ceil(1 << i)));
+    tree_structure.push(&level[..len]);
+    // Just like create_remaining_levels, the padding is the last hash repeated
+    let mut repeated = level[len - 1];
+    let mut level_len = len;
+    while level_len > 1 {
+        // Each parent is written over its left child, which was already read
+        for i in 0..level_len.div_ceil(2) {
+            let right = if 2 * i + 1 < level_len { level[2 * i + 1] } else { repeated };
+            level[i] = combine_with(hasher, ordering, level[2 * i], right);
+        }
+        repeated = combine_with(hasher, ordering, repeated, repeated);
+        level_len = level_len.div_ceil(2);
+        tree_structure.push(&level[..level_len]);
+    }
+    tree_structure
+}
+
+impl<H> MerkleTree<H> {
+    /// Checks if an index can be proven by `generate_proof`
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is InvalidIndex if the index is outside of the base
+    /// level, or PaddedIndex if it is a copy added as padding
+    pub(super) fn check_element_index(&self, index: usize) -> Result<(), MerkleError> {
+        if index >= padded_len_of(self.padding, self.diff_elements) {
+            Err(MerkleError::InvalidIndex { index, len: self.diff_elements })
+        } else if index >= self.diff_elements {
+            Err(MerkleError::PaddedIndex { index })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Writes the proof for a specific element into a buffer provided by
+    /// the caller, so no allocation happens. The hashes are the same that
+    /// `generate_proof` returns, in the same order.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hash_index`: The index of the element in the original input array
+    /// - `buffer`: The slice the hashes of the proof will be written into.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the quantity of hashes written at the start of the
+    /// buffer, or an Error if the index is invalid or the buffer is too small
+    pub fn generate_proof_into(&self, mut hash_index: usize, buffer: &mut [u64]) -> Result<usize, MerkleError> {
+        self.check_element_index(hash_index)?;
+        // Levels where the node is promoted have no sibling to write
+        let needed = self.proof_len(hash_index);
+        if buffer.len() < needed {
+            return Err(MerkleError::BufferTooSmall { needed, got: buffer.len() });
+        }
+        let siblings = (0..self.height() - 1).filter_map(|level| {
+            let sibling = self.get_node(level, hash_index ^ 1);
+            hash_index /= 2;
+            sibling
+        });
+        for (proof_hash, sibling) in buffer.iter_mut().zip(siblings) {
+            *proof_hash = sibling;
+        }
+        Ok(needed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{create_first_level, create_remaining_levels, DefaultBuildHasher};
+    use std::time::Instant;
+
+    #[test]
+    /// Test if the small tree levels are equal to the ones of the general
+    /// path for every len around the threshold.
+    fn small_levels_match_general_path() {
+        for len in 1..=SMALL_TREE_MAX_LEAVES + 4 {
+            let data: Vec<String> = (0..len).map(|i| format!("Element {i}")).collect();
+            let general = create_remaining_levels(create_first_level(&DefaultBuildHasher::default(), data.clone()));
+            let merkle = MerkleTree::new(data.clone());
+
+            assert_eq!(merkle.arr, general);
+            if len <= SMALL_TREE_MAX_LEAVES {
+                assert_eq!(create_small_levels(&DefaultBuildHasher::default(), PairOrdering::Positional, &data), general);
+            }
+        }
+    }
+
+    #[test]
+    /// Test if the proof written into a buffer is the same one generate_proof returns
+    fn proof_into_buffer_matches_generate_proof() {
+        let mut buffer = [0u64; 8];
+        for len in 1..=SMALL_TREE_MAX_LEAVES + 4 {
+            let data: Vec<usize> = (0..len).collect();
+            let merkle = MerkleTree::new(data);
+            for index in 0..len {
+                let written = merkle.generate_proof_into(index, &mut buffer).unwrap();
+                assert_eq!(buffer[..written], merkle.generate_proof(index).unwrap().hashes());
+            }
+        }
+    }
+
+    #[test]
+    /// Test if an invalid index or a small buffer make the proof fail
+    fn proof_into_buffer_errors() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+        let mut buffer = [0u64; 1];
+
+        assert_eq!(merkle.generate_proof_into(3, &mut buffer), Err(MerkleError::PaddedIndex { index: 3 }));
+        assert_eq!(merkle.generate_proof_into(0, &mut buffer), Err(MerkleError::BufferTooSmall { needed: 2, got: 1 }));
+    }
+
+    #[test]
+    #[ignore]
+    /// Benchmark the creation of 1M trees of 4 elements with the small tree
+    /// path against the general one.
+    ///
+    /// Run it with `cargo test --release -- --ignored small_tree_benchmark`
+    fn small_tree_benchmark() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree"];
+        let trees = 1_000_000;
+
+        let start = Instant::now();
+        for _ in 0..trees {
+            std::hint::black_box(create_remaining_levels(create_first_level(&DefaultBuildHasher::default(), std::hint::black_box(data.clone()))));
+        }
+        let general = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..trees {
+            std::hint::black_box(MerkleTree::new(std::hint::black_box(data.clone())));
+        }
+        let small = start.elapsed();
+
+        assert!(small < general, "small tree path took {small:?}, general path {general:?}");
+    }
+}
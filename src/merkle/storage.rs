@@ -0,0 +1,399 @@
+use std::fs;
+use std::path::Path;
+
+use super::proof_pack::{array_field, missing_field, usize_field};
+use super::{create_remaining_levels_with, padded_len_of, DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering, TreeStructure};
+use crate::encoding::ByteReader;
+use crate::error::MerkleError;
+use crate::json::{self, Json};
+use crate::limits::Limits;
+use crate::root_hash::{parse_hex, to_hex};
+
+/// Bytes every saved tree starts with.
+const MAGIC: &[u8; 4] = b"MKTR";
+const VERSION: u8 = 2;
+/// Version of the trees saved with the padding of every level.
+const PADDED_VERSION: u8 = 1;
+/// Flag set when the tree sorts the children of its nodes.
+const SORTED_PAIRS: u8 = 1;
+/// Flag set when the tree promotes odd nodes instead of repeating elements.
+const PROMOTE_ODD: u8 = 2;
+/// Flag set when the root of the tree commits to its len.
+const COMMIT_LEN: u8 = 4;
+/// Flag set when the tree pads its base level with `ZERO_LEAF`s.
+const ZERO_HASH: u8 = 8;
+
+impl MerkleTree {
+    /// Encodes the tree with a compact little-endian binary layout: the magic
+    /// bytes, the version, the flags, the quantity of different elements and
+    /// the quantity of levels, then every hash of every level starting from
+    /// the base level. Like in memory, the nodes that only cover padding are
+    /// not stored. The len of each level follows from the quantity of
+    /// elements, so it is not stored either.
+    ///
+    /// Slots that still hold a placeholder and the root history are not
+    /// stored, the decoded tree has neither.
+    ///
+    /// Decoding re-calculates the levels with `DefaultBuildHasher`, whose
+    /// hashes may change between Rust releases. To publish a root or a
+    /// proof for others, build the tree with `MerkleTree::new_portable`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let node_count = self.node_count();
+        let mut flags = 0;
+        if self.ordering == PairOrdering::Sorted {
+            flags |= SORTED_PAIRS;
+        }
+        match self.padding {
+            PaddingStrategy::RepeatLast => {}
+            PaddingStrategy::PromoteOdd => flags |= PROMOTE_ODD,
+            PaddingStrategy::ZeroHash => flags |= ZERO_HASH,
+        }
+        if self.commit_len {
+            flags |= COMMIT_LEN;
+        }
+        let mut bytes = Vec::with_capacity(18 + 8 * node_count);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(flags);
+        bytes.extend_from_slice(&(self.diff_elements as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.arr.len() as u32).to_le_bytes());
+        for hash in self.arr.iter().flatten() {
+            bytes.extend_from_slice(&hash.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a tree encoded with `to_bytes`, using the default `Limits`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        Self::from_bytes_with_limits(bytes, &Limits::default())
+    }
+
+    /// Decodes a tree encoded with `to_bytes`. Every level above the base
+    /// level is re-calculated and compared with the stored one, so a single
+    /// flipped bit in any hash is detected. Trees saved by the first version,
+    /// which stored the padding too, are also accepted.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if the bytes are not a valid
+    /// tree, exceed the limits or their levels do not match their base level
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(bytes.len())?;
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(MerkleError::InvalidEncoding("Not a saved tree".to_string()));
+        }
+        let version = reader.u8()?;
+        if version != VERSION && version != PADDED_VERSION {
+            return Err(MerkleError::InvalidEncoding(format!("Unsupported version {version}")));
+        }
+        let flags = reader.u8()?;
+        let ordering = if flags & SORTED_PAIRS != 0 { PairOrdering::Sorted } else { PairOrdering::Positional };
+        let padding = match (flags & PROMOTE_ODD != 0, flags & ZERO_HASH != 0) {
+            (false, false) => PaddingStrategy::RepeatLast,
+            (true, false) => PaddingStrategy::PromoteOdd,
+            (false, true) => PaddingStrategy::ZeroHash,
+            (true, true) => return Err(MerkleError::InvalidEncoding("A tree can not both promote odd nodes and pad with zeros".to_string())),
+        };
+        let commit_len = flags & COMMIT_LEN != 0;
+        let diff_elements = reader.usize()?;
+        limits.check_leaves(diff_elements)?;
+        let height = reader.u32()? as usize;
+        limits.check_height(height)?;
+
+        let level_lens = level_lens(padding, diff_elements, version == PADDED_VERSION);
+        if level_lens.len() != height {
+            return Err(MerkleError::InvalidEncoding(format!("Expected {} levels but got {height}", level_lens.len())));
+        }
+        let mut arr = TreeStructure::with_capacity(level_lens.clone());
+        for level_len in level_lens {
+            reader.ensure_items(level_len, 8)?;
+            arr.push((0..level_len).map(|_| reader.u64()).collect::<Result<Vec<u64>, MerkleError>>()?);
+        }
+        reader.finish()?;
+        Self::from_checked_levels(arr, diff_elements, ordering, padding, commit_len)
+    }
+
+    /// Encodes the tree as JSON, with every level as an array of hashes
+    /// starting from the base level. Hashes are written as 16 hex digits so
+    /// they do not lose precision in JSON numbers. Like `to_bytes`, the
+    /// placeholders and the root history are not stored.
+    pub fn to_json(&self) -> String {
+        let levels = self
+            .arr
+            .iter()
+            .map(|level| Json::Array(level.iter().map(|hash| Json::from(to_hex(*hash))).collect()))
+            .collect();
+        let ordering = match self.ordering {
+            PairOrdering::Positional => "positional",
+            PairOrdering::Sorted => "sorted",
+        };
+        let padding = match self.padding {
+            PaddingStrategy::RepeatLast => "repeat_last",
+            PaddingStrategy::PromoteOdd => "promote_odd",
+            PaddingStrategy::ZeroHash => "zero_hash",
+        };
+        let document = Json::Object(vec![
+            ("diff_elements".to_string(), Json::from(self.diff_elements)),
+            ("ordering".to_string(), Json::from(ordering.to_string())),
+            ("padding".to_string(), Json::from(padding.to_string())),
+            ("commit_len".to_string(), Json::Bool(self.commit_len)),
+            ("levels".to_string(), Json::Array(levels)),
+        ]);
+        document.to_string()
+    }
+
+    /// Decodes a tree encoded with `to_json`, using the default `Limits`
+    pub fn from_json(input: &str) -> Result<Self, MerkleError> {
+        Self::from_json_with_limits(input, &Limits::default())
+    }
+
+    /// Decodes a tree encoded with `to_json`. The len of every level is
+    /// checked, and the levels above the base level are re-calculated and
+    /// compared with the stored ones, just like `from_bytes` does.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if the text is not a valid
+    /// tree, exceeds the limits or its levels do not match its base level
+    pub fn from_json_with_limits(input: &str, limits: &Limits) -> Result<Self, MerkleError> {
+        limits.check_serialized_size(input.len())?;
+        let document = json::parse(input).map_err(MerkleError::InvalidEncoding)?;
+        let diff_elements = usize_field(&document, "diff_elements")?;
+        limits.check_leaves(diff_elements)?;
+        let ordering = match document.get("ordering").and_then(Json::as_str) {
+            Some("positional") => PairOrdering::Positional,
+            Some("sorted") => PairOrdering::Sorted,
+            _ => return Err(missing_field("ordering")),
+        };
+        let padding = match document.get("padding").and_then(Json::as_str) {
+            Some("repeat_last") => PaddingStrategy::RepeatLast,
+            Some("promote_odd") => PaddingStrategy::PromoteOdd,
+            Some("zero_hash") => PaddingStrategy::ZeroHash,
+            _ => return Err(missing_field("padding")),
+        };
+        // Documents saved before the len could be committed do not have it
+        let commit_len = matches!(document.get("commit_len"), Some(Json::Bool(true)));
+        let levels = array_field(&document, "levels")?;
+        limits.check_height(levels.len())?;
+        // Documents saved before the padding was no longer stored have it
+        let padded = levels.first().and_then(Json::as_array).is_some_and(|base_level| base_level.len() != diff_elements);
+        let level_lens = level_lens(padding, diff_elements, padded);
+        if levels.len() != level_lens.len() {
+            return Err(MerkleError::InvalidEncoding(format!("Expected {} levels but got {}", level_lens.len(), levels.len())));
+        }
+        let arr = levels
+            .iter()
+            .zip(level_lens)
+            .map(|(level, level_len)| {
+                let hashes = level.as_array().ok_or_else(|| missing_field("levels"))?;
+                if hashes.len() != level_len {
+                    return Err(MerkleError::InvalidEncoding(format!("Expected a level of {level_len} hashes but got {}", hashes.len())));
+                }
+                hashes.iter().map(|hash| parse_hex(hash.as_str().ok_or_else(|| missing_field("levels"))?)).collect()
+            })
+            .collect::<Result<Vec<Vec<u64>>, MerkleError>>()?;
+        Self::from_checked_levels(arr.into(), diff_elements, ordering, padding, commit_len)
+    }
+
+    /// Creates a tree from decoded levels that have the right lens, after
+    /// checking that every level above the base level matches it. The
+    /// levels may have the padding too, and it has to match as well.
+    fn from_checked_levels(arr: TreeStructure, diff_elements: usize, ordering: PairOrdering, padding: PaddingStrategy, commit_len: bool) -> Result<Self, MerkleError> {
+        let hasher = DefaultBuildHasher::default();
+        let levels = match arr.first() {
+            Some(base_level) => create_remaining_levels_with(&hasher, ordering, padding, base_level[..diff_elements].to_vec()),
+            None => TreeStructure::new(),
+        };
+        let mut merkle = Self::from_levels(levels, diff_elements, hasher, ordering, padding);
+        merkle.commit_len = commit_len;
+        let matches = arr.iter().enumerate().all(|(level, hashes)| {
+            hashes.iter().enumerate().all(|(index, hash)| merkle.get_node(level, index) == Some(*hash))
+        });
+        if !matches {
+            return Err(MerkleError::CorruptFile);
+        }
+        Ok(merkle)
+    }
+
+    /// Saves the tree to a file, encoded with `to_bytes`
+    ///
+    /// ### Arguments
+    ///
+    /// - `path`: The path of the file. It is created, or replaced if it exists.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Loads a tree saved with `save`, see `from_bytes`
+    ///
+    /// ### Arguments
+    ///
+    /// - `path`: The path of the file.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleTree, or an Error if the file can not be read,
+    /// it is not a valid tree or its hashes were corrupted
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MerkleError> {
+        let bytes = fs::read(path).map_err(|error| MerkleError::Io(error.kind()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Gets the len of every level of a tree with `diff_elements` different
+/// elements, from the base level to the root. An empty tree has no levels.
+/// When `padded` is true, the levels also have the nodes that only cover
+/// padding, like the first version saved them.
+fn level_lens(padding: PaddingStrategy, diff_elements: usize, padded: bool) -> Vec<usize> {
+    let mut level_len = if padded { padded_len_of(padding, diff_elements) } else { diff_elements };
+    let mut lens = Vec::new();
+    while level_len > 0 {
+        lens.push(level_len);
+        level_len = if level_len == 1 { 0 } else { level_len.div_ceil(2) };
+    }
+    lens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+    use std::env;
+
+    #[test]
+    /// Test if a saved tree loads with the same levels and configuration,
+    /// and a proof of the loaded tree verifies.
+    fn save_and_load() {
+        let path = env::temp_dir().join(format!("merkle-save-and-load-{}.bin", std::process::id()));
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let trees = [
+            MerkleTree::new(data.clone()),
+            MerkleTree::new_sorted(data.clone()),
+            MerkleTree::new_rfc6962(data.clone()),
+            MerkleTree::with_committed_len(data.clone()),
+            MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build(data.clone()).unwrap(),
+        ];
+        for merkle in trees {
+            merkle.save(&path).unwrap();
+            let loaded = MerkleTree::load(&path).unwrap();
+
+            assert_eq!(loaded, merkle);
+            assert_eq!(loaded.root(), merkle.root());
+            assert_eq!((loaded.len(), loaded.ordering(), loaded.padding()), (5, merkle.ordering(), merkle.padding()));
+            let proof = loaded.generate_proof(3).unwrap();
+            assert!(loaded.verify(&proof, hash_element("Tree")));
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(MerkleTree::load(&path).err(), Some(MerkleError::Io(std::io::ErrorKind::NotFound)));
+    }
+
+    #[test]
+    /// Test if trees of every size survive a round trip, including the empty one
+    fn bytes_round_trip() {
+        for len in [0, 1, 2, 3, 7, 8, 13] {
+            let merkle = MerkleTree::new((0..len).collect());
+            let decoded = MerkleTree::from_bytes(&merkle.to_bytes()).unwrap();
+            assert_eq!(decoded.arr, merkle.arr);
+            assert_eq!(decoded.len(), len);
+        }
+    }
+
+    #[test]
+    /// Test if flipping any bit of any hash is detected as a corrupt file
+    fn flipped_bits_are_detected() {
+        let bytes = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]).to_bytes();
+        for byte in 18..bytes.len() {
+            for bit in [0, 3, 7] {
+                let mut corrupted = bytes.clone();
+                corrupted[byte] ^= 1 << bit;
+                assert_eq!(MerkleTree::from_bytes(&corrupted).err(), Some(MerkleError::CorruptFile));
+            }
+        }
+    }
+
+    #[test]
+    /// Test if a tree of 5 elements keeps its padding layout through a JSON
+    /// round trip, and hashes are written as hex strings.
+    fn json_round_trip() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let zero_padded = MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build(data.clone()).unwrap();
+        for merkle in [MerkleTree::new(data.clone()), MerkleTree::new_sorted(data.clone()), MerkleTree::new_rfc6962(data.clone()), zero_padded] {
+            let json = merkle.to_json();
+            let decoded = MerkleTree::from_json(&json).unwrap();
+
+            assert_eq!(decoded.arr, merkle.arr);
+            assert_eq!((decoded.len(), decoded.ordering(), decoded.padding()), (5, merkle.ordering(), merkle.padding()));
+            assert!(json.contains(&format!("\"{}\"", to_hex(merkle.root().unwrap()))));
+        }
+        let empty = MerkleTree::new(Vec::<u8>::new());
+        assert_eq!(MerkleTree::from_json(&empty.to_json()).unwrap().arr, empty.arr);
+
+        let committed = MerkleTree::with_committed_len(data);
+        let decoded = MerkleTree::from_json(&committed.to_json()).unwrap();
+        assert_eq!((decoded.committed_len(), decoded.root()), (Some(5), committed.root()));
+    }
+
+    #[test]
+    /// Test if documents with missing fields, levels of the wrong len or
+    /// changed hashes are rejected.
+    fn invalid_json_is_error() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+        let json = merkle.to_json();
+        let root = to_hex(merkle.root().unwrap());
+        let documents = [
+            json.replace("\"diff_elements\":3", "\"diff_elements\":5"),
+            json.replace("\"diff_elements\":3", "\"diff_elements\":-3"),
+            json.replace("\"sorted\"", "\"positional\"").replace("\"positional\"", "\"other\""),
+            json.replace(&format!(",[\"{root}\"]"), ""),
+            json.replace(&root, "not a hash"),
+            json[..json.len() - 1].to_string(),
+        ];
+        for document in documents {
+            assert!(MerkleTree::from_json(&document).is_err(), "{document}");
+        }
+        let changed = json.replace(&root, &to_hex(merkle.root().unwrap() ^ 1));
+        assert_eq!(MerkleTree::from_json(&changed).err(), Some(MerkleError::CorruptFile));
+    }
+
+    #[test]
+    /// Test if trees saved with their padding, like the first version did,
+    /// still load and have that padding checked too.
+    fn padded_levels_still_load() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree", "Test"]);
+        let padded: Vec<Vec<u64>> = (0..merkle.height()).map(|level| merkle.padded_level(level)).collect();
+        let mut bytes = merkle.to_bytes()[..18].to_vec();
+        bytes[4] = PADDED_VERSION;
+        for hash in padded.iter().flatten() {
+            bytes.extend_from_slice(&hash.to_le_bytes());
+        }
+        assert_eq!(MerkleTree::from_bytes(&bytes), Ok(merkle.clone()));
+        let last = bytes.len() - 9;
+        bytes[last] ^= 1;
+        assert_eq!(MerkleTree::from_bytes(&bytes).err(), Some(MerkleError::CorruptFile));
+
+        let levels = padded.iter().map(|level| Json::Array(level.iter().map(|hash| Json::from(to_hex(*hash))).collect())).collect();
+        let document = Json::Object(vec![
+            ("diff_elements".to_string(), Json::from(5usize)),
+            ("ordering".to_string(), Json::from("positional".to_string())),
+            ("padding".to_string(), Json::from("repeat_last".to_string())),
+            ("levels".to_string(), Json::Array(levels)),
+        ]);
+        assert_eq!(MerkleTree::from_json(&document.to_string()), Ok(merkle));
+    }
+
+    #[test]
+    /// Test if truncated input and invalid headers are errors instead of panics
+    fn invalid_bytes_are_errors() {
+        let bytes = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]).to_bytes();
+        for len in 0..bytes.len() {
+            assert!(matches!(MerkleTree::from_bytes(&bytes[..len]), Err(MerkleError::InvalidEncoding(_))));
+        }
+        let mut wrong_height = bytes.clone();
+        wrong_height[14] = 2;
+        assert!(matches!(MerkleTree::from_bytes(&wrong_height), Err(MerkleError::InvalidEncoding(_))));
+        let mut wrong_magic = bytes;
+        wrong_magic[0] = b'X';
+        assert!(matches!(MerkleTree::from_bytes(&wrong_magic), Err(MerkleError::InvalidEncoding(_))));
+    }
+}
@@ -0,0 +1,223 @@
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "async-stream")]
+use std::{future, pin};
+
+use super::{combine_with, hash_element, MerkleTree, PairOrdering, TreeStructure};
+#[cfg(feature = "async-stream")]
+use crate::stream::Stream;
+
+impl MerkleTree {
+    /// Creates a new MerkleTree hashing the elements of an iterator as they
+    /// arrive, so the elements never have to be collected. The tree is the
+    /// same one `new` creates.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: An iterator over the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn from_iter_hashed<T: Hash, I: IntoIterator<Item = T>>(elements: I) -> Self {
+        Self::from_hashes(elements.into_iter().map(hash_element))
+    }
+
+    /// Creates a new MerkleTree from the hashes of its elements, as
+    /// `hash_element` returns them. Every node is hashed as soon as both of
+    /// its children arrive, so besides the levels of the tree only the last
+    /// node of each level is waiting for a sibling. Once the iterator ends,
+    /// the nodes still waiting are hashed with the padding.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hashes`: An iterator over the hashes that form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn from_hashes<I: IntoIterator<Item = u64>>(hashes: I) -> Self {
+        let hashes = hashes.into_iter();
+        let mut merkle = Self::with_capacity(hashes.size_hint().0);
+        for hash in hashes {
+            push_leaf(&mut merkle.arr, &merkle.hasher, merkle.ordering, hash);
+            merkle.diff_elements += 1;
+        }
+        merkle.hash_waiting_nodes();
+        merkle
+    }
+
+    /// Hashes the nodes still waiting for a sibling once every leaf was
+    /// pushed with `push_leaf`
+    fn hash_waiting_nodes(&mut self) {
+        // Only the path of the last element has nodes without a sibling
+        if let Some(last) = self.diff_elements.checked_sub(1) {
+            self.refresh_repeated();
+            self.rehash_from(last);
+        }
+    }
+}
+
+#[cfg(feature = "async-stream")]
+impl MerkleTree {
+    /// Creates a new MerkleTree hashing the elements of a stream as they
+    /// arrive, like `from_iter_hashed` does with an iterator. The tree is
+    /// the same one `new` creates over the collected elements.
+    ///
+    /// ### Arguments
+    ///
+    /// - `stream`: A stream of the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance, once the stream ends
+    pub async fn from_stream<T: Hash, S: Stream<Item = T>>(stream: S) -> Self {
+        Self::from_stream_with_progress(stream, |_| {}).await
+    }
+
+    /// Same as `from_stream`, calling a function with the quantity of
+    /// elements hashed so far after each one of them
+    ///
+    /// ### Arguments
+    ///
+    /// - `stream`: A stream of the elements that will be hashed and form the first level in the tree.
+    /// - `on_progress`: Called with the quantity of elements hashed after each element.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance, once the stream ends
+    pub async fn from_stream_with_progress<T: Hash, S: Stream<Item = T>>(stream: S, mut on_progress: impl FnMut(usize)) -> Self {
+        let mut stream = pin::pin!(stream);
+        let mut merkle = Self::default();
+        while let Some(element) = future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            push_leaf(&mut merkle.arr, &merkle.hasher, merkle.ordering, hash_element(element));
+            merkle.diff_elements += 1;
+            on_progress(merkle.diff_elements);
+        }
+        merkle.hash_waiting_nodes();
+        merkle
+    }
+}
+
+/// Adds a hash at the end of the base level, and hashes every parent whose
+/// two children are now complete.
+///
+/// ### Arguments
+///
+/// - `levels`: The levels of the tree, where only the nodes with both children exist.
+/// - `hasher`: Builds the hasher used for the nodes.
+/// - `ordering`: How the children of each node are ordered.
+/// - `hash`: The hash of the new element.
+fn push_leaf<H: BuildHasher>(levels: &mut TreeStructure, hasher: &H, ordering: PairOrdering, hash: u64) {
+    if levels.is_empty() {
+        levels.push([]);
+    }
+    levels.push_node(0, hash);
+    let mut level = 0;
+    while levels[level].len().is_multiple_of(2) {
+        let children = &levels[level];
+        let parent = combine_with(hasher, ordering, children[children.len() - 2], children[children.len() - 1]);
+        if level + 1 == levels.len() {
+            levels.push([]);
+        }
+        levels.push_node(level + 1, parent);
+        level += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "async-stream")]
+    use std::pin::Pin;
+    #[cfg(feature = "async-stream")]
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    /// Test if the streamed tree is the same as the one created from a vector,
+    /// for random lengths that are mostly not powers of 2.
+    fn streaming_matches_batch() {
+        let mut state: u64 = 0x2545f4914f6cdd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut lens: Vec<u64> = (0..40).map(|_| next() % 3000).collect();
+        lens.extend([0, 1, 2, 3, 64, 65, 1 << 12]);
+        for len in lens {
+            let batch = MerkleTree::new((0..len).collect());
+            // A filtered iterator gives no hint of its len
+            let streamed = MerkleTree::from_iter_hashed((0..len).filter(|_| true));
+
+            assert_eq!(streamed.root(), batch.root());
+            assert_eq!(streamed, batch);
+            assert_eq!(MerkleTree::from_hashes((0..len).map(hash_element)), batch);
+        }
+    }
+
+    #[test]
+    /// Test if a streamed tree keeps working as a normal tree
+    fn streamed_tree_can_grow() {
+        let mut streamed = MerkleTree::from_iter_hashed(["Crypto", "Merkle", "Rust"]);
+        streamed.add_element("Tree");
+        let proof = streamed.generate_proof(3).unwrap();
+
+        assert_eq!(streamed, MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"]));
+        assert!(streamed.verify(&proof, hash_element("Tree")));
+    }
+
+    /// Runs a future to completion on the current thread, polling it again
+    /// every time it is pending, since the streams of the tests wake right away
+    #[cfg(feature = "async-stream")]
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// A stream that is pending before each element, waking its task so
+    /// it is polled again
+    /// - `elements`: The elements still to give.
+    /// - `ready`: If the next poll gives an element instead of being pending.
+    #[cfg(feature = "async-stream")]
+    struct YieldingStream {
+        elements: std::ops::Range<usize>,
+        ready: bool,
+    }
+
+    #[cfg(feature = "async-stream")]
+    impl Stream for YieldingStream {
+        type Item = usize;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(self.elements.next())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async-stream")]
+    /// Test if a tree built from a stream is the one built from the
+    /// collected elements, also when the stream is pending between them.
+    fn stream_matches_batch() {
+        for len in [0usize, 1, 2, 3, 5, 64, 65, 1000] {
+            let batch = MerkleTree::new((0..len).collect());
+            assert_eq!(block_on(MerkleTree::from_stream(crate::stream::iter(0..len))), batch);
+
+            let mut progress = Vec::new();
+            let stream = YieldingStream { elements: 0..len, ready: false };
+            let streamed = block_on(MerkleTree::from_stream_with_progress(stream, |hashed| progress.push(hashed)));
+            assert_eq!(streamed, batch);
+            assert_eq!(progress, (1..=len).collect::<Vec<usize>>());
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::ops::ControlFlow;
+
+use super::{DefaultBuildHasher, MerkleTree, TreeStructure};
+
+/// The coordinates of a node in the tree. Level 0 is the base level and
+/// the last level is the root. The index is the position of the node in
+/// its level, from left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub level: usize,
+    pub index: usize,
+}
+
+/// Receives the nodes of the tree in depth-first order, see `MerkleTree::visit`.
+pub trait NodeVisitor {
+    /// Called once for every visited node. Returning `ControlFlow::Break`
+    /// skips every node below this one, and the traversal goes on with the
+    /// next node that is not in its subtree.
+    fn visit(&mut self, position: Position, hash: u64) -> ControlFlow<()>;
+}
+
+/// Iterator over the nodes of a tree level by level, from the root down to
+/// the base level and from left to right inside each level.
+pub struct BfsIter<'a, H = DefaultBuildHasher> {
+    tree: &'a MerkleTree<H>,
+    next: Option<Position>,
+}
+
+impl<H> Iterator for BfsIter<'_, H> {
+    type Item = (Position, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.next?;
+        let level = &self.tree.arr[position.level];
+        self.next = if position.index + 1 < level.len() {
+            Some(Position { level: position.level, index: position.index + 1 })
+        } else if position.level > 0 {
+            Some(Position { level: position.level - 1, index: 0 })
+        } else {
+            None
+        };
+        Some((position, level[position.index]))
+    }
+}
+
+/// Iterator over the nodes of a tree in depth-first pre-order: each node
+/// comes before its children, and the left subtree before the right one.
+pub struct DfsIter<'a, H = DefaultBuildHasher> {
+    tree: &'a MerkleTree<H>,
+    next: Option<Position>,
+}
+
+impl<H> Iterator for DfsIter<'_, H> {
+    type Item = (Position, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.next?;
+        self.next = next_in_dfs(&self.tree.arr, position, true);
+        Some((position, self.tree.arr[position.level][position.index]))
+    }
+}
+
+/// Gets the node that follows `position` in depth-first pre-order.
+///
+/// ### Arguments
+///
+/// - `levels`: The levels of the tree.
+/// - `position`: The current node.
+/// - `descend`: If the children of the current node have to be visited.
+///
+/// ### Returns
+///
+/// The next Position, or None if the traversal ended
+fn next_in_dfs(levels: &TreeStructure, position: Position, descend: bool) -> Option<Position> {
+    if descend && position.level > 0 {
+        return Some(Position { level: position.level - 1, index: position.index * 2 });
+    }
+    // Go up while we are on a right child or a promoted node, then move to the right sibling
+    let mut current = position;
+    while current.level + 1 < levels.len() {
+        if current.index.is_multiple_of(2) && current.index + 1 < levels[current.level].len() {
+            return Some(Position { level: current.level, index: current.index + 1 });
+        }
+        current = Position { level: current.level + 1, index: current.index / 2 };
+    }
+    None
+}
+
+impl<H> MerkleTree<H> {
+    /// Gets the position of the root, if the tree has one
+    fn root_position(&self) -> Option<Position> {
+        let level = self.arr.len().checked_sub(1)?;
+        (!self.arr[level].is_empty()).then_some(Position { level, index: 0 })
+    }
+
+    /// Iterates over every node stored in the tree, level by level from the
+    /// root down and from left to right in each level. The nodes that only
+    /// cover padding are not stored, so they are skipped.
+    /// The nodes are read as they are yielded, nothing is collected up front.
+    pub fn iter_bfs(&self) -> BfsIter<'_, H> {
+        BfsIter { tree: self, next: self.root_position() }
+    }
+
+    /// Iterates over every node stored in the tree, in depth-first pre-order
+    /// starting from the root. Like `iter_bfs`, the nodes that only cover
+    /// padding are skipped. The nodes are read as
+    /// they are yielded, nothing is collected up front.
+    pub fn iter_dfs(&self) -> DfsIter<'_, H> {
+        DfsIter { tree: self, next: self.root_position() }
+    }
+
+    /// Walks the tree in the same order as `iter_dfs`, letting the visitor
+    /// prune the subtree of any node it receives.
+    ///
+    /// ### Arguments
+    ///
+    /// - `visitor`: The NodeVisitor that receives every visited node.
+    pub fn visit<V: NodeVisitor>(&self, visitor: &mut V) {
+        let mut next = self.root_position();
+        while let Some(position) = next {
+            let flow = visitor.visit(position, self.arr[position.level][position.index]);
+            next = next_in_dfs(&self.arr, position, flow.is_continue());
+        }
+    }
+
+    /// Iterates over the levels of the tree, from the base level up to the
+    /// root level. Each level is the one stored, so the nodes that only cover
+    /// padding are not included, see `get_node`.
+    pub fn levels(&self) -> impl Iterator<Item = &[u64]> {
+        self.arr.iter()
+    }
+
+    /// Gets a level of the tree as it is stored, without the padding
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the level, 0 being the base level and `height() - 1` the root level.
+    ///
+    /// ### Returns
+    ///
+    /// The hashes of the level, or None if the tree has no such level
+    pub fn level(&self, index: usize) -> Option<&[u64]> {
+        self.arr.level_slice(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(level: usize, index: usize) -> Position {
+        Position { level, index }
+    }
+
+    fn four_leaves_tree() -> MerkleTree {
+        MerkleTree::new(vec!["Crypto", "Merkle", "Rust", "Tree"])
+    }
+
+    #[test]
+    /// Test the exact order of the nodes of a 4 leaves tree in BFS
+    fn bfs_order() {
+        let merkle = four_leaves_tree();
+        let order: Vec<Position> = merkle.iter_bfs().map(|(position, _)| position).collect();
+
+        assert_eq!(order, vec![pos(2, 0), pos(1, 0), pos(1, 1), pos(0, 0), pos(0, 1), pos(0, 2), pos(0, 3)]);
+    }
+
+    #[test]
+    /// Test the exact order of the nodes of a 4 leaves tree in DFS, and that
+    /// each yielded hash is the one stored at that position.
+    fn dfs_order() {
+        let merkle = four_leaves_tree();
+        let nodes: Vec<(Position, u64)> = merkle.iter_dfs().collect();
+        let order: Vec<Position> = nodes.iter().map(|(position, _)| *position).collect();
+
+        assert_eq!(order, vec![pos(2, 0), pos(1, 0), pos(0, 0), pos(0, 1), pos(1, 1), pos(0, 2), pos(0, 3)]);
+        for (position, hash) in nodes {
+            assert_eq!(hash, merkle.arr[position.level][position.index]);
+        }
+    }
+
+    #[test]
+    /// Test if both iterators yield every node of bigger trees exactly once
+    fn iterators_cover_every_node() {
+        for len in [1, 2, 5, 16] {
+            let merkle = MerkleTree::new((0..len).collect());
+            let node_count = merkle.node_count();
+            let mut bfs: Vec<Position> = merkle.iter_bfs().map(|(position, _)| position).collect();
+            let mut dfs: Vec<Position> = merkle.iter_dfs().map(|(position, _)| position).collect();
+            bfs.sort();
+            dfs.sort();
+
+            assert_eq!(bfs.len(), node_count);
+            assert_eq!(bfs, dfs);
+        }
+    }
+
+    #[test]
+    /// Test if both iterators yield every node of trees that promote odd
+    /// nodes, where some nodes have a single child.
+    fn iterators_cover_promoted_nodes() {
+        for len in [3, 5, 7, 11] {
+            let merkle = MerkleTree::new_rfc6962((0..len).collect());
+            let node_count = merkle.node_count();
+            let mut dfs: Vec<Position> = merkle.iter_dfs().map(|(position, _)| position).collect();
+            dfs.sort();
+            dfs.dedup();
+
+            assert_eq!(dfs.len(), node_count);
+            assert_eq!(merkle.iter_bfs().count(), node_count);
+        }
+    }
+
+    /// Counts the visited nodes, pruning the subtree of one node
+    struct PruningCounter {
+        prune: Position,
+        visited: Vec<Position>,
+    }
+
+    impl NodeVisitor for PruningCounter {
+        fn visit(&mut self, position: Position, _hash: u64) -> ControlFlow<()> {
+            self.visited.push(position);
+            if position == self.prune { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        }
+    }
+
+    #[test]
+    /// Test if breaking on a node skips its subtree but not the rest of the tree
+    fn visitor_prunes_subtree() {
+        let merkle = four_leaves_tree();
+        let mut visitor = PruningCounter { prune: pos(1, 0), visited: Vec::new() };
+        merkle.visit(&mut visitor);
+
+        assert_eq!(visitor.visited, vec![pos(2, 0), pos(1, 0), pos(1, 1), pos(0, 2), pos(0, 3)]);
+    }
+
+    #[test]
+    /// Test if breaking on the root visits only the root, and never breaking
+    /// visits the same nodes as iter_dfs.
+    fn visitor_without_pruning_matches_dfs() {
+        let merkle = MerkleTree::new((0..8).collect());
+        let mut root_only = PruningCounter { prune: pos(3, 0), visited: Vec::new() };
+        merkle.visit(&mut root_only);
+        let mut everything = PruningCounter { prune: pos(9, 9), visited: Vec::new() };
+        merkle.visit(&mut everything);
+
+        assert_eq!(root_only.visited, vec![pos(3, 0)]);
+        assert_eq!(everything.visited, merkle.iter_dfs().map(|(position, _)| position).collect::<Vec<_>>());
+    }
+
+    #[test]
+    /// Test if the levels go from the base level to the root, each one
+    /// half as long as the previous rounding up, since the padding is not stored.
+    fn levels_from_leaves_to_root() {
+        for len in [1usize, 2, 5, 8, 13] {
+            let merkle = MerkleTree::new((0..len).collect());
+            let levels: Vec<&[u64]> = merkle.levels().collect();
+            assert_eq!(levels.len(), merkle.height());
+            assert_eq!(levels[0].len(), merkle.len());
+            assert_eq!(levels.last().unwrap(), &[merkle.root().unwrap()]);
+            for pair in levels.windows(2) {
+                assert_eq!(pair[1].len(), pair[0].len().div_ceil(2));
+            }
+            for (index, level) in levels.iter().enumerate() {
+                assert_eq!(merkle.level(index), Some(*level));
+            }
+            assert_eq!(merkle.level(merkle.height()), None);
+        }
+        assert_eq!(MerkleTree::default().levels().count(), 0);
+        assert_eq!(MerkleTree::default().level(0), None);
+    }
+}
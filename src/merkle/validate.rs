@@ -0,0 +1,131 @@
+use std::hash::BuildHasher;
+
+use super::{create_remaining_levels_with, MerkleTree};
+use crate::error::ValidationError;
+
+impl<H: BuildHasher> MerkleTree<H> {
+    /// Checks that the levels of the tree are consistent, like after
+    /// decoding it or to look for a bug that changed it.
+    ///
+    /// First the shape is checked: the base level has one hash for each
+    /// different element, each level has half the nodes of the one below
+    /// it rounding up, and the last level only has the root. Then every
+    /// node above the base level is re-calculated from the base level and
+    /// compared with the stored one, from the lowest level up and from
+    /// left to right.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is Ok if the tree is consistent, or a ValidationError
+    /// with the first inconsistency found
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let base_len = self.arr.first().map_or(0, <[u64]>::len);
+        if base_len != self.diff_elements {
+            return Err(ValidationError::ElementCount { diff_elements: self.diff_elements, base_len });
+        }
+        if self.arr.is_empty() {
+            return Ok(());
+        }
+        for level in 1..self.height() {
+            let below = self.arr[level - 1].len();
+            // Nothing goes above a level with a single node
+            let expected = if below > 1 { below.div_ceil(2) } else { 0 };
+            let got = self.arr[level].len();
+            if got != expected {
+                return Err(ValidationError::LevelLength { level, expected, got });
+            }
+        }
+        let root_level_len = self.arr[self.height() - 1].len();
+        if root_level_len != 1 {
+            return Err(ValidationError::RootLevel { len: root_level_len });
+        }
+
+        let levels = create_remaining_levels_with(&self.hasher, self.ordering, self.padding, self.leaves().to_vec());
+        for level in 1..self.height() {
+            for (index, (expected, got)) in levels[level].iter().zip(&self.arr[level]).enumerate() {
+                if expected != got {
+                    return Err(ValidationError::NodeMismatch { level, index, expected: *expected, got: *got });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ValidationError;
+    use crate::{MerkleTree, PaddingStrategy};
+
+    /// Builds trees of 13 elements with each configuration
+    fn trees() -> Vec<MerkleTree> {
+        let data: Vec<usize> = (0..13).collect();
+        vec![
+            MerkleTree::new(data.clone()),
+            MerkleTree::new_sorted(data.clone()),
+            MerkleTree::new_rfc6962(data.clone()),
+            MerkleTree::with_committed_len(data.clone()),
+            MerkleTree::builder().padding(PaddingStrategy::ZeroHash).build(data).unwrap(),
+        ]
+    }
+
+    #[test]
+    /// Test if trees built, changed and decoded in every way are valid
+    fn built_trees_are_valid() {
+        for mut merkle in trees() {
+            assert_eq!(merkle.validate(), Ok(()));
+            merkle.add_elements(vec![13usize, 14, 15, 16]);
+            merkle.update_element(3, 100usize).unwrap();
+            merkle.pop_element();
+            assert_eq!(merkle.validate(), Ok(()));
+            assert_eq!(MerkleTree::from_bytes(&merkle.to_bytes()).unwrap().validate(), Ok(()));
+        }
+        assert_eq!(MerkleTree::default().validate(), Ok(()));
+        assert_eq!(MerkleTree::new(vec!["Crypto"]).validate(), Ok(()));
+    }
+
+    #[test]
+    /// Test if corrupting any single node above the base level is reported
+    /// at exactly its level and index, with the hash it should have.
+    fn reports_the_corrupted_node() {
+        for merkle in trees() {
+            for level in 1..merkle.height() {
+                for index in 0..merkle.arr[level].len() {
+                    let mut corrupted = merkle.clone();
+                    let expected = corrupted.arr[level][index];
+                    corrupted.arr[level][index] ^= 1;
+                    let mismatch = ValidationError::NodeMismatch { level, index, expected, got: expected ^ 1 };
+                    assert_eq!(corrupted.validate(), Err(mismatch));
+                }
+            }
+        }
+
+        // A changed leaf is data, so its parent is the node that does not match
+        let mut changed_leaf = MerkleTree::new((0..13usize).collect());
+        changed_leaf.arr[0][6] ^= 1;
+        assert!(matches!(changed_leaf.validate(), Err(ValidationError::NodeMismatch { level: 1, index: 3, .. })));
+    }
+
+    #[test]
+    /// Test if levels with a wrong len and a wrong quantity of elements are
+    /// reported before any hash is compared.
+    fn reports_the_wrong_shape() {
+        let merkle = MerkleTree::new((0..8usize).collect());
+
+        let mut more_elements = merkle.clone();
+        more_elements.diff_elements = 9;
+        assert_eq!(more_elements.validate(), Err(ValidationError::ElementCount { diff_elements: 9, base_len: 8 }));
+
+        let mut short_level = merkle.clone();
+        short_level.arr.pop_node(1);
+        assert_eq!(short_level.validate(), Err(ValidationError::LevelLength { level: 1, expected: 4, got: 3 }));
+
+        let mut without_root = merkle.clone();
+        without_root.arr.truncate(merkle.height() - 1);
+        assert_eq!(without_root.validate(), Err(ValidationError::RootLevel { len: 2 }));
+
+        let mut above_root = merkle.clone();
+        above_root.arr.push([merkle.root().unwrap()]);
+        assert_eq!(above_root.validate(), Err(ValidationError::LevelLength { level: 4, expected: 0, got: 1 }));
+    }
+}
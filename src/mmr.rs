@@ -0,0 +1,311 @@
+use std::hash::Hash;
+
+use crate::error::MerkleError;
+use crate::merkle::{concatenate_elements, hash_element, hash_node};
+
+/// The proof of an element of a Mmr. It leads from the leaf up to the peak
+/// of its mountain, and then the peaks are bagged into the root.
+/// - `leaf_index`: The index of the proven element, in the order it was appended.
+/// - `leaf_count`: Quantity of elements the Mmr had when the proof was generated.
+/// - `siblings`: The siblings of the path from the leaf to its peak, starting from the leaf.
+/// - `peaks`: Every peak of the Mmr, from the highest mountain to the lowest one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub siblings: Vec<u64>,
+    pub peaks: Vec<u64>,
+}
+
+/// Abstraction of a Merkle Mountain Range, a tree for append-only logs.
+///
+/// Instead of a single tree padded up to a power of 2, the elements form a
+/// list of perfect trees (the mountains) of decreasing height, one for each
+/// bit set in the quantity of elements. Appending an element only adds
+/// nodes: it may merge the last mountains into a higher one, but it never
+/// rewrites a node that already exists. The root bags the peaks of the
+/// mountains from right to left.
+///
+/// With 2^n elements there is a single mountain, and the root is the one
+/// of `MerkleTree::new` for those elements.
+/// - `levels`: The nodes of every mountain, grouped by their height. Each level only grows.
+/// - `leaf_count`: Quantity of elements appended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mmr {
+    levels: Vec<Vec<u64>>,
+    leaf_count: usize,
+}
+
+impl Mmr {
+    /// Creates an empty Mmr
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an element to the Mmr
+    ///
+    /// The leaf is pushed at the end of the base level. While the last two
+    /// mountains have the same height they are merged, pushing their parent
+    /// to the level above. This takes O(log n) hashes.
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element that will be hashed and appended.
+    ///
+    /// ### Returns
+    ///
+    /// The new root of the Mmr
+    pub fn append<T: Hash>(&mut self, element: T) -> u64 {
+        let mut node = hash_element(element);
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(Vec::new());
+            }
+            self.levels[level].push(node);
+            let len = self.levels[level].len();
+            if len % 2 == 1 {
+                break;
+            }
+            node = combine(self.levels[level][len - 2], node);
+            level += 1;
+        }
+        self.leaf_count += 1;
+        bag_peaks(&self.peaks()).expect("A Mmr with an element has a peak")
+    }
+
+    /// Gets the root of the Mmr, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        bag_peaks(&self.peaks())
+    }
+
+    /// Gets the peak of every mountain, from the highest to the lowest
+    pub fn peaks(&self) -> Vec<u64> {
+        // A level with an odd len has a mountain of that height, and its
+        // peak is the last node of the level
+        self.levels.iter().rev().filter(|level| level.len() % 2 == 1).map(|level| level[level.len() - 1]).collect()
+    }
+
+    /// Gets the quantity of elements appended
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Checks if no element was appended
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Gets the quantity of nodes stored in every mountain
+    pub fn node_count(&self) -> usize {
+        self.levels.iter().map(Vec::len).sum()
+    }
+
+    /// Generates the proof for a specific element against the current root
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaf_index`: The index of the element, in the order it was appended.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MmrProof, or an Error if there is no element at that index
+    pub fn generate_proof(&self, leaf_index: usize) -> Result<MmrProof, MerkleError> {
+        if leaf_index >= self.leaf_count {
+            return Err(MerkleError::InvalidIndex { index: leaf_index, len: self.leaf_count });
+        }
+        // The mountains are aligned to their size, so the path of the leaf
+        // reads the same indexes as in a binary tree
+        let (_, height) = mountain_of(self.leaf_count, leaf_index);
+        let siblings = (0..height).map(|level| self.levels[level][(leaf_index >> level) ^ 1]).collect();
+        Ok(MmrProof { leaf_index, leaf_count: self.leaf_count, siblings, peaks: self.peaks() })
+    }
+
+    /// Checks if the current root can be obtained with the use of a proof
+    /// and the hash of an element. The proof has to be generated with the
+    /// current quantity of elements.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The MmrProof of the element, as `generate_proof` returns it.
+    /// - `leaf`: The hash of the element.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &MmrProof, leaf: u64) -> bool {
+        proof.leaf_count == self.leaf_count && self.root().is_some_and(|root| verify_against_root(root, proof, leaf))
+    }
+}
+
+/// Checks if a root can be obtained with the use of a proof and a leaf,
+/// without having the Mmr. The root is the one the Mmr had when the proof
+/// was generated, so proofs keep verifying against the roots of the past.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the Mmr the element should belong to.
+/// - `proof`: The MmrProof of the element.
+/// - `leaf`: The hash of the element.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_against_root(root: u64, proof: &MmrProof, leaf: u64) -> bool {
+    if proof.leaf_index >= proof.leaf_count || proof.peaks.len() != proof.leaf_count.count_ones() as usize {
+        return false;
+    }
+    let (mountain, height) = mountain_of(proof.leaf_count, proof.leaf_index);
+    if proof.siblings.len() != height {
+        return false;
+    }
+    let mut node = leaf;
+    for (level, sibling) in proof.siblings.iter().enumerate() {
+        node = if (proof.leaf_index >> level).is_multiple_of(2) {
+            combine(node, *sibling)
+        } else {
+            combine(*sibling, node)
+        };
+    }
+    proof.peaks[mountain] == node && bag_peaks(&proof.peaks) == Some(root)
+}
+
+/// Finds the mountain that holds a leaf
+///
+/// ### Arguments
+///
+/// - `leaf_count`: Quantity of elements of the Mmr.
+/// - `leaf_index`: The index of the leaf, lower than `leaf_count`.
+///
+/// ### Returns
+///
+/// The index of the mountain, counting from the highest one, and its height
+fn mountain_of(leaf_count: usize, leaf_index: usize) -> (usize, usize) {
+    let mut start = 0;
+    let mut mountain = 0;
+    for height in (0..usize::BITS as usize).rev() {
+        if leaf_count & (1 << height) == 0 {
+            continue;
+        }
+        if leaf_index < start + (1 << height) {
+            return (mountain, height);
+        }
+        start += 1 << height;
+        mountain += 1;
+    }
+    unreachable!("The leaf index is lower than the leaf count")
+}
+
+/// Bags the peaks into a single root, hashing them from right to left
+///
+/// ### Returns
+///
+/// The root, or None if there are no peaks
+fn bag_peaks(peaks: &[u64]) -> Option<u64> {
+    peaks.iter().rev().copied().reduce(|bagged, peak| combine(peak, bagged))
+}
+
+/// Hashes the concatenation of two nodes, like the parents of a MerkleTree
+fn combine(left: u64, right: u64) -> u64 {
+    hash_node(concatenate_elements(left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    /// A xorshift generator, so the tests pick positions without a crate
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    /// Test if proofs of random positions taken while appending a few
+    /// hundred elements keep verifying against the root of their time,
+    /// and not against the roots that came after.
+    fn old_proofs_verify_against_their_roots() {
+        let mut state = 0x9e3779b97f4a7c15;
+        let mut mmr = Mmr::new();
+        let mut snapshots = Vec::new();
+        for element in 0..300usize {
+            let root = mmr.append(element);
+            assert_eq!(mmr.root(), Some(root));
+            if element % 37 == 0 {
+                let index = next_random(&mut state) as usize % mmr.len();
+                let proof = mmr.generate_proof(index).unwrap();
+                assert!(mmr.verify(&proof, hash_element(index)));
+                snapshots.push((root, index, proof));
+            }
+        }
+        for (root, index, proof) in &snapshots {
+            assert!(verify_against_root(*root, proof, hash_element(index)));
+            assert!(!verify_against_root(*root, proof, hash_element(index + 1)));
+            assert!(!verify_against_root(mmr.root().unwrap(), proof, hash_element(index)));
+            assert!(!mmr.verify(proof, hash_element(index)));
+        }
+        for index in 0..mmr.len() {
+            assert!(mmr.verify(&mmr.generate_proof(index).unwrap(), hash_element(index)));
+        }
+    }
+
+    #[test]
+    /// Test if appending never rewrites a node, and each append adds at
+    /// most one node per level.
+    fn appends_never_rewrite_nodes() {
+        let mut mmr = Mmr::new();
+        for element in 0..200usize {
+            let before = mmr.clone();
+            mmr.append(element);
+            assert!(mmr.node_count() - before.node_count() <= mmr.levels.len());
+            for (old, new) in before.levels.iter().zip(&mmr.levels) {
+                assert!(new.starts_with(old));
+            }
+        }
+        // Every mountain of 2^n leaves has 2^(n+1) - 1 nodes
+        assert_eq!(mmr.node_count(), 2 * 200 - 200usize.count_ones() as usize);
+    }
+
+    #[test]
+    /// Test if there is a peak for each bit of the len, and a single
+    /// mountain has the root of a MerkleTree with the same elements.
+    fn peaks_follow_the_len() {
+        let mut mmr = Mmr::new();
+        assert_eq!((mmr.root(), mmr.peaks().len()), (None, 0));
+        for element in 0..64usize {
+            mmr.append(element);
+            assert_eq!(mmr.peaks().len(), mmr.len().count_ones() as usize);
+            if mmr.len().is_power_of_two() {
+                assert_eq!(mmr.root(), MerkleTree::new((0..mmr.len()).collect()).root());
+            }
+        }
+    }
+
+    #[test]
+    /// Test if invalid indexes and proofs with the wrong shape are rejected
+    fn invalid_proofs_are_rejected() {
+        let mmr: Mmr = (0..11usize).fold(Mmr::new(), |mut mmr, element| {
+            mmr.append(element);
+            mmr
+        });
+        let root = mmr.root().unwrap();
+        assert_eq!(mmr.generate_proof(11), Err(MerkleError::InvalidIndex { index: 11, len: 11 }));
+
+        let proof = mmr.generate_proof(9).unwrap();
+        let mut wrong_index = proof.clone();
+        wrong_index.leaf_index = 8;
+        let mut missing_peak = proof.clone();
+        missing_peak.peaks.pop();
+        let mut extra_sibling = proof.clone();
+        extra_sibling.siblings.push(root);
+        let mut wrong_count = proof.clone();
+        wrong_count.leaf_count = 12;
+        for invalid in [wrong_index, missing_peak, extra_sibling, wrong_count] {
+            assert!(!verify_against_root(root, &invalid, hash_element(9usize)), "{invalid:?}");
+        }
+        assert!(verify_against_root(root, &proof, hash_element(9usize)));
+    }
+}
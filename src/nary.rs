@@ -0,0 +1,285 @@
+use std::hash::Hash;
+
+use crate::error::MerkleError;
+use crate::merkle::{hash_element, DefaultBuildHasher};
+use crate::verify::hash_children_with;
+
+/// The proof of an element of a NaryMerkleTree. Each level of the tree
+/// gives the position of the node in the path among its siblings, and the
+/// hashes of those `ARITY - 1` siblings from left to right.
+/// - `leaf_index`: The index of the proven element in the base level.
+/// - `levels`: The `(position, siblings)` pair of every level below the root, starting from the base level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NaryProof {
+    pub leaf_index: usize,
+    pub levels: Vec<(usize, Vec<u64>)>,
+}
+
+impl NaryProof {
+    /// Gets the quantity of sibling hashes in the proof
+    pub fn hash_count(&self) -> usize {
+        self.levels.iter().map(|(_, siblings)| siblings.len()).sum()
+    }
+}
+
+/// Abstraction of a Merkle Tree where every parent has `ARITY` children
+/// instead of 2. A wider tree is shorter, so its proofs have less levels,
+/// but each level of a proof carries `ARITY - 1` siblings.
+///
+/// The base level is padded with copies of the last element until its len
+/// is a power of `ARITY`, and every parent is the hash of the concatenation
+/// of its children. With an arity of 2 the tree has the root and the proof
+/// hashes of `MerkleTree::new`.
+/// - `arr`: A vector of vectors will be the structure of our tree. Each vector is a level on it.
+/// - `diff_elements`: Quantity of different elements in the base level.
+pub struct NaryMerkleTree<const ARITY: usize> {
+    arr: Vec<Vec<u64>>,
+    diff_elements: usize,
+}
+
+impl<const ARITY: usize> NaryMerkleTree<ARITY> {
+    /// Creates a new NaryMerkleTree. The arity has to be at least 2, which
+    /// is checked when the program is compiled.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A NaryMerkleTree instance
+    pub fn new<T: Hash>(elements: Vec<T>) -> Self {
+        const { assert!(ARITY >= 2, "A tree needs at least 2 children per node") };
+        let diff_elements = elements.len();
+        let mut level: Vec<u64> = elements.iter().map(hash_element).collect();
+        if let Some(&last) = level.last() {
+            level.resize(padded_len(ARITY, diff_elements), last);
+        }
+
+        let hasher = DefaultBuildHasher::default();
+        let mut arr = vec![level];
+        while arr[arr.len() - 1].len() > 1 {
+            let next_level = arr[arr.len() - 1].chunks(ARITY).map(|children| hash_children_with(&hasher, children)).collect();
+            arr.push(next_level);
+        }
+        if diff_elements == 0 {
+            arr.clear();
+        }
+        Self { arr, diff_elements }
+    }
+
+    /// Gets the root of the tree, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        self.arr.last().map(|level| level[0])
+    }
+
+    /// Gets the quantity of different elements of the tree
+    pub fn len(&self) -> usize {
+        self.diff_elements
+    }
+
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.diff_elements == 0
+    }
+
+    /// Gets the quantity of levels of the tree, the base level and the
+    /// root level included
+    pub fn height(&self) -> usize {
+        self.arr.len()
+    }
+
+    /// Generates the proof for a specific element in the tree
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the original input array
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the NaryProof, or an Error if the index is outside of
+    /// the base level or it is one of the copies added as padding
+    pub fn generate_proof(&self, index: usize) -> Result<NaryProof, MerkleError> {
+        let padded_len = self.arr.first().map_or(0, Vec::len);
+        if index >= padded_len {
+            return Err(MerkleError::InvalidIndex { index, len: self.diff_elements });
+        }
+        if index >= self.diff_elements {
+            return Err(MerkleError::PaddedIndex { index });
+        }
+        let mut node_index = index;
+        let levels = self.arr[..self.arr.len() - 1]
+            .iter()
+            .map(|level| {
+                let first = node_index - node_index % ARITY;
+                let position = node_index - first;
+                let siblings = (first..first + ARITY).filter(|child| *child != node_index).map(|child| level[child]).collect();
+                node_index /= ARITY;
+                (position, siblings)
+            })
+            .collect();
+        Ok(NaryProof { leaf_index: index, levels })
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a
+    /// proof and the hash of an element. The proof has to be the one of
+    /// a tree with this arity and this height.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The NaryProof of the element, as `generate_proof` returns it.
+    /// - `leaf`: The hash of the element.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &NaryProof, leaf: u64) -> bool {
+        proof.leaf_index < self.diff_elements
+            && proof.levels.len() + 1 == self.height()
+            && self.root().is_some_and(|root| verify_against_root::<ARITY>(root, proof, leaf))
+    }
+}
+
+/// Checks if a root can be obtained with the use of a proof and a leaf,
+/// without having the tree. Every position has to be the one the leaf
+/// index gives for that level, and every level has to carry `ARITY - 1`
+/// siblings.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the element should belong to.
+/// - `proof`: The NaryProof of the element.
+/// - `leaf`: The hash of the element.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_against_root<const ARITY: usize>(root: u64, proof: &NaryProof, leaf: u64) -> bool {
+    let hasher = DefaultBuildHasher::default();
+    let mut node = leaf;
+    let mut node_index = proof.leaf_index;
+    let mut children = Vec::with_capacity(ARITY);
+    for (position, siblings) in &proof.levels {
+        if *position != node_index % ARITY || siblings.len() != ARITY - 1 {
+            return false;
+        }
+        children.clear();
+        children.extend_from_slice(&siblings[..*position]);
+        children.push(node);
+        children.extend_from_slice(&siblings[*position..]);
+        node = hash_children_with(&hasher, &children);
+        node_index /= ARITY;
+    }
+    node_index == 0 && node == root
+}
+
+/// Gets the smallest power of the arity that is not less than the len
+///
+/// ### Panics
+///
+/// If that power does not fit in an usize
+fn padded_len(arity: usize, len: usize) -> usize {
+    let mut padded = 1usize;
+    while padded < len {
+        padded = padded.checked_mul(arity).expect("The len can not be padded to a power of the arity");
+    }
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    /// Checks that every element of a tree has a proof that verifies, and
+    /// that it fails with another leaf
+    fn assert_every_proof_verifies<const ARITY: usize>(len: usize) {
+        let merkle = NaryMerkleTree::<ARITY>::new((0..len).collect());
+        for index in 0..len {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify(&proof, hash_element(index)), "arity {ARITY}, len {len}, index {index}");
+            assert!(!merkle.verify(&proof, hash_element(index + 1)));
+        }
+    }
+
+    #[test]
+    /// Test if a tree of arity 2 has the root and proofs of MerkleTree
+    fn arity_2_matches_merkle_tree() {
+        for len in 1..20usize {
+            let data: Vec<usize> = (0..len).collect();
+            let nary = NaryMerkleTree::<2>::new(data.clone());
+            let merkle = MerkleTree::new(data);
+
+            assert_eq!(nary.root(), merkle.root());
+            for index in 0..len {
+                let siblings: Vec<u64> = nary.generate_proof(index).unwrap().levels.into_iter().flat_map(|(_, siblings)| siblings).collect();
+                assert_eq!(siblings, merkle.generate_proof(index).unwrap().hashes());
+            }
+        }
+    }
+
+    #[test]
+    /// Test if every proof verifies for several arities and lens, padded
+    /// ones included.
+    fn every_proof_verifies() {
+        for len in 1..=70 {
+            assert_every_proof_verifies::<3>(len);
+            assert_every_proof_verifies::<4>(len);
+            assert_every_proof_verifies::<8>(len);
+        }
+    }
+
+    #[test]
+    /// Test if a wider tree gives proofs with less levels: 256 leaves need
+    /// 8 levels of siblings with an arity of 2 but only 4 with an arity of 4.
+    fn wider_trees_give_shorter_proofs() {
+        let data: Vec<usize> = (0..256).collect();
+        let binary = NaryMerkleTree::<2>::new(data.clone()).generate_proof(100).unwrap();
+        let quaternary = NaryMerkleTree::<4>::new(data.clone()).generate_proof(100).unwrap();
+        let wide = NaryMerkleTree::<16>::new(data).generate_proof(100).unwrap();
+
+        assert_eq!((binary.levels.len(), binary.hash_count()), (8, 8));
+        assert_eq!((quaternary.levels.len(), quaternary.hash_count()), (4, 12));
+        assert_eq!((wide.levels.len(), wide.hash_count()), (2, 30));
+    }
+
+    #[test]
+    /// Test if the base level is padded up to a power of the arity with
+    /// copies of the last element.
+    fn padding_fills_a_power_of_the_arity() {
+        let merkle = NaryMerkleTree::<3>::new((0..10usize).collect());
+        assert_eq!(merkle.arr[0].len(), 27);
+        assert!(merkle.arr[0][10..].iter().all(|hash| *hash == hash_element(9usize)));
+        assert_eq!(merkle.height(), 4);
+        assert_eq!(NaryMerkleTree::<4>::new((0..10usize).collect()).arr[0].len(), 16);
+        assert_eq!(NaryMerkleTree::<4>::new(vec!["Crypto"]).height(), 1);
+    }
+
+    #[test]
+    /// Test if proofs with a wrong position, index, quantity of siblings or
+    /// height are rejected, and so are padded and invalid indexes.
+    fn invalid_proofs_are_rejected() {
+        let merkle = NaryMerkleTree::<4>::new((0..10usize).collect());
+        let proof = merkle.generate_proof(6).unwrap();
+        let leaf = hash_element(6usize);
+
+        let mut wrong_position = proof.clone();
+        wrong_position.levels[0].0 = 3;
+        let mut wrong_index = proof.clone();
+        wrong_index.leaf_index = 7;
+        let mut missing_sibling = proof.clone();
+        missing_sibling.levels[1].1.pop();
+        let mut short = proof.clone();
+        short.levels.pop();
+        for invalid in [wrong_position, wrong_index, missing_sibling, short] {
+            assert!(!merkle.verify(&invalid, leaf), "{invalid:?}");
+        }
+        assert!(merkle.verify(&proof, leaf));
+
+        assert_eq!(merkle.generate_proof(12), Err(MerkleError::PaddedIndex { index: 12 }));
+        assert_eq!(merkle.generate_proof(16), Err(MerkleError::InvalidIndex { index: 16, len: 10 }));
+        let empty = NaryMerkleTree::<4>::new(Vec::<u8>::new());
+        assert_eq!((empty.root(), empty.height()), (None, 0));
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+use std::hash::BuildHasher;
+
+use crate::error::{MerkleError, PolicyViolation};
+use crate::json::{self, Json};
+use crate::merkle::{DefaultBuildHasher, MerkleTree, PaddingStrategy, PairOrdering, Proof, commit_len_with, expected_proof_len_for};
+use crate::portable::PortableHash;
+use crate::verify::commit_domain_tag_with;
+
+/// A hasher without state, with an id that receipts use to say which one
+/// built the tree, so the verifier can create the same one from the id.
+pub trait HashAlgorithm: BuildHasher + Default {
+    /// The id written in the receipts of the trees with this hasher.
+    const ID: &'static str;
+}
+
+impl HashAlgorithm for DefaultBuildHasher {
+    const ID: &'static str = "default";
+}
+
+impl HashAlgorithm for PortableHash {
+    const ID: &'static str = "portable";
+}
+
+/// A proof together with what a verifier needs to know about the tree it
+/// came from. Everything but the algorithm is bound to the root, so a
+/// receipt that lies about them does not verify.
+/// - `algorithm`: The id of the hasher of the tree, see `HashAlgorithm`.
+/// - `ordering`: How the tree orders the children of its nodes.
+/// - `root`: The root of the tree, committing to the tree size and then to the domain tag when they are present.
+/// - `tree_size`: The quantity of different elements, when the root commits to it.
+/// - `domain_tag`: The tag of the deployment the root commits to, if any.
+/// - `proof`: The proof of the element.
+/// - `leaf`: The hash of the proven element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub algorithm: String,
+    pub ordering: PairOrdering,
+    pub root: u64,
+    pub tree_size: Option<usize>,
+    pub domain_tag: Option<String>,
+    pub proof: Proof,
+    pub leaf: u64,
+}
+
+impl<H: HashAlgorithm> MerkleTree<H> {
+    /// Generates the Receipt of an element, embedding the tree size when
+    /// the root commits to it, see `MerkleTree::with_committed_len`
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the base level.
+    /// - `domain_tag`: The tag the root of the receipt commits to, if any.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Receipt, or an Error if the index is not one of an
+    /// element. The padding is not part of the receipt, so the verifier
+    /// expects a sibling on every level, and trees that promote odd nodes
+    /// return UnsupportedPadding.
+    pub fn receipt(&self, index: usize, domain_tag: Option<&str>) -> Result<Receipt, MerkleError> {
+        if self.padding() == PaddingStrategy::PromoteOdd {
+            return Err(MerkleError::UnsupportedPadding);
+        }
+        let proof = self.generate_proof(index)?;
+        let root = self.root().ok_or(MerkleError::EmptyTree)?;
+        Ok(Receipt {
+            algorithm: H::ID.to_string(),
+            ordering: self.ordering(),
+            root: domain_tag.map_or(root, |tag| commit_domain_tag_with(&H::default(), root, tag)),
+            tree_size: self.committed_len(),
+            domain_tag: domain_tag.map(str::to_string),
+            proof,
+            leaf: self.get_leaf(index).ok_or(MerkleError::InvalidIndex { index, len: self.len() })?,
+        })
+    }
+}
+
+/// The rules a receipt has to follow to be accepted, on top of being
+/// valid. A new policy accepts every receipt that is valid.
+///
+/// ```
+/// use tree::policy::{PolicyVerifier, VerifierPolicy};
+/// use tree::MerkleTree;
+///
+/// let policy = VerifierPolicy::new().allow_algorithm("default").require_tree_size(true).max_depth(20);
+/// let merkle = MerkleTree::with_committed_len(vec!["Crypto", "Merkle", "Rust"]);
+/// assert_eq!(PolicyVerifier::new(policy).verify(&merkle.receipt(1, None).unwrap()), Ok(()));
+/// ```
+/// - `allowed_algorithms`: The ids of the hashers that are accepted. Every known one if it is empty.
+/// - `require_tree_size`: If the root must commit to the tree size.
+/// - `max_depth`: The maximum quantity of hashes in a proof, if any.
+/// - `domain_tag`: The domain tag the root must commit to, if any.
+/// - `allow_padded_slots`: If proofs of slots of the padding are accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierPolicy {
+    allowed_algorithms: Vec<String>,
+    require_tree_size: bool,
+    max_depth: Option<usize>,
+    domain_tag: Option<String>,
+    allow_padded_slots: bool,
+}
+
+impl VerifierPolicy {
+    /// Creates a VerifierPolicy that accepts every valid receipt
+    pub fn new() -> Self {
+        Self { allowed_algorithms: Vec::new(), require_tree_size: false, max_depth: None, domain_tag: None, allow_padded_slots: true }
+    }
+
+    /// Adds the id of a hasher to the accepted ones. Once one is added,
+    /// the hashers that were not added are rejected.
+    pub fn allow_algorithm(mut self, id: &str) -> Self {
+        self.allowed_algorithms.push(id.to_string());
+        self
+    }
+
+    /// Sets if the root must commit to the tree size
+    pub fn require_tree_size(mut self, require: bool) -> Self {
+        self.require_tree_size = require;
+        self
+    }
+
+    /// Sets the maximum quantity of hashes in a proof
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    /// Sets the domain tag the root must commit to
+    pub fn require_domain_tag(mut self, tag: &str) -> Self {
+        self.domain_tag = Some(tag.to_string());
+        self
+    }
+
+    /// Sets if proofs of slots of the padding are accepted. When they are
+    /// not, the tree size is needed to tell them apart, so receipts without
+    /// it are rejected.
+    pub fn allow_padded_slots(mut self, allow: bool) -> Self {
+        self.allow_padded_slots = allow;
+        self
+    }
+
+    /// Reads a policy from a JSON object, where every key is optional:
+    /// `allowed_algorithms` (an array of ids), `require_tree_size`,
+    /// `max_depth`, `domain_tag` and `allow_padded_slots`. Unknown keys are
+    /// an Error, so a misspelled rule is not silently left out.
+    pub fn from_json(input: &str) -> Result<Self, MerkleError> {
+        let invalid = |key: &str| MerkleError::InvalidEncoding(format!("Invalid policy rule '{key}'"));
+        let Json::Object(rules) = json::parse(input).map_err(MerkleError::InvalidEncoding)? else {
+            return Err(MerkleError::InvalidEncoding("A policy is a JSON object".to_string()));
+        };
+        let mut policy = Self::new();
+        for (key, value) in &rules {
+            match key.as_str() {
+                "allowed_algorithms" => {
+                    for id in value.as_array().ok_or_else(|| invalid(key))? {
+                        policy = policy.allow_algorithm(id.as_str().ok_or_else(|| invalid(key))?);
+                    }
+                }
+                "require_tree_size" => policy.require_tree_size = value.as_bool().ok_or_else(|| invalid(key))?,
+                "max_depth" => policy.max_depth = Some(value.as_u64().ok_or_else(|| invalid(key))? as usize),
+                "domain_tag" => policy.domain_tag = Some(value.as_str().ok_or_else(|| invalid(key))?.to_string()),
+                "allow_padded_slots" => policy.allow_padded_slots = value.as_bool().ok_or_else(|| invalid(key))?,
+                _ => return Err(MerkleError::InvalidEncoding(format!("Unknown policy rule '{key}'"))),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Checks every rule in the order they are listed in the struct
+    fn check(&self, receipt: &Receipt) -> Result<(), PolicyViolation> {
+        if !self.allowed_algorithms.is_empty() && !self.allowed_algorithms.contains(&receipt.algorithm) {
+            return Err(PolicyViolation::AlgorithmNotAllowed { algorithm: receipt.algorithm.clone() });
+        }
+        if self.require_tree_size && receipt.tree_size.is_none() {
+            return Err(PolicyViolation::TreeSizeRequired);
+        }
+        if let Some(max_depth) = self.max_depth
+            && receipt.proof.len() > max_depth
+        {
+            return Err(PolicyViolation::ProofTooDeep { max_depth, depth: receipt.proof.len() });
+        }
+        if let Some(expected) = &self.domain_tag
+            && receipt.domain_tag.as_ref() != Some(expected)
+        {
+            return Err(PolicyViolation::DomainTagMismatch { expected: expected.clone(), got: receipt.domain_tag.clone() });
+        }
+        if !self.allow_padded_slots {
+            let tree_size = receipt.tree_size.ok_or(PolicyViolation::TreeSizeRequired)?;
+            if receipt.proof.leaf_index() >= tree_size {
+                return Err(PolicyViolation::PaddedSlot { index: receipt.proof.leaf_index(), tree_size });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for VerifierPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Verifies receipts following a VerifierPolicy: the rules are checked
+/// first, and only a receipt that follows all of them is hashed.
+/// - `policy`: The rules the receipts have to follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyVerifier {
+    policy: VerifierPolicy,
+}
+
+impl PolicyVerifier {
+    /// Creates a PolicyVerifier with a policy
+    pub fn new(policy: VerifierPolicy) -> Self {
+        Self { policy }
+    }
+
+    /// Checks that a receipt follows the policy and that its proof leads
+    /// to its root
+    ///
+    /// ### Arguments
+    ///
+    /// - `receipt`: The receipt of the proven element.
+    ///
+    /// ### Returns
+    ///
+    /// A Result that is Ok if the receipt is accepted, or the first rule it
+    /// does not follow. `InvalidProof` is only returned after every rule passed.
+    pub fn verify(&self, receipt: &Receipt) -> Result<(), PolicyViolation> {
+        self.policy.check(receipt)?;
+        let valid = match receipt.algorithm.as_str() {
+            DefaultBuildHasher::ID => verify_receipt_with(&DefaultBuildHasher::default(), receipt),
+            PortableHash::ID => verify_receipt_with(&PortableHash, receipt),
+            algorithm => return Err(PolicyViolation::UnknownAlgorithm { algorithm: algorithm.to_string() }),
+        };
+        if valid { Ok(()) } else { Err(PolicyViolation::InvalidProof) }
+    }
+}
+
+/// Re-calculates the root of a receipt with the hasher of its algorithm.
+/// The sides of the proof have to be the ones of its index, so the index
+/// the padded slot rule checks is the one the proof was generated for.
+fn verify_receipt_with<H: BuildHasher>(hasher: &H, receipt: &Receipt) -> bool {
+    let Some(mut root) = receipt.proof.fold_with(hasher, receipt.ordering, receipt.leaf) else {
+        return false;
+    };
+    if let Some(tree_size) = receipt.tree_size {
+        if receipt.proof.leaf_index() >= tree_size || receipt.proof.len() != expected_proof_len_for(tree_size) {
+            return false;
+        }
+        root = commit_len_with(hasher, root, tree_size);
+    }
+    if let Some(tag) = &receipt.domain_tag {
+        root = commit_domain_tag_with(hasher, root, tag);
+    }
+    root == receipt.root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    const DATA: [&str; 5] = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+
+    fn verify(policy: VerifierPolicy, receipt: &Receipt) -> Result<(), PolicyViolation> {
+        PolicyVerifier::new(policy).verify(receipt)
+    }
+
+    #[test]
+    /// Test if the receipts of every kind of tree verify with a policy
+    /// without rules, and a changed receipt does not
+    fn valid_receipts_verify() {
+        let trees = [MerkleTree::new(DATA.to_vec()), MerkleTree::new_sorted(DATA.to_vec()), MerkleTree::with_committed_len(DATA.to_vec())];
+        for merkle in &trees {
+            for (index, element) in DATA.iter().enumerate() {
+                for tag in [None, Some("deployment")] {
+                    let receipt = merkle.receipt(index, tag).unwrap();
+                    assert_eq!(receipt.leaf, hash_element(element));
+                    assert_eq!(verify(VerifierPolicy::new(), &receipt), Ok(()));
+
+                    let other_leaf = Receipt { leaf: hash_element("Hash"), ..receipt.clone() };
+                    assert_eq!(verify(VerifierPolicy::new(), &other_leaf), Err(PolicyViolation::InvalidProof));
+                    let other_tag = Receipt { domain_tag: Some("other".to_string()), ..receipt.clone() };
+                    assert_eq!(verify(VerifierPolicy::new(), &other_tag), Err(PolicyViolation::InvalidProof));
+                }
+            }
+        }
+        let portable = MerkleTree::new_portable(DATA.to_vec()).receipt(2, None).unwrap();
+        assert_eq!(portable.algorithm, "portable");
+        assert_eq!(verify(VerifierPolicy::new(), &portable), Ok(()));
+        let unknown = Receipt { algorithm: "md5".to_string(), ..portable };
+        assert_eq!(verify(VerifierPolicy::new(), &unknown), Err(PolicyViolation::UnknownAlgorithm { algorithm: "md5".to_string() }));
+    }
+
+    #[test]
+    /// Test if only the allowed algorithms are accepted
+    fn algorithm_rule() {
+        let receipt = MerkleTree::new_portable(DATA.to_vec()).receipt(0, None).unwrap();
+        let policy = VerifierPolicy::new().allow_algorithm("default");
+        let violation = PolicyViolation::AlgorithmNotAllowed { algorithm: "portable".to_string() };
+        assert_eq!(verify(policy.clone(), &receipt), Err(violation));
+        assert_eq!(verify(policy.allow_algorithm("portable"), &receipt), Ok(()));
+    }
+
+    #[test]
+    /// Test if a valid receipt without the tree size is rejected when the
+    /// policy requires it
+    fn tree_size_rule() {
+        let policy = VerifierPolicy::new().require_tree_size(true);
+        let receipt = MerkleTree::new(DATA.to_vec()).receipt(1, None).unwrap();
+        assert_eq!(verify(VerifierPolicy::new(), &receipt), Ok(()));
+        assert_eq!(verify(policy.clone(), &receipt), Err(PolicyViolation::TreeSizeRequired));
+
+        let receipt = MerkleTree::with_committed_len(DATA.to_vec()).receipt(1, None).unwrap();
+        assert_eq!(receipt.tree_size, Some(5));
+        assert_eq!(verify(policy, &receipt), Ok(()));
+    }
+
+    #[test]
+    /// Test if proofs with more hashes than the max depth are rejected
+    fn max_depth_rule() {
+        let receipt = MerkleTree::new(DATA.to_vec()).receipt(4, None).unwrap();
+        assert_eq!(verify(VerifierPolicy::new().max_depth(3), &receipt), Ok(()));
+        assert_eq!(verify(VerifierPolicy::new().max_depth(2), &receipt), Err(PolicyViolation::ProofTooDeep { max_depth: 2, depth: 3 }));
+    }
+
+    #[test]
+    /// Test if a valid receipt with another domain tag, or none, is rejected
+    fn domain_tag_rule() {
+        let merkle = MerkleTree::new(DATA.to_vec());
+        let policy = VerifierPolicy::new().require_domain_tag("deployment");
+        assert_eq!(verify(policy.clone(), &merkle.receipt(3, Some("deployment")).unwrap()), Ok(()));
+        for tag in [None, Some("other")] {
+            let violation = PolicyViolation::DomainTagMismatch { expected: "deployment".to_string(), got: tag.map(str::to_string) };
+            assert_eq!(verify(policy.clone(), &merkle.receipt(3, tag).unwrap()), Err(violation));
+        }
+    }
+
+    #[test]
+    /// Test if a proof of a padded slot, which is valid for a tree that
+    /// does not commit to its len, is rejected when they are not allowed,
+    /// also when it is relabelled as the proof of an element, and a proof
+    /// shorter than the tree size gives is rejected
+    fn padded_slot_rule() {
+        let merkle = MerkleTree::new(DATA.to_vec());
+        let padded = Receipt { proof: merkle.generate_proof_padded(6).unwrap(), ..merkle.receipt(4, None).unwrap() };
+        assert_eq!(verify(VerifierPolicy::new(), &padded), Ok(()));
+
+        let policy = VerifierPolicy::new().allow_padded_slots(false);
+        assert_eq!(verify(policy.clone(), &padded), Err(PolicyViolation::TreeSizeRequired));
+        let claimed_size = Receipt { tree_size: Some(5), ..padded.clone() };
+        assert_eq!(verify(policy.clone(), &claimed_size), Err(PolicyViolation::PaddedSlot { index: 6, tree_size: 5 }));
+        // Relabelling the proof of the padded slot does not turn it into the proof of an element
+        let relabelled = Receipt { proof: Proof::new(4, padded.proof.siblings().to_vec()), tree_size: Some(5), ..padded };
+        assert_eq!(verify(policy.clone(), &relabelled), Err(PolicyViolation::InvalidProof));
+
+        let merkle = MerkleTree::with_committed_len(DATA.to_vec());
+        let committed = merkle.receipt(4, None).unwrap();
+        assert_eq!(verify(policy.clone(), &committed), Ok(()));
+        let padded_proof = merkle.generate_proof_padded(6).unwrap();
+        let relabelled = Receipt { proof: Proof::new(4, padded_proof.siblings().to_vec()), ..committed.clone() };
+        assert_eq!(verify(policy.clone(), &relabelled), Err(PolicyViolation::InvalidProof));
+        // The parent of the element passed as a leaf, with the rest of the path as its proof
+        let parent = merkle.get_node(1, 2).unwrap();
+        let short = Receipt { proof: Proof::new(2, committed.proof.siblings()[1..].to_vec()), leaf: parent, ..committed };
+        assert_eq!(verify(policy, &short), Err(PolicyViolation::InvalidProof));
+    }
+
+    #[test]
+    /// Test if the rules are checked before the proof, so an invalid
+    /// receipt that breaks a rule reports the rule
+    fn policy_is_checked_first() {
+        let receipt = Receipt { leaf: 0, ..MerkleTree::new(DATA.to_vec()).receipt(0, None).unwrap() };
+        assert_eq!(verify(VerifierPolicy::new(), &receipt), Err(PolicyViolation::InvalidProof));
+        assert_eq!(verify(VerifierPolicy::new().max_depth(1), &receipt), Err(PolicyViolation::ProofTooDeep { max_depth: 1, depth: 3 }));
+    }
+
+    #[test]
+    /// Test if a policy is read from JSON with any subset of its rules,
+    /// and unknown or mistyped rules are errors
+    fn policy_from_json() {
+        let policy = VerifierPolicy::from_json(
+            r#"{"allowed_algorithms":["default","portable"],"require_tree_size":true,"max_depth":20,"domain_tag":"x","allow_padded_slots":false}"#,
+        )
+        .unwrap();
+        let expected = VerifierPolicy::new()
+            .allow_algorithm("default")
+            .allow_algorithm("portable")
+            .require_tree_size(true)
+            .max_depth(20)
+            .require_domain_tag("x")
+            .allow_padded_slots(false);
+        assert_eq!(policy, expected);
+        assert_eq!(VerifierPolicy::from_json("{}"), Ok(VerifierPolicy::new()));
+        assert!(VerifierPolicy::from_json(r#"{"max_dept":20}"#).is_err());
+        assert!(VerifierPolicy::from_json(r#"{"require_tree_size":"yes"}"#).is_err());
+        assert!(VerifierPolicy::from_json("[]").is_err());
+    }
+}
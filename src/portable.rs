@@ -0,0 +1,275 @@
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::merkle::{hash_with, verify_against_root_with, MerkleTree, PairOrdering, Proof};
+
+/// The keys of the SipHash used by `PortableHash`. They are fixed, so every
+/// process hashes alike.
+pub const PORTABLE_KEYS: (u64, u64) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+
+/// A BuildHasher whose hashes never change: SipHash-2-4 implemented in
+/// this crate with the fixed `PORTABLE_KEYS`.
+///
+/// The hashes of `DefaultBuildHasher` come from the `DefaultHasher` of the
+/// standard library, whose algorithm is not specified and may change
+/// between Rust releases. A root computed with it may not be reproducible
+/// by a verifier built with another toolchain, so roots that are published
+/// or stored for others should come from `MerkleTree::new_portable`.
+///
+/// Integers are hashed as little-endian bytes and usizes as u64, so the
+/// hashes are also the same on every platform. The bytes each element
+/// writes are still the ones of its `Hash` implementation, so elements are
+/// best given as bytes or strings.
+///
+/// ```
+/// use tree::MerkleTree;
+///
+/// let merkle = MerkleTree::new_portable(vec!["Crypto", "Merkle", "Rust"]);
+/// assert_eq!(merkle.root_hex().unwrap(), "c2677aa3926c17e5");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PortableHash;
+
+impl BuildHasher for PortableHash {
+    type Hasher = SipHasher24;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHasher24::new_with_keys(PORTABLE_KEYS.0, PORTABLE_KEYS.1)
+    }
+}
+
+/// SipHash-2-4, as specified by its authors: 2 rounds for each block of 8
+/// bytes and 4 rounds to finish.
+/// - `state`: The 4 words of the internal state.
+/// - `tail`: The bytes received that do not fill a block yet, as a little-endian word.
+/// - `tail_len`: Quantity of bytes in `tail`.
+/// - `len`: Quantity of bytes received.
+#[derive(Debug, Clone)]
+pub struct SipHasher24 {
+    state: [u64; 4],
+    tail: u64,
+    tail_len: usize,
+    len: usize,
+}
+
+impl SipHasher24 {
+    /// Creates a SipHasher24 with the 128 bits of key split in two words
+    pub fn new_with_keys(key0: u64, key1: u64) -> Self {
+        let state = [key0 ^ 0x736f6d6570736575, key1 ^ 0x646f72616e646f6d, key0 ^ 0x6c7967656e657261, key1 ^ 0x7465646279746573];
+        Self { state, tail: 0, tail_len: 0, len: 0 }
+    }
+
+    /// Mixes the 4 words of the state
+    fn round(&mut self) {
+        let [v0, v1, v2, v3] = &mut self.state;
+        *v0 = v0.wrapping_add(*v1);
+        *v1 = v1.rotate_left(13) ^ *v0;
+        *v0 = v0.rotate_left(32);
+        *v2 = v2.wrapping_add(*v3);
+        *v3 = v3.rotate_left(16) ^ *v2;
+        *v0 = v0.wrapping_add(*v3);
+        *v3 = v3.rotate_left(21) ^ *v0;
+        *v2 = v2.wrapping_add(*v1);
+        *v1 = v1.rotate_left(17) ^ *v2;
+        *v2 = v2.rotate_left(32);
+    }
+
+    /// Compresses a block of 8 bytes into the state
+    fn compress(&mut self, block: u64) {
+        self.state[3] ^= block;
+        self.round();
+        self.round();
+        self.state[0] ^= block;
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len();
+        // Complete the block that was left waiting
+        while self.tail_len > 0 && self.tail_len < 8 && !bytes.is_empty() {
+            self.tail |= (bytes[0] as u64) << (8 * self.tail_len);
+            self.tail_len += 1;
+            bytes = &bytes[1..];
+        }
+        if self.tail_len == 8 {
+            self.compress(self.tail);
+            self.tail = 0;
+            self.tail_len = 0;
+        }
+        let mut blocks = bytes.chunks_exact(8);
+        for block in &mut blocks {
+            self.compress(u64::from_le_bytes(block.try_into().expect("A chunk has 8 bytes")));
+        }
+        for byte in blocks.remainder() {
+            self.tail |= (*byte as u64) << (8 * self.tail_len);
+            self.tail_len += 1;
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        // finish can not change the hasher, so the last rounds use a copy
+        let mut last = self.clone();
+        let block = ((self.len as u64 & 0xff) << 56) | self.tail;
+        last.compress(block);
+        last.state[2] ^= 0xff;
+        for _ in 0..4 {
+            last.round();
+        }
+        let [v0, v1, v2, v3] = last.state;
+        v0 ^ v1 ^ v2 ^ v3
+    }
+}
+
+impl MerkleTree<PortableHash> {
+    /// Creates a new MerkleTree whose hashes are the same with every Rust
+    /// release and on every platform, see `PortableHash`. It is the tree to
+    /// use for roots and proofs that are published or stored for others.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTree instance
+    pub fn new_portable<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        Self::with_hasher(elements, PortableHash)
+    }
+}
+
+/// Hashes an element the way a tree created with `MerkleTree::new_portable`
+/// hashes its leaves
+pub fn portable_leaf<T: Hash>(element: T) -> u64 {
+    hash_with(&PortableHash, element)
+}
+
+/// Checks if a root can be obtained with the use of a proof and a leaf,
+/// without having the tree, for a tree created with `MerkleTree::new_portable`
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the element should belong to.
+/// - `len`: The quantity of different elements of the tree.
+/// - `proof`: The Proof of the element, as `generate_proof` returns it.
+/// - `leaf`: The hash of the element, as `portable_leaf` returns it.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_portable_against_root(root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    verify_against_root_with(&PortableHash, PairOrdering::Positional, root, len, proof, leaf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hashes raw bytes with SipHash-2-4 and the keys of its reference vectors
+    fn reference_hash(bytes: &[u8]) -> u64 {
+        let mut hasher = SipHasher24::new_with_keys(PORTABLE_KEYS.0, PORTABLE_KEYS.1);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    /// Test if the hasher gives the reference vectors of SipHash-2-4, whose
+    /// key is the bytes 0 to 15 and whose messages are the bytes 0 to n - 1
+    fn matches_reference_vectors() {
+        let message: Vec<u8> = (0..64).collect();
+        assert_eq!(reference_hash(&message[..0]), 0x726fdb47dd0e0e31);
+        assert_eq!(reference_hash(&message[..8]), 0x93f5f5799a932462);
+        assert_eq!(reference_hash(&message[..15]), 0xa129ca6149be45e5);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    /// Test if the hash of bytes written in pieces of any len is the one of
+    /// the SipHash-2-4 of the standard library
+    fn matches_std_siphash() {
+        let message: Vec<u8> = (0..200u8).map(|byte| byte.wrapping_mul(31)).collect();
+        for len in 0..message.len() {
+            for piece in [1, 3, 8, 13] {
+                let mut ours = SipHasher24::new_with_keys(1, 2);
+                let mut std = std::hash::SipHasher::new_with_keys(1, 2);
+                for chunk in message[..len].chunks(piece) {
+                    ours.write(chunk);
+                    std.write(chunk);
+                }
+                assert_eq!(ours.finish(), std.finish(), "len {len}, pieces of {piece}");
+            }
+        }
+    }
+
+    #[test]
+    /// Test if fixed inputs give the same roots they gave when this hasher
+    /// was added, so any change to the hashes of the tree is caught
+    fn golden_roots() {
+        let cases: [(&[&str], &str); 5] = [
+            (&["Crypto"], "0a6720abf14e9d77"),
+            (&["Crypto", "Merkle"], "353764030ed8b421"),
+            (&["Crypto", "Merkle", "Rust"], "c2677aa3926c17e5"),
+            (&["Crypto", "Merkle", "Rust", "Tree", "Test"], "c5912e6de6144a15"),
+            (&["a", "b", "c", "d", "e", "f", "g", "h"], "6d31267cfd9fc6a1"),
+        ];
+        for (elements, root) in cases {
+            assert_eq!(MerkleTree::new_portable(elements.to_vec()).root_hex().as_deref(), Some(root), "{elements:?}");
+        }
+        assert_eq!(crate::root_hash::to_hex(portable_leaf(1usize)), "754ab14b7e4d475f");
+    }
+
+    #[test]
+    /// Test if a portable tree verifies its proofs, also without the tree
+    fn verifies_portable_proofs() {
+        let data = ["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let mut merkle = MerkleTree::new_portable(data[..4].to_vec());
+        merkle.add_element(data[4]);
+        let root = merkle.root().unwrap();
+        for (index, element) in data.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            assert!(merkle.verify(&proof, portable_leaf(element)));
+            assert!(verify_portable_against_root(root, 5, &proof, portable_leaf(element)));
+            assert!(!verify_against_root_with(&crate::DefaultBuildHasher::default(), PairOrdering::Positional, root, 5, &proof, portable_leaf(element)));
+        }
+    }
+}
+
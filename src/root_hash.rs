@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::MerkleError;
+
+/// The root of a Merkle Tree.
+///
+/// Roots and leaves are both hashes, so keeping them as bare u64s makes it
+/// easy to pass one where the other was expected. Wrapping the root makes
+/// that a type error. It is formatted as (and parsed from) a fixed-width
+/// lowercase hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RootHash(u64);
+
+impl RootHash {
+    /// Wraps a raw hash that is known to be a root.
+    pub fn from_raw(hash: u64) -> Self {
+        Self(hash)
+    }
+
+    /// Gets the raw hash, losing the guarantee that it is a root.
+    pub fn into_raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for RootHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_hex(self.0))
+    }
+}
+
+impl fmt::LowerHex for RootHash {
+    /// Writes the 16 lowercase hex digits, prefixed with `0x` for `{:#x}`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if f.alternate() { "0x" } else { "" };
+        write!(f, "{prefix}{:016x}", self.0)
+    }
+}
+
+impl fmt::UpperHex for RootHash {
+    /// Writes the 16 uppercase hex digits, prefixed with `0x` for `{:#X}`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if f.alternate() { "0x" } else { "" };
+        write!(f, "{prefix}{:016X}", self.0)
+    }
+}
+
+impl FromStr for RootHash {
+    type Err = MerkleError;
+
+    /// Parses the 16 hex digits that `Display` produces, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_hex(s).map(Self)
+    }
+}
+
+/// Formats a hash as 16 lowercase hex digits
+pub(crate) fn to_hex(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// Parses a hash from exactly 16 hex digits, in either case
+pub(crate) fn parse_hex(s: &str) -> Result<u64, MerkleError> {
+    if s.len() != 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(MerkleError::InvalidHex(s.to_string()));
+    }
+    u64::from_str_radix(s, 16).map_err(|_| MerkleError::InvalidHex(s.to_string()))
+}
+
+/// Parses bytes written as two hex digits each, in either case
+pub(crate) fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, MerkleError> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(MerkleError::InvalidHex(s.to_string()));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| MerkleError::InvalidHex(s.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    #[test]
+    /// Test if formatting keeps the leading zeros and parsing gets
+    /// back the same root.
+    fn display_and_parse_round_trip() {
+        let root = RootHash::from_raw(0x00ab_cdef_0123_4567);
+
+        assert_eq!(root.to_string(), "00abcdef01234567");
+        assert_eq!("00abcdef01234567".parse::<RootHash>(), Ok(root));
+        assert_eq!("00ABCDEF01234567".parse::<RootHash>(), Ok(root));
+    }
+
+    #[test]
+    /// Test if the hex formats keep the leading zeros in both cases
+    fn hex_formats_keep_leading_zeros() {
+        let root = RootHash::from_raw(0x0000_00ef_0123_4567);
+
+        assert_eq!(format!("{root:x}"), "000000ef01234567");
+        assert_eq!(format!("{root:X}"), "000000EF01234567");
+        assert_eq!(format!("{root:#x}"), "0x000000ef01234567");
+        assert_eq!(format!("{:x}", RootHash::from_raw(0)), "0000000000000000");
+        assert_eq!(format!("{root:X}").parse::<RootHash>(), Ok(root));
+    }
+
+    #[test]
+    /// Test if strings that are not 16 hex digits are rejected
+    fn parse_rejects_invalid_strings() {
+        for input in ["", "abcdef", "00abcdef012345678", "00abcdef0123456g", "+0abcdef01234567"] {
+            assert_eq!(input.parse::<RootHash>(), Err(MerkleError::InvalidHex(input.to_string())));
+        }
+    }
+
+    #[test]
+    /// Test if the root of a tree survives a round trip through a string
+    fn tree_root_round_trip() {
+        let merkle = MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+        let root = merkle.root_hash().unwrap();
+        let parsed: RootHash = root.to_string().parse().unwrap();
+
+        let other = MerkleTree::new(vec!["Crypto", "Merkle", "Tree"]);
+
+        assert_eq!(parsed, root);
+        assert_ne!(other.root_hash(), Some(parsed));
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use crate::error::MerkleError;
+use crate::merkle::{hash_element, verify_against_root, MerkleTree, Proof};
+
+/// Len in bytes of each salt generated by `SaltedMerkleTree::with_leaf_salts`
+pub const GENERATED_SALT_LEN: usize = 16;
+
+/// The salts the leaves of a SaltedMerkleTree were hashed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Salts {
+    /// Every leaf uses the same salt
+    Tree(Vec<u8>),
+    /// Each leaf has its own salt, in the order of the elements
+    Leaves(Vec<Vec<u8>>),
+}
+
+/// The proof of an element of a SaltedMerkleTree, with the salt of its
+/// leaf when the prover chose to disclose it.
+/// - `proof`: The Proof of the leaf, which carries its index.
+/// - `salt`: The salt of the leaf, None if it was not bundled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaltedProof {
+    pub proof: Proof,
+    pub salt: Option<Vec<u8>>,
+}
+
+/// A Merkle Tree whose leaves are the hashes of a salt followed by each
+/// element, so a proof does not let its recipient guess the elements of
+/// the siblings when they have low entropy, like emails. Without the salt
+/// the hashes of the siblings can not be compared with the hashes of
+/// candidate elements.
+///
+/// The salt is either the same for the whole tree or generated for each
+/// leaf. Above the leaves the tree is the one of `MerkleTree::new`.
+/// - `tree`: The tree built from the salted leaves.
+/// - `salts`: The salt of the tree or of each leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaltedMerkleTree {
+    tree: MerkleTree,
+    salts: Salts,
+}
+
+impl SaltedMerkleTree {
+    /// Creates a new SaltedMerkleTree where every leaf is salted with the
+    /// same salt
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: The bytes of the elements that will be salted, hashed and form the first level in the tree.
+    /// - `salt`: The salt of the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A SaltedMerkleTree instance
+    pub fn with_tree_salt<T: AsRef<[u8]>>(elements: &[T], salt: &[u8]) -> Self {
+        let tree = MerkleTree::from_hashes(elements.iter().map(|element| salted_leaf(salt, element.as_ref())));
+        Self { tree, salts: Salts::Tree(salt.to_vec()) }
+    }
+
+    /// Creates a new SaltedMerkleTree where each leaf is salted with its
+    /// own salt of `GENERATED_SALT_LEN` bytes. The salts are taken from the
+    /// random keys of a `RandomState`, so they change from a tree to
+    /// another even with the same elements.
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: The bytes of the elements that will be salted, hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A SaltedMerkleTree instance
+    pub fn with_leaf_salts<T: AsRef<[u8]>>(elements: &[T]) -> Self {
+        let state = RandomState::new();
+        let salts: Vec<Vec<u8>> = (0..elements.len())
+            .map(|index| [state.hash_one((index, 0u8)), state.hash_one((index, 1u8))].iter().flat_map(|half| half.to_le_bytes()).collect())
+            .collect();
+        let tree = MerkleTree::from_hashes(elements.iter().zip(&salts).map(|(element, salt)| salted_leaf(salt, element.as_ref())));
+        Self { tree, salts: Salts::Leaves(salts) }
+    }
+
+    /// Gets the tree built from the salted leaves
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    /// Gets the root of the tree, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        self.tree.root()
+    }
+
+    /// Gets the quantity of elements of the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Gets the salt the leaf of an element was hashed with
+    ///
+    /// ### Returns
+    ///
+    /// The salt, or None if the index is not one of an element
+    pub fn salt(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.len() {
+            return None;
+        }
+        match &self.salts {
+            Salts::Tree(salt) => Some(salt),
+            Salts::Leaves(salts) => Some(&salts[index]),
+        }
+    }
+
+    /// Generates the proof of an element, bundling the salt of its leaf if
+    /// the prover wants to disclose it along with the element
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element.
+    /// - `bundle_salt`: If the salt of the leaf goes in the proof.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the SaltedProof, or an Error if the index is not one of an element
+    pub fn generate_proof(&self, index: usize, bundle_salt: bool) -> Result<SaltedProof, MerkleError> {
+        let proof = self.tree.generate_proof(index)?;
+        let salt = self.salt(index).filter(|_| bundle_salt).map(<[u8]>::to_vec);
+        Ok(SaltedProof { proof, salt })
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a
+    /// proof, the bytes of an element and the salt of its leaf
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The Proof of the element.
+    /// - `element`: The bytes of the element.
+    /// - `salt`: The salt of the leaf of the element.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &Proof, element: &[u8], salt: &[u8]) -> bool {
+        self.tree.verify(proof, salted_leaf(salt, element))
+    }
+}
+
+/// Checks if a root can be obtained with the use of a proof, the bytes of
+/// an element and the salt of its leaf, without having the tree
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the element should belong to.
+/// - `len`: The quantity of different elements of the tree.
+/// - `proof`: The Proof of the element.
+/// - `element`: The bytes of the element.
+/// - `salt`: The salt of the leaf of the element, like the one bundled in a SaltedProof.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_salted_against_root(root: u64, len: usize, proof: &Proof, element: &[u8], salt: &[u8]) -> bool {
+    verify_against_root(root, len, proof, salted_leaf(salt, element))
+}
+
+/// Hashes the salt followed by the element, the way a SaltedMerkleTree
+/// hashes its leaves. Both are hashed with their len, so moving bytes from
+/// the end of the salt to the start of the element changes the hash.
+pub fn salted_leaf(salt: &[u8], element: &[u8]) -> u64 {
+    hash_element((salt, element))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMAILS: [&str; 5] = ["alice@example.com", "bob@example.com", "carol@example.com", "dave@example.com", "erin@example.com"];
+
+    #[test]
+    /// Test if two trees over the same elements with different salts have
+    /// unrelated roots and leaves, and none of them is the unsalted tree.
+    fn different_salts_give_unrelated_roots() {
+        let first = SaltedMerkleTree::with_tree_salt(&EMAILS, b"first salt");
+        let second = SaltedMerkleTree::with_tree_salt(&EMAILS, b"second salt");
+        let unsalted = MerkleTree::new(EMAILS.iter().map(|email| email.as_bytes()).collect());
+
+        assert_ne!(first.root(), second.root());
+        assert_ne!(first.root(), unsalted.root());
+        for index in 0..EMAILS.len() {
+            assert_ne!(first.tree().get_leaf(index), second.tree().get_leaf(index));
+        }
+        assert_eq!(first.root(), SaltedMerkleTree::with_tree_salt(&EMAILS, b"first salt").root());
+
+        // Generated salts differ from a tree to another
+        let generated = SaltedMerkleTree::with_leaf_salts(&EMAILS);
+        assert_ne!(generated.root(), SaltedMerkleTree::with_leaf_salts(&EMAILS).root());
+        assert_ne!(generated.salt(0), generated.salt(1));
+        assert_eq!(generated.salt(0).map(<[u8]>::len), Some(GENERATED_SALT_LEN));
+    }
+
+    #[test]
+    /// Test if a proof only verifies with the salt of its leaf, which can
+    /// be bundled in the proof or left out of it.
+    fn proof_needs_the_right_salt() {
+        let salted = SaltedMerkleTree::with_tree_salt(&EMAILS, b"tree salt");
+        let proof = salted.generate_proof(2, false).unwrap();
+        assert_eq!(proof.salt, None);
+        assert!(salted.verify(&proof.proof, EMAILS[2].as_bytes(), b"tree salt"));
+        assert!(!salted.verify(&proof.proof, EMAILS[2].as_bytes(), b"wrong salt"));
+        assert!(!salted.verify(&proof.proof, EMAILS[2].as_bytes(), b""));
+        assert!(!salted.tree().verify(&proof.proof, hash_element(EMAILS[2].as_bytes())));
+
+        let generated = SaltedMerkleTree::with_leaf_salts(&EMAILS);
+        let root = generated.root().unwrap();
+        for (index, email) in EMAILS.iter().enumerate() {
+            let bundled = generated.generate_proof(index, true).unwrap();
+            let salt = bundled.salt.unwrap();
+            assert!(verify_salted_against_root(root, EMAILS.len(), &bundled.proof, email.as_bytes(), &salt));
+            let other_salt = generated.salt((index + 1) % EMAILS.len()).unwrap();
+            assert!(!verify_salted_against_root(root, EMAILS.len(), &bundled.proof, email.as_bytes(), other_salt));
+        }
+        assert_eq!(generated.generate_proof(8, true), Err(MerkleError::InvalidIndex { index: 8, len: 5 }));
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::error::MerkleError;
+use crate::merkle::{verify_against_root_with, PairOrdering, Proof};
+use crate::{MerkleTree, PortableHash, RootHash};
+
+/// Tag mixed into every draw so these hashes can not collide with the ones of the tree.
+const DOMAIN: &str = "merkle-tree/selection/v2";
+
+/// Selects `k` distinct indexes out of a committed set, deterministically.
+///
+/// The draw works in counter mode: for `counter = 0, 1, 2, ...` the value
+/// `draw_value(root, leaf_count, seed, counter)` is computed, which is
+/// SipHash-2-4 with the fixed `PORTABLE_KEYS` of the bytes of `DOMAIN`,
+/// followed by the root, the leaf count, the len of the seed, the seed and
+/// the counter, every integer as 8 little-endian bytes. It does not depend
+/// on the Rust release or the platform, so a verifier written in any
+/// language gets the same indexes.
+///
+/// To keep every index equally likely, values in the last incomplete
+/// multiple of `leaf_count` are rejected. Accepted values are reduced
+/// modulo `leaf_count`. Indexes that were already chosen are skipped until
+/// `k` distinct ones are selected. Anyone with the root, the leaf count
+/// and the seed can repeat the draw.
+///
+/// ### Arguments
+///
+/// - `root`: The published root of the set.
+/// - `leaf_count`: The quantity of elements in the set.
+/// - `seed`: The seed revealed after the root was published.
+/// - `k`: The quantity of indexes to select.
+///
+/// ### Returns
+///
+/// A Result with the selected indexes in the order they were drawn, or an
+/// Error if there are fewer than `k` elements
+pub fn select(root: RootHash, leaf_count: usize, seed: &[u8], k: usize) -> Result<Vec<usize>, MerkleError> {
+    if k > leaf_count {
+        return Err(MerkleError::SelectionTooLarge { k, len: leaf_count });
+    }
+    let n = leaf_count as u64;
+    let accepted_below = u64::MAX - (u64::MAX % n.max(1));
+    let mut chosen = HashSet::with_capacity(k);
+    let mut selection = Vec::with_capacity(k);
+    let mut counter: u64 = 0;
+    while selection.len() < k {
+        let value = draw_value(root.into_raw(), n, seed, counter);
+        counter += 1;
+        if value >= accepted_below {
+            continue;
+        }
+        let index = (value % n) as usize;
+        if chosen.insert(index) {
+            selection.push(index);
+        }
+    }
+    Ok(selection)
+}
+
+/// Computes one value of the draw, see `select`
+fn draw_value(root: u64, leaf_count: u64, seed: &[u8], counter: u64) -> u64 {
+    let mut hasher = PortableHash.build_hasher();
+    hasher.write(DOMAIN.as_bytes());
+    hasher.write(&root.to_le_bytes());
+    hasher.write(&leaf_count.to_le_bytes());
+    hasher.write(&(seed.len() as u64).to_le_bytes());
+    hasher.write(seed);
+    hasher.write(&counter.to_le_bytes());
+    hasher.finish()
+}
+
+/// A draw together with what is needed to check it: the seed, and for each
+/// winner its leaf and its inclusion proof.
+///
+/// The set is a tree created with `MerkleTree::new_portable`, so a third
+/// party can check the proofs with the same portable hasher as the draw,
+/// whatever Rust release or language it uses.
+/// - `seed`: The seed revealed after the root was published.
+/// - `winners`: The leaf and the Proof of each winner, in the order they
+///   were drawn. The index of each proof is the index of the winner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionProof {
+    pub seed: Vec<u8>,
+    pub winners: Vec<(u64, Proof)>,
+}
+
+impl SelectionProof {
+    /// Draws `k` winners from a tree and bundles their proofs
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the SelectionProof, or an Error if the tree has fewer than `k` elements
+    pub fn draw(tree: &MerkleTree<PortableHash>, seed: &[u8], k: usize) -> Result<Self, MerkleError> {
+        let root = tree.root_hash().ok_or(MerkleError::EmptyTree)?;
+        let winners = select(root, tree.len(), seed, k)?
+            .into_iter()
+            .map(|index| {
+                let leaf = tree.get_leaf(index).ok_or(MerkleError::InvalidIndex { index, len: tree.len() })?;
+                Ok((leaf, tree.generate_proof(index)?))
+            })
+            .collect::<Result<Vec<_>, MerkleError>>()?;
+        Ok(Self { seed: seed.to_vec(), winners })
+    }
+
+    /// Checks both that the winners are the ones the seed draws and that
+    /// each of them is part of the committed set.
+    ///
+    /// ### Arguments
+    ///
+    /// - `root`: The published root of the set.
+    /// - `leaf_count`: The published quantity of elements in the set. It must
+    ///   come from the same trusted source as the root.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the draw and every proof are valid, false
+    /// otherwise. A proof with more or fewer hashes than a tree of
+    /// `leaf_count` elements has is never valid, see `verify_against_root_with`.
+    pub fn verify(&self, root: RootHash, leaf_count: usize) -> bool {
+        let expected = match select(root, leaf_count, &self.seed, self.winners.len()) {
+            Ok(expected) => expected,
+            Err(_) => return false,
+        };
+        self.winners.len() == expected.len()
+            && self.winners.iter().zip(expected).all(|((leaf, proof), index)| {
+                proof.leaf_index() == index
+                    && verify_against_root_with(&PortableHash, PairOrdering::Positional, root.into_raw(), leaf_count, proof, *leaf)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{combine_with, Side};
+
+    fn entries(len: usize) -> Vec<String> {
+        (0..len).map(|i| format!("entry-{i}@example.com")).collect()
+    }
+
+    #[test]
+    /// Test if the same root and seed always select the same distinct indexes
+    fn selection_is_deterministic_and_distinct() {
+        let merkle = MerkleTree::new(entries(50));
+        let root = merkle.root_hash().unwrap();
+        let first = select(root, 50, b"block 840000", 10).unwrap();
+        let second = select(root, 50, b"block 840000", 10).unwrap();
+        let mut unique = first.clone();
+        unique.sort();
+        unique.dedup();
+
+        assert_eq!(first, second);
+        assert_eq!(unique.len(), 10);
+        assert!(first.iter().all(|index| *index < 50));
+        // Selecting everything gives a permutation
+        let mut all = select(root, 50, b"block 840000", 50).unwrap();
+        all.sort();
+        assert_eq!(all, (0..50).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    /// Test if changing the seed changes the selection
+    fn different_seed_different_selection() {
+        let merkle = MerkleTree::new(entries(1000));
+        let root = merkle.root_hash().unwrap();
+
+        assert_ne!(select(root, 1000, b"seed one", 5).unwrap(), select(root, 1000, b"seed two", 5).unwrap());
+    }
+
+    #[test]
+    /// Test if a drawn proof verifies, and a proof for another set does not
+    fn proof_verifies_draw_and_membership() {
+        let merkle = MerkleTree::new_portable(entries(100));
+        let proof = SelectionProof::draw(&merkle, b"seed", 3).unwrap();
+        assert!(proof.verify(merkle.root_hash().unwrap(), 100));
+
+        // A set with one altered entry has another root, so the same winners
+        // are either not the ones drawn or their proofs fail.
+        let mut altered = entries(100);
+        altered[42] = String::from("mallory@example.com");
+        let altered_root = MerkleTree::new_portable(altered).root_hash().unwrap();
+        assert!(!proof.verify(altered_root, 100));
+        assert_ne!(select(altered_root, 100, b"seed", 3).unwrap(), select(merkle.root_hash().unwrap(), 100, b"seed", 3).unwrap());
+    }
+
+    #[test]
+    /// Test if swapping a winner for another entry is detected
+    fn tampered_winner_fails() {
+        let merkle = MerkleTree::new_portable(entries(100));
+        let mut proof = SelectionProof::draw(&merkle, b"seed", 3).unwrap();
+        let index = (proof.winners[0].1.leaf_index() + 1) % 100;
+        proof.winners[0] = (merkle.get_leaf(index).unwrap(), merkle.generate_proof(index).unwrap());
+
+        assert!(!proof.verify(merkle.root_hash().unwrap(), 100));
+    }
+
+    #[test]
+    /// Test if the draw gives the indexes an independent implementation of
+    /// the derivation gives, so it can not change with a Rust release
+    fn selection_matches_golden_indexes() {
+        let root = RootHash::from_raw(0x0123456789abcdef);
+
+        assert_eq!(select(root, 1000, b"block 840000", 5).unwrap(), [442, 896, 273, 480, 217]);
+        assert_eq!(select(root, 7, b"", 7).unwrap(), [1, 5, 0, 2, 3, 6, 4]);
+    }
+
+    #[test]
+    /// Test if a winner whose proof has an extra hash does not verify even
+    /// if the hashes fold to the root
+    fn wrong_proof_len_fails() {
+        let merkle = MerkleTree::new_portable(entries(1));
+        let mut proof = SelectionProof::draw(&merkle, b"seed", 1).unwrap();
+        let root = merkle.root_hash().unwrap();
+        assert!(proof.verify(root, 1));
+
+        // A single leaf is its own root, so it would fold to a node that
+        // has it as a child
+        let leaf = proof.winners[0].0;
+        let forged_root = RootHash::from_raw(combine_with(&PortableHash, PairOrdering::Positional, leaf, leaf));
+        proof.winners[0].1 = Proof::new(0, vec![(leaf, Side::Right)]);
+        assert!(!proof.verify(forged_root, 1));
+    }
+
+    #[test]
+    /// Test if selecting more elements than the set has fails
+    fn selection_too_large() {
+        let root = MerkleTree::new(entries(3)).root_hash().unwrap();
+
+        assert_eq!(select(root, 3, b"seed", 4), Err(MerkleError::SelectionTooLarge { k: 4, len: 3 }));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::digest_tree::{Bytes32, Digest, Double, DigestTree, OddNode};
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INITIAL_STATE: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
+/// Hashes some bytes with SHA-256.
+///
+/// ### Arguments
+///
+/// - `data`: The bytes to hash.
+///
+/// ### Returns
+///
+/// The 32 bytes of the hash
+pub fn sha256(data: &[u8]) -> Bytes32 {
+    let mut state = INITIAL_STATE;
+    let mut blocks = data.chunks_exact(64);
+    for block in &mut blocks {
+        compress(&mut state, block);
+    }
+
+    // The remaining bytes, a 1 bit, zeros and the length in bits take one or two blocks
+    let remainder = blocks.remainder();
+    let mut last = [0u8; 128];
+    last[..remainder.len()].copy_from_slice(remainder);
+    last[remainder.len()] = 0x80;
+    let last_len = if remainder.len() < 56 { 64 } else { 128 };
+    last[last_len - 8..last_len].copy_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for block in last[..last_len].chunks_exact(64) {
+        compress(&mut state, block);
+    }
+
+    let mut output = [0u8; 32];
+    for (bytes, word) in output.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    output
+}
+
+/// The SHA-256 compression function over a block of 64 bytes
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut schedule = [0u32; 64];
+    for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().expect("chunks of 4 bytes"));
+    }
+    for i in 16..64 {
+        let s0 = schedule[i - 15].rotate_right(7) ^ schedule[i - 15].rotate_right(18) ^ (schedule[i - 15] >> 3);
+        let s1 = schedule[i - 2].rotate_right(17) ^ schedule[i - 2].rotate_right(19) ^ (schedule[i - 2] >> 10);
+        schedule[i] = schedule[i - 16].wrapping_add(s0).wrapping_add(schedule[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (round_constant, word) in ROUND_CONSTANTS.iter().zip(schedule) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let temp1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(*round_constant).wrapping_add(word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+/// The SHA-256 backend of a DigestTree
+pub struct Sha256;
+
+impl Digest for Sha256 {
+    fn digest(data: &[u8]) -> Bytes32 {
+        sha256(data)
+    }
+}
+
+/// The hashes of RFC 6962 (Certificate Transparency): a leaf is SHA-256 of
+/// a 0x00 byte and its data, and a parent is SHA-256 of a 0x01 byte and its
+/// two children, so a leaf can never be passed off as a parent. RFC 6962
+/// trees go up with the last node of every odd level unchanged, see
+/// `OddNode::Promote`.
+pub struct Rfc6962;
+
+impl Digest for Rfc6962 {
+    fn digest(data: &[u8]) -> Bytes32 {
+        let mut prefixed = Vec::with_capacity(data.len() + 1);
+        prefixed.push(0x00);
+        prefixed.extend_from_slice(data);
+        sha256(&prefixed)
+    }
+
+    fn combine(left: &Bytes32, right: &Bytes32) -> Bytes32 {
+        let mut prefixed = [0u8; 65];
+        prefixed[0] = 0x01;
+        prefixed[1..33].copy_from_slice(left);
+        prefixed[33..].copy_from_slice(right);
+        sha256(&prefixed)
+    }
+}
+
+/// The hashes of Bitcoin: SHA-256 applied twice (SHA256d), with the last
+/// node of every level with an odd len combined with itself, see
+/// `OddNode::Duplicate`.
+pub struct Bitcoin;
+
+impl Digest for Bitcoin {
+    const ODD_NODE: OddNode = OddNode::Duplicate;
+
+    fn digest(data: &[u8]) -> Bytes32 {
+        Double::<Sha256>::digest(data)
+    }
+}
+
+/// A Merkle Tree that hashes like Bitcoin: every node is SHA-256 applied
+/// twice (SHA256d) to the 64 bytes of its children, and the last node of
+/// every odd level is duplicated, so its root is the merkle root of a block
+/// for any quantity of transactions.
+///
+/// The leaves of a block are its txids, which are already hashed, so the
+/// tree is built with `from_leaf_hashes`. Txids and roots are shown by
+/// Bitcoin with their bytes reversed.
+pub type BitcoinTree = DigestTree<Bitcoin>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest_tree::{root_of, to_hex as hex, OddNode};
+
+    /// Reads a hash in the reversed byte order Bitcoin shows them in
+    fn from_display_hex(text: &str) -> Bytes32 {
+        let mut hash = [0u8; 32];
+        for (i, byte) in hash.iter_mut().rev().enumerate() {
+            *byte = u8::from_str_radix(&text[2 * i..2 * i + 2], 16).unwrap();
+        }
+        hash
+    }
+
+    #[test]
+    /// Test SHA-256 against published vectors, including inputs whose
+    /// padding takes one more block.
+    fn sha256_matches_known_vectors() {
+        assert_eq!(hex(&sha256(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(
+            hex(&sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(hex(&sha256(&[b'a'; 1000])), "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3");
+    }
+
+    #[test]
+    /// Test the root of block 100000, which has 4 transactions
+    fn reproduces_block_100000_merkle_root() {
+        let txids = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ];
+        let merkle = BitcoinTree::from_leaf_hashes(txids.iter().map(|txid| from_display_hex(txid)).collect()).unwrap();
+
+        let expected_root = from_display_hex("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
+        assert_eq!(merkle.root(), expected_root);
+
+        let proof = merkle.generate_proof(2).unwrap();
+        assert!(merkle.verify(&proof, 2, from_display_hex(txids[2])));
+    }
+
+    #[test]
+    /// Test the root of block 170, the first one with a transaction
+    /// that is not a coinbase
+    fn reproduces_block_170_merkle_root() {
+        let txids = [
+            "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082",
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+        ];
+        let merkle = BitcoinTree::from_leaf_hashes(txids.iter().map(|txid| from_display_hex(txid)).collect()).unwrap();
+
+        let expected_root = from_display_hex("7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff");
+        assert_eq!(merkle.root(), expected_root);
+    }
+
+    #[test]
+    /// Test if odd levels are completed like Bitcoin does, also for 6
+    /// transactions, where repeating the last txid gives another root.
+    /// The roots were computed with Bitcoin's algorithm over the txids of
+    /// blocks 100000 and 170, which it reproduces for block 100000.
+    fn duplicates_the_last_node_of_odd_levels() {
+        let txids = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+            "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082",
+            "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+        ];
+        let roots = [
+            (5, "69b063f99f6228dfd0fd3a40b2e662ba67e48675abf0f1bc1d11f7e3b2d9d5c1"),
+            (6, "533e06a6ad689157be0109a51b8fe778d297a2531100f022ba7460a4a00d51e3"),
+        ];
+        for (len, root) in roots {
+            let leaf_hashes: Vec<Bytes32> = txids[..len].iter().map(|txid| from_display_hex(txid)).collect();
+            let merkle = BitcoinTree::from_leaf_hashes(leaf_hashes.clone()).unwrap();
+            assert_eq!(merkle.root(), from_display_hex(root), "{len} transactions");
+            for (index, leaf) in leaf_hashes.into_iter().enumerate() {
+                let proof = merkle.generate_proof(index).unwrap();
+                assert!(merkle.verify(&proof, index, leaf), "{len} transactions, index {index}");
+            }
+        }
+        let leaf_hashes = txids.iter().map(|txid| from_display_hex(txid)).collect();
+        assert_ne!(root_of::<Double<Sha256>>(leaf_hashes, OddNode::RepeatLast).unwrap(), from_display_hex(roots[1].1));
+    }
+
+    #[test]
+    /// Test the roots of the first 1 to 8 leaves of the reference tree of
+    /// Certificate Transparency
+    fn reproduces_rfc6962_reference_roots() {
+        let leaves: [&[u8]; 8] = [
+            &[],
+            &[0x00],
+            &[0x10],
+            &[0x20, 0x21],
+            &[0x30, 0x31],
+            &[0x40, 0x41, 0x42, 0x43],
+            &[0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57],
+            &[0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f],
+        ];
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+        for (len, root) in (1..=8).zip(roots) {
+            let leaf_hashes = leaves[..len].iter().map(|leaf| Rfc6962::digest(leaf)).collect();
+            assert_eq!(hex(&root_of::<Rfc6962>(leaf_hashes, OddNode::Promote).unwrap()), root, "{len} leaves");
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::error::MerkleError;
+use crate::merkle::{DefaultBuildHasher, MerkleTree, Proof};
+
+/// A MerkleTree that can be read and changed from many threads at once,
+/// like a writer appending elements while readers generate proofs.
+///
+/// Cloning it gives another handle to the same tree. Reads take a read
+/// lock, so they run alongside each other, and mutations take a write
+/// lock, so a reader never sees a tree in the middle of a mutation. Each
+/// method takes the lock once, so to get a proof together with the root
+/// it verifies against use `generate_proof_with_root`: calling `root` and
+/// `generate_proof` separately may see two versions of the tree.
+/// - `tree`: The tree shared by every handle.
+pub struct SharedMerkleTree<H = DefaultBuildHasher> {
+    tree: Arc<RwLock<MerkleTree<H>>>,
+}
+
+impl SharedMerkleTree {
+    /// Creates a new SharedMerkleTree
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A SharedMerkleTree instance
+    pub fn new<T: Hash + Clone>(elements: Vec<T>) -> Self {
+        Self::from_tree(MerkleTree::new(elements))
+    }
+}
+
+impl<H> SharedMerkleTree<H> {
+    /// Creates a SharedMerkleTree that shares a tree already built
+    pub fn from_tree(tree: MerkleTree<H>) -> Self {
+        Self { tree: Arc::new(RwLock::new(tree)) }
+    }
+
+    /// Takes the read lock. A writer that panicked may have left the tree
+    /// half changed, so the panic is propagated instead of reading it.
+    fn read_lock(&self) -> RwLockReadGuard<'_, MerkleTree<H>> {
+        self.tree.read().expect("A writer panicked while changing the tree")
+    }
+
+    /// Takes the write lock, see `read_lock`
+    fn write_lock(&self) -> RwLockWriteGuard<'_, MerkleTree<H>> {
+        self.tree.write().expect("A writer panicked while changing the tree")
+    }
+
+    /// Runs a function with the tree under the read lock, for the reads
+    /// that have no method of their own. Everything it reads is from the
+    /// same version of the tree.
+    pub fn read<R>(&self, f: impl FnOnce(&MerkleTree<H>) -> R) -> R {
+        f(&self.read_lock())
+    }
+
+    /// Runs a function with the tree under the write lock, for the
+    /// mutations that have no method of their own
+    pub fn write<R>(&self, f: impl FnOnce(&mut MerkleTree<H>) -> R) -> R {
+        f(&mut self.write_lock())
+    }
+}
+
+impl<H: BuildHasher> SharedMerkleTree<H> {
+    /// Gets the root of the tree, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        self.read_lock().root()
+    }
+
+    /// Gets the quantity of different elements of the tree
+    pub fn len(&self) -> usize {
+        self.read_lock().len()
+    }
+
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.read_lock().is_empty()
+    }
+
+    /// Generates the Proof for an element of the tree, see `MerkleTree::generate_proof`
+    pub fn generate_proof(&self, index: usize) -> Result<Proof, MerkleError> {
+        self.read_lock().generate_proof(index)
+    }
+
+    /// Generates the Proof for an element together with the root of the
+    /// same version of the tree, so the proof always verifies against it
+    /// even if elements are added meanwhile
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the base level.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the Proof and the root, or an Error if the index is not one of an element
+    pub fn generate_proof_with_root(&self, index: usize) -> Result<(Proof, u64), MerkleError> {
+        let tree = self.read_lock();
+        let proof = tree.generate_proof(index)?;
+        let root = tree.root().ok_or(MerkleError::EmptyTree)?;
+        Ok((proof, root))
+    }
+
+    /// Checks a proof against the current root, see `MerkleTree::verify`
+    pub fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+        self.read_lock().verify(proof, leaf)
+    }
+
+    /// Adds an element at the end of the tree, see `MerkleTree::add_element`
+    pub fn add_element<T: Hash + Clone>(&self, new_elem: T) {
+        self.write_lock().add_element(new_elem);
+    }
+
+    /// Adds many elements at the end of the tree at once, see `MerkleTree::add_elements`
+    pub fn add_elements<T: Hash + Clone>(&self, new_elems: Vec<T>) {
+        self.write_lock().add_elements(new_elems);
+    }
+
+    /// Replaces an element of the tree, see `MerkleTree::update_element`
+    pub fn update_element<T: Hash>(&self, index: usize, new_elem: T) -> Result<(), MerkleError> {
+        self.write_lock().update_element(index, new_elem)
+    }
+
+    /// Removes the last element of the tree, see `MerkleTree::pop_element`
+    pub fn pop_element(&self) -> Option<u64> {
+        self.write_lock().pop_element()
+    }
+}
+
+impl<H: Clone> SharedMerkleTree<H> {
+    /// Copies the tree as it is now, so it can be read without holding
+    /// the lock while it keeps changing
+    pub fn snapshot(&self) -> MerkleTree<H> {
+        self.read_lock().clone()
+    }
+}
+
+impl<H> Clone for SharedMerkleTree<H> {
+    /// Creates another handle to the same tree
+    fn clone(&self) -> Self {
+        Self { tree: Arc::clone(&self.tree) }
+    }
+}
+
+impl<H: BuildHasher> fmt::Debug for SharedMerkleTree<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedMerkleTree").field(&*self.read_lock()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::{hash_element, verify_against_root};
+    use std::collections::HashMap;
+    use std::thread;
+
+    /// A xorshift generator, so the readers pick indexes without a crate
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    /// Test if the handles share the tree, and each method gives what the
+    /// tree itself gives.
+    fn handles_share_the_tree() {
+        let data = vec!["Crypto", "Merkle", "Rust"];
+        let shared = SharedMerkleTree::new(data.clone());
+        let other = shared.clone();
+        other.add_element("Tree");
+        other.add_elements(vec!["Test", "Hash"]);
+        other.update_element(1, "Root").unwrap();
+        assert_eq!(other.pop_element(), Some(hash_element("Hash")));
+
+        let expected = MerkleTree::new(vec!["Crypto", "Root", "Rust", "Tree", "Test"]);
+        assert_eq!(shared.snapshot(), expected);
+        assert_eq!((shared.root(), shared.len(), shared.is_empty()), (expected.root(), 5, false));
+        let (proof, root) = shared.generate_proof_with_root(2).unwrap();
+        assert_eq!((&proof, root), (&expected.generate_proof(2).unwrap(), expected.root().unwrap()));
+        assert!(shared.verify(&proof, hash_element("Rust")));
+        assert_eq!(shared.read(MerkleTree::height), expected.height());
+        assert_eq!(shared.update_element(5, "Leaf"), Err(MerkleError::InvalidIndex { index: 5, len: 5 }));
+    }
+
+    #[test]
+    /// Test if proofs generated by several readers while a writer appends
+    /// elements always verify against the root given with them, and that
+    /// root is one the tree really had.
+    fn concurrent_appends_and_proofs() {
+        const APPENDS: usize = 2000;
+        let mut sequential = MerkleTree::new(vec![0usize]);
+        let mut roots = HashMap::from([(sequential.root().unwrap(), 1)]);
+        for element in 1..=APPENDS {
+            sequential.add_element(element);
+            roots.insert(sequential.root().unwrap(), element + 1);
+        }
+
+        let shared = SharedMerkleTree::new(vec![0usize]);
+        thread::scope(|scope| {
+            let writer = shared.clone();
+            scope.spawn(move || {
+                for element in 1..=APPENDS {
+                    writer.add_element(element);
+                }
+            });
+            for reader in 0..4u64 {
+                let (shared, roots) = (shared.clone(), &roots);
+                scope.spawn(move || {
+                    let mut state = reader + 1;
+                    while shared.len() <= APPENDS {
+                        let index = next_random(&mut state) as usize % shared.len();
+                        let (proof, root) = shared.generate_proof_with_root(index).unwrap();
+                        let len = roots[&root];
+                        assert!(verify_against_root(root, len, &proof, hash_element(index)));
+                    }
+                });
+            }
+        });
+        assert_eq!(shared.snapshot(), sequential);
+    }
+}
@@ -0,0 +1,288 @@
+//! Generates the Solidity library that verifies the proofs of a tree, and
+//! the arguments it receives for each proof.
+//!
+//! The library hashes exactly like the configured DigestTree: `KeccakTree`
+//! or `Sha256` trees, `Sorted` pairs or positional ones, and leaves given
+//! as their hash or as the raw bytes of the element.
+
+use std::fmt::Write;
+
+use crate::digest_tree::{Bytes32, to_hex};
+use crate::error::MerkleError;
+use crate::json::Json;
+use crate::verify::PairOrdering;
+
+/// The hash function a tree was built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The u64 `DefaultHasher` of `MerkleTree`.
+    DefaultHasher,
+    /// The u64 `PortableHash` of `MerkleTree::new_portable`.
+    Portable,
+    /// The BLAKE3 DigestTree.
+    Blake3,
+    /// The Keccak-256 DigestTree, `KeccakTree`.
+    Keccak256,
+    /// The SHA-256 DigestTree.
+    Sha256,
+}
+
+impl Backend {
+    /// Gets the Solidity function with the same output, or None if the
+    /// EVM can not compute it
+    fn solidity_function(self) -> Option<&'static str> {
+        match self {
+            Backend::Keccak256 => Some("keccak256"),
+            Backend::Sha256 => Some("sha256"),
+            Backend::DefaultHasher | Backend::Portable | Backend::Blake3 => None,
+        }
+    }
+}
+
+/// How the verifier receives the leaf being proven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafEncoding {
+    /// The digest of the element, as a `bytes32`.
+    Hash,
+    /// The raw bytes of the element, which the verifier hashes.
+    Bytes,
+}
+
+/// How a tree was built, which the generated verifier must match.
+/// - `backend`: The hash function of the leaves and the parents.
+/// - `ordering`: How the children of each node are ordered.
+/// - `leaf`: How the verifier receives the leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifierConfig {
+    pub backend: Backend,
+    pub ordering: PairOrdering,
+    pub leaf: LeafEncoding,
+}
+
+impl VerifierConfig {
+    /// Gets the Solidity hash function of the backend
+    fn function(&self) -> Result<&'static str, MerkleError> {
+        self.backend
+            .solidity_function()
+            .ok_or_else(|| MerkleError::UnsupportedOnChain(format!("{:?} has no equivalent that Solidity can compute", self.backend)))
+    }
+}
+
+/// Generates a Solidity library with a `verify` function that checks the
+/// proofs of a tree built as the config says.
+///
+/// With positional pairs `verify` also receives a directions bitmask,
+/// where the bit `i` is 1 when `proof[i]` goes on the left. It is the
+/// index of the leaf, and it is rejected if it has bits above the proof.
+///
+/// ### Arguments
+///
+/// - `config`: How the tree was built.
+///
+/// ### Returns
+///
+/// A Result with the source of the library, or an Error if the backend
+/// can not be computed on-chain
+pub fn generate_verifier(config: &VerifierConfig) -> Result<String, MerkleError> {
+    let function = config.function()?;
+    let hash_pair = |left: &str, right: &str| format!("{function}(abi.encodePacked({left}, {right}))");
+    let (leaf_param, leaf_hash) = match config.leaf {
+        LeafEncoding::Hash => ("bytes32 leaf", "leaf".to_string()),
+        LeafEncoding::Bytes => ("bytes calldata data", format!("{function}(data)")),
+    };
+    let (ordering, directions_param, directions_doc) = match config.ordering {
+        PairOrdering::Positional => (
+            "positional",
+            ", uint256 directions",
+            "    /// @param directions Bit i is 1 when proof[i] is the left child, the index of the leaf.\n",
+        ),
+        PairOrdering::Sorted => ("sorted", "", ""),
+    };
+    let leaf_name = match config.leaf {
+        LeafEncoding::Hash => "hashed",
+        LeafEncoding::Bytes => "raw",
+    };
+
+    let mut source = String::new();
+    writeln!(source, "// SPDX-License-Identifier: MIT").unwrap();
+    writeln!(source, "// Verifies proofs of a tree with {function}, {ordering} pairs and {leaf_name} leaves.").unwrap();
+    writeln!(source, "pragma solidity ^0.8.0;").unwrap();
+    writeln!(source).unwrap();
+    writeln!(source, "library MerkleVerifier {{").unwrap();
+    writeln!(source, "    /// @notice Checks that the leaf is in the tree with the root.").unwrap();
+    source.push_str(directions_doc);
+    writeln!(
+        source,
+        "    function verify(bytes32[] calldata proof, bytes32 root, {leaf_param}{directions_param}) internal pure returns (bool) {{"
+    )
+    .unwrap();
+    if config.ordering == PairOrdering::Positional {
+        writeln!(source, "        if (directions >> proof.length != 0) {{").unwrap();
+        writeln!(source, "            return false;").unwrap();
+        writeln!(source, "        }}").unwrap();
+    }
+    writeln!(source, "        bytes32 node = {leaf_hash};").unwrap();
+    writeln!(source, "        for (uint256 i = 0; i < proof.length; i++) {{").unwrap();
+    match config.ordering {
+        PairOrdering::Positional => {
+            writeln!(source, "            if ((directions >> i) & 1 == 0) {{").unwrap();
+            writeln!(source, "                node = {};", hash_pair("node", "proof[i]")).unwrap();
+            writeln!(source, "            }} else {{").unwrap();
+            writeln!(source, "                node = {};", hash_pair("proof[i]", "node")).unwrap();
+            writeln!(source, "            }}").unwrap();
+        }
+        PairOrdering::Sorted => {
+            writeln!(source, "            bytes32 sibling = proof[i];").unwrap();
+            writeln!(source, "            node = node <= sibling ? {} : {};", hash_pair("node", "sibling"), hash_pair("sibling", "node"))
+                .unwrap();
+        }
+    }
+    writeln!(source, "        }}").unwrap();
+    writeln!(source, "        return node == root;").unwrap();
+    writeln!(source, "    }}").unwrap();
+    writeln!(source, "}}").unwrap();
+    Ok(source)
+}
+
+/// Writes the arguments of the generated `verify` for a proof, in order,
+/// as a JSON array with every hash and byte string as 0x prefixed hex and
+/// the directions as a decimal string, like ethers and cast take them.
+///
+/// ### Arguments
+///
+/// - `config`: How the tree was built.
+/// - `root`: The root of the tree.
+/// - `proof`: The hashes of the proof, as `DigestTree::generate_proof` gives them.
+/// - `index`: The index of the leaf.
+/// - `leaf`: The digest of the element, or its raw bytes if the leaf encoding is `Bytes`.
+///
+/// ### Returns
+///
+/// A Result with the arguments, or an Error if the backend can not be
+/// computed on-chain or a leaf given as a hash does not have 32 bytes
+pub fn export_calldata(
+    config: &VerifierConfig,
+    root: &Bytes32,
+    proof: &[Bytes32],
+    index: usize,
+    leaf: &[u8],
+) -> Result<String, MerkleError> {
+    config.function()?;
+    if config.leaf == LeafEncoding::Hash && leaf.len() != 32 {
+        return Err(MerkleError::InvalidEncoding(format!("A leaf hash has 32 bytes, not {}", leaf.len())));
+    }
+    let hex = |bytes: &[u8]| Json::from(format!("0x{}", to_hex(bytes)));
+    let mut arguments = vec![Json::Array(proof.iter().map(|hash| hex(hash)).collect()), hex(root), hex(leaf)];
+    if config.ordering == PairOrdering::Positional {
+        arguments.push(Json::from(index.to_string()));
+    }
+    Ok(Json::Array(arguments).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::{env, fs, io};
+
+    const CONFIGS: [(Backend, PairOrdering, LeafEncoding, &str); 8] = [
+        (Backend::Keccak256, PairOrdering::Positional, LeafEncoding::Hash, include_str!("../tests/solidity/keccak256_positional_hash.sol")),
+        (
+            Backend::Keccak256,
+            PairOrdering::Positional,
+            LeafEncoding::Bytes,
+            include_str!("../tests/solidity/keccak256_positional_bytes.sol"),
+        ),
+        (Backend::Keccak256, PairOrdering::Sorted, LeafEncoding::Hash, include_str!("../tests/solidity/keccak256_sorted_hash.sol")),
+        (Backend::Keccak256, PairOrdering::Sorted, LeafEncoding::Bytes, include_str!("../tests/solidity/keccak256_sorted_bytes.sol")),
+        (Backend::Sha256, PairOrdering::Positional, LeafEncoding::Hash, include_str!("../tests/solidity/sha256_positional_hash.sol")),
+        (Backend::Sha256, PairOrdering::Positional, LeafEncoding::Bytes, include_str!("../tests/solidity/sha256_positional_bytes.sol")),
+        (Backend::Sha256, PairOrdering::Sorted, LeafEncoding::Hash, include_str!("../tests/solidity/sha256_sorted_hash.sol")),
+        (Backend::Sha256, PairOrdering::Sorted, LeafEncoding::Bytes, include_str!("../tests/solidity/sha256_sorted_bytes.sol")),
+    ];
+
+    #[test]
+    /// Test if the verifier of every configuration matches its golden file
+    fn verifiers_match_golden_files() {
+        for (backend, ordering, leaf, golden) in CONFIGS {
+            let config = VerifierConfig { backend, ordering, leaf };
+            assert_eq!(generate_verifier(&config).unwrap(), golden, "{config:?}");
+        }
+    }
+
+    #[test]
+    /// Test if the verifier hashes pairs and leaves with the function of
+    /// its backend, and only sorts the pairs when the tree does.
+    fn verifiers_hash_like_the_backend() {
+        for (backend, ordering, leaf, _) in CONFIGS {
+            let source = generate_verifier(&VerifierConfig { backend, ordering, leaf }).unwrap();
+            let function = backend.solidity_function().unwrap();
+            assert!(source.contains(&format!("{function}(abi.encodePacked(")));
+            assert_eq!(source.contains(&format!("{function}(data)")), leaf == LeafEncoding::Bytes);
+            assert_eq!(source.contains("node <= sibling"), ordering == PairOrdering::Sorted);
+            assert_eq!(source.contains("uint256 directions"), ordering == PairOrdering::Positional);
+        }
+    }
+
+    #[test]
+    /// Test if the backends that the EVM can not compute are refused
+    fn refuses_backends_not_verifiable_on_chain() {
+        for backend in [Backend::DefaultHasher, Backend::Portable, Backend::Blake3] {
+            let config = VerifierConfig { backend, ordering: PairOrdering::Positional, leaf: LeafEncoding::Hash };
+            assert!(matches!(generate_verifier(&config), Err(MerkleError::UnsupportedOnChain(_))));
+            assert!(matches!(export_calldata(&config, &[0; 32], &[], 0, &[0; 32]), Err(MerkleError::UnsupportedOnChain(_))));
+        }
+    }
+
+    #[test]
+    /// Test if the arguments follow the order of `verify`, with the
+    /// directions only for positional pairs
+    fn calldata_matches_the_signature() {
+        let (root, proof) = ([0xaa; 32], [[0x01; 32], [0x02; 32]]);
+        let positional = VerifierConfig { backend: Backend::Keccak256, ordering: PairOrdering::Positional, leaf: LeafEncoding::Hash };
+        let expected =
+            format!("[[\"0x{}\",\"0x{}\"],\"0x{}\",\"0x{}\",\"2\"]", "01".repeat(32), "02".repeat(32), "aa".repeat(32), "0b".repeat(32));
+        assert_eq!(export_calldata(&positional, &root, &proof, 2, &[0x0b; 32]).unwrap(), expected);
+
+        let sorted = VerifierConfig { ordering: PairOrdering::Sorted, leaf: LeafEncoding::Bytes, ..positional };
+        let expected = format!("[[\"0x{}\",\"0x{}\"],\"0x{}\",\"0x437279\"]", "01".repeat(32), "02".repeat(32), "aa".repeat(32));
+        assert_eq!(export_calldata(&sorted, &root, &proof, 2, b"Cry").unwrap(), expected);
+
+        assert!(matches!(export_calldata(&positional, &root, &proof, 2, b"Cry"), Err(MerkleError::InvalidEncoding(_))));
+    }
+
+    #[cfg(feature = "keccak")]
+    #[test]
+    /// Test if the calldata of a KeccakTree proof has the proof the tree
+    /// gives and its index as the directions
+    fn calldata_of_a_keccak_tree() {
+        use crate::keccak::{KeccakTree, keccak256};
+
+        let merkle = KeccakTree::new(&["Crypto", "Merkle", "Rust", "Tree", "Test"]).unwrap();
+        let proof = merkle.generate_proof(3).unwrap();
+        let config = VerifierConfig { backend: Backend::Keccak256, ordering: PairOrdering::Positional, leaf: LeafEncoding::Bytes };
+        let calldata = export_calldata(&config, &merkle.root(), &proof, 3, b"Tree").unwrap();
+
+        let hashes: Vec<String> = proof.iter().map(|hash| format!("\"0x{}\"", to_hex(hash))).collect();
+        assert_eq!(calldata, format!("[[{}],\"0x{}\",\"0x54726565\",\"3\"]", hashes.join(","), to_hex(&merkle.root())));
+        assert_eq!(merkle.leaf(3), Some(keccak256(b"Tree")));
+    }
+
+    #[test]
+    /// Test if every verifier compiles with solc. It is skipped when solc
+    /// is not installed.
+    fn verifiers_compile_with_solc() {
+        let dir = env::temp_dir().join(format!("merkle-solidity-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (i, (backend, ordering, leaf, _)) in CONFIGS.into_iter().enumerate() {
+            let path = dir.join(format!("Verifier{i}.sol"));
+            fs::write(&path, generate_verifier(&VerifierConfig { backend, ordering, leaf }).unwrap()).unwrap();
+            match Command::new("solc").arg(&path).output() {
+                Ok(output) => assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => break,
+                Err(error) => panic!("Could not run solc: {error}"),
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
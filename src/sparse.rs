@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use crate::error::MerkleError;
+use crate::merkle::{concatenate_elements, hash_node};
+
+/// Hash of a leaf that was never set, or that was set back to the default
+pub const EMPTY_LEAF: u64 = 0;
+
+/// The proof of a key of a SparseMerkleTree. It has a sibling for every
+/// level below the root, so it proves the value of a key that was set as
+/// well as the emptiness of a key that was not.
+/// - `key`: The proven key, which is the index of its leaf.
+/// - `siblings`: The siblings of the path from the leaf to the root, starting from the leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseProof {
+    pub key: usize,
+    pub siblings: Vec<u64>,
+}
+
+/// Abstraction of a Merkle Tree of a fixed depth with a leaf for every
+/// possible key, where almost every leaf is `EMPTY_LEAF`.
+///
+/// A subtree of empty leaves has the same hash wherever it is, so those
+/// hashes are computed once per level and only the nodes that differ from
+/// them are stored. Setting a leaf back to `EMPTY_LEAF` removes the nodes
+/// of its path that become default again.
+/// - `nodes`: The nodes that are not default, by their level and their index in it. The leaves are level 0.
+/// - `defaults`: The hash of a subtree of empty leaves for each level, from the leaves to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMerkleTree<const DEPTH: usize = 32> {
+    nodes: HashMap<(usize, usize), u64>,
+    defaults: Vec<u64>,
+}
+
+impl<const DEPTH: usize> Default for SparseMerkleTree<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize> SparseMerkleTree<DEPTH> {
+    /// Creates a SparseMerkleTree where every leaf is empty. The depth has
+    /// to leave room for `2^DEPTH` keys in an usize, which is checked when
+    /// the program is compiled.
+    pub fn new() -> Self {
+        const { assert!(DEPTH >= 1 && DEPTH < usize::BITS as usize, "The keys of the tree have to fit in an usize") };
+        Self { nodes: HashMap::new(), defaults: default_hashes(DEPTH) }
+    }
+
+    /// Gets the quantity of keys of the tree, which is `2^DEPTH`
+    pub fn capacity(&self) -> usize {
+        1 << DEPTH
+    }
+
+    /// Gets the root of the tree
+    pub fn root(&self) -> u64 {
+        self.node(DEPTH, 0)
+    }
+
+    /// Gets the hash of the leaf of a key, which is `EMPTY_LEAF` if it was not set
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the hash, or an Error if the key is outside of the tree
+    pub fn get(&self, key: usize) -> Result<u64, MerkleError> {
+        self.check_key(key)?;
+        Ok(self.node(0, key))
+    }
+
+    /// Gets the quantity of nodes stored, which are the ones that differ
+    /// from the hash of an empty subtree of their level
+    pub fn stored_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Sets the leaf of a key and recomputes the path up to the root,
+    /// which takes `DEPTH` hashes. Setting it to `EMPTY_LEAF` prunes the
+    /// nodes of the path that become default.
+    ///
+    /// ### Arguments
+    ///
+    /// - `key`: The key, which is the index of its leaf.
+    /// - `value_hash`: The new hash of the leaf.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the new root, or an Error if the key is outside of the tree
+    pub fn set(&mut self, key: usize, value_hash: u64) -> Result<u64, MerkleError> {
+        self.check_key(key)?;
+        let mut node = value_hash;
+        let mut index = key;
+        for level in 0..=DEPTH {
+            if node == self.defaults[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), node);
+            }
+            if level == DEPTH {
+                break;
+            }
+            let sibling = self.node(level, index ^ 1);
+            node = if index.is_multiple_of(2) { combine(node, sibling) } else { combine(sibling, node) };
+            index /= 2;
+        }
+        Ok(node)
+    }
+
+    /// Generates the proof of a key, whether it was set or not
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the SparseProof, or an Error if the key is outside of the tree
+    pub fn prove(&self, key: usize) -> Result<SparseProof, MerkleError> {
+        self.check_key(key)?;
+        let siblings = (0..DEPTH).map(|level| self.node(level, (key >> level) ^ 1)).collect();
+        Ok(SparseProof { key, siblings })
+    }
+
+    /// Checks if the root of the tree can be obtained with the use of a
+    /// proof and the hash of a leaf. Verifying with `EMPTY_LEAF` checks
+    /// that the key was not set.
+    ///
+    /// ### Arguments
+    ///
+    /// - `proof`: The SparseProof of the key, as `prove` returns it.
+    /// - `value_hash`: The hash of the leaf of the key.
+    ///
+    /// ### Returns
+    ///
+    /// A bool that is true if the root can be obtained with that information, false otherwise
+    pub fn verify(&self, proof: &SparseProof, value_hash: u64) -> bool {
+        verify_against_root::<DEPTH>(self.root(), proof, value_hash)
+    }
+
+    /// Gets a node, or the hash of an empty subtree if it is not stored
+    fn node(&self, level: usize, index: usize) -> u64 {
+        self.nodes.get(&(level, index)).copied().unwrap_or(self.defaults[level])
+    }
+
+    /// Checks that a key has a leaf in the tree
+    fn check_key(&self, key: usize) -> Result<(), MerkleError> {
+        if key >= self.capacity() {
+            return Err(MerkleError::InvalidIndex { index: key, len: self.capacity() });
+        }
+        Ok(())
+    }
+}
+
+/// Checks if a root can be obtained with the use of a proof and the hash of
+/// a leaf, without having the tree. The proof has to have a sibling for
+/// each of the `DEPTH` levels below the root.
+///
+/// ### Arguments
+///
+/// - `root`: The root of the tree the key should belong to.
+/// - `proof`: The SparseProof of the key.
+/// - `value_hash`: The hash of the leaf of the key, `EMPTY_LEAF` to prove that it was not set.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify_against_root<const DEPTH: usize>(root: u64, proof: &SparseProof, value_hash: u64) -> bool {
+    if proof.siblings.len() != DEPTH || proof.key >> DEPTH != 0 {
+        return false;
+    }
+    let mut node = value_hash;
+    for (level, sibling) in proof.siblings.iter().enumerate() {
+        node = if (proof.key >> level).is_multiple_of(2) {
+            combine(node, *sibling)
+        } else {
+            combine(*sibling, node)
+        };
+    }
+    node == root
+}
+
+/// Computes the hash of a subtree of empty leaves for each level
+///
+/// ### Returns
+///
+/// A vector with `depth + 1` hashes, from the leaves to the root
+fn default_hashes(depth: usize) -> Vec<u64> {
+    let mut defaults = vec![EMPTY_LEAF];
+    for level in 0..depth {
+        defaults.push(combine(defaults[level], defaults[level]));
+    }
+    defaults
+}
+
+/// Hashes the concatenation of two nodes, like the parents of a MerkleTree
+fn combine(left: u64, right: u64) -> u64 {
+    hash_node(concatenate_elements(left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    /// Builds every level of a tree with all its leaves, from the leaves to the root
+    fn dense_levels(leaves: Vec<u64>) -> Vec<Vec<u64>> {
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next_level = levels[levels.len() - 1].chunks(2).map(|pair| combine(pair[0], pair[1])).collect();
+            levels.push(next_level);
+        }
+        levels
+    }
+
+    #[test]
+    /// Test if a sparse tree with a handful of entries has the root and
+    /// proofs of a dense tree of the same depth.
+    fn matches_a_dense_tree() {
+        let entries = [(0, "Crypto"), (7, "Merkle"), (4096, "Rust"), (65535, "Tree"), (30000, "Test")];
+        let mut sparse = SparseMerkleTree::<16>::new();
+        let mut leaves = vec![EMPTY_LEAF; 1 << 16];
+        for (key, value) in entries {
+            sparse.set(key, hash_element(value)).unwrap();
+            leaves[key] = hash_element(value);
+        }
+        let dense = dense_levels(leaves);
+
+        assert_eq!(sparse.root(), dense[16][0]);
+        for key in [0, 1, 7, 4096, 12345, 30000, 65535] {
+            let proof = sparse.prove(key).unwrap();
+            let siblings: Vec<u64> = (0..16).map(|level| dense[level][(key >> level) ^ 1]).collect();
+            assert_eq!(proof.siblings, siblings);
+            assert!(sparse.verify(&proof, dense[0][key]));
+        }
+        // Each entry stores its own path, with the nodes near the root shared
+        assert!(sparse.stored_nodes() <= entries.len() * 17);
+    }
+
+    #[test]
+    /// Test if unset keys have proofs of emptiness, which fail once the key
+    /// is set, and a proof of a set key does not prove emptiness.
+    fn proves_emptiness() {
+        let mut sparse = SparseMerkleTree::<32>::new();
+        sparse.set(42, hash_element("Crypto")).unwrap();
+        let empty = sparse.prove(43).unwrap();
+        assert!(sparse.verify(&empty, EMPTY_LEAF));
+        assert_eq!(sparse.get(43), Ok(EMPTY_LEAF));
+
+        let set = sparse.prove(42).unwrap();
+        assert!(sparse.verify(&set, hash_element("Crypto")));
+        assert!(!sparse.verify(&set, EMPTY_LEAF));
+
+        sparse.set(43, hash_element("Merkle")).unwrap();
+        assert!(!sparse.verify(&empty, EMPTY_LEAF));
+        assert!(sparse.verify(&sparse.prove(43).unwrap(), hash_element("Merkle")));
+    }
+
+    #[test]
+    /// Test if setting leaves back to the default prunes every stored node
+    /// and gives the root of an empty tree again.
+    fn resetting_prunes_storage() {
+        let mut sparse = SparseMerkleTree::<32>::new();
+        let empty_root = sparse.root();
+        for key in [3, 9, 1 << 20, (1 << 32) - 1] {
+            sparse.set(key, hash_element(key)).unwrap();
+        }
+        assert_ne!(sparse.root(), empty_root);
+        sparse.set(9, EMPTY_LEAF).unwrap();
+        let mut expected = SparseMerkleTree::<32>::new();
+        for key in [3, 1 << 20, (1 << 32) - 1] {
+            expected.set(key, hash_element(key)).unwrap();
+        }
+        assert_eq!(sparse, expected);
+        for key in [3, 1 << 20, (1 << 32) - 1] {
+            sparse.set(key, EMPTY_LEAF).unwrap();
+        }
+        assert_eq!((sparse.root(), sparse.stored_nodes()), (empty_root, 0));
+    }
+
+    #[test]
+    /// Test if keys outside of the tree and proofs with the wrong shape are rejected
+    fn invalid_keys_and_proofs_are_rejected() {
+        let mut sparse = SparseMerkleTree::<8>::new();
+        assert_eq!(sparse.set(256, 1), Err(MerkleError::InvalidIndex { index: 256, len: 256 }));
+        assert_eq!(sparse.prove(256), Err(MerkleError::InvalidIndex { index: 256, len: 256 }));
+        sparse.set(5, hash_element("Rust")).unwrap();
+
+        let proof = sparse.prove(5).unwrap();
+        let mut short = proof.clone();
+        short.siblings.pop();
+        let mut wrong_key = proof.clone();
+        wrong_key.key = 5 + 256;
+        for invalid in [short, wrong_key] {
+            assert!(!sparse.verify(&invalid, hash_element("Rust")), "{invalid:?}");
+        }
+        assert!(sparse.verify(&proof, hash_element("Rust")));
+    }
+}
@@ -0,0 +1,509 @@
+//! SSZ merkleization, the `hash_tree_root` of the Ethereum consensus layer,
+//! and proofs of any node of it by its generalized index.
+//!
+//! A value is split in chunks of 32 bytes, which are the leaves of a
+//! SHA-256 tree padded with zero chunks up to the next power of 2 of the
+//! limit of its type. The root of a list is then hashed together with the
+//! quantity of elements it has.
+
+use crate::digest_tree::{Bytes32, Digest};
+use crate::error::MerkleError;
+use crate::sha256::Sha256;
+
+/// Bytes of each chunk, the leaves of the trees SSZ merkleizes.
+pub const BYTES_PER_CHUNK: usize = 32;
+
+/// A basic SSZ type: an unsigned integer or a boolean. Vectors and lists of
+/// them are packed, so many values share each chunk.
+pub trait Basic {
+    /// Bytes of the serialized value.
+    const SIZE: usize;
+
+    /// Appends the little-endian serialization of the value
+    fn serialize(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_basic_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl Basic for $uint {
+                const SIZE: usize = size_of::<$uint>();
+
+                fn serialize(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_basic_uint!(u8, u16, u32, u64, u128);
+
+impl Basic for bool {
+    const SIZE: usize = 1;
+
+    fn serialize(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+}
+
+/// A value of an SSZ type, with what is needed to merkleize it.
+///
+/// A byte vector is `vector::<u8>` and a byte list is `list::<u8>`.
+/// ```
+/// use tree::ssz::SszValue;
+///
+/// let checkpoint = SszValue::container(vec![SszValue::basic(3u64), SszValue::vector(&[0u8; 32])]);
+/// let root = checkpoint.hash_tree_root();
+/// let gindex = checkpoint.child_gindex(0).unwrap();
+/// let proof = checkpoint.prove_gindex(gindex).unwrap();
+/// assert!(tree::ssz::verify_gindex(root, SszValue::basic(3u64).hash_tree_root(), gindex, &proof));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SszValue {
+    kind: Kind,
+}
+
+/// How each kind of value is merkleized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    /// A basic value, alone in its chunk.
+    Basic(Vec<u8>),
+    /// A vector or list of basic values, packed in chunks.
+    /// - `bytes`: The serialization of every value, one after the other.
+    /// - `size`: Bytes of each value.
+    /// - `limit_chunks`: Maximum quantity of chunks of the type.
+    /// - `list`: If the len is mixed in with the root.
+    Packed { bytes: Vec<u8>, size: usize, limit_chunks: usize, list: bool },
+    /// A container, or a vector or list of composite values, where each
+    /// value is the subtree of a chunk.
+    /// - `values`: The fields or elements.
+    /// - `limit`: Maximum quantity of values of the type.
+    /// - `list`: If the len is mixed in with the root.
+    Composite { values: Vec<SszValue>, limit: usize, list: bool },
+}
+
+impl SszValue {
+    /// Creates a basic value, like an `uint64` or a `boolean`
+    pub fn basic<T: Basic>(value: T) -> Self {
+        let mut bytes = Vec::with_capacity(T::SIZE);
+        value.serialize(&mut bytes);
+        Self { kind: Kind::Basic(bytes) }
+    }
+
+    /// Creates a vector of basic values, with as many values as its type has
+    pub fn vector<T: Basic>(values: &[T]) -> Self {
+        let bytes = pack(values);
+        let limit_chunks = bytes.len().div_ceil(BYTES_PER_CHUNK);
+        Self { kind: Kind::Packed { bytes, size: T::SIZE, limit_chunks, list: false } }
+    }
+
+    /// Creates a list of basic values
+    ///
+    /// ### Arguments
+    ///
+    /// - `values`: The elements of the list.
+    /// - `limit`: The maximum quantity of elements of the type of the list.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the list, or an Error if it has more elements than its limit
+    pub fn list<T: Basic>(values: &[T], limit: usize) -> Result<Self, MerkleError> {
+        if values.len() > limit {
+            return Err(MerkleError::ListTooLong { len: values.len(), limit });
+        }
+        let limit_chunks = limit.checked_mul(T::SIZE).ok_or(MerkleError::ListTooLong { len: values.len(), limit })?;
+        let limit_chunks = limit_chunks.div_ceil(BYTES_PER_CHUNK);
+        Ok(Self { kind: Kind::Packed { bytes: pack(values), size: T::SIZE, limit_chunks, list: true } })
+    }
+
+    /// Creates a container with its fields in the order of its type
+    pub fn container(fields: Vec<SszValue>) -> Self {
+        let limit = fields.len();
+        Self { kind: Kind::Composite { values: fields, limit, list: false } }
+    }
+
+    /// Creates a vector of composite values, like containers or other vectors
+    pub fn composite_vector(values: Vec<SszValue>) -> Self {
+        Self::container(values)
+    }
+
+    /// Creates a list of composite values, like containers or other lists
+    ///
+    /// ### Arguments
+    ///
+    /// - `values`: The elements of the list.
+    /// - `limit`: The maximum quantity of elements of the type of the list.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the list, or an Error if it has more elements than its limit
+    pub fn composite_list(values: Vec<SszValue>, limit: usize) -> Result<Self, MerkleError> {
+        if values.len() > limit {
+            return Err(MerkleError::ListTooLong { len: values.len(), limit });
+        }
+        Ok(Self { kind: Kind::Composite { values, limit, list: true } })
+    }
+
+    /// Gets the `hash_tree_root` of the value
+    pub fn hash_tree_root(&self) -> Bytes32 {
+        self.node().root()
+    }
+
+    /// Gets the generalized index of an element or field of the value,
+    /// relative to its root. Concatenate it with `concat_gindices` to get
+    /// to the nodes inside of that element. The index of a packed basic
+    /// value is the one of the chunk it is in.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element or field.
+    ///
+    /// ### Returns
+    ///
+    /// An Option with the generalized index, or None if the value has no
+    /// element at that index or the index does not fit in an u64
+    pub fn child_gindex(&self, index: usize) -> Option<u64> {
+        let (chunk, limit_chunks, list) = match &self.kind {
+            Kind::Basic(_) => return None,
+            Kind::Packed { bytes, size, limit_chunks, list } if index < bytes.len() / size => {
+                (index * size / BYTES_PER_CHUNK, *limit_chunks, *list)
+            }
+            Kind::Composite { values, limit, list } if index < values.len() => (index, *limit, *list),
+            _ => return None,
+        };
+        let depth = depth_of(limit_chunks);
+        // The data of a list is the left child of its root
+        let first = 1u64.checked_shl(depth + u32::from(list))?;
+        first.checked_add(chunk as u64)
+    }
+
+    /// Gets the hash of a node of the tree of the value
+    ///
+    /// ### Arguments
+    ///
+    /// - `gindex`: The generalized index of the node, 1 being the root.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the hash, or an Error if the tree has no node at that index
+    pub fn node_at_gindex(&self, gindex: u64) -> Result<Bytes32, MerkleError> {
+        let mut siblings = Vec::new();
+        descend(&self.node(), &path_of(gindex)?, &mut siblings).ok_or(MerkleError::InvalidGindex { gindex })
+    }
+
+    /// Generates the proof of a node of the tree of the value, which is
+    /// checked with `verify_gindex`. The first hash is the sibling of the
+    /// node and the last one is the sibling of a child of the root.
+    ///
+    /// ### Arguments
+    ///
+    /// - `gindex`: The generalized index of the node, 1 being the root.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the hashes of the proof, or an Error if the tree has no node at that index
+    pub fn prove_gindex(&self, gindex: u64) -> Result<Vec<Bytes32>, MerkleError> {
+        let mut siblings = Vec::new();
+        descend(&self.node(), &path_of(gindex)?, &mut siblings).ok_or(MerkleError::InvalidGindex { gindex })?;
+        siblings.reverse();
+        Ok(siblings)
+    }
+
+    /// Builds the tree of the value
+    fn node(&self) -> Node {
+        match &self.kind {
+            Kind::Basic(bytes) => Node::Leaf(chunk_of(bytes)),
+            Kind::Packed { bytes, size, limit_chunks, list } => {
+                let chunks = bytes.chunks(BYTES_PER_CHUNK).map(|chunk| Node::Leaf(chunk_of(chunk))).collect();
+                let data = merkleize(chunks, *limit_chunks);
+                if *list { mix_in_length(data, bytes.len() / size) } else { data }
+            }
+            Kind::Composite { values, limit, list } => {
+                let data = merkleize(values.iter().map(SszValue::node).collect(), *limit);
+                if *list { mix_in_length(data, values.len()) } else { data }
+            }
+        }
+    }
+}
+
+/// Checks the proof of a node against a root without having the value:
+/// the bits of the generalized index tell on which side each hash goes.
+///
+/// ### Arguments
+///
+/// - `root`: The trusted `hash_tree_root`.
+/// - `leaf`: The hash of the node being proven.
+/// - `gindex`: The generalized index of the node.
+/// - `proof`: The hashes that make up the proof, from the sibling of the node up.
+///
+/// ### Returns
+///
+/// A bool that is true if the proof has one hash for each level above the
+/// node and leads to the root, false otherwise
+pub fn verify_gindex(root: Bytes32, leaf: Bytes32, gindex: u64, proof: &[Bytes32]) -> bool {
+    if gindex == 0 || proof.len() != gindex.ilog2() as usize {
+        return false;
+    }
+    let mut hash = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        hash = if (gindex >> level) & 1 == 1 { Sha256::combine(sibling, &hash) } else { Sha256::combine(&hash, sibling) };
+    }
+    hash == root
+}
+
+/// Joins generalized indexes, each relative to the node the one before it
+/// points to, into one relative to the first root.
+///
+/// ### Returns
+///
+/// An Option with the generalized index, or None if one of them is 0 or
+/// the result does not fit in an u64
+pub fn concat_gindices(gindices: &[u64]) -> Option<u64> {
+    let mut result = 1u64;
+    for gindex in gindices {
+        let depth = gindex.checked_ilog2()?;
+        result = result.checked_shl(depth).filter(|shifted| shifted >> depth == result)? | (gindex ^ (1 << depth));
+    }
+    Some(result)
+}
+
+/// Gets the hash of a subtree of zero chunks with the given height
+pub fn zero_hash(height: usize) -> Bytes32 {
+    let mut hash = [0u8; 32];
+    for _ in 0..height {
+        hash = Sha256::combine(&hash, &hash);
+    }
+    hash
+}
+
+/// A node of the tree of a value.
+#[derive(Debug)]
+enum Node {
+    /// A chunk.
+    Leaf(Bytes32),
+    /// A subtree of zero chunks with the given height, which is not stored.
+    Zero(usize),
+    /// Two children and their hash.
+    Pair(Box<Node>, Box<Node>, Bytes32),
+}
+
+impl Node {
+    fn root(&self) -> Bytes32 {
+        match self {
+            Node::Leaf(chunk) => *chunk,
+            Node::Zero(height) => zero_hash(*height),
+            Node::Pair(_, _, hash) => *hash,
+        }
+    }
+
+    fn pair(left: Node, right: Node) -> Node {
+        let hash = Sha256::combine(&left.root(), &right.root());
+        Node::Pair(Box::new(left), Box::new(right), hash)
+    }
+}
+
+/// Serializes basic values one after the other
+fn pack<T: Basic>(values: &[T]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(values.len() * T::SIZE);
+    for value in values {
+        value.serialize(&mut bytes);
+    }
+    bytes
+}
+
+/// Pads up to 32 bytes with zeros
+fn chunk_of(bytes: &[u8]) -> Bytes32 {
+    let mut chunk = [0u8; 32];
+    chunk[..bytes.len()].copy_from_slice(bytes);
+    chunk
+}
+
+/// Height of the tree of a type with a limit of chunks
+fn depth_of(limit_chunks: usize) -> u32 {
+    limit_chunks.next_power_of_two().trailing_zeros()
+}
+
+/// Builds a tree over the chunks, padded with zero chunks up to the next
+/// power of 2 of the limit. The padding is kept as `Node::Zero` subtrees,
+/// so a list with a huge limit does not take more memory than its chunks.
+fn merkleize(chunks: Vec<Node>, limit_chunks: usize) -> Node {
+    let depth = depth_of(limit_chunks) as usize;
+    if chunks.is_empty() {
+        return Node::Zero(depth);
+    }
+    let mut level = chunks;
+    for height in 0..depth {
+        let mut nodes = level.into_iter();
+        let mut next = Vec::with_capacity(nodes.len().div_ceil(2));
+        while let Some(left) = nodes.next() {
+            next.push(Node::pair(left, nodes.next().unwrap_or(Node::Zero(height))));
+        }
+        level = next;
+    }
+    level.pop().expect("the limit is not lower than the quantity of chunks")
+}
+
+/// Hashes the root of the data of a list with its len
+fn mix_in_length(data: Node, len: usize) -> Node {
+    Node::pair(data, Node::Leaf(chunk_of(&(len as u64).to_le_bytes())))
+}
+
+/// Gets the sides taken from the root to reach a generalized index, true
+/// being the right child
+fn path_of(gindex: u64) -> Result<Vec<bool>, MerkleError> {
+    let depth = gindex.checked_ilog2().ok_or(MerkleError::InvalidGindex { gindex })?;
+    Ok((0..depth).rev().map(|bit| (gindex >> bit) & 1 == 1).collect())
+}
+
+/// Follows a path from a node, pushing the sibling of every node on the
+/// way, from the top down
+///
+/// ### Returns
+///
+/// An Option with the hash of the node at the end of the path, or None if
+/// the path goes below a chunk
+fn descend(node: &Node, path: &[bool], siblings: &mut Vec<Bytes32>) -> Option<Bytes32> {
+    let Some((&right, rest)) = path.split_first() else {
+        return Some(node.root());
+    };
+    match node {
+        Node::Pair(left_child, right_child, _) => {
+            let (next, sibling) = if right { (right_child, left_child) } else { (left_child, right_child) };
+            siblings.push(sibling.root());
+            descend(next, rest, siblings)
+        }
+        Node::Zero(height) if *height > 0 => {
+            siblings.push(zero_hash(height - 1));
+            descend(&Node::Zero(height - 1), rest, siblings)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest_tree::to_hex;
+    use crate::json::{self, Json};
+
+    const FIXTURES: &str = include_str!("../tests/vectors/ssz.json");
+
+    /// Builds the value of each case of the fixtures file
+    fn fixture_value(name: &str) -> SszValue {
+        match name {
+            "zero_hash_1" => SszValue::vector(&[0u8; 64]),
+            "zero_hash_2" => SszValue::vector(&[0u64; 16]),
+            "zero_hash_3" => SszValue::composite_vector(vec![SszValue::vector(&[0u128; 2]); 8]),
+            "empty_deposit_list" => SszValue::composite_list(Vec::new(), 1 << 32).unwrap(),
+            "uint64" => SszValue::basic(0x0123_4567_89ab_cdefu64),
+            "byte_vector_32" => SszValue::vector(&core::array::from_fn::<u8, 32, _>(|i| i as u8)),
+            "byte_list" => SszValue::list(b"Crypto Merkle Rust Tree Test", 256).unwrap(),
+            "uint16_list" => SszValue::list(&[1u16, 2, 3, 0xffff], 1024).unwrap(),
+            "bool_vector" => SszValue::vector(&[true, false, true]),
+            "container" => container(),
+            _ => panic!("Unknown fixture {name}"),
+        }
+    }
+
+    /// A container with a basic field, a byte vector and a list of containers
+    fn container() -> SszValue {
+        let entries = (0..5u64).map(|i| SszValue::container(vec![SszValue::basic(i), SszValue::basic(i % 2 == 0)])).collect();
+        SszValue::container(vec![SszValue::basic(42u64), SszValue::vector(&[7u8; 48]), SszValue::composite_list(entries, 16).unwrap()])
+    }
+
+    #[test]
+    /// Test if the root of every value of the fixtures matches byte for byte
+    fn fixtures_match() {
+        let fixtures = json::parse(FIXTURES).unwrap();
+        let cases = fixtures.get("cases").and_then(Json::as_array).unwrap();
+        assert_eq!(cases.len(), 10);
+        for case in cases {
+            let name = case.get("name").and_then(Json::as_str).unwrap();
+            let root = case.get("root").and_then(Json::as_str).unwrap();
+            assert_eq!(to_hex(&fixture_value(name).hash_tree_root()), root, "{name}");
+        }
+    }
+
+    #[test]
+    /// Test if the roots that SSZ defines without hashing are kept as they are
+    fn single_chunk_roots() {
+        let mut expected = [0u8; 32];
+        expected[..8].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(SszValue::basic(5u64).hash_tree_root(), expected);
+        assert_eq!(SszValue::vector(&[5u64]).hash_tree_root(), expected);
+
+        let bytes: [u8; 32] = core::array::from_fn(|i| i as u8 + 1);
+        assert_eq!(SszValue::vector(&bytes).hash_tree_root(), bytes);
+        assert_eq!(SszValue::basic(true).hash_tree_root()[0], 1);
+        assert_eq!(zero_hash(0), [0u8; 32]);
+    }
+
+    #[test]
+    /// Test if a list is the root of its data hashed with its len, and
+    /// if a list with more elements than its limit can not be created.
+    fn lists_mix_in_their_len() {
+        let list = SszValue::list(&[1u64, 2, 3], 8).unwrap();
+        let data = SszValue::vector(&[1u64, 2, 3, 0, 0, 0, 0, 0]).hash_tree_root();
+        assert_eq!(list.hash_tree_root(), Sha256::combine(&data, &chunk_of(&[3])));
+
+        assert_eq!(SszValue::list(&[1u8; 5], 4), Err(MerkleError::ListTooLong { len: 5, limit: 4 }));
+        assert_eq!(SszValue::composite_list(vec![SszValue::basic(1u8); 3], 2), Err(MerkleError::ListTooLong { len: 3, limit: 2 }));
+    }
+
+    #[test]
+    /// Test if every node of a nested value, including the zero padding
+    /// and the len of a list, is proven and verified by its generalized index.
+    fn every_gindex_is_proven() {
+        let value = container();
+        let root = value.hash_tree_root();
+        // The list of 16 containers has 4 levels plus the len, and each
+        // container 1 level, below the 2 levels of the outer container
+        for gindex in 1..1u64 << 9 {
+            let Ok(leaf) = value.node_at_gindex(gindex) else {
+                continue;
+            };
+            let proof = value.prove_gindex(gindex).unwrap();
+            assert_eq!(proof.len(), gindex.ilog2() as usize);
+            assert!(verify_gindex(root, leaf, gindex, &proof), "{gindex}");
+            let mut other_leaf = leaf;
+            other_leaf[0] ^= 1;
+            assert!(!verify_gindex(root, other_leaf, gindex, &proof));
+            if let Some((_, shorter)) = proof.split_last() {
+                assert!(!verify_gindex(root, leaf, gindex, shorter));
+            }
+        }
+        assert_eq!(value.prove_gindex(0), Err(MerkleError::InvalidGindex { gindex: 0 }));
+        // The first field is a single chunk, with nothing below it
+        assert_eq!(value.prove_gindex(8), Err(MerkleError::InvalidGindex { gindex: 8 }));
+    }
+
+    #[test]
+    /// Test if the generalized index of a field inside of an element of a
+    /// list points to that field.
+    fn nested_gindices_point_to_fields() {
+        let value = container();
+        let Kind::Composite { values, .. } = &value.kind else { unreachable!() };
+        let list = &values[2];
+        let Kind::Composite { values: entries, .. } = &list.kind else { unreachable!() };
+
+        let gindex =
+            concat_gindices(&[value.child_gindex(2).unwrap(), list.child_gindex(3).unwrap(), entries[3].child_gindex(1).unwrap()]).unwrap();
+        // After the root, field 2 of 3 takes 2 levels, the data side of the
+        // list 1, entry 3 of 16 another 4 and field 1 of 2 the last one
+        assert_eq!(gindex, (((0b110 << 1) << 4 | 3) << 1) | 1);
+        let leaf = SszValue::basic(false).hash_tree_root();
+        assert_eq!(value.node_at_gindex(gindex), Ok(leaf));
+        assert!(verify_gindex(value.hash_tree_root(), leaf, gindex, &value.prove_gindex(gindex).unwrap()));
+
+        // The len of the list is the right child of its root
+        let len_gindex = concat_gindices(&[value.child_gindex(2).unwrap(), 3]).unwrap();
+        assert_eq!(value.node_at_gindex(len_gindex), Ok(chunk_of(&[5])));
+
+        // Packed values are proven by their chunk
+        let bytes = SszValue::vector(&[7u8; 48]);
+        assert_eq!((bytes.child_gindex(31), bytes.child_gindex(32), bytes.child_gindex(48)), (Some(2), Some(3), None));
+        assert_eq!(concat_gindices(&[1 << 40, 1 << 40]), None);
+        assert_eq!(concat_gindices(&[0]), None);
+    }
+}
@@ -0,0 +1,44 @@
+//! Asynchronous sources of elements, see `MerkleTree::from_stream`.
+//!
+//! It is behind the `async-stream` feature, which adds no dependency: the
+//! crate is built without `futures-core` or `tokio`, so `Stream` is defined
+//! here with the same `poll_next` as the `Stream` of `futures-core`, the one
+//! the streams of `tokio` implement. A stream of those crates is used by
+//! wrapping it in a type that forwards `poll_next` to it, and any executor
+//! can await `MerkleTree::from_stream`.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A source of values that arrive asynchronously, one at a time.
+pub trait Stream {
+    /// The type of the values of the stream.
+    type Item;
+
+    /// Tries to get the next value of the stream. It is `Poll::Pending`
+    /// when the value has not arrived yet, and the waker of `cx` is woken
+    /// once it may have. It is `Poll::Ready(None)` when the stream ended.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// A stream whose values are the ones of an iterator, all of them ready
+/// as soon as they are asked for. See `iter`.
+/// - `iter`: The iterator the values are taken from.
+#[derive(Debug, Clone)]
+pub struct Iter<I> {
+    iter: I,
+}
+
+/// Creates a stream with the values of an iterator, like
+/// `futures::stream::iter` does
+pub fn iter<I: IntoIterator>(values: I) -> Iter<I::IntoIter> {
+    Iter { iter: values.into_iter() }
+}
+
+impl<I: Iterator + Unpin> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.iter.next())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::MerkleError;
+use crate::merkle::{diff_to_power_of_2, hash_element};
+
+/// A node of a Merkle Sum Tree. Besides its hash, every node carries
+/// the sum of the amounts of the leaves below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SumNode {
+    pub hash: u64,
+    pub sum: u64,
+}
+
+/// Node used to fill the base level until it has a len that is a power of 2.
+/// It carries a zero amount so it never changes the committed total.
+const PADDING: SumNode = SumNode { hash: 0, sum: 0 };
+
+/// Abstraction of a Merkle Sum Tree. It has the same structure as the
+/// MerkleTree, but each parent node commits to the hashes and the sums
+/// of both children, and its sum is the sum of theirs. A proof then
+/// shows both that a leaf is included and that its amount was counted
+/// in the total of the root.
+/// - `arr`: A vector of vectors will be the structure of our tree. Each vector is a level on it.
+/// - `diff_elements`: Quantity of leaves that are not padding in the base level.
+pub struct MerkleSumTree {
+    arr: Vec<Vec<SumNode>>,
+    diff_elements: usize,
+}
+
+impl MerkleSumTree {
+    /// Creates a new MerkleSumTree
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaves`: A vector with the `(hash, amount)` pairs that form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the MerkleSumTree, or an Error if there are no leaves
+    /// or if the total of the amounts does not fit in an u64
+    pub fn new(leaves: Vec<(u64, u64)>) -> Result<Self, MerkleError> {
+        if leaves.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        let diff_elements = leaves.len();
+        let mut level: Vec<SumNode> = leaves.into_iter().map(|(hash, sum)| SumNode { hash, sum }).collect();
+        let diff = diff_to_power_of_2(level.len());
+        level.extend(std::iter::repeat_n(PADDING, diff));
+
+        let mut arr = vec![level];
+        while arr[arr.len() - 1].len() != 1 {
+            let next_level = arr[arr.len() - 1]
+                .chunks(2)
+                .map(|chunk| combine(chunk[0], chunk[1]).ok_or(MerkleError::SumOverflow))
+                .collect::<Result<Vec<SumNode>, MerkleError>>()?;
+            arr.push(next_level);
+        }
+        Ok(Self { arr, diff_elements })
+    }
+
+    /// Gets the root of the tree
+    ///
+    /// ### Returns
+    ///
+    /// A tuple with the hash of the root and the total of every amount in the tree
+    pub fn root(&self) -> (u64, u64) {
+        let root = self.arr[self.arr.len() - 1][0];
+        (root.hash, root.sum)
+    }
+
+    /// Generates the proof for a specific leaf in the tree. Every node
+    /// in the proof is the sibling of a node in the path to the root.
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the leaf in the original input array
+    ///
+    /// ### Returns
+    ///
+    /// A Result that contains the vector with the sibling nodes or an Error
+    /// if the index is invalid
+    pub fn prove(&self, mut index: usize) -> Result<Vec<SumNode>, MerkleError> {
+        if index >= self.diff_elements {
+            return Err(MerkleError::InvalidIndex { index, len: self.diff_elements });
+        }
+        let mut proof = Vec::new();
+        for level in &self.arr[..self.arr.len() - 1] {
+            proof.push(level[index ^ 1]);
+            index /= 2;
+        }
+        Ok(proof)
+    }
+}
+
+/// Checks if a leaf and a proof lead to the root, both on the hash and
+/// on the total.
+///
+/// ### Arguments
+///
+/// - `root`: The `(hash, total)` pair of the root.
+/// - `index`: The index of the leaf in the input array.
+/// - `leaf`: The `(hash, amount)` pair of the leaf.
+/// - `proof`: The sibling nodes that make up the proof.
+///
+/// ### Returns
+///
+/// A bool that is true if the root can be obtained with that information, false otherwise
+pub fn verify(root: (u64, u64), mut index: usize, leaf: (u64, u64), proof: &[SumNode]) -> bool {
+    let mut node = SumNode { hash: leaf.0, sum: leaf.1 };
+    for sibling in proof {
+        let combined = if index.is_multiple_of(2) {
+            combine(node, *sibling)
+        } else {
+            combine(*sibling, node)
+        };
+        node = match combined {
+            Some(parent) => parent,
+            None => return false,
+        };
+        index /= 2;
+    }
+    (node.hash, node.sum) == root
+}
+
+/// Creates the parent of two nodes. The hash commits to the hash and the
+/// sum of each child, in order, and the sum is the sum of both.
+///
+/// ### Returns
+///
+/// The parent node, or None if the sum overflows
+fn combine(left: SumNode, right: SumNode) -> Option<SumNode> {
+    let sum = left.sum.checked_add(right.sum)?;
+    let hash = hash_element((left.hash, left.sum, right.hash, right.sum));
+    Some(SumNode { hash, sum })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves() -> Vec<(u64, u64)> {
+        vec![
+            (hash_element("Alice"), 120),
+            (hash_element("Bob"), 30),
+            (hash_element("Carol"), 7),
+            (hash_element("Dave"), 0),
+            (hash_element("Eve"), 55),
+        ]
+    }
+
+    #[test]
+    /// Test if the total of the root is the sum of every amount, without
+    /// the padding leaves adding anything.
+    fn root_total_matches_input_sum() {
+        let merkle = MerkleSumTree::new(leaves()).unwrap();
+        let expected_total: u64 = leaves().iter().map(|(_, amount)| amount).sum();
+
+        assert_eq!(merkle.root().1, expected_total);
+        assert_eq!(merkle.arr[0].len(), 8);
+        assert!(merkle.arr[0][5..].iter().all(|node| node.sum == 0));
+    }
+
+    #[test]
+    /// Test if the proof of every leaf verifies against the root
+    fn verifies_leaf_proofs() {
+        let merkle = MerkleSumTree::new(leaves()).unwrap();
+
+        for (index, leaf) in leaves().into_iter().enumerate() {
+            let proof = merkle.prove(index).unwrap();
+            assert!(verify(merkle.root(), index, leaf, &proof));
+        }
+    }
+
+    #[test]
+    /// Test if changing the sum of a sibling makes the verification fail,
+    /// even if the hash of the sibling is kept.
+    fn rejects_tampered_sibling_sum() {
+        let merkle = MerkleSumTree::new(leaves()).unwrap();
+        let mut proof = merkle.prove(2).unwrap();
+        proof[1].sum += 1;
+
+        assert!(!verify(merkle.root(), 2, leaves()[2], &proof));
+    }
+
+    #[test]
+    /// Test if claiming a different amount for a leaf makes the verification fail
+    fn rejects_wrong_leaf_amount() {
+        let merkle = MerkleSumTree::new(leaves()).unwrap();
+        let proof = merkle.prove(0).unwrap();
+        let (hash, amount) = leaves()[0];
+
+        assert!(!verify(merkle.root(), 0, (hash, amount - 1), &proof));
+    }
+
+    #[test]
+    /// Test if invalid inputs return the expected errors
+    fn creation_and_proof_errors() {
+        let overflowing = vec![(hash_element("Alice"), u64::MAX), (hash_element("Bob"), 1)];
+        let merkle = MerkleSumTree::new(leaves()).unwrap();
+
+        assert_eq!(MerkleSumTree::new(overflowing).err(), Some(MerkleError::SumOverflow));
+        assert_eq!(MerkleSumTree::new(Vec::new()).err(), Some(MerkleError::EmptyTree));
+        assert_eq!(merkle.prove(5), Err(MerkleError::InvalidIndex { index: 5, len: 5 }));
+    }
+}
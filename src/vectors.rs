@@ -0,0 +1,384 @@
+use crate::error::MerkleError;
+use crate::json::{self, Json};
+use crate::merkle::{hash_with, PaddingStrategy};
+use crate::root_hash::{parse_hex, to_hex};
+use crate::verify::commit_domain_tag_with;
+use crate::{MerkleTree, PortableHash, Proof, Side};
+
+/// A configuration of the tree that vectors can be generated for.
+///
+/// Every profile hashes with `PortableHash`, SipHash-2-4 with the fixed
+/// `PORTABLE_KEYS`, so the vectors are the same with every Rust release and
+/// other implementations can reproduce them. With integers as little-endian
+/// bytes, a leaf is the hash of `0x00 || len(bytes) as u64 || bytes` and a
+/// parent the hash of `0x01 || 16 as u64 || left || right`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// The tree of `MerkleTree::new_portable`: positional pairs, and the
+    /// base level padded to a power of 2 by repeating the last element.
+    Default,
+    /// The shape of RFC 6962: the last node of a level with an odd len is
+    /// promoted to the next level, and nothing is repeated.
+    Rfc6962,
+    /// Like `Default`, with the smaller child of each pair hashed first.
+    Sorted,
+    /// Like `Default`, with a root that is the hash of
+    /// `0x02 || top || len as u64`, see `MerkleTree::with_committed_len`.
+    CommittedLen,
+}
+
+impl Profile {
+    /// Every profile, in the order they are listed to users
+    pub const ALL: [Profile; 4] = [Profile::Default, Profile::Rfc6962, Profile::Sorted, Profile::CommittedLen];
+
+    /// Gets the name of the profile, the one written in the documents
+    pub fn name(&self) -> &'static str {
+        match self {
+            Profile::Default => "default",
+            Profile::Rfc6962 => "rfc6962",
+            Profile::Sorted => "sorted",
+            Profile::CommittedLen => "committed-len",
+        }
+    }
+
+    /// Gets the profile with a name, or None if there is no such profile
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|profile| profile.name() == name)
+    }
+
+    /// Builds the tree of the profile over some leaves
+    fn build(&self, leaves: &[Vec<u8>]) -> Result<MerkleTree<PortableHash>, MerkleError> {
+        let builder = MerkleTree::builder().hasher(PortableHash);
+        let builder = match self {
+            Profile::Default => builder,
+            Profile::Rfc6962 => builder.padding(PaddingStrategy::PromoteOdd),
+            Profile::Sorted => builder.sorted_pairs(true),
+            Profile::CommittedLen => builder.commit_len(true),
+        };
+        builder.build(leaves.iter().map(Vec::as_slice).collect())
+    }
+}
+
+/// What a vector document is generated for.
+/// - `profile`: The configuration of the tree.
+/// - `domain_tag`: A domain tag the document also commits the root to, see
+///   `commit_domain_tag_with`. The tagged root is the hash of
+///   `0x03 || root || tag || 0xff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorConfig {
+    pub profile: Profile,
+    pub domain_tag: Option<String>,
+}
+
+impl VectorConfig {
+    /// Creates a VectorConfig for a profile, without a domain tag
+    pub fn new(profile: Profile) -> Self {
+        Self { profile, domain_tag: None }
+    }
+
+    /// Sets the domain tag the root is committed to
+    pub fn domain_tag(mut self, tag: &str) -> Self {
+        self.domain_tag = Some(tag.to_string());
+        self
+    }
+}
+
+/// Generates a test vector document for a tree built from some leaves.
+///
+/// The document is a JSON object with the profile name, the domain tag if
+/// there is one, the leaves as hex encoded bytes, the hashes of every level
+/// from the base level up, the root, the root committed to the domain tag
+/// if there is one, and the proofs of the selected indexes with the side
+/// of each sibling. Hashes are written as 16 hex digits of the u64 in big
+/// endian order.
+///
+/// ### Arguments
+///
+/// - `config`: The profile and domain tag of the vectors.
+/// - `leaves`: The raw bytes of every element of the tree.
+/// - `indices`: The indexes whose proofs are included.
+///
+/// ### Returns
+///
+/// A Result with the JSON document, or an Error if there are no leaves or
+/// an index is invalid
+pub fn generate(config: &VectorConfig, leaves: &[Vec<u8>], indices: &[usize]) -> Result<String, MerkleError> {
+    if leaves.is_empty() {
+        return Err(MerkleError::EmptyTree);
+    }
+    let tree = config.profile.build(leaves)?;
+    let root = tree.root().ok_or(MerkleError::EmptyTree)?;
+
+    // The levels are written with their padding, which the tree does not store
+    let levels: Vec<Vec<u64>> = (0..tree.height()).map(|level| tree.padded_level(level)).collect();
+
+    let proofs = indices
+        .iter()
+        .map(|index| {
+            let proof = tree.generate_proof(*index)?;
+            Ok(Json::Object(vec![
+                ("index".to_string(), Json::from(*index)),
+                ("leaf".to_string(), Json::from(to_hex(levels[0][*index]))),
+                ("siblings".to_string(), siblings_to_json(&proof)),
+            ]))
+        })
+        .collect::<Result<Vec<Json>, MerkleError>>()?;
+
+    let mut document = vec![("profile".to_string(), Json::from(config.profile.name().to_string()))];
+    if let Some(tag) = &config.domain_tag {
+        document.push(("domain_tag".to_string(), Json::from(tag.clone())));
+    }
+    document.push(("leaves".to_string(), Json::Array(leaves.iter().map(|leaf| Json::from(bytes_to_hex(leaf))).collect())));
+    document.push(("levels".to_string(), Json::Array(levels.iter().map(|level| hashes_to_json(level)).collect())));
+    document.push(("root".to_string(), Json::from(to_hex(root))));
+    if let Some(tag) = &config.domain_tag {
+        document.push(("tagged_root".to_string(), Json::from(to_hex(commit_domain_tag_with(&PortableHash, root, tag)))));
+    }
+    document.push(("proofs".to_string(), Json::Array(proofs)));
+    Ok(Json::Object(document).to_string())
+}
+
+/// Re-runs this crate against a test vector document and compares every
+/// value it contains: the leaf hashes, every level, the root, the tagged
+/// root and each proof.
+///
+/// ### Arguments
+///
+/// - `document`: A JSON document with the layout `generate` produces.
+///
+/// ### Returns
+///
+/// A Result with a description of every divergence found (empty if the
+/// document matches), or an Error if the document is malformed or uses an
+/// unknown profile
+pub fn check(document: &str) -> Result<Vec<String>, MerkleError> {
+    let document = json::parse(document).map_err(MerkleError::InvalidEncoding)?;
+    let name = document.get("profile").and_then(Json::as_str).ok_or_else(|| invalid("profile"))?;
+    let profile = Profile::from_name(name).ok_or_else(|| MerkleError::InvalidEncoding(format!("Unknown profile '{name}'")))?;
+    let domain_tag = match document.get("domain_tag") {
+        Some(tag) => Some(tag.as_str().ok_or_else(|| invalid("domain_tag"))?),
+        None => None,
+    };
+    let leaves = document
+        .get("leaves")
+        .and_then(Json::as_array)
+        .ok_or_else(|| invalid("leaves"))?
+        .iter()
+        .map(|leaf| leaf.as_str().and_then(hex_to_bytes).ok_or_else(|| invalid("leaves")))
+        .collect::<Result<Vec<Vec<u8>>, MerkleError>>()?;
+    let levels = document
+        .get("levels")
+        .and_then(Json::as_array)
+        .ok_or_else(|| invalid("levels"))?
+        .iter()
+        .map(json_to_hashes)
+        .collect::<Result<Vec<Vec<u64>>, MerkleError>>()?;
+    let root = parse_hex(document.get("root").and_then(Json::as_str).ok_or_else(|| invalid("root"))?)?;
+
+    let tree = profile.build(&leaves)?;
+    let expected_levels: Vec<Vec<u64>> = (0..tree.height()).map(|level| tree.padded_level(level)).collect();
+    let mut divergences = Vec::new();
+    if levels.len() != expected_levels.len() {
+        divergences.push(format!("Expected {} levels but got {}", expected_levels.len(), levels.len()));
+    }
+    for (level, (got, expected)) in levels.iter().zip(&expected_levels).enumerate() {
+        if got.len() != expected.len() {
+            divergences.push(format!("Level {level}: expected {} hashes but got {}", expected.len(), got.len()));
+        }
+        for (index, (got, expected)) in got.iter().zip(expected).enumerate() {
+            if got != expected {
+                divergences.push(format!("Level {level} index {index}: expected {} but got {}", to_hex(*expected), to_hex(*got)));
+            }
+        }
+    }
+    let expected_root = tree.root().unwrap_or_default();
+    if root != expected_root {
+        divergences.push(format!("Root: expected {} but got {}", to_hex(expected_root), to_hex(root)));
+    }
+    match (domain_tag, document.get("tagged_root")) {
+        (Some(tag), Some(tagged_root)) => {
+            let tagged_root = parse_hex(tagged_root.as_str().ok_or_else(|| invalid("tagged_root"))?)?;
+            let expected = commit_domain_tag_with(&PortableHash, expected_root, tag);
+            if tagged_root != expected {
+                divergences.push(format!("Tagged root: expected {} but got {}", to_hex(expected), to_hex(tagged_root)));
+            }
+        }
+        (None, None) => {}
+        _ => return Err(invalid("tagged_root")),
+    }
+
+    for proof in document.get("proofs").and_then(Json::as_array).ok_or_else(|| invalid("proofs"))? {
+        let index = proof.get("index").and_then(Json::as_u64).ok_or_else(|| invalid("proofs.index"))? as usize;
+        let leaf = parse_hex(proof.get("leaf").and_then(Json::as_str).ok_or_else(|| invalid("proofs.leaf"))?)?;
+        let siblings = json_to_siblings(proof.get("siblings").ok_or_else(|| invalid("proofs.siblings"))?)?;
+        match leaves.get(index) {
+            Some(bytes) if hash_with(&PortableHash, bytes.as_slice()) != leaf => {
+                divergences.push(format!("Proof {index}: the leaf is not the hash of the input"))
+            }
+            None => divergences.push(format!("Proof {index}: the index is not a leaf")),
+            _ => {}
+        }
+        if !tree.verify(&Proof::new(index, siblings), leaf) {
+            divergences.push(format!("Proof {index}: does not lead to the root"));
+        }
+    }
+    Ok(divergences)
+}
+
+fn invalid(field: &str) -> MerkleError {
+    MerkleError::InvalidEncoding(format!("Missing or invalid field '{field}'"))
+}
+
+fn hashes_to_json(hashes: &[u64]) -> Json {
+    Json::Array(hashes.iter().map(|hash| Json::from(to_hex(*hash))).collect())
+}
+
+fn siblings_to_json(proof: &Proof) -> Json {
+    let siblings = proof.siblings().iter().map(|(hash, side)| {
+        let side = match side {
+            Side::Left => "left",
+            Side::Right => "right",
+        };
+        Json::Object(vec![("hash".to_string(), Json::from(to_hex(*hash))), ("side".to_string(), Json::from(side.to_string()))])
+    });
+    Json::Array(siblings.collect())
+}
+
+fn json_to_siblings(value: &Json) -> Result<Vec<(u64, Side)>, MerkleError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("proofs.siblings"))?
+        .iter()
+        .map(|sibling| {
+            let side = match sibling.get("side").and_then(Json::as_str) {
+                Some("left") => Side::Left,
+                Some("right") => Side::Right,
+                _ => return Err(invalid("proofs.siblings.side")),
+            };
+            Ok((parse_hex(sibling.get("hash").and_then(Json::as_str).ok_or_else(|| invalid("proofs.siblings.hash"))?)?, side))
+        })
+        .collect()
+}
+
+fn json_to_hashes(value: &Json) -> Result<Vec<u64>, MerkleError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("hashes"))?
+        .iter()
+        .map(|hash| parse_hex(hash.as_str().ok_or_else(|| invalid("hashes"))?))
+        .collect()
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{BuildHasher, Hasher};
+
+    use super::*;
+
+    const GOLDEN_DEFAULT: &str = include_str!("../tests/vectors/default.json");
+    const GOLDEN_RFC6962: &str = include_str!("../tests/vectors/rfc6962.json");
+
+    fn golden_leaves() -> Vec<Vec<u8>> {
+        ["Crypto", "Merkle", "Rust", "Tree", "Test"].iter().map(|leaf| leaf.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    /// Test if the committed golden vectors still match this crate, both
+    /// when checked and when generated again from their inputs.
+    fn golden_vectors_match() {
+        for (golden, profile) in [(GOLDEN_DEFAULT, Profile::Default), (GOLDEN_RFC6962, Profile::Rfc6962)] {
+            assert_eq!(check(golden), Ok(Vec::new()));
+            assert_eq!(generate(&VectorConfig::new(profile), &golden_leaves(), &[0, 2, 4]).unwrap(), golden.trim_end());
+        }
+    }
+
+    #[test]
+    /// Test if the vectors of every profile, with and without a domain tag,
+    /// pass the check, and if the profiles give different roots.
+    fn every_profile_passes_its_check() {
+        let mut roots = Vec::new();
+        for profile in Profile::ALL {
+            assert_eq!(Profile::from_name(profile.name()), Some(profile));
+            for config in [VectorConfig::new(profile), VectorConfig::new(profile).domain_tag("giveaway-2026")] {
+                let document = generate(&config, &golden_leaves(), &[0, 1, 2, 3, 4]).unwrap();
+                assert_eq!(check(&document), Ok(Vec::new()), "{config:?}");
+                roots.push(json::parse(&document).unwrap().get("root").unwrap().as_str().unwrap().to_string());
+            }
+        }
+        roots.dedup();
+        assert_eq!(roots.len(), Profile::ALL.len());
+    }
+
+    /// Hashes some bytes as they are with the algorithm of PortableHash
+    fn siphash(bytes: &[u8]) -> u64 {
+        let mut hasher = PortableHash.build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    #[test]
+    /// Test if the leaf and the root of the default profile are the
+    /// SipHash-2-4 of the bytes its documentation describes
+    fn default_profile_hashes_the_documented_bytes() {
+        let mut leaf_bytes = vec![0x00];
+        leaf_bytes.extend_from_slice(&6u64.to_le_bytes());
+        leaf_bytes.extend_from_slice(b"Crypto");
+        let leaf = siphash(&leaf_bytes);
+        assert_eq!(leaf, hash_with(&PortableHash, b"Crypto".as_slice()));
+
+        let mut node_bytes = vec![0x01];
+        node_bytes.extend_from_slice(&16u64.to_le_bytes());
+        node_bytes.extend_from_slice(&leaf.to_le_bytes());
+        node_bytes.extend_from_slice(&leaf.to_le_bytes());
+        let single = VectorConfig::new(Profile::Default);
+        let document = generate(&single, &[b"Crypto".to_vec(), b"Crypto".to_vec()], &[]).unwrap();
+        let root = json::parse(&document).unwrap().get("root").unwrap().as_str().unwrap().to_string();
+        assert_eq!(root, to_hex(siphash(&node_bytes)));
+    }
+
+    #[test]
+    /// Test if a changed hash in a level, a wrong proof and a wrong tagged
+    /// root are reported
+    fn check_reports_divergences() {
+        let config = VectorConfig::new(Profile::Default).domain_tag("tag");
+        let document = generate(&config, &golden_leaves(), &[1]).unwrap();
+        let parsed = json::parse(&document).unwrap();
+        for field in ["root", "tagged_root"] {
+            let hash = parsed.get(field).unwrap().as_str().unwrap().to_string();
+            let tampered_hash = format!("{}{}", &hash[..15], if hash.ends_with('0') { '1' } else { '0' });
+            let divergences = check(&document.replace(&hash, &tampered_hash)).unwrap();
+            assert!(!divergences.is_empty(), "{field}");
+        }
+        let root = parsed.get("root").unwrap().as_str().unwrap();
+        let divergences = check(&document.replace(root, "0000000000000000")).unwrap();
+        assert!(divergences.iter().any(|divergence| divergence.starts_with("Root")));
+        assert!(divergences.iter().any(|divergence| divergence.starts_with("Level")));
+    }
+
+    #[test]
+    /// Test if malformed documents and unknown profiles are errors
+    fn check_rejects_malformed_documents() {
+        assert!(check("{}").is_err());
+        assert!(check(&GOLDEN_DEFAULT.replace("\"default\"", "\"unknown\"")).is_err());
+        assert!(check(&GOLDEN_DEFAULT.replace("\"profile\":\"default\"", "\"profile\":\"default\",\"domain_tag\":\"x\"")).is_err());
+    }
+
+    #[test]
+    /// Test if leaves survive a round trip through hex
+    fn hex_round_trip() {
+        assert_eq!(hex_to_bytes(&bytes_to_hex(&[0, 1, 0xab, 0xff])), Some(vec![0, 1, 0xab, 0xff]));
+        assert_eq!(hex_to_bytes("abc"), None);
+        assert_eq!(hex_to_bytes("zz"), None);
+    }
+}
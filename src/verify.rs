@@ -0,0 +1,353 @@
+//! The parts of the tree needed to check a proof against a root. They only
+//! use `core` and `alloc`, so they are the ones available without the
+//! `std` feature. Every function takes the hasher to use, since the
+//! default one of the trees needs `std`.
+
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+
+/// Prefix hashed before every element, see `hash_leaf_with`.
+const LEAF_PREFIX: u8 = 0x00;
+
+/// Prefix hashed before the concatenation of two children, see `hash_node_with`.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Prefix hashed before a root and the len of its tree, see `commit_len_with`.
+const LEN_PREFIX: u8 = 0x02;
+
+/// Prefix hashed before a root and a domain tag, see `commit_domain_tag_with`.
+const TAG_PREFIX: u8 = 0x03;
+
+/// How the two children of a node are ordered before hashing them into their parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairOrdering {
+    /// The left child goes first, so a proof needs the index of the leaf
+    /// to know on which side each hash goes.
+    #[default]
+    Positional,
+    /// The smaller child goes first, like OpenZeppelin's MerkleProof. A
+    /// proof can then be checked with the leaf alone, without its index.
+    Sorted,
+}
+
+/// The side a hash of a proof goes on when it is combined with the hash
+/// obtained so far, starting from the leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// The hash of the proof is the left child: proof + hash
+    Left,
+    /// The hash of the proof is the right child: hash + proof
+    Right,
+}
+
+/// The hashes needed to get from a leaf to the root of a tree, each one
+/// with the side it goes on. The sides have to be the ones the index of the
+/// leaf gives them, so the proof only verifies for that index.
+/// - `leaf_index`: The index of the proven element in the input array.
+/// - `siblings`: The hashes of the proof, from the sibling of the leaf up to
+///   the sibling of a child of the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub(crate) leaf_index: usize,
+    pub(crate) siblings: Vec<(u64, Side)>,
+}
+
+impl Proof {
+    /// Creates a new Proof
+    ///
+    /// ### Arguments
+    ///
+    /// - `leaf_index`: The index of the proven element in the input array.
+    /// - `siblings`: The hashes of the proof with their sides, starting from the sibling of the leaf.
+    ///
+    /// ### Returns
+    ///
+    /// A Proof instance
+    pub fn new(leaf_index: usize, siblings: Vec<(u64, Side)>) -> Self {
+        Self { leaf_index, siblings }
+    }
+
+    /// Gets the index of the proven element in the input array
+    pub fn leaf_index(&self) -> usize {
+        self.leaf_index
+    }
+
+    /// Gets the hashes of the proof with their sides, starting from the
+    /// sibling of the leaf
+    pub fn siblings(&self) -> &[(u64, Side)] {
+        &self.siblings
+    }
+
+    /// Gets the hashes of the proof without their sides, the way proofs
+    /// were returned before they carried them
+    pub fn hashes(&self) -> Vec<u64> {
+        self.siblings.iter().map(|(hash, _)| *hash).collect()
+    }
+
+    /// Gets the quantity of hashes in the proof
+    pub fn len(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Checks if the proof has no hashes, which only happens when the
+    /// tree has a single element
+    pub fn is_empty(&self) -> bool {
+        self.siblings.is_empty()
+    }
+
+    /// Gets the root that the proof leads to when starting from a leaf, in
+    /// a tree with a sibling on every level. See `fold_at_levels`.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hasher`: Builds the hasher used for every parent.
+    /// - `ordering`: How the children of each node are ordered before hashing them.
+    /// - `leaf`: The hash of the proven element.
+    ///
+    /// ### Returns
+    ///
+    /// The hash obtained after combining the leaf with every hash in the
+    /// proof, or None if a hash is not on the side the index gives it
+    pub(crate) fn fold_with<H: BuildHasher>(&self, hasher: &H, ordering: PairOrdering, leaf: u64) -> Option<u64> {
+        let levels: Vec<usize> = (0..self.siblings.len()).collect();
+        self.fold_at_levels(hasher, ordering, &levels, leaf)
+    }
+
+    /// Gets the root that the proof leads to when starting from a leaf. The
+    /// side of each hash has to be the one the index of the leaf gives it
+    /// on its level, so a proof relabelled with another index is rejected
+    /// instead of proving the same leaf at that index.
+    ///
+    /// ### Arguments
+    ///
+    /// - `hasher`: Builds the hasher used for every parent.
+    /// - `ordering`: How the children of each node are ordered before hashing them.
+    /// - `levels`: The levels where the path of the leaf has a sibling, see `sibling_levels`.
+    /// - `leaf`: The hash of the proven element.
+    ///
+    /// ### Returns
+    ///
+    /// The hash obtained after combining the leaf with every hash in the
+    /// proof, or None if the proof does not have one hash for each level or
+    /// a hash is not on the side the index gives it
+    pub(crate) fn fold_at_levels<H: BuildHasher>(&self, hasher: &H, ordering: PairOrdering, levels: &[usize], leaf: u64) -> Option<u64> {
+        if levels.len() != self.siblings.len() {
+            return None;
+        }
+        let mut hash = leaf;
+        for (level, (sibling, side)) in levels.iter().zip(&self.siblings) {
+            let node_index = u32::try_from(*level).ok().and_then(|level| self.leaf_index.checked_shr(level)).unwrap_or(0);
+            hash = match (node_index.is_multiple_of(2), side) {
+                (true, Side::Right) => combine_with(hasher, ordering, hash, *sibling),
+                (false, Side::Left) => combine_with(hasher, ordering, *sibling, hash),
+                _ => return None,
+            };
+        }
+        Some(hash)
+    }
+}
+
+/// Gets the levels where the path of a leaf has a sibling, from the base
+/// level up
+///
+/// ### Arguments
+///
+/// - `index`: The index of the leaf.
+/// - `base_len`: The len of the base level. For trees that pad it, the len
+///   with the padding, so every level has a sibling.
+///
+/// ### Returns
+///
+/// The levels, one for each hash of the proof of the leaf
+#[cfg(feature = "std")]
+pub(crate) fn sibling_levels(index: usize, base_len: usize) -> Vec<usize> {
+    let mut levels = Vec::new();
+    let (mut node_index, mut level_len, mut level) = (index, base_len, 0);
+    while level_len > 1 {
+        if node_index ^ 1 < level_len {
+            levels.push(level);
+        }
+        node_index /= 2;
+        level_len = level_len.div_ceil(2);
+        level += 1;
+    }
+    levels
+}
+
+/// Same as `verify_against_root` for a tree with any hasher and pair
+/// ordering. They have to be the ones of the tree the proof came from.
+pub fn verify_against_root_with<H: BuildHasher>(hasher: &H, ordering: PairOrdering, root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    proof.leaf_index < len && proof.len() == expected_proof_len_for(len) && proof.fold_with(hasher, ordering, leaf) == Some(root)
+}
+
+/// Same as `verify_against_root_with` for a tree whose root commits to its
+/// quantity of different elements, see `MerkleTree::with_committed_len`.
+/// The proof only verifies with the len the tree had, its index has to be
+/// one of those elements and it needs one hash for each level above the
+/// leaves, `expected_proof_len_for(len)`.
+pub fn verify_committed_against_root_with<H: BuildHasher>(hasher: &H, ordering: PairOrdering, root: u64, len: usize, proof: &Proof, leaf: u64) -> bool {
+    proof.leaf_index < len
+        && proof.len() == expected_proof_len_for(len)
+        && proof.fold_with(hasher, ordering, leaf).is_some_and(|hash| commit_len_with(hasher, hash, len) == root)
+}
+
+/// Hashes the root of the levels of a tree together with its quantity of
+/// different elements, so two trees that only differ in their padding do
+/// not share a root
+///
+/// ### Arguments
+///
+/// - `hasher`: Builds the hasher used for the new root.
+/// - `root`: The hash of the only node in the last level of the tree.
+/// - `len`: The quantity of different elements of the tree.
+///
+/// ### Returns
+///
+/// An u64 that represents the root that commits to the len
+pub fn commit_len_with<H: BuildHasher>(hasher: &H, root: u64, len: usize) -> u64 {
+    hasher.hash_one((LEN_PREFIX, root, len as u64))
+}
+
+/// Hashes a root together with a domain tag, so a proof issued for one
+/// deployment does not verify against the roots of another one that uses
+/// the same elements
+///
+/// ### Arguments
+///
+/// - `hasher`: Builds the hasher used for the new root.
+/// - `root`: The root of the tree, which may already commit to its len.
+/// - `tag`: The domain tag of the deployment.
+///
+/// ### Returns
+///
+/// An u64 that represents the root that commits to the tag
+pub fn commit_domain_tag_with<H: BuildHasher>(hasher: &H, root: u64, tag: &str) -> u64 {
+    hasher.hash_one((TAG_PREFIX, root, tag))
+}
+
+/// Gets the quantity of hashes in every proof of a tree with `leaf_count`
+/// different elements that pads its base level, so a verifier without the
+/// tree can reject a proof of another len before hashing anything. When
+/// the tree promotes odd nodes it is the len of the longest proofs.
+///
+/// ### Arguments
+///
+/// - `leaf_count`: The quantity of different elements of the tree.
+///
+/// ### Returns
+///
+/// The quantity of levels above the leaves, 0 for a tree with a single element or none
+pub fn expected_proof_len_for(leaf_count: usize) -> usize {
+    leaf_count.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Hashes two children into their parent, ordering them first
+/// 
+/// ### Arguments
+/// 
+/// - `hasher`: Builds the hasher used for the parent.
+/// - `ordering`: How the children are ordered before concatenating them.
+/// - `left`: The hash of the left child.
+/// - `right`: The hash of the right child.
+/// 
+/// ### Returns
+/// 
+/// An u64 that represents the hash of the parent
+pub(crate) fn combine_with<H: BuildHasher>(hasher: &H, ordering: PairOrdering, left: u64, right: u64) -> u64 {
+    let (first, second) = match ordering {
+        PairOrdering::Sorted if right < left => (right, left),
+        _ => (left, right),
+    };
+    hash_node_with(hasher, concatenate_elements(first, second))
+}
+
+/// Concatenates to elements into one
+/// 
+/// Each element takes exactly 8 bytes (little endian), so two different
+/// pairs can never give the same concatenation. With decimal strings the
+/// pairs (1, 23) and (12, 3) were both "123".
+/// 
+/// ### Arguments
+/// 
+/// - `elem1`: An u64 that will be the first part of the concatenation.
+/// - `elem2`: An u64 that will be the second part of the concatenation.
+/// 
+/// ### Returns
+/// 
+/// The 16 bytes that are the result of the concatenation of the 2 elements
+pub(crate) fn concatenate_elements(elem1: u64, elem2: u64) -> [u8; 16] {
+    let mut concatenated = [0u8; 16];
+    concatenated[..8].copy_from_slice(&elem1.to_le_bytes());
+    concatenated[8..].copy_from_slice(&elem2.to_le_bytes());
+    concatenated
+}
+
+/// Hashes an element with a hasher built by `hasher`, the way a tree with
+/// that hasher hashes its leaves
+pub fn hash_leaf_with<H: BuildHasher, T: Hash>(hasher: &H, element: T) -> u64 {
+    hasher.hash_one((LEAF_PREFIX, element))
+}
+
+/// Hashes the concatenation of two children with a hasher built by `hasher`
+pub(crate) fn hash_node_with<H: BuildHasher>(hasher: &H, concatenated: [u8; 16]) -> u64 {
+    hasher.hash_one((NODE_PREFIX, concatenated))
+}
+
+/// Hashes the concatenation of any quantity of children, each one taking
+/// 8 bytes like in `concatenate_elements`. Two children give the same hash
+/// as `hash_node_with`, since an array is hashed like the slice it holds.
+#[cfg(feature = "std")]
+pub(crate) fn hash_children_with<H: BuildHasher>(hasher: &H, children: &[u64]) -> u64 {
+    let concatenated: Vec<u8> = children.iter().flat_map(|child| child.to_le_bytes()).collect();
+    hasher.hash_one((NODE_PREFIX, concatenated.as_slice()))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::MerkleTree;
+
+    /// The FNV-1a hasher, which only needs `core`
+    #[derive(Default)]
+    struct Fnv(u64);
+
+    impl core::hash::Hasher for Fnv {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FnvBuilder;
+
+    impl BuildHasher for FnvBuilder {
+        type Hasher = Fnv;
+
+        fn build_hasher(&self) -> Fnv {
+            Fnv(0xcbf29ce484222325)
+        }
+    }
+
+    #[test]
+    /// Test if the proofs of a tree with an injected hasher verify with the
+    /// functions available without `std`, and only for their own leaf.
+    fn verify_with_injected_hasher() {
+        let data = vec!["Crypto", "Merkle", "Rust", "Tree", "Test"];
+        let merkle = MerkleTree::with_hasher(data.clone(), FnvBuilder);
+        let root = merkle.root().unwrap();
+        for (index, element) in data.iter().enumerate() {
+            let proof = merkle.generate_proof(index).unwrap();
+            let leaf = hash_leaf_with(&FnvBuilder, element);
+
+            assert_eq!(merkle.get_leaf(index), Some(leaf));
+            assert!(verify_against_root_with(&FnvBuilder, PairOrdering::Positional, root, 5, &proof, leaf));
+            assert!(!verify_against_root_with(&FnvBuilder, PairOrdering::Positional, root, 5, &proof, leaf ^ 1));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::hash::Hash;
+use std::slice;
+
+use crate::error::MerkleError;
+use crate::merkle::{MerkleTree, Proof};
+
+/// A MerkleTree that keeps the elements it was built with, so the element
+/// at an index can be known without keeping a parallel vector.
+///
+/// The tree hashes the elements by reference and its padding is only
+/// hashes, so each element is owned once and never cloned. Every method
+/// that changes the elements changes the tree the same way, so
+/// `get_element(i)` always hashes to `get_leaf(i)`.
+/// - `tree`: The tree of the hashes of the elements, the same one `MerkleTree::new` builds.
+/// - `elements`: The different elements, in the order of the base level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTreeWithData<T> {
+    tree: MerkleTree,
+    elements: Vec<T>,
+}
+
+impl<T: Hash> MerkleTreeWithData<T> {
+    /// Creates a new MerkleTreeWithData
+    ///
+    /// ### Arguments
+    ///
+    /// - `elements`: A vector with the elements that will be kept, hashed and form the first level in the tree.
+    ///
+    /// ### Returns
+    ///
+    /// A MerkleTreeWithData instance
+    pub fn new(elements: Vec<T>) -> Self {
+        let tree = MerkleTree::from_iter_hashed(elements.iter());
+        Self { tree, elements }
+    }
+
+    /// Gets the tree of the hashes of the elements
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    /// Gets the root of the tree, or None if it is empty
+    pub fn root(&self) -> Option<u64> {
+        self.tree.root()
+    }
+
+    /// Gets the quantity of elements of the tree
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Checks if the tree has no elements
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Gets an element of the tree
+    ///
+    /// ### Arguments
+    ///
+    /// - `index`: The index of the element in the base level.
+    ///
+    /// ### Returns
+    ///
+    /// A reference to the element, or None if the index is not one of an element
+    pub fn get_element(&self, index: usize) -> Option<&T> {
+        self.elements.get(index)
+    }
+
+    /// Gets the hash of an element, the one a proof of it is verified with
+    pub fn get_leaf(&self, index: usize) -> Option<u64> {
+        self.tree.get_leaf(index)
+    }
+
+    /// Iterates over the elements of the tree, in the order of the base level
+    pub fn elements(&self) -> slice::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    /// Generates the Proof for an element of the tree, see `MerkleTree::generate_proof`
+    pub fn generate_proof(&self, index: usize) -> Result<Proof, MerkleError> {
+        self.tree.generate_proof(index)
+    }
+
+    /// Generates the proofs of every occurrence of an element
+    ///
+    /// ### Arguments
+    ///
+    /// - `element`: The element to prove. It is hashed the same way the elements of the tree were.
+    ///
+    /// ### Returns
+    ///
+    /// A vector with a Proof for each index that holds the element, empty if it is not in the tree
+    pub fn proofs_by_value(&self, element: &T) -> Vec<Proof> {
+        self.tree.indices_of(element).into_iter().filter_map(|index| self.tree.generate_proof(index).ok()).collect()
+    }
+
+    /// Checks if the root of the tree can be obtained with a proof and
+    /// the hash of an element, see `MerkleTree::verify`
+    pub fn verify(&self, proof: &Proof, leaf: u64) -> bool {
+        self.tree.verify(proof, leaf)
+    }
+
+    /// Adds an element at the end of the tree, see `MerkleTree::add_element`
+    pub fn add_element(&mut self, new_elem: T) {
+        self.tree.add_element(&new_elem);
+        self.elements.push(new_elem);
+    }
+
+    /// Adds many elements at the end of the tree, see `MerkleTree::add_elements`
+    pub fn add_elements(&mut self, new_elems: Vec<T>) {
+        self.tree.add_elements(new_elems.iter().collect());
+        self.elements.extend(new_elems);
+    }
+
+    /// Replaces an element of the tree, see `MerkleTree::update_element`
+    ///
+    /// ### Returns
+    ///
+    /// A Result with the element that was replaced, or an Error if the
+    /// index is not one of an element
+    pub fn update_element(&mut self, index: usize, new_elem: T) -> Result<T, MerkleError> {
+        self.tree.update_element(index, &new_elem)?;
+        Ok(std::mem::replace(&mut self.elements[index], new_elem))
+    }
+
+    /// Removes the last element of the tree, see `MerkleTree::pop_element`
+    ///
+    /// ### Returns
+    ///
+    /// The removed element, or None if the tree is empty
+    pub fn pop_element(&mut self) -> Option<T> {
+        self.tree.pop_element()?;
+        self.elements.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::hash_element;
+
+    /// Checks that every element hashes to its leaf and the tree is the
+    /// one `MerkleTree::new` builds for the elements
+    fn assert_in_sync(merkle: &MerkleTreeWithData<String>) {
+        for index in 0..merkle.len() {
+            assert_eq!(merkle.get_element(index).map(hash_element), merkle.get_leaf(index));
+        }
+        assert_eq!(merkle.get_element(merkle.len()), None);
+        assert_eq!(merkle.tree(), &MerkleTree::new(merkle.elements().collect()));
+    }
+
+    #[test]
+    /// Test if the elements are kept in sync with the tree after several
+    /// mutations, and only the different elements are owned.
+    fn elements_follow_mutations() {
+        let mut merkle = MerkleTreeWithData::new(["Crypto", "Merkle", "Rust"].map(String::from).to_vec());
+        assert_in_sync(&merkle);
+        assert_eq!(merkle.tree().padded_len(), 4);
+        assert_eq!(merkle.elements().len(), 3);
+
+        merkle.add_element("Tree".to_string());
+        merkle.add_elements(vec!["Test".to_string(), "Hash".to_string()]);
+        assert_in_sync(&merkle);
+        assert_eq!(merkle.update_element(1, "Root".to_string()), Ok("Merkle".to_string()));
+        assert_eq!(merkle.update_element(6, "Leaf".to_string()), Err(MerkleError::InvalidIndex { index: 6, len: 6 }));
+        assert_in_sync(&merkle);
+        assert_eq!(merkle.pop_element(), Some("Hash".to_string()));
+        assert_in_sync(&merkle);
+
+        let elements: Vec<&str> = merkle.elements().map(String::as_str).collect();
+        assert_eq!(elements, ["Crypto", "Root", "Rust", "Tree", "Test"]);
+        while merkle.pop_element().is_some() {}
+        assert!(merkle.is_empty() && merkle.root().is_none());
+    }
+
+    #[test]
+    /// Test if every occurrence of an element is proven by value
+    fn proves_by_value() {
+        let merkle = MerkleTreeWithData::new(vec!["Crypto", "Rust", "Merkle", "Rust"]);
+        let proofs = merkle.proofs_by_value(&"Rust");
+
+        assert_eq!(proofs.iter().map(Proof::leaf_index).collect::<Vec<usize>>(), [1, 3]);
+        for proof in &proofs {
+            assert!(merkle.verify(proof, hash_element("Rust")));
+        }
+        assert!(merkle.proofs_by_value(&"Tree").is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "std")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Runs the merkle binary with the arguments
+fn merkle(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_merkle")).args(args).output().unwrap()
+}
+
+/// Gets what the binary printed, without the last newline
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap().trim_end().to_string()
+}
+
+/// Creates an empty directory for the files of a test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("merkle-cli-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+/// Test if a tree built from a file proves its leaves, and the proofs only
+/// verify with the right root, index and leaf.
+fn build_prove_verify() {
+    let dir = test_dir("round-trip");
+    let leaves = dir.join("leaves.txt");
+    fs::write(&leaves, "Crypto\nMerkle\nRust\nTree\nTest\n").unwrap();
+
+    let built = merkle(&["build", leaves.to_str().unwrap()]);
+    assert!(built.status.success());
+    let root = stdout(&built);
+    assert_eq!(root.len(), 16);
+    let tree = dir.join("leaves.tree");
+    assert!(tree.exists());
+
+    let proved = merkle(&["prove", tree.to_str().unwrap(), "2"]);
+    assert!(proved.status.success());
+    let proof = stdout(&proved);
+
+    let valid = merkle(&["verify", &root, &proof, "2", "Rust"]);
+    assert_eq!(valid.status.code(), Some(0));
+    assert_eq!(stdout(&valid), "valid");
+    for (index, leaf) in [("2", "Tree"), ("3", "Rust")] {
+        assert_eq!(merkle(&["verify", &root, &proof, index, leaf]).status.code(), Some(1));
+    }
+    assert_eq!(merkle(&["verify", &"0".repeat(16), &proof, "2", "Rust"]).status.code(), Some(1));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+/// Test if the tree is saved where the second argument says, and the root
+/// printed is the one of the library.
+fn build_to_custom_path() {
+    let dir = test_dir("custom-path");
+    let leaves = dir.join("leaves.txt");
+    let tree = dir.join("saved.bin");
+    fs::write(&leaves, "Crypto\nMerkle\nRust").unwrap();
+
+    let built = merkle(&["build", leaves.to_str().unwrap(), tree.to_str().unwrap()]);
+    assert!(built.status.success());
+    let expected = tree::MerkleTree::new(vec!["Crypto", "Merkle", "Rust"]);
+    assert_eq!(stdout(&built), expected.root_hex().unwrap());
+    assert_eq!(tree::MerkleTree::load(&tree).unwrap(), expected);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+/// Test if a valid proof is only accepted when it follows the rules of
+/// the policy file, and the broken rule is printed.
+fn verify_with_policy() {
+    let dir = test_dir("policy");
+    let leaves = dir.join("leaves.txt");
+    fs::write(&leaves, "Crypto\nMerkle\nRust\nTree\nTest\n").unwrap();
+    let root = stdout(&merkle(&["build", leaves.to_str().unwrap()]));
+    let tree = dir.join("leaves.tree");
+    let proof = stdout(&merkle(&["prove", tree.to_str().unwrap(), "2"]));
+
+    let policy = dir.join("policy.json");
+    let policy_path = policy.to_str().unwrap();
+    fs::write(&policy, r#"{"allowed_algorithms":["default"],"max_depth":3}"#).unwrap();
+    let valid = merkle(&["verify", &root, &proof, "2", "Rust", "--policy", policy_path]);
+    assert_eq!((valid.status.code(), stdout(&valid)), (Some(0), "valid".to_string()));
+    let wrong_leaf = merkle(&["verify", &root, &proof, "2", "Tree", "--policy", policy_path]);
+    assert_eq!((wrong_leaf.status.code(), stdout(&wrong_leaf)), (Some(1), "invalid".to_string()));
+
+    fs::write(&policy, r#"{"max_depth":2}"#).unwrap();
+    let too_deep = merkle(&["verify", &root, &proof, "2", "Rust", "--policy", policy_path]);
+    assert_eq!(too_deep.status.code(), Some(1));
+    assert_eq!(stdout(&too_deep), "invalid: The proof has 3 hashes but at most 2 are allowed");
+
+    fs::write(&policy, r#"{"require_tree_size":true}"#).unwrap();
+    assert_eq!(merkle(&["verify", &root, &proof, "2", "Rust", "--policy", policy_path]).status.code(), Some(1));
+
+    fs::write(&policy, r#"{"max_dept":2}"#).unwrap();
+    assert_eq!(merkle(&["verify", &root, &proof, "2", "Rust", "--policy", policy_path]).status.code(), Some(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+/// Test if wrong arguments, missing files and invalid input exit with 2
+fn invalid_input_exits_with_2() {
+    let dir = test_dir("invalid");
+    let empty = dir.join("empty.txt");
+    fs::write(&empty, "").unwrap();
+    let missing = dir.join("missing.tree");
+
+    let cases: [&[&str]; 6] = [
+        &[],
+        &["prove"],
+        &["build", empty.to_str().unwrap()],
+        &["prove", missing.to_str().unwrap(), "0"],
+        &["verify", "not a root", "00", "0", "Rust"],
+        &["verify", &"0".repeat(16), "zz", "0", "Rust"],
+    ];
+    for args in cases {
+        let output = merkle(args);
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(!output.stderr.is_empty());
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+/// Test if the vectors printed for a file of leaves are the committed
+/// golden ones, and a vector file is checked for divergences.
+fn vectors_generate_and_check() {
+    let dir = test_dir("vectors");
+    let leaves = dir.join("leaves.txt");
+    fs::write(&leaves, "Crypto\nMerkle\nRust\nTree\nTest\n").unwrap();
+
+    let generated = merkle(&["vectors", leaves.to_str().unwrap(), "--profile", "rfc6962", "--domain-tag", "giveaway"]);
+    assert!(generated.status.success());
+    let document = stdout(&generated);
+    let config = tree::vectors::VectorConfig::new(tree::vectors::Profile::Rfc6962).domain_tag("giveaway");
+    let leaf_bytes: Vec<Vec<u8>> = ["Crypto", "Merkle", "Rust", "Tree", "Test"].iter().map(|leaf| leaf.as_bytes().to_vec()).collect();
+    assert_eq!(document, tree::vectors::generate(&config, &leaf_bytes, &[0, 1, 2, 3, 4]).unwrap());
+
+    let golden = merkle(&["vectors", "--check", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors/default.json")]);
+    assert_eq!(golden.status.code(), Some(0));
+    assert_eq!(stdout(&golden), "");
+
+    let vectors = dir.join("vectors.json");
+    let root = document.split("\"root\":\"").nth(1).unwrap()[..16].to_string();
+    fs::write(&vectors, document.replace(&root, &"0".repeat(16))).unwrap();
+    let tampered = merkle(&["vectors", "--check", vectors.to_str().unwrap()]);
+    assert_eq!(tampered.status.code(), Some(1));
+    assert!(stdout(&tampered).contains("Root: expected"));
+
+    assert_eq!(merkle(&["vectors", leaves.to_str().unwrap(), "--profile", "unknown"]).status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
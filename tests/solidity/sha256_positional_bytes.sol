@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: MIT
+// Verifies proofs of a tree with sha256, positional pairs and raw leaves.
+pragma solidity ^0.8.0;
+
+library MerkleVerifier {
+    /// @notice Checks that the leaf is in the tree with the root.
+    /// @param directions Bit i is 1 when proof[i] is the left child, the index of the leaf.
+    function verify(bytes32[] calldata proof, bytes32 root, bytes calldata data, uint256 directions) internal pure returns (bool) {
+        if (directions >> proof.length != 0) {
+            return false;
+        }
+        bytes32 node = sha256(data);
+        for (uint256 i = 0; i < proof.length; i++) {
+            if ((directions >> i) & 1 == 0) {
+                node = sha256(abi.encodePacked(node, proof[i]));
+            } else {
+                node = sha256(abi.encodePacked(proof[i], node));
+            }
+        }
+        return node == root;
+    }
+}
@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: MIT
+// Verifies proofs of a tree with sha256, sorted pairs and hashed leaves.
+pragma solidity ^0.8.0;
+
+library MerkleVerifier {
+    /// @notice Checks that the leaf is in the tree with the root.
+    function verify(bytes32[] calldata proof, bytes32 root, bytes32 leaf) internal pure returns (bool) {
+        bytes32 node = leaf;
+        for (uint256 i = 0; i < proof.length; i++) {
+            bytes32 sibling = proof[i];
+            node = node <= sibling ? sha256(abi.encodePacked(node, sibling)) : sha256(abi.encodePacked(sibling, node));
+        }
+        return node == root;
+    }
+}